@@ -2,6 +2,7 @@
 // Created by Alex Khomich on 2019-02-06.
 //
 
+#include <stdbool.h>
 #include <stdint.h>
 
 #ifndef WOFF_DECODER_WOFFDECODER_H
@@ -25,9 +26,107 @@ typedef enum Error {
     WriteToFileError,
     ReadFromFileError,
     InputPathError,
-    OutputPathError
+    OutputPathError,
+    TableNotFound,
+    InvalidTableData,
+    InvalidHeadMagicNumber,
+    InvalidSfntStructure,
+    UnsupportedFontFormat,
+    InvalidEotStructure,
+    UnsupportedEotCompression,
+    SanitizeRejected,
+    EmbeddingRestricted
 } Error;
 
+/// Font container format detected by its magic number
+typedef enum FontFormat {
+    Unknown,
+    Woff,
+    Woff2,
+    TrueType,
+    AppleTrueType,
+    OpenTypeCff,
+    TrueTypeCollection,
+    Eot
+} FontFormat;
+
+/// Font embedding permission level from fsType bits 0-3
+typedef enum EmbeddingLevel {
+    Installable,
+    Restricted,
+    PreviewAndPrint,
+    Editable
+} EmbeddingLevel;
+
+/// Font embedding permissions parsed from fsType
+///
+/// #Fields
+///
+/// `level` - embedding permission level
+/// `no_subsetting` - the font must not be subsetted before embedding
+/// `bitmap_only` - only bitmaps of the font may be embedded
+typedef struct EmbeddingPermissions {
+    EmbeddingLevel level;
+    bool no_subsetting;
+    bool bitmap_only;
+} EmbeddingPermissions;
+
+/// Kind of non-fatal issue found while decoding
+typedef enum WarningKind {
+    TotalSfntSizeMismatch,
+    NonZeroReserved,
+    UnsortedTableDirectory,
+    ExtraDataAfterCompressedStream,
+    MissingRequiredTable
+} WarningKind;
+
+/// Non-fatal issue found while decoding. Fields which are not used by the kind of warning are zero
+///
+/// #Fields
+///
+/// `kind` - kind of warning
+/// `tag` - tag of the table
+/// `offset` - offset from the beginning of WOFF data
+/// `declared` - declared value (`total_sfnt_size`, `reserved`)
+/// `actual` - actual value (size of SFNT, number of extra bytes)
+typedef struct CWarning {
+    WarningKind kind;
+    uint32_t tag;
+    uint32_t offset;
+    uint32_t declared;
+    uint32_t actual;
+} CWarning;
+
+/// Kind of repair made by lenient decoding
+typedef enum RepairKind {
+    WoffLengthMismatch,
+    TableDirectoryTruncated,
+    TableDropped,
+    TableDataTruncated,
+    RawDeflateStream,
+    CompressedStreamTruncated,
+    ExtraDecompressedData,
+    OrigLengthMismatch,
+    CompLengthMismatch
+} RepairKind;
+
+/// Repair made by lenient decoding. Fields which are not used by the kind of repair are zero
+///
+/// #Fields
+///
+/// `kind` - kind of repair
+/// `tag` - tag of the table
+/// `offset` - offset of the table data from the beginning of WOFF data
+/// `declared` - declared value (`length`, number of tables, `comp_length`, `orig_length`)
+/// `actual` - actual value (size of WOFF data, recovered number of tables, available or recovered length)
+typedef struct CRepair {
+    RepairKind kind;
+    uint32_t tag;
+    uint32_t offset;
+    uint32_t declared;
+    uint32_t actual;
+} CRepair;
+
 /// Result structure with decoded SFNT data
 ///
 /// #Fields
@@ -35,10 +134,18 @@ typedef enum Error {
 /// `decoded_data` - decoded SFNT data
 /// `decoded_data_len` - length of decoded SFNT data
 /// `error` - type of error. None - returned result has no errors.
+/// `warnings` - non-fatal issues found while decoding, use `decoded_result_warning` to access them
+/// `warnings_count` - number of warnings
+/// `repairs` - repairs made by lenient decoding, use `decoded_result_repair` to access them
+/// `repairs_count` - number of repairs
 typedef struct DecodedResult {
     uint8_t* decoded_data;
     size_t decoded_data_len;
     Error error;
+    CWarning* warnings;
+    size_t warnings_count;
+    CRepair* repairs;
+    size_t repairs_count;
 } DecodedResult;
 
 /// `FileRWResult` structure with length of decoded data and error
@@ -52,12 +159,37 @@ typedef struct FileRWResult {
     Error error;
 } FileRWResult;
 
+/// Options of WOFF decoding
+///
+/// #Fields
+///
+/// `include_tables` - array of tags of tables which have to be kept (may be NULL if `include_tables_len` is 0)
+/// `include_tables_len` - number of tags in `include_tables`. If 0 - all tables are kept.
+/// `exclude_tables` - array of tags of tables which have to be dropped (may be NULL if `exclude_tables_len` is 0)
+/// `exclude_tables_len` - number of tags in `exclude_tables`
+/// `preserve_table_order` - lay out tables data in the original physical order
+/// `lenient` - best-effort repair of damaged WOFF data instead of failing
+/// `sanitize` - validate table contents after decoding
+/// `enforce_embedding_permissions` - fail if the font is marked as restricted license embedding or its 'OS/2' table can't be read
+typedef struct CDecodeOptions {
+    const uint32_t* include_tables;
+    size_t include_tables_len;
+    const uint32_t* exclude_tables;
+    size_t exclude_tables_len;
+    bool preserve_table_order;
+    bool lenient;
+    bool sanitize;
+    bool enforce_embedding_permissions;
+} CDecodeOptions;
+
 #ifdef __cplusplus
 extern "C" {
 #endif
 
 /// Decode .woff file data to SFNT bytes wrapped for using with C wrapper
 /// And returns Result structure with decoded data
+/// Functions without decoding options load data in other formats like `load_font_from_data_wrapped`,
+/// so SFNT data passes through untouched. Functions with options accept WOFF data only
 DecodedResult* decode_from_file_wrapped(const char* path);
 
 /// Decode WOFF data to SFNT data wrapped for using with C wrapper
@@ -70,6 +202,54 @@ FileRWResult* decode_file_to_file_wrapped(const char* in_path, const char* out_p
 /// Decode WOFF data to SFNT file wrapped for using with C wrapper
 FileRWResult* decode_data_to_file_wrapped(const uint8_t* source_buf, size_t woff_data_size, const char* out_path);
 
+/// Decode .woff file data to SFNT file with decoding options wrapped for using with C wrapper
+/// `options` may be NULL, then all tables are kept
+FileRWResult* decode_file_to_file_with_options_wrapped(const char* in_path, const char* out_path, const CDecodeOptions* options);
+
+/// Decode WOFF data to SFNT data with decoding options wrapped for using with C wrapper
+/// `options` may be NULL, then all tables are kept
+DecodedResult* decode_from_data_with_options_wrapped(const uint8_t* source_buf, size_t woff_data_size, const CDecodeOptions* options);
+
+/// Decode WOFF data to SFNT file with decoding options wrapped for using with C wrapper
+/// `options` may be NULL, then all tables are kept
+FileRWResult* decode_data_to_file_with_options_wrapped(const uint8_t* source_buf, size_t woff_data_size, const char* out_path, const CDecodeOptions* options);
+
+/// Decode EOT data to SFNT data wrapped for using with C wrapper
+/// XOR obfuscated and MicroType Express compressed font data is supported
+DecodedResult* decode_eot_from_data_wrapped(const uint8_t* source_buf, size_t eot_data_size);
+
+/// Detects font format of the data. Using with C wrapper
+FontFormat detect_font_format_wrapped(const uint8_t* source_buf, size_t data_size);
+
+/// Loads font data in any supported format (WOFF, SFNT, the first font of TTC) to SFNT data
+/// wrapped for using with C wrapper
+DecodedResult* load_font_from_data_wrapped(const uint8_t* source_buf, size_t data_size);
+
+/// Decode WOFF data of variable font with TrueType outlines to SFNT data of its static instance
+/// at user coordinates (`axis_tags[i]`, `axis_values[i]`) wrapped for using with C wrapper.
+/// Axes without coordinates use their default values
+DecodedResult* decode_static_instance_from_data_wrapped(const uint8_t* source_buf, size_t woff_data_size,
+                                                        const uint32_t* axis_tags, const float* axis_values,
+                                                        size_t coordinates_count);
+
+/// Reads font embedding permissions from fsType of 'OS/2' table of font data in any supported format
+/// to `permissions`. Using with C wrapper
+Error font_embedding_permissions_wrapped(const uint8_t* source_buf, size_t data_size, EmbeddingPermissions* permissions);
+
+/// Returns number of warnings of decoded result. Using with C wrapper
+size_t decoded_result_warnings_count(const DecodedResult* result);
+
+/// Copies warning with `index` of decoded result to `warning`.
+/// Returns false if there is no such warning. Using with C wrapper
+bool decoded_result_warning(const DecodedResult* result, size_t index, CWarning* warning);
+
+/// Returns number of repairs made by lenient decoding of decoded result. Using with C wrapper
+size_t decoded_result_repairs_count(const DecodedResult* result);
+
+/// Copies repair with `index` of decoded result to `repair`.
+/// Returns false if there is no such repair. Using with C wrapper
+bool decoded_result_repair(const DecodedResult* result, size_t index, CRepair* repair);
+
 /// Destroys buffer with decoded data. Using with C wrapper
 void destroy_decoded_result(DecodedResult* buff_data);
 
@@ -0,0 +1,104 @@
+//! Embedded bitmap glyphs from 'CBLC'/'CBDT' and 'sbix' tables
+
+use crate::font::FontTables;
+use crate::tables::cblc::CblcTable;
+use crate::tables::sbix::SbixTable;
+use crate::utils::*;
+use crate::Error;
+
+/// Encoding of embedded image data
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Tiff,
+}
+
+/// 'CBLC'/'CBDT' glyph metrics in pixels. Vertical metrics are zero if the font stores only small metrics
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BitmapMetrics {
+    pub height: u8,
+    pub width: u8,
+    pub hori_bearing_x: i8,
+    pub hori_bearing_y: i8,
+    pub hori_advance: u8,
+    pub vert_bearing_x: i8,
+    pub vert_bearing_y: i8,
+    pub vert_advance: u8,
+}
+
+/// Embedded image of a glyph
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitmapGlyph {
+    pub image_format: ImageFormat,
+    // Encoded image data
+    pub data: Vec<u8>,
+    // Size of the strike the image belongs to in pixels per em
+    pub ppem_x: u16,
+    pub ppem_y: u16,
+    // Glyph metrics, 'CBLC'/'CBDT' only
+    pub metrics: Option<BitmapMetrics>,
+    // Position of the left bottom corner of the image relative to the glyph origin, 'sbix' only
+    pub origin_offset: (i16, i16),
+}
+
+/// Returns the image of the glyph from the strike which suits `ppem` best: the smallest strike
+/// not smaller than `ppem` or the largest one. 'CBLC'/'CBDT' tables are preferred over 'sbix' table.
+/// `None` if the font has no embedded image of the glyph
+pub(crate) fn bitmap_glyph<F: FontTables + ?Sized>(font: &F, glyph_id: u16, ppem: u16) -> Result<Option<BitmapGlyph>, Error> {
+    if font.has_table(tag(b"CBLC")) && font.has_table(tag(b"CBDT")) {
+        let cblc = CblcTable::parse(&font.table_data(tag(b"CBLC"))?)?;
+        let strikes: Vec<(usize, u16)> = cblc.strikes.iter().enumerate()
+            .filter(|(_, strike)| (strike.start_glyph_id..=strike.end_glyph_id).contains(&glyph_id))
+            .map(|(index, strike)| (index, strike.ppem_y as u16))
+            .collect();
+        if let Some(strike_index) = best_strike(&strikes, ppem) {
+            let cbdt = font.table_data(tag(b"CBDT"))?;
+            if let Some(glyph) = cblc.glyph_image(&cbdt, strike_index, glyph_id)? {
+                return Ok(Some(glyph));
+            }
+        }
+    }
+    if font.has_table(tag(b"sbix")) {
+        let num_glyphs = font.maxp()?.num_glyphs;
+        let sbix_data = font.table_data(tag(b"sbix"))?;
+        let sbix = SbixTable::parse(&sbix_data, num_glyphs)?;
+        let strikes: Vec<(usize, u16)> = sbix.strikes.iter().enumerate().map(|(index, strike)| (index, strike.ppem)).collect();
+        if let Some(strike_index) = best_strike(&strikes, ppem) {
+            return sbix.glyph_image(&sbix_data, strike_index, glyph_id);
+        }
+    }
+    Ok(None)
+}
+
+/// Index of the smallest strike not smaller than `ppem` or of the largest strike from (index, ppem) pairs
+fn best_strike(strikes: &[(usize, u16)], ppem: u16) -> Option<usize> {
+    strikes.iter().filter(|(_, size)| *size >= ppem).min_by_key(|(_, size)| *size)
+        .or_else(|| strikes.iter().max_by_key(|(_, size)| *size))
+        .map(|(index, _)| *index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Sfnt;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_bitmap_glyph() {
+        let mut tables = truetype_font_tables();
+        let (cblc, cbdt) = cblc_and_cbdt(&[20, 40], 1, &[(1, vec![1]), (2, vec![2])]);
+        tables.push((tag(b"CBLC"), cblc));
+        tables.push((tag(b"CBDT"), cbdt));
+        tables.push((tag(b"sbix"), sbix_table(5, &[(64, vec![(3, tag(b"png "), vec![3])])])));
+        let font = Sfnt::from_tables(0x00010000, tables).unwrap();
+
+        debug_assert!(font.bitmap_glyph(1, 16).unwrap().unwrap().ppem_y == 20);
+        debug_assert!(font.bitmap_glyph(1, 30).unwrap().unwrap().ppem_y == 40);
+        debug_assert!(font.bitmap_glyph(2, 100).unwrap().unwrap().ppem_y == 40);
+        let glyph = font.bitmap_glyph(3, 16).unwrap().unwrap();
+        debug_assert!(glyph.ppem_y == 64 && glyph.data == vec![3]);
+        debug_assert!(font.bitmap_glyph(4, 16).unwrap().is_none());
+    }
+}
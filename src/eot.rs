@@ -0,0 +1,489 @@
+//! Embedded OpenType (EOT) container parsing and writing.
+//! Font data may be XOR obfuscated and MicroType Express compressed, see `crate::mtx`
+
+use crate::font::{FontTables, Sfnt};
+use crate::format::{EOT_MAGIC_NUMBER, EOT_MAGIC_NUMBER_OFFSET};
+use crate::mtx::decompress_mtx;
+use crate::tables::name::name_id;
+use crate::Error;
+use bytebuffer::{ByteReader, Endian};
+
+pub(crate) const EOT_VERSION_1_0: u32 = 0x00010000;
+pub(crate) const EOT_VERSION_2_1: u32 = 0x00020001;
+pub(crate) const EOT_VERSION_2_2: u32 = 0x00020002;
+
+/// Font data is a subset of the original font
+pub const EOT_FLAG_SUBSET: u32 = 0x00000001;
+/// Font data is compressed with MicroType Express
+pub const EOT_FLAG_COMPRESSED: u32 = 0x00000004;
+/// Font data is obfuscated with XOR
+pub const EOT_FLAG_XOR_ENCRYPTED: u32 = 0x10000000;
+
+// every byte of obfuscated font data is XOR-ed with this key
+pub(crate) const EOT_XOR_KEY: u8 = 0x50;
+// RootStringCheckSum is the sum of root string bytes XOR-ed with this key
+const EOT_ROOT_STRING_CHECKSUM_KEY: u32 = 0x50475342;
+// DEFAULT_CHARSET of Windows GDI
+const EOT_DEFAULT_CHARSET: u8 = 1;
+// bit 0 of fsSelection field of 'OS/2'
+const OS2_FS_SELECTION_ITALIC: u16 = 0x0001;
+
+/// Options of EOT writing
+///
+/// #Fields
+///
+/// `version` - EOT version, 0x00020001 by default as the most widely supported one with root strings
+/// `root_strings` - URLs of the sites allowed to use the font. If empty - the font is not restricted
+/// `xor_encrypt` - obfuscate font data with XOR
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EotOptions {
+    pub version: u32,
+    pub root_strings: Vec<String>,
+    pub xor_encrypt: bool,
+}
+
+impl Default for EotOptions {
+    fn default() -> Self {
+        EotOptions {
+            version: EOT_VERSION_2_1,
+            root_strings: vec![],
+            xor_encrypt: false,
+        }
+    }
+}
+
+/// EOT header (all versions). Strings are stored as UTF-16LE in the file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EotHeader {
+    // Total structure length in bytes (including string and font data)
+    pub eot_size: u32,
+    // Length of the font data in bytes
+    pub font_data_size: u32,
+    // Version number of this format - 0x00010000, 0x00020001 or 0x00020002
+    pub version: u32,
+    // Processing flags, see `EOT_FLAG_*` constants
+    pub flags: u32,
+    // The PANOSE value for this font
+    pub font_panose: [u8; 10],
+    // In Windows this is derived from TEXTMETRIC.tmCharSet
+    pub charset: u8,
+    // If the font is italic set to 0x01, otherwise 0x00
+    pub italic: u8,
+    // The weight value for this font (usWeightClass of 'OS/2')
+    pub weight: u32,
+    // Type of embedding permissions (fsType of 'OS/2')
+    pub fs_type: u16,
+    // ulUnicodeRange1..4 of 'OS/2'
+    pub unicode_range: [u32; 4],
+    // ulCodePageRange1..2 of 'OS/2'
+    pub code_page_range: [u32; 2],
+    // checkSumAdjustment of 'head'
+    pub check_sum_adjustment: u32,
+    pub family_name: String,
+    pub style_name: String,
+    pub version_name: String,
+    pub full_name: String,
+    // Null-separated list of sites allowed to use the font (version 0x00020001 and later)
+    pub root_string: String,
+    // Checksum of the root string (version 0x00020002)
+    pub root_string_checksum: u32,
+    // Code page of EUDC font (version 0x00020002)
+    pub eudc_code_page: u32,
+    // Signature of the font data, must be empty (version 0x00020002)
+    pub signature: Vec<u8>,
+    // Processing flags of EUDC font data (version 0x00020002)
+    pub eudc_flags: u32,
+    // EUDC font data (version 0x00020002)
+    pub eudc_font_data: Vec<u8>,
+}
+
+impl EotHeader {
+    /// Fills EOT header from 'name', 'OS/2' and 'head' tables of the font.
+    /// Sizes are set by `transform_to_u8_vec`
+    pub fn from_font<F: FontTables + ?Sized>(font: &F, options: &EotOptions) -> Result<Self, Error> {
+        if ![EOT_VERSION_1_0, EOT_VERSION_2_1, EOT_VERSION_2_2].contains(&options.version)
+            || (options.version == EOT_VERSION_1_0 && !options.root_strings.is_empty()) {
+            return Err(Error::InvalidEotStructure);
+        }
+        let name = font.name()?;
+        let os2 = font.os2()?;
+        let head = font.head()?;
+        let get_name = |id: u16| name.get(id, &["en-US", "en"]).unwrap_or_default().to_string();
+
+        let root_string = options.root_strings.join("\0");
+        let root_string_checksum = if options.version == EOT_VERSION_2_2 {
+            root_string_bytes(&root_string)
+                .iter()
+                .fold(0u32, |sum, byte| sum.wrapping_add(*byte as u32))
+                ^ EOT_ROOT_STRING_CHECKSUM_KEY
+        } else {
+            0
+        };
+
+        Ok(EotHeader {
+            version: options.version,
+            flags: if options.xor_encrypt { EOT_FLAG_XOR_ENCRYPTED } else { 0 },
+            font_panose: os2.panose,
+            charset: EOT_DEFAULT_CHARSET,
+            italic: (os2.fs_selection & OS2_FS_SELECTION_ITALIC != 0) as u8,
+            weight: os2.us_weight_class as u32,
+            fs_type: os2.fs_type,
+            unicode_range: os2.ul_unicode_range,
+            code_page_range: os2.ul_code_page_range.unwrap_or_default(),
+            check_sum_adjustment: head.check_sum_adjustment,
+            family_name: get_name(name_id::FAMILY),
+            style_name: get_name(name_id::SUBFAMILY),
+            version_name: get_name(name_id::VERSION),
+            full_name: get_name(name_id::FULL_NAME),
+            root_string,
+            root_string_checksum,
+            ..Default::default()
+        })
+    }
+
+    /// Writes EOT header followed by `font_data`. EOT and font data sizes are calculated
+    /// and font data is obfuscated if `EOT_FLAG_XOR_ENCRYPTED` flag is set
+    pub fn transform_to_u8_vec(&self, font_data: &[u8]) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![];
+        // EOTSize is set at the end
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&(font_data.len() as u32).to_le_bytes());
+        data.extend_from_slice(&self.version.to_le_bytes());
+        data.extend_from_slice(&self.flags.to_le_bytes());
+        data.extend_from_slice(&self.font_panose);
+        data.push(self.charset);
+        data.push(self.italic);
+        data.extend_from_slice(&self.weight.to_le_bytes());
+        data.extend_from_slice(&self.fs_type.to_le_bytes());
+        data.extend_from_slice(&EOT_MAGIC_NUMBER.to_le_bytes());
+        self.unicode_range.iter().for_each(|range| data.extend_from_slice(&range.to_le_bytes()));
+        self.code_page_range.iter().for_each(|range| data.extend_from_slice(&range.to_le_bytes()));
+        data.extend_from_slice(&self.check_sum_adjustment.to_le_bytes());
+        // Reserved1..4
+        data.extend_from_slice(&[0; 16]);
+
+        write_name(&mut data, &self.family_name);
+        write_name(&mut data, &self.style_name);
+        write_name(&mut data, &self.version_name);
+        write_name(&mut data, &self.full_name);
+        if self.version >= EOT_VERSION_2_1 {
+            write_name(&mut data, &self.root_string);
+        }
+        if self.version == EOT_VERSION_2_2 {
+            data.extend_from_slice(&self.root_string_checksum.to_le_bytes());
+            data.extend_from_slice(&self.eudc_code_page.to_le_bytes());
+            // Padding6
+            data.extend_from_slice(&0u16.to_le_bytes());
+            data.extend_from_slice(&(self.signature.len() as u16).to_le_bytes());
+            data.extend_from_slice(&self.signature);
+            data.extend_from_slice(&self.eudc_flags.to_le_bytes());
+            data.extend_from_slice(&(self.eudc_font_data.len() as u32).to_le_bytes());
+            data.extend_from_slice(&self.eudc_font_data);
+        }
+
+        if self.flags & EOT_FLAG_XOR_ENCRYPTED != 0 {
+            data.extend(font_data.iter().map(|byte| byte ^ EOT_XOR_KEY));
+        } else {
+            data.extend_from_slice(font_data);
+        }
+        let eot_size = data.len() as u32;
+        data[0..4].copy_from_slice(&eot_size.to_le_bytes());
+        data
+    }
+
+    /// Parses EOT header and returns it along with the offset of the font data
+    pub fn parse(data: &[u8]) -> Result<(Self, usize), Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        buffer.set_endian(Endian::LittleEndian);
+        let invalid = |_| Error::InvalidEotStructure;
+
+        let mut header = EotHeader {
+            eot_size: buffer.read_u32().map_err(invalid)?,
+            font_data_size: buffer.read_u32().map_err(invalid)?,
+            version: buffer.read_u32().map_err(invalid)?,
+            flags: buffer.read_u32().map_err(invalid)?,
+            ..Default::default()
+        };
+        if ![EOT_VERSION_1_0, EOT_VERSION_2_1, EOT_VERSION_2_2].contains(&header.version) {
+            return Err(Error::InvalidEotStructure);
+        }
+        let panose = buffer.read_bytes(10).map_err(invalid)?;
+        header.font_panose.copy_from_slice(&panose);
+        header.charset = buffer.read_u8().map_err(invalid)?;
+        header.italic = buffer.read_u8().map_err(invalid)?;
+        header.weight = buffer.read_u32().map_err(invalid)?;
+        header.fs_type = buffer.read_u16().map_err(invalid)?;
+        debug_assert!(buffer.get_rpos() == EOT_MAGIC_NUMBER_OFFSET);
+        if buffer.read_u16().map_err(invalid)? != EOT_MAGIC_NUMBER {
+            return Err(Error::InvalidEotStructure);
+        }
+        for range in header.unicode_range.iter_mut() {
+            *range = buffer.read_u32().map_err(invalid)?;
+        }
+        for range in header.code_page_range.iter_mut() {
+            *range = buffer.read_u32().map_err(invalid)?;
+        }
+        header.check_sum_adjustment = buffer.read_u32().map_err(invalid)?;
+        // Reserved1..4
+        buffer.read_bytes(16).map_err(invalid)?;
+
+        header.family_name = read_name(&mut buffer)?;
+        header.style_name = read_name(&mut buffer)?;
+        header.version_name = read_name(&mut buffer)?;
+        header.full_name = read_name(&mut buffer)?;
+        if header.version >= EOT_VERSION_2_1 {
+            header.root_string = read_name(&mut buffer)?;
+        }
+        if header.version == EOT_VERSION_2_2 {
+            header.root_string_checksum = buffer.read_u32().map_err(invalid)?;
+            header.eudc_code_page = buffer.read_u32().map_err(invalid)?;
+            // Padding6
+            buffer.read_u16().map_err(invalid)?;
+            let signature_size = buffer.read_u16().map_err(invalid)?;
+            header.signature = buffer.read_bytes(signature_size as usize).map_err(invalid)?;
+            header.eudc_flags = buffer.read_u32().map_err(invalid)?;
+            let eudc_font_size = buffer.read_u32().map_err(invalid)?;
+            header.eudc_font_data = buffer.read_bytes(eudc_font_size as usize).map_err(invalid)?;
+        }
+
+        let font_data_offset = buffer.get_rpos();
+        if font_data_offset + header.font_data_size as usize > data.len() {
+            return Err(Error::InvalidEotStructure);
+        }
+        Ok((header, font_data_offset))
+    }
+}
+
+/// Reads padding, size and UTF-16LE string of EOT header
+fn read_name(buffer: &mut ByteReader) -> Result<String, Error> {
+    let invalid = |_| Error::InvalidEotStructure;
+    // PaddingN
+    buffer.read_u16().map_err(invalid)?;
+    let size = buffer.read_u16().map_err(invalid)? as usize;
+    if !size.is_multiple_of(2) {
+        return Err(Error::InvalidEotStructure);
+    }
+    let mut units: Vec<u16> = Vec::with_capacity(size / 2);
+    for _ in 0..size / 2 {
+        units.push(buffer.read_u16().map_err(invalid)?);
+    }
+    Ok(String::from_utf16_lossy(&units))
+}
+
+/// UTF-16LE bytes of EOT header string
+fn root_string_bytes(value: &str) -> Vec<u8> {
+    value.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect()
+}
+
+/// Writes padding, size and UTF-16LE string of EOT header
+fn write_name(data: &mut Vec<u8>, value: &str) {
+    let bytes = root_string_bytes(value);
+    // PaddingN
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+    data.extend_from_slice(&bytes);
+}
+
+/// Wraps SFNT data to EOT. Header fields are filled from tables of the font
+pub(crate) fn encode_eot(sfnt: Vec<u8>, options: &EotOptions) -> Result<Vec<u8>, Error> {
+    let font = Sfnt::from_vec(sfnt)?;
+    let header = EotHeader::from_font(&font, options)?;
+    Ok(header.transform_to_u8_vec(font.as_bytes()))
+}
+
+/// Extracts SFNT data from EOT. XOR obfuscation is removed first,
+/// then MicroType Express compressed data is decompressed
+pub(crate) fn decode_eot(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let (header, font_data_offset) = EotHeader::parse(data)?;
+    let mut font_data = data[font_data_offset..font_data_offset + header.font_data_size as usize].to_vec();
+    if header.flags & EOT_FLAG_XOR_ENCRYPTED != 0 {
+        font_data.iter_mut().for_each(|byte| *byte ^= EOT_XOR_KEY);
+    }
+    if header.flags & EOT_FLAG_COMPRESSED != 0 {
+        return decompress_mtx(&font_data);
+    }
+    Ok(font_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tables::glyf::{build_glyf_and_loca, CompositeGlyph, Glyph, SimpleGlyph};
+    use crate::test_utils::*;
+    use crate::utils::tag;
+
+    fn test_header(version: u32, flags: u32) -> EotHeader {
+        EotHeader {
+            version,
+            flags,
+            weight: 400,
+            fs_type: 8,
+            family_name: "Test".to_string(),
+            style_name: "Regular".to_string(),
+            version_name: "Version 1.0".to_string(),
+            full_name: "Test Regular".to_string(),
+            root_string: if version >= EOT_VERSION_2_1 { "http://example.com".to_string() } else { String::new() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decode_eot_versions() {
+        let sfnt = build_sfnt(0x00010000, &[(tag(b"head"), head_table(1000, 0))]);
+        for version in [EOT_VERSION_1_0, EOT_VERSION_2_1, EOT_VERSION_2_2] {
+            let eot = build_eot(&test_header(version, 0), &sfnt);
+            let (header, _) = EotHeader::parse(&eot).unwrap();
+            debug_assert!(header.version == version && header.full_name == "Test Regular" && header.fs_type == 8);
+            debug_assert!(header.root_string == test_header(version, 0).root_string);
+            debug_assert!(decode_eot(&eot).unwrap() == sfnt);
+        }
+    }
+
+    #[test]
+    fn test_decode_eot_xor() {
+        let sfnt = build_sfnt(0x00010000, &[(tag(b"head"), head_table(1000, 0))]);
+        let obfuscated: Vec<u8> = sfnt.iter().map(|byte| byte ^ EOT_XOR_KEY).collect();
+        let eot = build_eot(&test_header(EOT_VERSION_2_2, EOT_FLAG_XOR_ENCRYPTED), &obfuscated);
+        debug_assert!(decode_eot(&eot).unwrap() == sfnt);
+    }
+
+    /// TrueType font with instructions, 'cvt ', 'hdmx' and 'VDMX' tables and glyphs in the form
+    /// they are rebuilt from MTX data
+    fn mtx_test_font() -> Vec<u8> {
+        let with_instructions = |glyph: Vec<u8>, instructions: &[u8]| match Glyph::parse(&glyph).unwrap() {
+            Glyph::Simple(simple) => Glyph::Simple(SimpleGlyph { instructions: instructions.to_vec(), ..simple }),
+            Glyph::Composite(composite) => Glyph::Composite(CompositeGlyph { instructions: instructions.to_vec(), ..composite }),
+            Glyph::Empty => Glyph::Empty,
+        }.transform_to_u8_vec();
+        // PUSHB[4] 1 7 1 9 1, PUSHW[1] 300 -2, code
+        let pushes = [0xB4, 1, 7, 1, 9, 1, 0xB9, 0x01, 0x2C, 0xFF, 0xFE, 0x1D, 0x2B];
+        let glyphs = vec![
+            with_instructions(simple_glyph(&[&[(0, 0, true), (0, 700, true), (500, 700, true), (500, 0, true)]]), &[]),
+            with_instructions(simple_glyph(&[&[(100, 0, true), (100, 500, true), (600, 500, true)], &[(5000, -3000, false)]]), &pushes),
+            with_instructions(simple_glyph(&[&[(0, 0, true), (200, 400, false), (400, 0, true)]]), &[]),
+            vec![],
+            with_instructions(composite_glyph([0, 0, 800, 500], &[(1, 0, 0, None), (2, 600, 0, Some(0.5))]), &[0xB0, 5, 0x2B]),
+        ];
+        let (glyf, loca, index_to_loc_format) = build_glyf_and_loca(&glyphs);
+        let mut tables: Vec<(u32, Vec<u8>)> = truetype_font_tables()
+            .into_iter()
+            .filter(|(table_tag, _)| ![tag(b"glyf"), tag(b"loca"), tag(b"head")].contains(table_tag))
+            .collect();
+        let cvt: Vec<u8> = [0i16, 50, -20, 1000].iter().flat_map(|value| value.to_be_bytes()).collect();
+        tables.extend([
+            (tag(b"head"), head_table(1000, index_to_loc_format)),
+            (tag(b"glyf"), glyf),
+            (tag(b"loca"), loca),
+            (tag(b"cvt "), cvt),
+            (tag(b"hdmx"), hdmx_table(&[(12, vec![6, 9, 5, 3, 10]), (16, vec![8, 11, 6, 4, 13])])),
+            (tag(b"VDMX"), vdmx_table(10, 12, &[(10, 8, -2), (12, 11, -3)])),
+        ]);
+        Sfnt::from_tables(0x00010000, tables).unwrap().into_vec()
+    }
+
+    #[test]
+    fn test_decode_eot_compressed() {
+        let sfnt = mtx_test_font();
+        for is_run_length_encoded in [false, true] {
+            let mtx = mtx_data(&sfnt, is_run_length_encoded);
+            let eot = build_eot(&test_header(EOT_VERSION_2_1, EOT_FLAG_COMPRESSED), &mtx);
+            debug_assert!(decode_eot(&eot).unwrap() == sfnt);
+
+            let obfuscated: Vec<u8> = mtx.iter().map(|byte| byte ^ EOT_XOR_KEY).collect();
+            let eot = build_eot(&test_header(EOT_VERSION_2_2, EOT_FLAG_COMPRESSED | EOT_FLAG_XOR_ENCRYPTED), &obfuscated);
+            debug_assert!(decode_eot(&eot).unwrap() == sfnt);
+        }
+        let font = Sfnt::from_vec(sfnt).unwrap();
+        let glyphs = font.truetype_glyphs().unwrap();
+        debug_assert!(glyphs.glyph(3).unwrap() == Glyph::Empty && glyphs.glyph(4).unwrap().bounds() == Some([0, 0, 800, 500]));
+    }
+
+    /// Compares font decoded from real MTX data (e.g. made by WEFT) with the original font. Tables are
+    /// compared as is except of 'head' fields changed by rebuilding and 'glyf' and 'loca' which are
+    /// compared glyph by glyph
+    #[test]
+    #[ignore = "needs test_fonts/mtx-compressed.eot and its original font test_fonts/mtx-compressed.ttf"]
+    fn test_decode_eot_compressed_fixture() {
+        let eot = std::fs::read("test_fonts/mtx-compressed.eot").unwrap();
+        let original = Sfnt::from_vec(std::fs::read("test_fonts/mtx-compressed.ttf").unwrap()).unwrap();
+        let decoded = Sfnt::from_vec(decode_eot(&eot).unwrap()).unwrap();
+        debug_assert!(decoded.table_tags() == original.table_tags());
+        for table_tag in original.table_tags() {
+            let (expected, actual) = (original.table_data(table_tag).unwrap(), decoded.table_data(table_tag).unwrap());
+            if table_tag == tag(b"head") {
+                // checkSumAdjustment and indexToLocFormat
+                debug_assert!(expected[..8] == actual[..8] && expected[12..50] == actual[12..50] && expected[52..] == actual[52..]);
+            } else if table_tag != tag(b"glyf") && table_tag != tag(b"loca") {
+                debug_assert!(expected == actual, "table {:08X} differs", table_tag);
+            }
+        }
+        let (expected, actual) = (original.truetype_glyphs().unwrap(), decoded.truetype_glyphs().unwrap());
+        for glyph_id in 0..original.maxp().unwrap().num_glyphs {
+            debug_assert!(expected.glyph(glyph_id).unwrap() == actual.glyph(glyph_id).unwrap(), "glyph {} differs", glyph_id);
+        }
+    }
+
+    #[test]
+    fn test_decode_eot_errors() {
+        let sfnt = build_sfnt(0x00010000, &[(tag(b"head"), head_table(1000, 0))]);
+        let eot = build_eot(&test_header(EOT_VERSION_2_1, EOT_FLAG_COMPRESSED), &sfnt);
+        debug_assert!(decode_eot(&eot).err() == Some(Error::UnsupportedEotCompression));
+
+        let mut eot = build_eot(&test_header(EOT_VERSION_2_1, 0), &sfnt);
+        eot.truncate(eot.len() - 1);
+        debug_assert!(decode_eot(&eot).err() == Some(Error::InvalidEotStructure));
+
+        let eot = build_eot(&test_header(0x00030000, 0), &sfnt);
+        debug_assert!(decode_eot(&eot).err() == Some(Error::InvalidEotStructure));
+    }
+
+    fn eot_test_font() -> Vec<u8> {
+        let names = [
+            (3, 1, 0x409, name_id::FAMILY, utf16_be("Test")),
+            (3, 1, 0x409, name_id::SUBFAMILY, utf16_be("Italic")),
+            (3, 1, 0x409, name_id::FULL_NAME, utf16_be("Test Italic")),
+            (3, 1, 0x409, name_id::VERSION, utf16_be("Version 2.0")),
+        ];
+        build_sfnt(0x00010000, &[
+            (tag(b"head"), head_table(1000, 0)),
+            (tag(b"name"), name_table(&names, &[])),
+            (tag(b"OS/2"), os2_table(4, 700, 8)),
+        ])
+    }
+
+    #[test]
+    fn test_encode_eot() {
+        let sfnt = eot_test_font();
+        let font = Sfnt::from_vec(sfnt.clone()).unwrap();
+        let os2 = font.os2().unwrap();
+        let options = EotOptions { root_strings: vec!["http://a.com".to_string(), "http://b.com".to_string()], ..Default::default() };
+
+        let eot = encode_eot(sfnt.clone(), &options).unwrap();
+        let (header, _) = EotHeader::parse(&eot).unwrap();
+        debug_assert!(header.eot_size as usize == eot.len() && header.font_data_size as usize == sfnt.len());
+        debug_assert!(header.version == EOT_VERSION_2_1);
+        debug_assert!(header.family_name == "Test" && header.style_name == "Italic");
+        debug_assert!(header.full_name == "Test Italic" && header.version_name == "Version 2.0");
+        debug_assert!(header.root_string == "http://a.com\0http://b.com");
+        debug_assert!(header.italic == (os2.fs_selection & 1) as u8);
+        debug_assert!(header.weight == os2.us_weight_class as u32 && header.fs_type == os2.fs_type);
+        debug_assert!(header.font_panose == os2.panose && header.unicode_range == os2.ul_unicode_range);
+        debug_assert!(header.check_sum_adjustment == font.head().unwrap().check_sum_adjustment);
+        debug_assert!(eot == build_eot(&header, &sfnt));
+        debug_assert!(decode_eot(&eot).unwrap() == sfnt);
+    }
+
+    #[test]
+    fn test_encode_eot_version_2_2_with_xor() {
+        let sfnt = eot_test_font();
+        let options = EotOptions { version: EOT_VERSION_2_2, root_strings: vec!["ab".to_string()], xor_encrypt: true };
+        let eot = encode_eot(sfnt.clone(), &options).unwrap();
+        let (header, _) = EotHeader::parse(&eot).unwrap();
+        debug_assert!(header.flags == EOT_FLAG_XOR_ENCRYPTED);
+        debug_assert!(header.root_string_checksum == (b'a' as u32 + b'b' as u32) ^ EOT_ROOT_STRING_CHECKSUM_KEY);
+        debug_assert!(decode_eot(&eot).unwrap() == sfnt);
+
+        let options = EotOptions { version: EOT_VERSION_1_0, root_strings: vec!["ab".to_string()], xor_encrypt: false };
+        debug_assert!(encode_eot(sfnt, &options).err() == Some(Error::InvalidEotStructure));
+    }
+}
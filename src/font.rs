@@ -0,0 +1,425 @@
+//! Access to separate tables of a font.
+//!
+//! `Sfnt` wraps already decoded SFNT data and `WoffFont` works directly with WOFF data:
+//! it reads only WOFF header and table directory and decompresses a table only when it's requested.
+
+use crate::structures::*;
+use crate::bitmap::BitmapGlyph;
+use crate::metrics::FontMetrics;
+use crate::outline::PathBuilder;
+use crate::tables::avar::AvarTable;
+use crate::tables::cblc::CblcTable;
+use crate::tables::cff::CffGlyphs;
+use crate::tables::cmap::CmapTable;
+use crate::tables::colr::ColrTable;
+use crate::tables::cpal::CpalTable;
+use crate::tables::fvar::FvarTable;
+use crate::tables::glyf::TrueTypeGlyphs;
+use crate::tables::hhea::HheaTable;
+use crate::tables::hmtx::HmtxTable;
+use crate::tables::kern::KernTable;
+use crate::tables::layout::LayoutTable;
+use crate::layout::LayoutCatalogue;
+use crate::tables::loca::LocaTable;
+use crate::tables::maxp::MaxpTable;
+use crate::tables::name::NameTable;
+use crate::tables::os2::Os2Table;
+use crate::tables::post::PostTable;
+use crate::tables::sbix::SbixTable;
+use crate::shaping::{GlyphRun, ShapingOptions};
+use crate::tables::stat::StatTable;
+use crate::tables::svg::SvgTable;
+use crate::utils::*;
+use crate::variation::VariationInfo;
+use crate::{create_woff_header, inflate_table, read_woff_table_directory, sanity_check, Error};
+use bytebuffer::ByteReader;
+use std::borrow::Cow;
+use std::mem::size_of;
+
+/// Common interface for fonts which allows to get raw data of separate tables
+/// and parsed representations of the tables supported by the library
+pub trait FontTables {
+    /// Returns raw (uncompressed) data of the table with `tag`
+    /// or `TableNotFound` error if font doesn't contain such table
+    fn table_data(&self, tag: u32) -> Result<Cow<'_, [u8]>, Error>;
+
+    /// Returns tags of all tables the font contains
+    fn table_tags(&self) -> Vec<u32>;
+
+    /// Checks if the font contains table with `tag`
+    fn has_table(&self, tag: u32) -> bool {
+        self.table_tags().contains(&tag)
+    }
+
+    /// Parses 'head' table of the font
+    fn head(&self) -> Result<SfntHeaderTable, Error> {
+        SfntHeaderTable::parse(&self.table_data(tag(b"head"))?)
+    }
+
+    /// Parses 'name' table of the font
+    fn name(&self) -> Result<NameTable, Error> {
+        NameTable::parse(&self.table_data(tag(b"name"))?)
+    }
+
+    /// Parses 'OS/2' table of the font
+    fn os2(&self) -> Result<Os2Table, Error> {
+        Os2Table::parse(&self.table_data(tag(b"OS/2"))?)
+    }
+
+    /// Parses 'hhea' table of the font
+    fn hhea(&self) -> Result<HheaTable, Error> {
+        HheaTable::parse(&self.table_data(tag(b"hhea"))?)
+    }
+
+    /// Parses 'maxp' table of the font
+    fn maxp(&self) -> Result<MaxpTable, Error> {
+        MaxpTable::parse(&self.table_data(tag(b"maxp"))?)
+    }
+
+    /// Parses 'post' table header of the font
+    fn post(&self) -> Result<PostTable, Error> {
+        PostTable::parse(&self.table_data(tag(b"post"))?)
+    }
+
+    /// Parses 'cmap' table of the font
+    fn cmap(&self) -> Result<CmapTable, Error> {
+        CmapTable::parse(&self.table_data(tag(b"cmap"))?)
+    }
+
+    /// Parses 'CPAL' table of the font
+    fn cpal(&self) -> Result<CpalTable, Error> {
+        CpalTable::parse(&self.table_data(tag(b"CPAL"))?)
+    }
+
+    /// Parses 'COLR' table of the font, paint graphs of color glyphs are parsed on request
+    fn colr(&self) -> Result<ColrTable<'_>, Error> {
+        ColrTable::parse(self.table_data(tag(b"COLR"))?)
+    }
+
+    /// Parses 'CBLC' table of the font, images are read from 'CBDT' table with `CblcTable::glyph_image`
+    fn cblc(&self) -> Result<CblcTable, Error> {
+        CblcTable::parse(&self.table_data(tag(b"CBLC"))?)
+    }
+
+    /// Parses 'sbix' table of the font using number of glyphs from 'maxp'
+    fn sbix(&self) -> Result<SbixTable, Error> {
+        let maxp = self.maxp()?;
+        SbixTable::parse(&self.table_data(tag(b"sbix"))?, maxp.num_glyphs)
+    }
+
+    /// Returns embedded image of the glyph from the strike which suits `ppem` best, see `BitmapGlyph`.
+    /// `None` if the font has no embedded image of the glyph
+    fn bitmap_glyph(&self, glyph_id: u16, ppem: u16) -> Result<Option<BitmapGlyph>, Error> {
+        crate::bitmap::bitmap_glyph(self, glyph_id, ppem)
+    }
+
+    /// Parses 'SVG ' table of the font, documents are read on request
+    fn svg(&self) -> Result<SvgTable<'_>, Error> {
+        SvgTable::parse(self.table_data(tag(b"SVG "))?)
+    }
+
+    /// Parses headers of 'GSUB' table of the font: scripts, features, lookups and feature variations
+    fn gsub(&self) -> Result<LayoutTable<'_>, Error> {
+        LayoutTable::parse_gsub(self.table_data(tag(b"GSUB"))?)
+    }
+
+    /// Parses headers of 'GPOS' table of the font: scripts, features, lookups and feature variations
+    fn gpos(&self) -> Result<LayoutTable<'_>, Error> {
+        LayoutTable::parse_gpos(self.table_data(tag(b"GPOS"))?)
+    }
+
+    /// Collects scripts, language systems and features of 'GSUB' and 'GPOS' tables
+    fn layout_catalogue(&self) -> Result<LayoutCatalogue, Error> {
+        LayoutCatalogue::from_font(self)
+    }
+
+    /// Parses 'kern' table of the font
+    fn kern(&self) -> Result<KernTable, Error> {
+        KernTable::parse(&self.table_data(tag(b"kern"))?)
+    }
+
+    /// Shapes the text into positioned glyphs and measures its advance, see `ShapingOptions`.
+    /// 'head', 'cmap', 'hhea', 'maxp' and 'hmtx' tables are required
+    fn shape(&self, text: &str, options: &ShapingOptions) -> Result<GlyphRun, Error> {
+        crate::shaping::shape_text(self, text, options)
+    }
+
+    /// Parses 'fvar' table of the font
+    fn fvar(&self) -> Result<FvarTable, Error> {
+        FvarTable::parse(&self.table_data(tag(b"fvar"))?)
+    }
+
+    /// Parses 'avar' table of the font
+    fn avar(&self) -> Result<AvarTable, Error> {
+        AvarTable::parse(&self.table_data(tag(b"avar"))?)
+    }
+
+    /// Parses 'STAT' table of the font
+    fn stat(&self) -> Result<StatTable, Error> {
+        StatTable::parse(&self.table_data(tag(b"STAT"))?)
+    }
+
+    /// Parses 'hmtx' table of the font using number of metrics from 'hhea' and number of glyphs from 'maxp'
+    fn hmtx(&self) -> Result<HmtxTable, Error> {
+        let hhea = self.hhea()?;
+        let maxp = self.maxp()?;
+        HmtxTable::parse(&self.table_data(tag(b"hmtx"))?, hhea.number_of_h_metrics, maxp.num_glyphs)
+    }
+
+    /// Parses 'loca' table of the font using its format from 'head' and number of glyphs from 'maxp'
+    fn loca(&self) -> Result<LocaTable, Error> {
+        let head = self.head()?;
+        let maxp = self.maxp()?;
+        LocaTable::parse(&self.table_data(tag(b"loca"))?, head.index_to_loc_format, maxp.num_glyphs)
+    }
+
+    /// Returns TrueType glyphs of the font ('glyf' and 'loca' tables)
+    fn truetype_glyphs(&self) -> Result<TrueTypeGlyphs<'_>, Error> {
+        let loca = self.loca()?;
+        Ok(TrueTypeGlyphs::new(self.table_data(tag(b"glyf"))?, loca))
+    }
+
+    /// Returns CFF glyphs of the font from 'CFF ' table or, if there is no such table, from 'CFF2' table
+    fn cff_glyphs(&self) -> Result<CffGlyphs<'_>, Error> {
+        match self.table_data(tag(b"CFF ")) {
+            Ok(data) => CffGlyphs::parse_cff(data),
+            Err(Error::TableNotFound) => CffGlyphs::parse_cff2(self.table_data(tag(b"CFF2"))?),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Emits glyph outline to the path builder: quadratic one for fonts with 'glyf' table
+    /// and cubic one for fonts with CFF or CFF2 outlines
+    fn draw_glyph<B: PathBuilder + ?Sized>(&self, glyph_id: u16, builder: &mut B) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        if self.has_table(tag(b"glyf")) {
+            self.truetype_glyphs()?.draw(glyph_id, builder)
+        } else {
+            self.cff_glyphs()?.draw(glyph_id, builder)
+        }
+    }
+
+    /// Collects font metrics summary from 'head', 'hhea', 'maxp', 'OS/2' and 'post' tables
+    fn metrics(&self) -> Result<FontMetrics, Error> {
+        FontMetrics::from_font(self)
+    }
+
+    /// Collects variation axes, named instances and axis values from 'fvar', 'avar' and 'STAT' tables.
+    /// Names are taken from 'name' table in the first available of `preferred_languages`
+    fn variation_info(&self, preferred_languages: &[&str]) -> Result<VariationInfo, Error> {
+        VariationInfo::from_font(self, preferred_languages)
+    }
+}
+
+/// Decoded SFNT font data with parsed table directory
+pub struct Sfnt {
+    data: Vec<u8>,
+    version: u32,
+    table_records: Vec<SfntTableRecord>,
+}
+
+impl Sfnt {
+    /// Creates `Sfnt` from SFNT data (e.g. result of `decode_from_slice`).
+    /// Returns `InvalidSfntStructure` error if table directory points outside of the data
+    pub fn from_vec(data: Vec<u8>) -> Result<Self, Error> {
+        let sfnt_offset_table_size = size_of::<SfntOffsetTable>();
+
+        let mut buffer = ByteReader::from_bytes(&data);
+        let version = try_read_u32_be(&mut buffer).map_err(|_| Error::InvalidSfntStructure)?;
+        let num_tables = try_read_u16_be(&mut buffer).map_err(|_| Error::InvalidSfntStructure)?;
+
+        let mut table_records: Vec<SfntTableRecord> = Vec::with_capacity(num_tables as usize);
+        buffer.set_rpos(sfnt_offset_table_size);
+        for _ in 0..num_tables {
+            let record = SfntTableRecord {
+                table_tag: try_read_u32_be(&mut buffer).map_err(|_| Error::InvalidSfntStructure)?,
+                checksum: try_read_u32_be(&mut buffer).map_err(|_| Error::InvalidSfntStructure)?,
+                offset: try_read_u32_be(&mut buffer).map_err(|_| Error::InvalidSfntStructure)?,
+                length: try_read_u32_be(&mut buffer).map_err(|_| Error::InvalidSfntStructure)?,
+            };
+            if try_slice(&data, record.offset as usize, record.length as usize).is_err() {
+                return Err(Error::InvalidSfntStructure);
+            }
+            table_records.push(record);
+        }
+
+        Ok(Sfnt {
+            data,
+            version,
+            table_records,
+        })
+    }
+
+    /// Builds SFNT data from tables data and creates `Sfnt` from it.
+    /// Tables are sorted by tag, every table is padded to 4 bytes, table checksums are calculated
+    /// and checkSumAdjustment of 'head' table (if there is one) is updated for the whole font
+    pub fn from_tables(version: u32, tables: Vec<(u32, Vec<u8>)>) -> Result<Self, Error> {
+        let mut tables = tables;
+        tables.sort_by_key(|(tag, _)| *tag);
+        let num_tables = tables.len() as u16;
+        let search_range = calculate_search_range(num_tables);
+        let offset_table = SfntOffsetTable {
+            version,
+            num_tables,
+            search_range,
+            entry_selector: calculate_entry_selector(search_range),
+            range_shift: calculate_range_shift(num_tables, search_range),
+        };
+
+        let mut data = offset_table.transform_to_u8_vec();
+        let mut table_offset = size_of::<SfntOffsetTable>() + tables.len() * size_of::<SfntTableRecord>();
+        let mut head_offset: Option<usize> = None;
+        for (table_tag, table) in tables.iter_mut() {
+            if *table_tag == tag(b"head") {
+                if table.len() < HEAD_CHECK_SUM_ADJUSTMENT_OFFSET + 4 {
+                    return Err(Error::InvalidTableData);
+                }
+                table[HEAD_CHECK_SUM_ADJUSTMENT_OFFSET..HEAD_CHECK_SUM_ADJUSTMENT_OFFSET + 4].fill(0);
+                head_offset = Some(table_offset);
+            }
+            let record = SfntTableRecord {
+                table_tag: *table_tag,
+                checksum: calculate_table_checksum(table),
+                offset: table_offset as u32,
+                length: table.len() as u32,
+            };
+            data.append(&mut record.transform_to_u8_vec());
+            table_offset += calculate_padded_len(table.len() as u32) as usize;
+        }
+        for (_, table) in tables {
+            data.extend_from_slice(&table);
+            data.resize(calculate_padded_len(data.len() as u32) as usize, 0);
+        }
+        if let Some(head_offset) = head_offset {
+            let adjustment = HEAD_CHECK_SUM_MAGIC.wrapping_sub(calculate_table_checksum(&data));
+            let start = head_offset + HEAD_CHECK_SUM_ADJUSTMENT_OFFSET;
+            data[start..start + 4].copy_from_slice(&adjustment.to_be_bytes());
+        }
+        Sfnt::from_vec(data)
+    }
+
+    /// SFNT version (0x00010000 for TrueType outlines or 'OTTO' for CFF outlines)
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Raw SFNT data
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns raw SFNT data and consumes `Sfnt`
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl FontTables for Sfnt {
+    fn table_data(&self, tag: u32) -> Result<Cow<'_, [u8]>, Error> {
+        match self.table_records.iter().find(|record| record.table_tag == tag) {
+            Some(record) => {
+                let start = record.offset as usize;
+                let end = start + record.length as usize;
+                Ok(Cow::Borrowed(&self.data[start..end]))
+            }
+            None => Err(Error::TableNotFound),
+        }
+    }
+
+    fn table_tags(&self) -> Vec<u32> {
+        self.table_records.iter().map(|record| record.table_tag).collect()
+    }
+}
+
+/// WOFF font which decompresses tables lazily, on request.
+/// Useful when only several tables are needed and there is no reason to build the whole SFNT.
+pub struct WoffFont<'a> {
+    buf: &'a [u8],
+    flavor: u32,
+    table_entries: Vec<WoffTableDirectoryEntry>,
+}
+
+impl<'a> WoffFont<'a> {
+    /// Checks WOFF data and reads its table directory
+    pub fn new(buf: &'a [u8]) -> Result<Self, Error> {
+        let error = sanity_check(buf);
+        if error != Error::None {
+            return Err(error);
+        }
+        let woff_header = create_woff_header(buf);
+        let table_entries = read_woff_table_directory(buf, &woff_header)?;
+        Ok(WoffFont {
+            buf,
+            flavor: woff_header.flavor,
+            table_entries,
+        })
+    }
+
+    /// The "sfnt version" of the font packed to WOFF
+    pub fn flavor(&self) -> u32 {
+        self.flavor
+    }
+}
+
+impl FontTables for WoffFont<'_> {
+    fn table_data(&self, tag: u32) -> Result<Cow<'_, [u8]>, Error> {
+        match self.table_entries.iter().find(|entry| entry.tag == tag) {
+            Some(entry) => Ok(Cow::Owned(inflate_table(self.buf, entry, &mut vec![])?)),
+            None => Err(Error::TableNotFound),
+        }
+    }
+
+    fn table_tags(&self) -> Vec<u32> {
+        self.table_entries.iter().map(|entry| entry.tag).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::decode_to_sfnt;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn test_tables() -> Vec<(u32, Vec<u8>)> {
+        vec![
+            (tag(b"head"), head_table(2048, 1)),
+            (tag(b"zzzz"), vec![0u8; 300]),
+            (tag(b"abcd"), vec![1, 2, 3]),
+        ]
+    }
+
+    #[test]
+    fn test_head_from_decoded_and_lazy_font() {
+        let woff = build_woff(0x00010000, &test_tables());
+        let sfnt = decode_to_sfnt(&woff).unwrap();
+        let woff_font = WoffFont::new(&woff).unwrap();
+        let head = sfnt.head().unwrap();
+        debug_assert!(head == woff_font.head().unwrap());
+        debug_assert!(head.units_per_em == 2048 && head.index_to_loc_format == 1);
+        debug_assert!((head.x_min, head.y_min, head.x_max, head.y_max) == (-100, -200, 900, 800));
+        debug_assert!(head.created_time() == UNIX_EPOCH + Duration::from_secs(1_577_836_800));
+        debug_assert!(head.modified_time() == UNIX_EPOCH);
+        debug_assert!(sfnt.table_data(tag(b"zzzz")).unwrap().as_ref() == [0u8; 300]);
+        debug_assert!(woff_font.table_data(tag(b"nope")) == Err(Error::TableNotFound));
+    }
+
+    #[test]
+    fn test_head_invalid_magic_number() {
+        let mut head = head_table(1000, 0);
+        head[12] = 0;
+        debug_assert!(SfntHeaderTable::parse(&head) == Err(Error::InvalidHeadMagicNumber));
+        debug_assert!(SfntHeaderTable::parse(&head[..20]) == Err(Error::InvalidTableData));
+    }
+
+    #[test]
+    fn test_sfnt_directory_out_of_bounds() {
+        let mut sfnt = build_sfnt(0x00010000, &test_tables());
+        debug_assert!(Sfnt::from_vec(sfnt.clone()).unwrap().table_tags().len() == 3);
+        // make length of the last table record larger than the data
+        sfnt[12 + 16 * 2 + 12] = 0xFF;
+        debug_assert!(Sfnt::from_vec(sfnt).err() == Some(Error::InvalidSfntStructure));
+    }
+}
@@ -0,0 +1,130 @@
+//! Detection of font container format by its magic numbers
+
+use crate::font::Sfnt;
+use crate::structures::SfntTableRecord;
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+use std::mem::size_of;
+
+// 'MagicNumber' field of EOT header, stored in little-endian order at `EOT_MAGIC_NUMBER_OFFSET`
+pub(crate) const EOT_MAGIC_NUMBER: u16 = 0x504C;
+pub(crate) const EOT_MAGIC_NUMBER_OFFSET: usize = 34;
+
+/// Font container format detected by `detect_format`
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FontFormat {
+    Unknown,
+    // 'wOFF'
+    Woff,
+    // 'wOF2'
+    Woff2,
+    // SFNT with 0x00010000 version
+    TrueType,
+    // SFNT with 'true' version (Apple TrueType)
+    AppleTrueType,
+    // SFNT with 'OTTO' version (CFF outlines)
+    OpenTypeCff,
+    // 'ttcf'
+    TrueTypeCollection,
+    // Embedded OpenType
+    Eot,
+}
+
+impl FontFormat {
+    /// Checks if data in this format is a bare SFNT which can be used without decoding
+    pub fn is_sfnt(&self) -> bool {
+        matches!(self, FontFormat::TrueType | FontFormat::AppleTrueType | FontFormat::OpenTypeCff)
+    }
+}
+
+/// Detects font container format by the magic number at the beginning of the data
+/// (or by EOT magic number for Embedded OpenType which has no leading signature)
+pub fn detect_format(data: &[u8]) -> FontFormat {
+    if data.len() >= 4 {
+        match u32::from_be_bytes([data[0], data[1], data[2], data[3]]) {
+            0x00010000 => return FontFormat::TrueType,
+            value if value == tag(b"wOFF") => return FontFormat::Woff,
+            value if value == tag(b"wOF2") => return FontFormat::Woff2,
+            value if value == tag(b"true") => return FontFormat::AppleTrueType,
+            value if value == tag(b"OTTO") => return FontFormat::OpenTypeCff,
+            value if value == tag(b"ttcf") => return FontFormat::TrueTypeCollection,
+            _ => {}
+        }
+    }
+    if data.len() >= EOT_MAGIC_NUMBER_OFFSET + 2 {
+        let magic = u16::from_le_bytes([data[EOT_MAGIC_NUMBER_OFFSET], data[EOT_MAGIC_NUMBER_OFFSET + 1]]);
+        if magic == EOT_MAGIC_NUMBER {
+            return FontFormat::Eot;
+        }
+    }
+    FontFormat::Unknown
+}
+
+/// Extracts the font with `index` from TrueType collection as standalone `Sfnt`
+pub(crate) fn collection_font(data: &[u8], index: u32) -> Result<Sfnt, Error> {
+    let mut buffer = ByteReader::from_bytes(data);
+    // skip 'ttcf' tag and version
+    buffer.set_rpos(8);
+    let num_fonts = try_read_u32_be(&mut buffer).map_err(|_| Error::InvalidSfntStructure)?;
+    if index >= num_fonts {
+        return Err(Error::InvalidSfntStructure);
+    }
+    buffer.set_rpos(12 + index as usize * 4);
+    let font_offset = try_read_u32_be(&mut buffer).map_err(|_| Error::InvalidSfntStructure)? as usize;
+
+    buffer.set_rpos(font_offset);
+    let version = try_read_u32_be(&mut buffer).map_err(|_| Error::InvalidSfntStructure)?;
+    let num_tables = try_read_u16_be(&mut buffer).map_err(|_| Error::InvalidSfntStructure)?;
+    buffer.set_rpos(font_offset + 12);
+
+    let mut tables: Vec<(u32, Vec<u8>)> = Vec::with_capacity(num_tables as usize);
+    for _ in 0..num_tables {
+        let mut record = [0u32; size_of::<SfntTableRecord>() / 4];
+        for value in record.iter_mut() {
+            *value = try_read_u32_be(&mut buffer).map_err(|_| Error::InvalidSfntStructure)?;
+        }
+        // table offsets in collection are counted from the beginning of the collection
+        let table = try_slice(data, record[2] as usize, record[3] as usize).map_err(|_| Error::InvalidSfntStructure)?;
+        tables.push((record[0], table.to_vec()));
+    }
+    Sfnt::from_tables(version, tables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::FontTables;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_detect_format() {
+        let tables = [(tag(b"head"), head_table(1000, 0))];
+        debug_assert!(detect_format(&build_woff(0x00010000, &tables)) == FontFormat::Woff);
+        debug_assert!(detect_format(&build_sfnt(0x00010000, &tables)) == FontFormat::TrueType);
+        debug_assert!(detect_format(&build_sfnt(tag(b"OTTO"), &tables)) == FontFormat::OpenTypeCff);
+        debug_assert!(detect_format(&build_sfnt(tag(b"true"), &tables)) == FontFormat::AppleTrueType);
+        debug_assert!(detect_format(b"wOF2\0\x01\0\0") == FontFormat::Woff2);
+        debug_assert!(detect_format(b"ttcf\0\x01\0\0") == FontFormat::TrueTypeCollection);
+
+        let mut eot = vec![0u8; 82];
+        eot[EOT_MAGIC_NUMBER_OFFSET..EOT_MAGIC_NUMBER_OFFSET + 2].copy_from_slice(&EOT_MAGIC_NUMBER.to_le_bytes());
+        debug_assert!(detect_format(&eot) == FontFormat::Eot);
+
+        debug_assert!(detect_format(b"") == FontFormat::Unknown);
+        debug_assert!(detect_format(b"not a font at all") == FontFormat::Unknown);
+    }
+
+    #[test]
+    fn test_collection_font() {
+        let first = [(tag(b"head"), head_table(1000, 0)), (tag(b"maxp"), maxp_table(3, 0))];
+        let second = [(tag(b"head"), head_table(2048, 1)), (tag(b"maxp"), maxp_table(5, 0))];
+        let collection = build_collection(&[&first, &second]);
+
+        let font = collection_font(&collection, 1).unwrap();
+        debug_assert!(font.head().unwrap().units_per_em == 2048);
+        debug_assert!(font.maxp().unwrap().num_glyphs == 5);
+        debug_assert!(collection_font(&collection, 2).is_err());
+    }
+}
@@ -0,0 +1,563 @@
+//! Static instances of variable fonts with TrueType outlines.
+//!
+//! Glyph outlines are varied with 'gvar' deltas (deltas of untouched points are inferred with IUP),
+//! advance widths with 'HVAR' or, if there is no such table, with 'gvar' phantom points,
+//! font-wide metrics with 'MVAR' and CVT values with 'cvar'. Variation tables are dropped,
+//! all other tables are copied as is.
+
+use crate::font::{FontTables, Sfnt};
+use crate::outline::GlyphPoint;
+use crate::structures::HEAD_INDEX_TO_LOC_FORMAT_OFFSET;
+use crate::tables::glyf::{build_glyf_and_loca, Glyph, TrueTypeGlyphs, ARGS_ARE_XY_VALUES};
+use crate::tables::gvar::{GvarTable, PHANTOM_POINT_COUNT};
+use crate::tables::hmtx::HmtxTable;
+use crate::tables::hvar::HvarTable;
+use crate::tables::loca::LocaTable;
+use crate::tables::mvar::MvarTable;
+use crate::tables::tuple_variations::parse_tuple_variations;
+use crate::utils::*;
+use crate::variation::AxisInfo;
+use crate::Error;
+use std::borrow::Cow;
+
+/// Version of SFNT with TrueType outlines
+const SFNT_VERSION_TRUETYPE: u32 = 0x00010000;
+
+/// Tables which describe variations and are dropped from static instance
+const VARIATION_TABLES: [u32; 7] = [
+    tag(b"fvar"), tag(b"avar"), tag(b"gvar"), tag(b"cvar"), tag(b"HVAR"), tag(b"VVAR"), tag(b"MVAR"),
+];
+
+/// Tables which are rebuilt by instancing
+const REBUILT_TABLES: [u32; 5] = [tag(b"glyf"), tag(b"loca"), tag(b"hmtx"), tag(b"hhea"), tag(b"head")];
+
+// Offsets of fields updated in 'head', 'hhea' and 'OS/2' tables
+const HEAD_X_MIN_OFFSET: usize = 36;
+const HHEA_ADVANCE_WIDTH_MAX_OFFSET: usize = 10;
+const HHEA_NUMBER_OF_H_METRICS_OFFSET: usize = 34;
+const OS2_WEIGHT_CLASS_OFFSET: usize = 4;
+const OS2_WIDTH_CLASS_OFFSET: usize = 6;
+
+/// 'MVAR' value tags with the table and the offset of 16-bit field they vary.
+/// The last value is set for unsigned fields
+const MVAR_VALUES: [(u32, u32, usize, bool); 28] = [
+    (tag(b"hasc"), tag(b"OS/2"), 68, false),
+    (tag(b"hdsc"), tag(b"OS/2"), 70, false),
+    (tag(b"hlgp"), tag(b"OS/2"), 72, false),
+    (tag(b"hcla"), tag(b"OS/2"), 74, true),
+    (tag(b"hcld"), tag(b"OS/2"), 76, true),
+    (tag(b"xhgt"), tag(b"OS/2"), 86, false),
+    (tag(b"cpht"), tag(b"OS/2"), 88, false),
+    (tag(b"sbxs"), tag(b"OS/2"), 10, false),
+    (tag(b"sbys"), tag(b"OS/2"), 12, false),
+    (tag(b"sbxo"), tag(b"OS/2"), 14, false),
+    (tag(b"sbyo"), tag(b"OS/2"), 16, false),
+    (tag(b"spxs"), tag(b"OS/2"), 18, false),
+    (tag(b"spys"), tag(b"OS/2"), 20, false),
+    (tag(b"spxo"), tag(b"OS/2"), 22, false),
+    (tag(b"spyo"), tag(b"OS/2"), 24, false),
+    (tag(b"strs"), tag(b"OS/2"), 26, false),
+    (tag(b"stro"), tag(b"OS/2"), 28, false),
+    (tag(b"hcrs"), tag(b"hhea"), 18, false),
+    (tag(b"hcrn"), tag(b"hhea"), 20, false),
+    (tag(b"hcof"), tag(b"hhea"), 22, false),
+    (tag(b"vasc"), tag(b"vhea"), 4, false),
+    (tag(b"vdsc"), tag(b"vhea"), 6, false),
+    (tag(b"vlgp"), tag(b"vhea"), 8, false),
+    (tag(b"vcrs"), tag(b"vhea"), 18, false),
+    (tag(b"vcrn"), tag(b"vhea"), 20, false),
+    (tag(b"vcof"), tag(b"vhea"), 22, false),
+    (tag(b"undo"), tag(b"post"), 8, false),
+    (tag(b"unds"), tag(b"post"), 10, false),
+];
+
+/// usWidthClass values and their 'wdth' axis percentages
+const WIDTH_CLASSES: [(u16, f32); 9] =
+    [(1, 50.0), (2, 62.5), (3, 75.0), (4, 87.5), (5, 100.0), (6, 112.5), (7, 125.0), (8, 150.0), (9, 200.0)];
+
+/// Glyph varied by 'gvar' before its bounds and metrics are finalized
+struct VariedGlyph {
+    glyph: Glyph,
+    advance: u16,
+    // left side bearing from 'HVAR', otherwise it's calculated from the bounds and the left phantom point
+    left_side_bearing: Option<i16>,
+    left_phantom_x: f32,
+}
+
+/// Creates static instance of variable font with TrueType outlines at user `coordinates` (axis tag, value).
+/// Axes without coordinates use their default values, values outside of the axis range are clamped.
+/// Returns `TableNotFound` error if the font has no 'fvar' table and `UnsupportedFontFormat` error
+/// if it has no 'glyf' table (e.g. CFF2 fonts)
+pub fn instantiate_font<F: FontTables + ?Sized>(font: &F, coordinates: &[(u32, f32)]) -> Result<Sfnt, Error> {
+    let variation_info = font.variation_info(&[])?;
+    if !font.has_table(tag(b"glyf")) {
+        return Err(Error::UnsupportedFontFormat);
+    }
+    let normalized = variation_info.normalize(coordinates);
+
+    let glyphs = font.truetype_glyphs()?;
+    let hmtx = font.hmtx()?;
+    let gvar = optional_table(font, tag(b"gvar"), GvarTable::parse)?;
+    let hvar = optional_table(font, tag(b"HVAR"), HvarTable::parse)?;
+
+    let mut varied: Vec<VariedGlyph> = Vec::with_capacity(glyphs.num_glyphs() as usize);
+    for glyph_id in 0..glyphs.num_glyphs() {
+        varied.push(vary_glyph(&glyphs, &hmtx, gvar.as_ref(), hvar.as_ref(), glyph_id, &normalized)?);
+    }
+    let (glyf, loca, index_to_loc_format) = finalize_glyphs(&mut varied)?;
+
+    let new_hmtx = HmtxTable {
+        advances: varied.iter().map(|glyph| glyph.advance).collect(),
+        left_side_bearings: varied.iter().map(|glyph| glyph.left_side_bearing.unwrap_or_default()).collect(),
+    };
+    let (hmtx_data, number_of_h_metrics) = new_hmtx.transform_to_u8_vec();
+
+    font.head()?;
+    let mut head = font.table_data(tag(b"head"))?.into_owned();
+    head[HEAD_INDEX_TO_LOC_FORMAT_OFFSET..HEAD_INDEX_TO_LOC_FORMAT_OFFSET + 2]
+        .copy_from_slice(&index_to_loc_format.to_be_bytes());
+    if let Some(bounds) = font_bounds(&varied) {
+        for (index, value) in bounds.iter().enumerate() {
+            write_i16(&mut head, HEAD_X_MIN_OFFSET + index * 2, *value);
+        }
+    }
+
+    font.hhea()?;
+    let mut hhea = font.table_data(tag(b"hhea"))?.into_owned();
+    update_hhea(&mut hhea, &varied, number_of_h_metrics);
+
+    let mut tables: Vec<(u32, Vec<u8>)> = vec![
+        (tag(b"glyf"), glyf),
+        (tag(b"loca"), loca),
+        (tag(b"hmtx"), hmtx_data),
+        (tag(b"hhea"), hhea),
+        (tag(b"head"), head),
+    ];
+    for table_tag in font.table_tags() {
+        if !VARIATION_TABLES.contains(&table_tag) && !REBUILT_TABLES.contains(&table_tag) {
+            tables.push((table_tag, font.table_data(table_tag)?.into_owned()));
+        }
+    }
+
+    if let Some(mvar) = optional_table(font, tag(b"MVAR"), MvarTable::parse)? {
+        apply_mvar(&mvar, &mut tables, &normalized);
+    }
+    if font.has_table(tag(b"cvar")) {
+        if let Some((_, cvt)) = tables.iter_mut().find(|(table_tag, _)| *table_tag == tag(b"cvt ")) {
+            apply_cvar(&font.table_data(tag(b"cvar"))?, variation_info.axes.len() as u16, cvt, &normalized)?;
+        }
+    }
+    if let Some((_, os2)) = tables.iter_mut().find(|(table_tag, _)| *table_tag == tag(b"OS/2")) {
+        update_os2_classes(os2, &variation_info.axes, coordinates);
+    }
+
+    Sfnt::from_tables(SFNT_VERSION_TRUETYPE, tables)
+}
+
+/// Parses optional table, `None` if the font doesn't have it
+fn optional_table<F, T>(font: &F, table_tag: u32, parse: fn(&[u8]) -> Result<T, Error>) -> Result<Option<T>, Error>
+where
+    F: FontTables + ?Sized,
+{
+    match font.table_data(table_tag) {
+        Ok(data) => parse(&data).map(Some),
+        Err(Error::TableNotFound) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Applies 'gvar' deltas to the glyph points (composite glyph component offsets) and its phantom points
+fn vary_glyph(
+    glyphs: &TrueTypeGlyphs,
+    hmtx: &HmtxTable,
+    gvar: Option<&GvarTable>,
+    hvar: Option<&HvarTable>,
+    glyph_id: u16,
+    coordinates: &[f32],
+) -> Result<VariedGlyph, Error> {
+    let mut glyph = glyphs.glyph(glyph_id)?;
+    let advance = hmtx.advance(glyph_id).unwrap_or_default() as f32;
+    let left_side_bearing = hmtx.left_side_bearing(glyph_id).unwrap_or_default();
+
+    let mut points: Vec<GlyphPoint> = match &glyph {
+        Glyph::Empty => vec![],
+        Glyph::Simple(simple) => simple.points.clone(),
+        Glyph::Composite(composite) => composite.components.iter().map(|component| GlyphPoint {
+            x: component.arg1 as f32,
+            y: component.arg2 as f32,
+            on_curve: true,
+        }).collect(),
+    };
+    let outline_point_count = points.len();
+    // left side bearing, advance, top and bottom phantom points
+    let left_phantom_x = glyph.bounds().map(|bounds| bounds[0]).unwrap_or_default() as f32 - left_side_bearing as f32;
+    points.extend_from_slice(&[
+        GlyphPoint { x: left_phantom_x, y: 0.0, on_curve: true },
+        GlyphPoint { x: left_phantom_x + advance, y: 0.0, on_curve: true },
+        GlyphPoint { x: 0.0, y: 0.0, on_curve: true },
+        GlyphPoint { x: 0.0, y: 0.0, on_curve: true },
+    ]);
+
+    let end_pts_of_contours = match &glyph {
+        Glyph::Simple(simple) => simple.end_pts_of_contours.clone(),
+        _ => vec![],
+    };
+    let deltas = match gvar {
+        Some(gvar) => glyph_deltas(gvar, glyph_id, &points, &end_pts_of_contours, coordinates)?,
+        None => vec![(0.0, 0.0); points.len()],
+    };
+    let moved: Vec<(f32, f32)> = points
+        .iter()
+        .zip(deltas.iter())
+        .map(|(point, (dx, dy))| ((point.x + dx).round(), (point.y + dy).round()))
+        .collect();
+
+    match &mut glyph {
+        Glyph::Empty => {}
+        Glyph::Simple(simple) => {
+            for (point, &(x, y)) in simple.points.iter_mut().zip(moved.iter()) {
+                point.x = x;
+                point.y = y;
+            }
+            simple.bounds = points_bounds(&simple.points).unwrap_or_default();
+        }
+        Glyph::Composite(composite) => {
+            for (component, &(x, y)) in composite.components.iter_mut().zip(moved.iter()) {
+                // point numbers of anchored components can't be varied
+                if component.flags & ARGS_ARE_XY_VALUES != 0 {
+                    component.arg1 = x as i32;
+                    component.arg2 = y as i32;
+                }
+            }
+        }
+    }
+
+    let (left_phantom_x, right_phantom_x) = (moved[outline_point_count].0, moved[outline_point_count + 1].0);
+    let advance = match hvar {
+        Some(hvar) => advance + hvar.advance_delta(glyph_id, coordinates),
+        None => right_phantom_x - left_phantom_x,
+    };
+    let left_side_bearing = hvar
+        .and_then(|hvar| hvar.left_side_bearing_delta(glyph_id, coordinates))
+        .map(|delta| to_i16(left_side_bearing as f32 + delta));
+    Ok(VariedGlyph {
+        glyph,
+        advance: advance.round().clamp(0.0, u16::MAX as f32) as u16,
+        left_side_bearing,
+        left_phantom_x,
+    })
+}
+
+/// Sums scaled deltas of all glyph tuple variations. `points` include phantom points,
+/// deltas of untouched points of simple glyph contours are inferred
+fn glyph_deltas(
+    gvar: &GvarTable,
+    glyph_id: u16,
+    points: &[GlyphPoint],
+    end_pts_of_contours: &[u16],
+    coordinates: &[f32],
+) -> Result<Vec<(f32, f32)>, Error> {
+    let mut deltas: Vec<(f32, f32)> = vec![(0.0, 0.0); points.len()];
+    for variation in gvar.glyph_variations(glyph_id, points.len())? {
+        let scalar = variation.scalar(coordinates);
+        if scalar == 0.0 {
+            continue;
+        }
+        let mut tuple_deltas: Vec<Option<(f32, f32)>> = vec![None; points.len()];
+        let explicit_deltas = variation.x_deltas.iter().zip(variation.y_deltas.iter());
+        match &variation.points {
+            Some(point_numbers) => {
+                for (&point, (&dx, &dy)) in point_numbers.iter().zip(explicit_deltas) {
+                    if let Some(delta) = tuple_deltas.get_mut(point as usize) {
+                        *delta = Some((dx as f32, dy as f32));
+                    }
+                }
+                infer_deltas(&points[..points.len() - PHANTOM_POINT_COUNT], end_pts_of_contours, &mut tuple_deltas);
+            }
+            None => {
+                for (delta, (&dx, &dy)) in tuple_deltas.iter_mut().zip(explicit_deltas) {
+                    *delta = Some((dx as f32, dy as f32));
+                }
+            }
+        }
+        for (delta, tuple_delta) in deltas.iter_mut().zip(tuple_deltas.iter()) {
+            if let Some((dx, dy)) = tuple_delta {
+                delta.0 += dx * scalar;
+                delta.1 += dy * scalar;
+            }
+        }
+    }
+    Ok(deltas)
+}
+
+/// Infers deltas of untouched points of every contour by interpolation of deltas of the nearest
+/// touched points (IUP). Contours without touched points stay untouched
+fn infer_deltas(points: &[GlyphPoint], end_pts_of_contours: &[u16], deltas: &mut [Option<(f32, f32)>]) {
+    let mut start = 0usize;
+    for &end in end_pts_of_contours {
+        let end = (end as usize + 1).min(points.len());
+        let touched: Vec<usize> = (start..end).filter(|&index| deltas[index].is_some()).collect();
+        if touched.is_empty() {
+            start = end;
+            continue;
+        }
+        for index in start..end {
+            if deltas[index].is_some() {
+                continue;
+            }
+            // the nearest touched points before and after the point, the contour is closed
+            let previous = touched.iter().rev().find(|&&touched| touched < index).or(touched.last()).copied().unwrap();
+            let next = touched.iter().find(|&&touched| touched > index).or(touched.first()).copied().unwrap();
+            let (previous_delta, next_delta) = (deltas[previous].unwrap(), deltas[next].unwrap());
+            deltas[index] = Some((
+                interpolate(points[index].x, points[previous].x, points[next].x, previous_delta.0, next_delta.0),
+                interpolate(points[index].y, points[previous].y, points[next].y, previous_delta.1, next_delta.1),
+            ));
+        }
+        start = end;
+    }
+}
+
+/// Interpolates delta of coordinate `value` between reference coordinates with their deltas.
+/// Coordinates outside of the reference range take delta of the nearest reference
+fn interpolate(value: f32, first: f32, second: f32, first_delta: f32, second_delta: f32) -> f32 {
+    if first == second {
+        return if first_delta == second_delta { first_delta } else { 0.0 };
+    }
+    let (low, high, low_delta, high_delta) = if first < second {
+        (first, second, first_delta, second_delta)
+    } else {
+        (second, first, second_delta, first_delta)
+    };
+    if value <= low {
+        low_delta
+    } else if value >= high {
+        high_delta
+    } else {
+        low_delta + (value - low) * (high_delta - low_delta) / (high - low)
+    }
+}
+
+/// Builds 'glyf' and 'loca' tables of varied glyphs. Bounds of composite glyphs are calculated
+/// from resolved outlines, left side bearings which aren't set by 'HVAR' are calculated from bounds
+fn finalize_glyphs(varied: &mut [VariedGlyph]) -> Result<(Vec<u8>, Vec<u8>, i16), Error> {
+    let glyph_data: Vec<Vec<u8>> = varied.iter().map(|glyph| glyph.glyph.transform_to_u8_vec()).collect();
+    let (mut glyf, loca, index_to_loc_format) = build_glyf_and_loca(&glyph_data);
+
+    let loca_table = LocaTable::parse(&loca, index_to_loc_format, varied.len() as u16)?;
+    let composite_bounds: Vec<(usize, [i16; 4])> = {
+        let glyphs = TrueTypeGlyphs::new(Cow::Borrowed(&glyf), loca_table.clone());
+        let mut composite_bounds = vec![];
+        for (glyph_id, glyph) in varied.iter().enumerate() {
+            if let Glyph::Composite(_) = glyph.glyph {
+                let bounds = glyphs.outline(glyph_id as u16)?.bounds().unwrap_or_default();
+                composite_bounds.push((glyph_id, [to_i16(bounds.0), to_i16(bounds.1), to_i16(bounds.2), to_i16(bounds.3)]));
+            }
+        }
+        composite_bounds
+    };
+    for (glyph_id, bounds) in composite_bounds {
+        // bounds follow numberOfContours in glyph header
+        let offset = loca_table.offsets[glyph_id] as usize + 2;
+        for (index, value) in bounds.iter().enumerate() {
+            write_i16(&mut glyf, offset + index * 2, *value);
+        }
+        if let Glyph::Composite(composite) = &mut varied[glyph_id].glyph {
+            composite.bounds = bounds;
+        }
+    }
+
+    for glyph in varied.iter_mut() {
+        if glyph.left_side_bearing.is_none() {
+            let x_min = glyph.glyph.bounds().map(|bounds| bounds[0]).unwrap_or_default();
+            glyph.left_side_bearing = Some(to_i16(x_min as f32 - glyph.left_phantom_x));
+        }
+    }
+    Ok((glyf, loca, index_to_loc_format))
+}
+
+/// Bounds of points rounded to integers
+fn points_bounds(points: &[GlyphPoint]) -> Option<[i16; 4]> {
+    let first = points.first()?;
+    let bounds = points.iter().fold((first.x, first.y, first.x, first.y), |bounds, point| {
+        (bounds.0.min(point.x), bounds.1.min(point.y), bounds.2.max(point.x), bounds.3.max(point.y))
+    });
+    Some([to_i16(bounds.0), to_i16(bounds.1), to_i16(bounds.2), to_i16(bounds.3)])
+}
+
+/// Union of bounds of all glyphs with outlines
+fn font_bounds(varied: &[VariedGlyph]) -> Option<[i16; 4]> {
+    varied.iter().filter_map(|glyph| glyph.glyph.bounds()).reduce(|union, bounds| {
+        [union[0].min(bounds[0]), union[1].min(bounds[1]), union[2].max(bounds[2]), union[3].max(bounds[3])]
+    })
+}
+
+/// Updates horizontal metrics maxima and numberOfHMetrics of 'hhea' table
+fn update_hhea(hhea: &mut [u8], varied: &[VariedGlyph], number_of_h_metrics: u16) {
+    let advance_width_max = varied.iter().map(|glyph| glyph.advance).max().unwrap_or_default();
+    let mut min_left_side_bearing = i16::MAX;
+    let mut min_right_side_bearing = i16::MAX;
+    let mut x_max_extent = i16::MIN;
+    for glyph in varied {
+        if let (Some(bounds), Some(left_side_bearing)) = (glyph.glyph.bounds(), glyph.left_side_bearing) {
+            let extent = left_side_bearing as i32 + bounds[2] as i32 - bounds[0] as i32;
+            min_left_side_bearing = min_left_side_bearing.min(left_side_bearing);
+            min_right_side_bearing = min_right_side_bearing.min(to_i16((glyph.advance as i32 - extent) as f32));
+            x_max_extent = x_max_extent.max(to_i16(extent as f32));
+        }
+    }
+    hhea[HHEA_ADVANCE_WIDTH_MAX_OFFSET..HHEA_ADVANCE_WIDTH_MAX_OFFSET + 2].copy_from_slice(&advance_width_max.to_be_bytes());
+    if x_max_extent != i16::MIN {
+        for (index, value) in [min_left_side_bearing, min_right_side_bearing, x_max_extent].iter().enumerate() {
+            write_i16(hhea, HHEA_ADVANCE_WIDTH_MAX_OFFSET + 2 + index * 2, *value);
+        }
+    }
+    hhea[HHEA_NUMBER_OF_H_METRICS_OFFSET..HHEA_NUMBER_OF_H_METRICS_OFFSET + 2]
+        .copy_from_slice(&number_of_h_metrics.to_be_bytes());
+}
+
+/// Applies 'MVAR' deltas to the fields of 'OS/2', 'hhea', 'vhea' and 'post' tables
+fn apply_mvar(mvar: &MvarTable, tables: &mut [(u32, Vec<u8>)], coordinates: &[f32]) {
+    for &(value_tag, table_tag, offset, unsigned) in MVAR_VALUES.iter() {
+        let delta = match mvar.delta(value_tag, coordinates) {
+            Some(delta) => delta,
+            None => continue,
+        };
+        let data = match tables.iter_mut().find(|(tag, data)| *tag == table_tag && data.len() >= offset + 2) {
+            Some((_, data)) => data,
+            None => continue,
+        };
+        let bytes = [data[offset], data[offset + 1]];
+        if unsigned {
+            let value = (u16::from_be_bytes(bytes) as f32 + delta).round().clamp(0.0, u16::MAX as f32) as u16;
+            data[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+        } else {
+            write_i16(data, offset, to_i16(i16::from_be_bytes(bytes) as f32 + delta));
+        }
+    }
+}
+
+/// Applies 'cvar' deltas to CVT values
+fn apply_cvar(cvar: &[u8], axis_count: u16, cvt: &mut [u8], coordinates: &[f32]) -> Result<(), Error> {
+    let value_count = cvt.len() / 2;
+    // tuple variations follow the version of the table
+    let variations = parse_tuple_variations(cvar, 4, axis_count, &[], value_count, false)?;
+    let mut deltas: Vec<f32> = vec![0.0; value_count];
+    for variation in variations {
+        let scalar = variation.scalar(coordinates);
+        let indices: Vec<usize> = match &variation.points {
+            Some(points) => points.iter().map(|&point| point as usize).collect(),
+            None => (0..value_count).collect(),
+        };
+        for (index, delta) in indices.into_iter().zip(variation.x_deltas.iter()) {
+            if let Some(value) = deltas.get_mut(index) {
+                *value += *delta as f32 * scalar;
+            }
+        }
+    }
+    for (index, delta) in deltas.iter().enumerate() {
+        let value = i16::from_be_bytes([cvt[index * 2], cvt[index * 2 + 1]]);
+        write_i16(cvt, index * 2, to_i16(value as f32 + delta));
+    }
+    Ok(())
+}
+
+/// Sets usWeightClass and usWidthClass of 'OS/2' table from 'wght' and 'wdth' axes coordinates
+fn update_os2_classes(os2: &mut [u8], axes: &[AxisInfo], coordinates: &[(u32, f32)]) {
+    if os2.len() < OS2_WIDTH_CLASS_OFFSET + 2 {
+        return;
+    }
+    let axis_value = |axis_tag: u32| -> Option<f32> {
+        let axis = axes.iter().find(|axis| axis.tag == axis_tag)?;
+        let value = coordinates.iter().find(|(tag, _)| *tag == axis_tag).map(|(_, value)| *value);
+        Some(value.unwrap_or(axis.default_value).clamp(axis.min_value, axis.max_value))
+    };
+    if let Some(weight) = axis_value(tag(b"wght")) {
+        let weight_class = weight.round().clamp(1.0, 1000.0) as u16;
+        os2[OS2_WEIGHT_CLASS_OFFSET..OS2_WEIGHT_CLASS_OFFSET + 2].copy_from_slice(&weight_class.to_be_bytes());
+    }
+    if let Some(width) = axis_value(tag(b"wdth")) {
+        let (width_class, _) = WIDTH_CLASSES
+            .iter()
+            .min_by(|first, second| (first.1 - width).abs().total_cmp(&(second.1 - width).abs()))
+            .unwrap();
+        os2[OS2_WIDTH_CLASS_OFFSET..OS2_WIDTH_CLASS_OFFSET + 2].copy_from_slice(&width_class.to_be_bytes());
+    }
+}
+
+/// Rounds value to i16 with saturation
+fn to_i16(value: f32) -> i16 {
+    value.round().clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+fn write_i16(data: &mut [u8], offset: usize, value: i16) {
+    data[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn variable_font_tables() -> Vec<(u32, Vec<u8>)> {
+        let mut tables = truetype_font_tables();
+        tables.push((tag(b"fvar"), fvar_table(&[(tag(b"wght"), 100.0, 400.0, 900.0, 256)], &[])));
+        let mut glyph_variations = vec![vec![]; 5];
+        // the right bottom corner is inferred, the advance width follows the right phantom point
+        glyph_variations[0] = vec![(1.0, Some(vec![0, 2, 5]), vec![(0, 0), (100, 0), (100, 0)])];
+        glyph_variations[4] = vec![(1.0, None, vec![(50, 0), (0, 0), (0, 0), (0, 0), (0, 0), (0, 0)])];
+        tables.push((tag(b"gvar"), gvar_table(&glyph_variations)));
+        tables.push((tag(b"cvt "), [100i16, 200].iter().flat_map(|value| value.to_be_bytes()).collect()));
+        tables.push((tag(b"cvar"), cvar_table(&[(1.0, vec![10, -20])])));
+        tables
+    }
+
+    fn glyph_points(font: &Sfnt, glyph_id: u16) -> Vec<(f32, f32)> {
+        match font.truetype_glyphs().unwrap().glyph(glyph_id).unwrap() {
+            Glyph::Simple(simple) => simple.points.iter().map(|point| (point.x, point.y)).collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_instantiate_font() {
+        let font = Sfnt::from_tables(SFNT_VERSION_TRUETYPE, variable_font_tables()).unwrap();
+
+        let instance = instantiate_font(&font, &[(tag(b"wght"), 900.0)]).unwrap();
+        debug_assert!(glyph_points(&instance, 0) == vec![(0.0, 0.0), (0.0, 700.0), (600.0, 700.0), (600.0, 0.0)]);
+        debug_assert!(instance.hmtx().unwrap().advance(0) == Some(600));
+        debug_assert!(instance.truetype_glyphs().unwrap().glyph(4).unwrap().bounds() == Some([150, 0, 800, 500]));
+        debug_assert!(instance.head().unwrap().x_min == 0 && instance.head().unwrap().x_max == 800);
+        debug_assert!(instance.os2().unwrap().us_weight_class == 900);
+        debug_assert!(instance.hhea().unwrap().advance_width_max == 800);
+        for table_tag in [tag(b"fvar"), tag(b"gvar"), tag(b"cvar")] {
+            debug_assert!(!instance.has_table(table_tag));
+        }
+
+        let instance = instantiate_font(&font, &[(tag(b"wght"), 650.0)]).unwrap();
+        debug_assert!(glyph_points(&instance, 0)[2] == (550.0, 700.0));
+        debug_assert!(instance.hmtx().unwrap().advance(0) == Some(550));
+        debug_assert!(*instance.table_data(tag(b"cvt ")).unwrap() == [0, 105, 0, 190]);
+
+        let instance = instantiate_font(&font, &[]).unwrap();
+        debug_assert!(glyph_points(&instance, 0) == glyph_points(&font, 0));
+        debug_assert!(instance.os2().unwrap().us_weight_class == 400);
+
+        let static_font = Sfnt::from_tables(SFNT_VERSION_TRUETYPE, truetype_font_tables()).unwrap();
+        debug_assert!(instantiate_font(&static_font, &[]).err() == Some(Error::TableNotFound));
+    }
+
+    #[test]
+    fn test_instantiate_font_with_hvar_and_mvar() {
+        let mut tables = variable_font_tables();
+        tables.push((tag(b"HVAR"), hvar_table(&[-100, 0, 0, 0, 40])));
+        tables.push((tag(b"MVAR"), mvar_table(&[(tag(b"undo"), -50)])));
+        let font = Sfnt::from_tables(SFNT_VERSION_TRUETYPE, tables).unwrap();
+
+        let instance = instantiate_font(&font, &[(tag(b"wght"), 650.0)]).unwrap();
+        debug_assert!(instance.hmtx().unwrap().advance(0) == Some(450));
+        debug_assert!(instance.hmtx().unwrap().advance(4) == Some(820));
+        debug_assert!(instance.post().unwrap().underline_position == -125);
+        debug_assert!(!instance.has_table(tag(b"HVAR")) && !instance.has_table(tag(b"MVAR")));
+    }
+}
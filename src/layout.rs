@@ -0,0 +1,147 @@
+//! Catalogue of scripts, language systems and features supported by 'GSUB' and 'GPOS' tables
+
+use crate::font::FontTables;
+use crate::tables::layout::LayoutTable;
+use crate::Error;
+
+/// Features of a language system. Feature tags are unique and keep the order of the table,
+/// the required feature goes first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LanguageFeatures {
+    // `None` for the default language system of the script
+    pub language_tag: Option<u32>,
+    pub gsub_features: Vec<u32>,
+    pub gpos_features: Vec<u32>,
+}
+
+/// Language systems of a script from both tables, the default language system goes first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptFeatures {
+    pub script_tag: u32,
+    pub languages: Vec<LanguageFeatures>,
+}
+
+/// Scripts, language systems and features of the font
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LayoutCatalogue {
+    // Scripts in the order of 'GSUB' table followed by scripts only 'GPOS' table has
+    pub scripts: Vec<ScriptFeatures>,
+    // All feature tags of both tables sorted and unique
+    pub feature_tags: Vec<u32>,
+    // Set if any of the tables has feature variations
+    pub has_feature_variations: bool,
+}
+
+impl LayoutCatalogue {
+    /// Collects catalogue from 'GSUB' and 'GPOS' tables, both of them are optional
+    pub(crate) fn from_font<F: FontTables + ?Sized>(font: &F) -> Result<Self, Error> {
+        let mut catalogue = LayoutCatalogue::default();
+        for (table, is_gsub) in [(optional(font.gsub())?, true), (optional(font.gpos())?, false)] {
+            if let Some(table) = table {
+                catalogue.add_table(&table, is_gsub);
+            }
+        }
+        catalogue.feature_tags.sort_unstable();
+        catalogue.feature_tags.dedup();
+        Ok(catalogue)
+    }
+
+    /// Language systems of the script, `None` if no table has the script
+    pub fn script(&self, script_tag: u32) -> Option<&ScriptFeatures> {
+        self.scripts.iter().find(|script| script.script_tag == script_tag)
+    }
+
+    fn add_table(&mut self, table: &LayoutTable, is_gsub: bool) {
+        self.has_feature_variations |= !table.feature_variations.is_empty();
+        self.feature_tags.extend(table.features.iter().map(|feature| feature.tag));
+        for script in table.scripts.iter() {
+            let position = match self.scripts.iter().position(|known| known.script_tag == script.tag) {
+                Some(position) => position,
+                None => {
+                    self.scripts.push(ScriptFeatures { script_tag: script.tag, languages: vec![] });
+                    self.scripts.len() - 1
+                }
+            };
+            let languages = &mut self.scripts[position].languages;
+            for language in script.default_language.iter().chain(script.languages.iter()) {
+                let mut feature_tags: Vec<u32> = vec![];
+                for (_, feature) in table.language_features(language) {
+                    if !feature_tags.contains(&feature.tag) {
+                        feature_tags.push(feature.tag);
+                    }
+                }
+                let entry = match languages.iter_mut().position(|known| known.language_tag == language.tag) {
+                    Some(index) => &mut languages[index],
+                    None => {
+                        let entry = LanguageFeatures { language_tag: language.tag, gsub_features: vec![], gpos_features: vec![] };
+                        // the default language system goes first
+                        if language.tag.is_none() {
+                            languages.insert(0, entry);
+                            &mut languages[0]
+                        } else {
+                            languages.push(entry);
+                            languages.last_mut().unwrap()
+                        }
+                    }
+                };
+                if is_gsub {
+                    entry.gsub_features = feature_tags;
+                } else {
+                    entry.gpos_features = feature_tags;
+                }
+            }
+        }
+    }
+}
+
+/// `None` if the table is missing
+pub(crate) fn optional<T>(table: Result<T, Error>) -> Result<Option<T>, Error> {
+    match table {
+        Ok(table) => Ok(Some(table)),
+        Err(Error::TableNotFound) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Sfnt;
+    use crate::test_utils::*;
+    use crate::utils::*;
+
+    #[test]
+    fn test_layout_catalogue() {
+        let mut tables = truetype_font_tables();
+        let font = Sfnt::from_tables(0x00010000, tables.clone()).unwrap();
+        debug_assert!(font.layout_catalogue().unwrap() == LayoutCatalogue::default());
+
+        tables.push((tag(b"GSUB"), layout_table(
+            &[(tag(b"latn"), vec![(Some(tag(b"NLD ")), None, vec![1]), (None, None, vec![0, 1])])],
+            &[(tag(b"liga"), vec![0]), (tag(b"smcp"), vec![0])],
+            &[(1, vec![])],
+            &[],
+        )));
+        tables.push((tag(b"GPOS"), layout_table(
+            &[(tag(b"latn"), vec![(None, Some(0), vec![1, 0])]), (tag(b"grek"), vec![(None, None, vec![1])])],
+            &[(tag(b"mark"), vec![0]), (tag(b"kern"), vec![0])],
+            &[(2, vec![])],
+            &[(vec![], vec![])],
+        )));
+        let font = Sfnt::from_tables(0x00010000, tables).unwrap();
+        let catalogue = font.layout_catalogue().unwrap();
+        debug_assert!(catalogue.has_feature_variations);
+        debug_assert!(catalogue.feature_tags == vec![tag(b"kern"), tag(b"liga"), tag(b"mark"), tag(b"smcp")]);
+        debug_assert!(catalogue.scripts.iter().map(|script| script.script_tag).collect::<Vec<u32>>() == vec![tag(b"latn"), tag(b"grek")]);
+        let latin = catalogue.script(tag(b"latn")).unwrap();
+        debug_assert!(latin.languages == vec![
+            LanguageFeatures {
+                language_tag: None,
+                gsub_features: vec![tag(b"liga"), tag(b"smcp")],
+                gpos_features: vec![tag(b"mark"), tag(b"kern")],
+            },
+            LanguageFeatures { language_tag: Some(tag(b"NLD ")), gsub_features: vec![tag(b"smcp")], gpos_features: vec![] },
+        ]);
+        debug_assert!(catalogue.script(tag(b"grek")).unwrap().languages[0].gsub_features.is_empty());
+    }
+}
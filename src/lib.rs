@@ -3,8 +3,28 @@ extern crate flate2;
 
 pub(crate) mod utils;
 mod structures;
+mod font;
+mod tables;
+mod metrics;
+mod variation;
+mod bitmap;
+mod layout;
+mod shaping;
+mod outline;
+mod subset;
+mod instance;
+mod format;
+mod eot;
+mod mtx;
+mod repair;
+mod warning;
+mod sanitize;
+mod lint;
+#[cfg(test)]
+mod test_utils;
 
 use crate::structures::*;
+use crate::tables::os2::OS2_FS_TYPE_OFFSET;
 use crate::utils::*;
 use std::mem::size_of;
 use flate2::{Decompress, FlushDecompress};
@@ -12,6 +32,45 @@ use std::ffi::CStr;
 use std::os::raw::c_char;
 use bytebuffer::ByteBuffer;
 
+pub use crate::font::{FontTables, Sfnt, WoffFont};
+pub use crate::eot::{EotHeader, EotOptions, EOT_FLAG_COMPRESSED, EOT_FLAG_SUBSET, EOT_FLAG_XOR_ENCRYPTED};
+pub use crate::format::{detect_format, FontFormat};
+pub use crate::structures::SfntHeaderTable;
+pub use crate::metrics::FontMetrics;
+pub use crate::variation::{AxisInfo, AxisValueInfo, InstanceInfo, VariationInfo};
+pub use crate::tables::avar::{AvarTable, SegmentMap};
+pub use crate::tables::fvar::{FvarTable, NamedInstance, VariationAxis, AXIS_FLAG_HIDDEN};
+pub use crate::tables::stat::{StatAxisRecord, StatAxisValue, StatTable, ELIDABLE_AXIS_VALUE_NAME, OLDER_SIBLING_FONT_ATTRIBUTE};
+pub use crate::bitmap::{BitmapGlyph, BitmapMetrics, ImageFormat};
+pub use crate::tables::cblc::{BitmapStrike, CblcTable};
+pub use crate::tables::sbix::{SbixStrike, SbixTable};
+pub use crate::tables::svg::{SvgDocumentRange, SvgTable};
+pub use crate::layout::{LanguageFeatures, LayoutCatalogue, ScriptFeatures};
+pub use crate::tables::layout::{AxisCondition, Feature, FeatureVariation, LanguageSystem, LayoutTable, Lookup, Script, DEFAULT_SCRIPT_TAG, USE_MARK_FILTERING_SET};
+pub use crate::shaping::{GlyphRun, PositionedGlyph, ShapingOptions, DEFAULT_GPOS_FEATURES, DEFAULT_GSUB_FEATURES};
+pub use crate::tables::gpos::ValueRecord;
+pub use crate::tables::kern::KernTable;
+pub use crate::tables::cff::CffGlyphs;
+pub use crate::tables::cmap::{CmapEncodingRecord, CmapGroup, CmapSegment, CmapSubtable, CmapTable, VariationSelectorRecord};
+pub use crate::tables::colr::{ClipBox, ColorGlyph, ColorLayer, ColorLine, ColorStop, ColrTable, CompositeMode, Extend, Paint, FOREGROUND_PALETTE_INDEX};
+pub use crate::tables::cpal::{Color, CpalTable, NO_LABEL_NAME_ID, PALETTE_USABLE_WITH_DARK_BACKGROUND, PALETTE_USABLE_WITH_LIGHT_BACKGROUND};
+pub use crate::repair::{CRepair, Repair, RepairKind};
+pub use crate::lint::{lint, LintFinding, LintReport, LintRule, Severity, LINT_RULES};
+pub use crate::sanitize::{sanitize, SanitizeAction, SanitizeIssue, SanitizeIssueKind, SanitizeReport};
+pub use crate::warning::{CWarning, Warning, WarningKind};
+pub use crate::outline::{GlyphOutline, GlyphPoint, PathBuilder, SvgPathBuilder};
+pub use crate::subset::{subset_font, FontSubset, SubsetGlyphs};
+pub use crate::instance::instantiate_font;
+pub use crate::tables::glyf::{CompositeGlyph, Glyph, GlyphComponent, SimpleGlyph, TrueTypeGlyphs};
+pub use crate::tables::hhea::HheaTable;
+pub use crate::tables::hmtx::HmtxTable;
+pub use crate::tables::loca::LocaTable;
+pub use crate::tables::maxp::MaxpTable;
+pub use crate::tables::name::{name_id, NameRecord, NameTable};
+pub use crate::tables::os2::{EmbeddingLevel, EmbeddingPermissions, Os2Table};
+pub use crate::tables::post::PostTable;
+pub use crate::utils::tag;
+
 /// Result structure with decoded SFNT data
 ///
 /// #Fields
@@ -19,11 +78,19 @@ use bytebuffer::ByteBuffer;
 /// `decoded_data` - decoded SFNT data
 /// `decoded_data_len` - length of decoded SFNT data
 /// `error` - type of error. None - returned result has no errors.
+/// `warnings` - non-fatal issues found while decoding, use `decoded_result_warning` to access them
+/// `warnings_count` - number of warnings
+/// `repairs` - repairs made by lenient decoding, use `decoded_result_repair` to access them
+/// `repairs_count` - number of repairs
 #[repr(C)]
 pub struct DecodedResult {
     pub decoded_data: *mut u8,
     pub decoded_data_len: usize,
     pub error: Error,
+    pub warnings: *mut CWarning,
+    pub warnings_count: usize,
+    pub repairs: *mut CRepair,
+    pub repairs_count: usize,
 }
 
 struct DecodedData {
@@ -31,6 +98,10 @@ struct DecodedData {
     pub table_records: Vec<SfntTableRecord>,
     pub data_tables: Vec<Vec<u8>>,
     pub decompressed_data_size: u32,
+    pub repairs: Vec<Repair>,
+    pub warnings: Vec<Warning>,
+    pub sanitize_issues: Vec<SanitizeIssue>,
+    pub embedding_permissions: Option<EmbeddingPermissions>,
     pub error: Error,
 }
 
@@ -42,6 +113,10 @@ impl DecodedResult {
             decoded_data: std::ptr::null_mut(),
             decoded_data_len: 0,
             error: err,
+            warnings: std::ptr::null_mut(),
+            warnings_count: 0,
+            repairs: std::ptr::null_mut(),
+            repairs_count: 0,
         }))
     }
 
@@ -59,6 +134,26 @@ impl DecodedResult {
         }
     }
 
+    fn create_vec_result_ptr(result: Result<Vec<u8>, Error>) -> *mut Self {
+        match result {
+            Ok(data) => {
+                let decoded_data_len = data.len();
+                // boxed slice has no spare capacity, so it can be freed knowing its length only
+                let decoded_data = Box::into_raw(data.into_boxed_slice()) as *mut u8;
+                Box::into_raw(Box::new(Self {
+                    decoded_data,
+                    decoded_data_len,
+                    error: Error::None,
+                    warnings: std::ptr::null_mut(),
+                    warnings_count: 0,
+                    repairs: std::ptr::null_mut(),
+                    repairs_count: 0,
+                }))
+            }
+            Err(err) => DecodedResult::create_error_result_ptr(err),
+        }
+    }
+
     fn create_result_ptr(result: Result<DecodedData, Error>) -> *mut Self {
         match result {
             Ok(data) => {
@@ -67,6 +162,8 @@ impl DecodedResult {
                     data.table_records,
                     data.data_tables,
                     data.decompressed_data_size as usize,
+                    &data.warnings,
+                    &data.repairs,
                     data.error,
                 )
             }
@@ -114,6 +211,13 @@ impl FileRWResult {
         }
     }
 
+    fn create_vec_result_ptr(result: Result<Vec<u8>, Error>, out_path: &str) -> *mut Self {
+        match result {
+            Ok(data) => Box::into_raw(Box::new(create_ttf_file(&data, out_path))),
+            Err(err) => FileRWResult::create_error_result_ptr(err),
+        }
+    }
+
     fn create_result_ptr(result: Result<DecodedData, Error>, out_path: &str) -> *mut Self {
         match result {
             Ok(data) => {
@@ -132,6 +236,101 @@ impl FileRWResult {
     }
 }
 
+/// Options of WOFF decoding
+///
+/// #Fields
+///
+/// `include_tables` - tags of tables which have to be kept in decoded SFNT. If empty - all tables are kept.
+/// `exclude_tables` - tags of tables which have to be dropped from decoded SFNT.
+/// Filtered tables are not decompressed at all.
+/// `preserve_table_order` - lay out tables data in the original physical order inferred from WOFF data offsets
+/// instead of tag order. Table records are tag-sorted in both cases.
+/// `lenient` - best-effort repair of damaged WOFF data instead of failing, see `Repair`
+/// `sanitize` - validate table contents after decoding, see `sanitize`. Broken subtables are dropped
+/// and `SanitizeRejected` error is returned if the font can't be used safely
+/// `enforce_embedding_permissions` - fail with `EmbeddingRestricted` error if fsType of 'OS/2' table
+/// is set to restricted license embedding. Fonts with 'OS/2' table which fsType can't be read are rejected too
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+    pub include_tables: Vec<u32>,
+    pub exclude_tables: Vec<u32>,
+    pub preserve_table_order: bool,
+    pub lenient: bool,
+    pub sanitize: bool,
+    pub enforce_embedding_permissions: bool,
+}
+
+impl DecodeOptions {
+    /// Checks if the table with `tag` has to be kept in decoded SFNT
+    pub fn keeps_table(&self, tag: u32) -> bool {
+        (self.include_tables.is_empty() || self.include_tables.contains(&tag)) && !self.exclude_tables.contains(&tag)
+    }
+}
+
+/// Options of WOFF decoding for using with C wrapper, see `DecodeOptions`
+///
+/// #Fields
+///
+/// `include_tables` - pointer to array of tags of tables which have to be kept (may be null if `include_tables_len` is 0)
+/// `include_tables_len` - number of tags in `include_tables`. If 0 - all tables are kept.
+/// `exclude_tables` - pointer to array of tags of tables which have to be dropped (may be null if `exclude_tables_len` is 0)
+/// `exclude_tables_len` - number of tags in `exclude_tables`
+/// `preserve_table_order` - lay out tables data in the original physical order
+/// `lenient` - best-effort repair of damaged WOFF data instead of failing
+/// `sanitize` - validate table contents after decoding
+/// `enforce_embedding_permissions` - fail if the font is marked as restricted license embedding or its 'OS/2' table can't be read
+#[repr(C)]
+pub struct CDecodeOptions {
+    pub include_tables: *const u32,
+    pub include_tables_len: usize,
+    pub exclude_tables: *const u32,
+    pub exclude_tables_len: usize,
+    pub preserve_table_order: bool,
+    pub lenient: bool,
+    pub sanitize: bool,
+    pub enforce_embedding_permissions: bool,
+}
+
+impl CDecodeOptions {
+    /// # Safety
+    /// Be sure that non-null arrays contain the specified number of tags
+    unsafe fn to_decode_options(&self) -> DecodeOptions {
+        let tags = |ptr: *const u32, len: usize| -> Vec<u32> {
+            if ptr.is_null() || len == 0 {
+                vec![]
+            } else {
+                std::slice::from_raw_parts(ptr, len).to_vec()
+            }
+        };
+        DecodeOptions {
+            include_tables: tags(self.include_tables, self.include_tables_len),
+            exclude_tables: tags(self.exclude_tables, self.exclude_tables_len),
+            preserve_table_order: self.preserve_table_order,
+            lenient: self.lenient,
+            sanitize: self.sanitize,
+            enforce_embedding_permissions: self.enforce_embedding_permissions,
+        }
+    }
+}
+
+/// Decoded SFNT data along with the report of repairs made by lenient decoding
+///
+/// #Fields
+///
+/// `data` - decoded SFNT data
+/// `repairs` - every repair made to recover the data. Always empty with strict decoding
+/// `warnings` - non-fatal issues found while decoding
+/// `sanitize_issues` - issues found and fixed by the sanitize pass if `sanitize` option is set
+/// `embedding_permissions` - permissions from fsType of 'OS/2' table, `None` if there is no valid 'OS/2' table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeOutput {
+    pub data: Vec<u8>,
+    pub repairs: Vec<Repair>,
+    pub warnings: Vec<Warning>,
+    pub sanitize_issues: Vec<SanitizeIssue>,
+    pub embedding_permissions: Option<EmbeddingPermissions>,
+}
+
 /// Enum with types of error
 /// If `Error` with type `None` that means no errors occurred
 #[repr(C)]
@@ -153,6 +352,15 @@ pub enum Error {
     ReadFromFileError,
     InputPathError,
     OutputPathError,
+    TableNotFound,
+    InvalidTableData,
+    InvalidHeadMagicNumber,
+    InvalidSfntStructure,
+    UnsupportedFontFormat,
+    InvalidEotStructure,
+    UnsupportedEotCompression,
+    SanitizeRejected,
+    EmbeddingRestricted,
 }
 
 /// # Safety
@@ -172,7 +380,10 @@ pub unsafe extern "C" fn decode_from_file_wrapped(path: *const c_char) -> *mut D
     if read_result.error != Error::None {
         return DecodedResult::create_error_result_ptr(read_result.error);
     }
-    DecodedResult::create_result_ptr(decode_internal(&mut buf))
+    if !is_woff_input(&buf) {
+        return DecodedResult::create_vec_result_ptr(load_font(&buf).map(Sfnt::into_vec));
+    }
+    DecodedResult::create_result_ptr(decode_internal(&buf, &DecodeOptions::default()))
 }
 
 /// # Safety
@@ -186,12 +397,11 @@ pub unsafe extern "C" fn decode_from_data_wrapped(
     woff_data_size: usize,
 ) -> *mut DecodedResult {
     if !source_buf.is_null() && woff_data_size > 0 {
-        let mut data: Vec<u8> = Vec::from_raw_parts(
-            source_buf as *mut u8,
-            woff_data_size,
-            woff_data_size,
-        );
-        DecodedResult::create_result_ptr(decode_internal(&mut data))
+        let data = std::slice::from_raw_parts(source_buf, woff_data_size);
+        if !is_woff_input(data) {
+            return DecodedResult::create_vec_result_ptr(load_font(data).map(Sfnt::into_vec));
+        }
+        DecodedResult::create_result_ptr(decode_internal(data, &DecodeOptions::default()))
     } else {
         DecodedResult::create_error_result_ptr(Error::DecodeError)
     }
@@ -223,7 +433,10 @@ pub unsafe extern "C" fn decode_file_to_file_wrapped(
     if read_result.error != Error::None {
         return FileRWResult::create_error_result_ptr(read_result.error);
     }
-    FileRWResult::create_result_ptr(decode_internal(&mut buf), out_path)
+    if !is_woff_input(&buf) {
+        return FileRWResult::create_vec_result_ptr(load_font(&buf).map(Sfnt::into_vec), out_path);
+    }
+    FileRWResult::create_result_ptr(decode_internal(&buf, &DecodeOptions::default()), out_path)
 }
 
 /// # Safety
@@ -244,17 +457,237 @@ pub unsafe extern "C" fn decode_data_to_file_wrapped(
     };
 
     if !source_buf.is_null() && woff_data_size > 0 {
-        let mut data: Vec<u8> = Vec::from_raw_parts(
-            source_buf as *mut u8,
-            woff_data_size,
-            woff_data_size,
-        );
-        FileRWResult::create_result_ptr(decode_internal(&mut data), str_path)
+        let data = std::slice::from_raw_parts(source_buf, woff_data_size);
+        if !is_woff_input(data) {
+            return FileRWResult::create_vec_result_ptr(load_font(data).map(Sfnt::into_vec), str_path);
+        }
+        FileRWResult::create_result_ptr(decode_internal(data, &DecodeOptions::default()), str_path)
+    } else {
+        FileRWResult::create_error_result_ptr(Error::DecodeError)
+    }
+}
+
+/// # Safety
+/// Be sure that `source_buf` pointer is not null and stay allocated while decode in progress and the
+/// `woff_data_size` is the actual size of `source_buf`. `options` may be null, then all tables are kept
+///
+/// Decode WOFF data to SFNT data with decoding options wrapped for using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn decode_from_data_with_options_wrapped(
+    source_buf: *const u8,
+    woff_data_size: usize,
+    options: *const CDecodeOptions,
+) -> *mut DecodedResult {
+    let options = if options.is_null() { DecodeOptions::default() } else { (*options).to_decode_options() };
+    if !source_buf.is_null() && woff_data_size > 0 {
+        let data = std::slice::from_raw_parts(source_buf, woff_data_size);
+        DecodedResult::create_result_ptr(decode_internal(data, &options))
+    } else {
+        DecodedResult::create_error_result_ptr(Error::DecodeError)
+    }
+}
+
+/// # Safety
+/// Be sure that `in_path` and `out_path` pointers are not null and exists.
+/// `options` may be null, then all tables are kept
+///
+/// Decode .woff file data to SFNT file with decoding options wrapped for using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn decode_file_to_file_with_options_wrapped(
+    in_path: *const c_char,
+    out_path: *const c_char,
+    options: *const CDecodeOptions,
+) -> *mut FileRWResult {
+    let c_srt = CStr::from_ptr(in_path);
+    let in_path = match c_srt.to_str() {
+        Ok(string) => string,
+        Err(_) => return FileRWResult::create_error_result_ptr(Error::InputPathError)
+    };
+
+    let c_srt = CStr::from_ptr(out_path);
+    let out_path = match c_srt.to_str() {
+        Ok(string) => string,
+        Err(_) => return FileRWResult::create_error_result_ptr(Error::OutputPathError)
+    };
+    let options = if options.is_null() { DecodeOptions::default() } else { (*options).to_decode_options() };
+    let mut buf: Vec<u8> = vec![];
+    let read_result = read_file(in_path, &mut buf);
+    if read_result.error != Error::None {
+        return FileRWResult::create_error_result_ptr(read_result.error);
+    }
+    FileRWResult::create_result_ptr(decode_internal(&buf, &options), out_path)
+}
+
+/// # Safety
+/// Be sure that `source_buf` pointer is not null and stay allocated while decode in progress and the
+/// `woff_data_size` is the actual size of `source_buf`. Also the `path` is not null and exists.
+/// `options` may be null, then all tables are kept
+///
+/// Decode WOFF data to SFNT file with decoding options wrapped for using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn decode_data_to_file_with_options_wrapped(
+    source_buf: *const u8,
+    woff_data_size: usize,
+    path: *const c_char,
+    options: *const CDecodeOptions,
+) -> *mut FileRWResult {
+    let c_srt = CStr::from_ptr(path);
+    let str_path = match c_srt.to_str() {
+        Ok(string) => string,
+        Err(_) => return FileRWResult::create_error_result_ptr(Error::InputPathError)
+    };
+    let options = if options.is_null() { DecodeOptions::default() } else { (*options).to_decode_options() };
+
+    if !source_buf.is_null() && woff_data_size > 0 {
+        let data = std::slice::from_raw_parts(source_buf, woff_data_size);
+        FileRWResult::create_result_ptr(decode_internal(data, &options), str_path)
     } else {
         FileRWResult::create_error_result_ptr(Error::DecodeError)
     }
 }
 
+/// # Safety
+/// Be sure that `source_buf` pointer is not null and stay allocated while decode in progress and the
+/// `eot_data_size` is the actual size of `source_buf`
+///
+/// Decode EOT data to SFNT data wrapped for using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn decode_eot_from_data_wrapped(source_buf: *const u8, eot_data_size: usize) -> *mut DecodedResult {
+    if source_buf.is_null() || eot_data_size == 0 {
+        return DecodedResult::create_error_result_ptr(Error::InputBufferIsEmpty);
+    }
+    DecodedResult::create_vec_result_ptr(decode_eot_from_slice(std::slice::from_raw_parts(source_buf, eot_data_size)))
+}
+
+/// # Safety
+/// Be sure that `source_buf` pointer is not null and the `data_size` is the actual size of `source_buf`
+///
+/// Detects font format of the data. Using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn detect_font_format_wrapped(source_buf: *const u8, data_size: usize) -> FontFormat {
+    if source_buf.is_null() || data_size == 0 {
+        return FontFormat::Unknown;
+    }
+    detect_format(std::slice::from_raw_parts(source_buf, data_size))
+}
+
+/// # Safety
+/// Be sure that `source_buf` pointer is not null and stay allocated while loading in progress and the
+/// `data_size` is the actual size of `source_buf`
+///
+/// Loads font data in any supported format to SFNT data wrapped for using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn load_font_from_data_wrapped(source_buf: *const u8, data_size: usize) -> *mut DecodedResult {
+    if source_buf.is_null() || data_size == 0 {
+        return DecodedResult::create_error_result_ptr(Error::InputBufferIsEmpty);
+    }
+    DecodedResult::create_vec_result_ptr(load_font(std::slice::from_raw_parts(source_buf, data_size)).map(Sfnt::into_vec))
+}
+
+/// # Safety
+/// Be sure that `source_buf` pointer is not null and stay allocated while decode in progress, the
+/// `woff_data_size` is the actual size of `source_buf` and non-null `axis_tags` and `axis_values`
+/// arrays contain `coordinates_count` items
+///
+/// Decode WOFF data of variable font to SFNT data of its static instance at user coordinates
+/// (`axis_tags[i]`, `axis_values[i]`) wrapped for using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn decode_static_instance_from_data_wrapped(
+    source_buf: *const u8,
+    woff_data_size: usize,
+    axis_tags: *const u32,
+    axis_values: *const f32,
+    coordinates_count: usize,
+) -> *mut DecodedResult {
+    if source_buf.is_null() || woff_data_size == 0 {
+        return DecodedResult::create_error_result_ptr(Error::InputBufferIsEmpty);
+    }
+    let coordinates: Vec<(u32, f32)> = if axis_tags.is_null() || axis_values.is_null() || coordinates_count == 0 {
+        vec![]
+    } else {
+        std::slice::from_raw_parts(axis_tags, coordinates_count)
+            .iter()
+            .copied()
+            .zip(std::slice::from_raw_parts(axis_values, coordinates_count).iter().copied())
+            .collect()
+    };
+    let buf = std::slice::from_raw_parts(source_buf, woff_data_size);
+    DecodedResult::create_vec_result_ptr(decode_to_static_instance(buf, &coordinates))
+}
+
+/// # Safety
+/// Be sure that `source_buf` pointer is not null and stay allocated while loading in progress, the
+/// `data_size` is the actual size of `source_buf` and `permissions` is not null
+///
+/// Reads font embedding permissions from fsType of 'OS/2' table of font data in any supported format
+/// to `permissions`. Using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn font_embedding_permissions_wrapped(
+    source_buf: *const u8,
+    data_size: usize,
+    permissions: *mut EmbeddingPermissions,
+) -> Error {
+    if source_buf.is_null() || data_size == 0 {
+        return Error::InputBufferIsEmpty;
+    }
+    let os2 = match load_font(std::slice::from_raw_parts(source_buf, data_size)).and_then(|font| font.os2()) {
+        Ok(os2) => os2,
+        Err(err) => return err,
+    };
+    if !permissions.is_null() {
+        *permissions = os2.embedding_permissions();
+    }
+    Error::None
+}
+
+/// # Safety
+/// Be sure that `result` is null or points to `DecodedResult` returned by decode functions
+///
+/// Returns number of warnings of decoded result. Using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn decoded_result_warnings_count(result: *const DecodedResult) -> usize {
+    if result.is_null() { 0 } else { (*result).warnings_count }
+}
+
+/// # Safety
+/// Be sure that `result` is null or points to `DecodedResult` returned by decode functions
+/// and `warning` is not null
+///
+/// Copies warning with `index` of decoded result to `warning`.
+/// Returns false if there is no such warning. Using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn decoded_result_warning(result: *const DecodedResult, index: usize, warning: *mut CWarning) -> bool {
+    if result.is_null() || warning.is_null() || index >= (*result).warnings_count {
+        return false;
+    }
+    *warning = *(*result).warnings.add(index);
+    true
+}
+
+/// # Safety
+/// Be sure that `result` is null or points to `DecodedResult` returned by decode functions
+///
+/// Returns number of repairs made by lenient decoding of decoded result. Using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn decoded_result_repairs_count(result: *const DecodedResult) -> usize {
+    if result.is_null() { 0 } else { (*result).repairs_count }
+}
+
+/// # Safety
+/// Be sure that `result` is null or points to `DecodedResult` returned by decode functions
+/// and `repair` is not null
+///
+/// Copies repair with `index` of decoded result to `repair`.
+/// Returns false if there is no such repair. Using with C wrapper
+#[no_mangle]
+pub unsafe extern "C" fn decoded_result_repair(result: *const DecodedResult, index: usize, repair: *mut CRepair) -> bool {
+    if result.is_null() || repair.is_null() || index >= (*result).repairs_count {
+        return false;
+    }
+    *repair = *(*result).repairs.add(index);
+    true
+}
+
 /// # Safety
 /// Be sure that the pointer to the `data` you want to deallocate is not null
 ///
@@ -263,7 +696,13 @@ pub unsafe extern "C" fn decode_data_to_file_wrapped(
 pub unsafe extern "C" fn destroy_decoded_result(data: *mut DecodedResult) {
     if !data.is_null() {
         if !(*data).decoded_data.is_null() {
-            drop(Box::from_raw((*data).decoded_data));
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut((*data).decoded_data, (*data).decoded_data_len)));
+        }
+        if !(*data).warnings.is_null() {
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut((*data).warnings, (*data).warnings_count)));
+        }
+        if !(*data).repairs.is_null() {
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut((*data).repairs, (*data).repairs_count)));
         }
         drop(Box::from_raw(data));
     }
@@ -280,54 +719,156 @@ pub unsafe extern "C" fn destroy_file_rw_result(data: *mut FileRWResult) {
     }
 }
 
-/// Decode .woff file data to SFNT bytes.
+/// Decode .woff file data to SFNT bytes. See `decode_from_slice` for other formats
 pub fn decode_from_file(path: &str) -> Result<Vec<u8>, Error> {
     let mut buf: Vec<u8> = vec![];
     let read_result = read_file(path, &mut buf);
     if read_result.error != Error::None {
         return Err(read_result.error);
     };
-    DecodedResult::create_result(decode_internal(&mut buf))
+    decode_from_slice(&buf)
 }
 
-/// Decode .woff file data to SFNT file
+/// Decode .woff file data to SFNT file. See `decode_from_slice` for other formats
 pub fn decode_from_file_to_file(in_path: &str, out_path: &str) -> Error {
     let mut buf: Vec<u8> = vec![];
     let read_result = read_file(in_path, &mut buf);
     if read_result.error != Error::None {
         return read_result.error;
     }
-    FileRWResult::create_result(decode_internal(&mut buf), out_path)
+    decode_from_slice_to_file(&buf, out_path)
 }
 
-/// Decode WOFF data from vector to SFNT data
+/// Decode WOFF data from vector to SFNT data. See `decode_from_slice` for other formats
+#[allow(clippy::ptr_arg)]
 pub fn decode_from_vec(buf: &mut Vec<u8>) -> Result<Vec<u8>, Error> {
-    DecodedResult::create_result(decode_internal(buf))
+    decode_from_slice(buf)
 }
 
-/// Decode WOFF data from vector to SFNT file
+/// Decode WOFF data from vector to SFNT file. See `decode_from_slice` for other formats
+#[allow(clippy::ptr_arg)]
 pub fn decode_from_vec_to_file(buf: &mut Vec<u8>, out_path: &str) -> Error {
-    FileRWResult::create_result(decode_internal(buf), out_path)
+    decode_from_slice_to_file(buf, out_path)
 }
 
-/// Decode WOFF data from slice to SFNT data
+/// Decode WOFF data from slice to SFNT data.
+/// Data in other formats detected by `detect_format` is loaded with `load_font`,
+/// so SFNT data passes through untouched. Functions with `DecodeOptions` accept WOFF data only
 pub fn decode_from_slice(buf: &[u8]) -> Result<Vec<u8>, Error> {
-    let mut data: Vec<u8> = Vec::from(buf);
-    DecodedResult::create_result(decode_internal(&mut data))
+    if !is_woff_input(buf) {
+        return load_font(buf).map(Sfnt::into_vec);
+    }
+    DecodedResult::create_result(decode_internal(buf, &DecodeOptions::default()))
+}
+
+/// Decode WOFF data from slice to `Sfnt` which gives access to separate tables of decoded font.
+/// See `decode_from_slice` for other formats
+pub fn decode_to_sfnt(buf: &[u8]) -> Result<Sfnt, Error> {
+    if !is_woff_input(buf) {
+        return load_font(buf);
+    }
+    Sfnt::from_vec(DecodedResult::create_result(decode_internal(buf, &DecodeOptions::default()))?)
+}
+
+/// Decode EOT data from slice to SFNT data. XOR obfuscated and MicroType Express compressed
+/// font data is supported, `UnsupportedEotCompression` error is returned for MTX data with unknown number of blocks
+pub fn decode_eot_from_slice(buf: &[u8]) -> Result<Vec<u8>, Error> {
+    eot::decode_eot(buf)
+}
+
+/// Decode EOT data from slice to `Sfnt`
+pub fn decode_eot_to_sfnt(buf: &[u8]) -> Result<Sfnt, Error> {
+    Sfnt::from_vec(eot::decode_eot(buf)?)
+}
+
+/// Decode WOFF data from slice to SFNT data and wrap it to EOT.
+/// EOT header is filled from 'name', 'OS/2' and 'head' tables of the decoded font
+pub fn decode_from_slice_to_eot(buf: &[u8], options: &EotOptions) -> Result<Vec<u8>, Error> {
+    eot::encode_eot(decode_from_slice(buf)?, options)
+}
+
+/// Decode .woff file to SFNT data and write it wrapped to EOT to the output file
+pub fn decode_from_file_to_eot_file(in_path: &str, out_path: &str, options: &EotOptions) -> Error {
+    let mut buf: Vec<u8> = vec![];
+    let read_result = read_file(in_path, &mut buf);
+    if read_result.error != Error::None {
+        return read_result.error;
+    }
+    match decode_from_slice_to_eot(&buf, options) {
+        Ok(data) => create_ttf_file(&data, out_path).error,
+        Err(err) => err,
+    }
+}
+
+/// Decode WOFF data of variable font with TrueType outlines to SFNT data of its static instance
+/// at user `coordinates` (axis tag, value), see `instantiate_font`
+pub fn decode_to_static_instance(buf: &[u8], coordinates: &[(u32, f32)]) -> Result<Vec<u8>, Error> {
+    instantiate_font(&decode_to_sfnt(buf)?, coordinates).map(Sfnt::into_vec)
+}
+
+/// Loads font from data in any supported format detected by `detect_format`.
+/// WOFF data is decoded, SFNT data is used as is and the first font is taken from TrueType collection.
+/// Returns `UnsupportedFontFormat` error for formats which can't be decoded
+pub fn load_font(buf: &[u8]) -> Result<Sfnt, Error> {
+    match detect_format(buf) {
+        FontFormat::Woff => decode_to_sfnt(buf),
+        FontFormat::TrueType | FontFormat::AppleTrueType | FontFormat::OpenTypeCff => Sfnt::from_vec(buf.to_vec()),
+        FontFormat::TrueTypeCollection => format::collection_font(buf, 0),
+        FontFormat::Eot => decode_eot_to_sfnt(buf),
+        FontFormat::Woff2 | FontFormat::Unknown => Err(Error::UnsupportedFontFormat),
+    }
+}
+
+/// Decode WOFF data from slice to SFNT data using decoding options
+pub fn decode_from_slice_with_options(buf: &[u8], options: &DecodeOptions) -> Result<Vec<u8>, Error> {
+    DecodedResult::create_result(decode_internal(buf, options))
+}
+
+/// Decode WOFF data from slice to SFNT data using decoding options and report non-fatal issues
+/// along with repairs made if `lenient` option is set
+pub fn decode_from_slice_with_report(buf: &[u8], options: &DecodeOptions) -> Result<DecodeOutput, Error> {
+    let mut decoded = decode_internal(buf, options)?;
+    let repairs = std::mem::take(&mut decoded.repairs);
+    let warnings = std::mem::take(&mut decoded.warnings);
+    let sanitize_issues = std::mem::take(&mut decoded.sanitize_issues);
+    let embedding_permissions = decoded.embedding_permissions;
+    Ok(DecodeOutput {
+        data: DecodedResult::create_result(Ok(decoded))?,
+        repairs,
+        warnings,
+        sanitize_issues,
+        embedding_permissions,
+    })
+}
+
+/// Decode WOFF data from slice to `Sfnt` using decoding options
+pub fn decode_to_sfnt_with_options(buf: &[u8], options: &DecodeOptions) -> Result<Sfnt, Error> {
+    Sfnt::from_vec(DecodedResult::create_result(decode_internal(buf, options))?)
 }
 
-/// Decode WOFF data from slice to SFNT file
+/// Decode WOFF data from slice to SFNT file. See `decode_from_slice` for other formats
 pub fn decode_from_slice_to_file(buf: &[u8], out_path: &str) -> Error {
-    let mut data: Vec<u8> = Vec::from(buf);
-    FileRWResult::create_result(decode_internal(&mut data), out_path)
+    if !is_woff_input(buf) {
+        return match load_font(buf) {
+            Ok(font) => create_ttf_file(font.as_bytes(), out_path).error,
+            Err(err) => err,
+        };
+    }
+    FileRWResult::create_result(decode_internal(buf, &DecodeOptions::default()), out_path)
+}
+
+/// Checks if the data has to be decoded as WOFF by decode functions without options.
+/// Data which isn't recognized at all is decoded as WOFF too, so it fails with WOFF errors as before
+fn is_woff_input(buf: &[u8]) -> bool {
+    matches!(detect_format(buf), FontFormat::Woff | FontFormat::Unknown)
 }
 
 /// Sanity check for WOFF file
-fn sanity_check(buf: &mut Vec<u8>) -> Error {
+fn sanity_check(buf: &[u8]) -> Error {
     if buf.is_empty() { return Error::InputBufferIsEmpty; }
     if buf.len() < size_of::<WoffHeader>() { return Error::InvalidWoffSize; }
 
-    let mut woff_signature_buf = ByteBuffer::from_bytes(&[b'w', b'O', b'F', b'F']);
+    let mut woff_signature_buf = ByteBuffer::from_bytes(b"wOFF");
     let woff_signature = read_u32_be(&mut woff_signature_buf);
     let woff_header = create_woff_header(buf);
 
@@ -346,98 +887,117 @@ fn sanity_check(buf: &mut Vec<u8>) -> Error {
 }
 
 /// Main function to decode and construct SFNT file or data form WOFF file
-fn decode_internal(buf: &mut Vec<u8>) -> Result<DecodedData, Error> {
-    let mut error = sanity_check(buf);
+fn decode_internal(buf: &[u8], options: &DecodeOptions) -> Result<DecodedData, Error> {
+    let mut repairs: Vec<Repair> = vec![];
+    if options.lenient {
+        repair::sanity_check(buf, &mut repairs)?;
+    } else {
+        let error = sanity_check(buf);
 
-    // return result with error from sanity check if error occurred
-    if error != Error::None {
-        return Err(error);
+        // return result with error from sanity check if error occurred
+        if error != Error::None {
+            return Err(error);
+        }
     }
 
-    // We need to know sizes of several SFNT and WOFF structures.
+    // We need to know sizes of several SFNT structures.
     let sfnt_offset_table_size = size_of::<SfntOffsetTable>();
     let sfnt_table_record_size = size_of::<SfntTableRecord>();
-    let woff_table_directory_size = size_of::<WoffTableDirectoryEntry>();
-    let woff_header_size = size_of::<WoffHeader>();
 
     // Construct WOFF header.
-    let woff_header = create_woff_header(buf);
+    let mut woff_header = create_woff_header(buf);
+    let declared_tables_count = woff_header.num_tables as usize;
+
+    let mut woff_table_dir_entry_container = if options.lenient {
+        woff_header.num_tables = repair::directory_entries_count(buf, &woff_header, &mut repairs);
+        (0..woff_header.num_tables as usize)
+            .map(|table_number| {
+                let next_table_offset = size_of::<WoffHeader>() + table_number * size_of::<WoffTableDirectoryEntry>();
+                create_woff_table_dir_entry(buf, next_table_offset)
+            })
+            .collect()
+    } else {
+        read_woff_table_directory(buf, &woff_header)?
+    };
+    let mut warnings = warning::check_directory(&woff_header, &woff_table_dir_entry_container);
+
+    // fsType is checked even if 'OS/2' table is filtered out. Enforcement fails closed:
+    // the font is rejected if its 'OS/2' table can't be read
+    let embedding_permissions = match read_embedding_permissions(buf, &woff_table_dir_entry_container, options.lenient) {
+        Ok(permissions) => permissions,
+        Err(err) if options.enforce_embedding_permissions => return Err(err),
+        Err(_) => None,
+    };
+    if options.enforce_embedding_permissions
+        && embedding_permissions.map(|permissions| permissions.level) == Some(EmbeddingLevel::Restricted) {
+        return Err(Error::EmbeddingRestricted);
+    }
+
+    woff_table_dir_entry_container.retain(|entry| options.keeps_table(entry.tag));
+
+    // sort all entries by tag or by data offset to keep the original physical order of tables
+    if options.preserve_table_order {
+        woff_table_dir_entry_container.sort_by_key(|entry| (entry.offset, entry.tag));
+    } else {
+        woff_table_dir_entry_container.sort_by_key(|entry| entry.tag);
+    }
+
+    let mut sfnt_table_data_vec: Vec<Vec<u8>> = Vec::with_capacity(woff_table_dir_entry_container.len());
+    let mut inflated_entries: Vec<WoffTableDirectoryEntry> = Vec::with_capacity(woff_table_dir_entry_container.len());
+    for mut table_dir_entry in woff_table_dir_entry_container {
+        let sfnt_table_data = if options.lenient {
+            // tables which can't be recovered are dropped
+            match repair::inflate_table(buf, &mut table_dir_entry, &mut repairs) {
+                Some(data) => data,
+                None => continue,
+            }
+        } else {
+            inflate_table(buf, &table_dir_entry, &mut warnings)?
+        };
+        sfnt_table_data_vec.push(sfnt_table_data);
+        inflated_entries.push(table_dir_entry);
+    }
+    let num_tables = inflated_entries.len() as u16;
+
+    let mut sanitize_issues: Vec<SanitizeIssue> = vec![];
+    if options.sanitize {
+        let mut tables: Vec<(u32, Vec<u8>)> = inflated_entries
+            .iter()
+            .map(|entry| entry.tag)
+            .zip(sfnt_table_data_vec.drain(..))
+            .collect();
+        sanitize_issues = sanitize::sanitize_tables(&mut tables);
+        if sanitize::is_rejected(&sanitize_issues) {
+            return Err(Error::SanitizeRejected);
+        }
+        // tables with dropped subtables keep their length, but checksums change
+        for (entry, (_, data)) in inflated_entries.iter_mut().zip(tables.iter()) {
+            if sanitize_issues.iter().any(|issue| issue.tag == entry.tag) {
+                entry.orig_checksum = calculate_table_checksum(data);
+            }
+        }
+        sfnt_table_data_vec = tables.into_iter().map(|(_, data)| data).collect();
+    }
 
-    let search_range = calculate_search_range(woff_header.num_tables);
+    let search_range = calculate_search_range(num_tables);
     let entry_selector = calculate_entry_selector(search_range);
-    let range_shift = calculate_range_shift(woff_header.num_tables, search_range);
+    let range_shift = calculate_range_shift(num_tables, search_range);
 
     // Construct SFNT header (sfnt_offset_table) with its builder.
     let sfnt_offset_table = SfntOffsetTable {
         version: woff_header.flavor,
-        num_tables: woff_header.num_tables,
+        num_tables,
         search_range,
         entry_selector,
         range_shift,
     };
 
     let sfnt_num_tables = sfnt_offset_table.num_tables;
-    let mut sfnt_table_offset = sfnt_offset_table_size;
-
-    let mut woff_table_dir_entry_container: Vec<WoffTableDirectoryEntry> = Vec::with_capacity(sfnt_num_tables as usize);
-
-    // construct each SFNT table record
-    for table_number in 0..sfnt_num_tables as usize {
-        let next_table_offset = woff_header_size + (table_number * woff_table_directory_size);
-        let woff_table_dir_entry = create_woff_table_dir_entry(buf, next_table_offset);
-        // check if dir_entry parameters are correct
-        // and if not return Result with error
-        if (woff_table_dir_entry.orig_length < woff_table_dir_entry.comp_length)
-            || (woff_table_dir_entry.offset as usize > buf.len() - woff_table_dir_entry.comp_length as usize) {
-            error = Error::InvalidWoffStructure;
-            return Err(error);
-        }
-        woff_table_dir_entry_container.push(woff_table_dir_entry);
-        sfnt_table_offset += sfnt_table_record_size
-    }
-
-    // sort all entries by tag
-    woff_table_dir_entry_container.sort_by(
-        |first_entry, second_entry| first_entry.tag.cmp(&second_entry.tag)
-    );
+    let mut sfnt_table_offset = sfnt_offset_table_size + sfnt_num_tables as usize * sfnt_table_record_size;
 
     let mut sfnt_table_records_vec: Vec<SfntTableRecord> = Vec::with_capacity(sfnt_num_tables as usize);
-    let mut sfnt_table_data_vec: Vec<Vec<u8>> = Vec::with_capacity(sfnt_num_tables as usize);
-
-    for table_dir_entry in woff_table_dir_entry_container.iter().take(sfnt_num_tables as usize) {
-        let start_offset = table_dir_entry.offset as usize;
-        let end_offset = (table_dir_entry.offset + table_dir_entry.comp_length) as usize;
-
-        let mut sfnt_table_data: Vec<u8> = Vec::with_capacity(table_dir_entry.orig_length as usize);
-        let source_slice = &buf[start_offset..end_offset];
-
-        if table_dir_entry.orig_length != table_dir_entry.comp_length {
-            // decompress table data
-            let mut decompressor = Decompress::new(true);
-            // match decompressor result status
-            match decompressor.decompress_vec(source_slice, &mut sfnt_table_data, FlushDecompress::None) {
-                Ok(stat) => {
-                    if stat == flate2::Status::Ok {
-                        error = Error::OutBufferFull;
-                        return Err(error);
-                    };
-                    if stat == flate2::Status::BufError {
-                        error = Error::BuffError;
-                        return Err(error);
-                    };
-                    if stat == flate2::Status::StreamEnd {
-                        error = Error::None
-                    };
-                }
-                Err(_) => {
-                    error = Error::DecompressError;
-                    return Err(error);
-                }
-            };
-        } else {
-            sfnt_table_data.extend_from_slice(source_slice);
-        }
 
+    for (table_dir_entry, sfnt_table_data) in inflated_entries.iter().zip(sfnt_table_data_vec.iter_mut()) {
         let sfnt_table_record = SfntTableRecord {
             table_tag: table_dir_entry.tag,
             checksum: table_dir_entry.orig_checksum,
@@ -457,21 +1017,151 @@ fn decode_internal(buf: &mut Vec<u8>) -> Result<DecodedData, Error> {
         } else {
             sfnt_table_offset += table_dir_entry.orig_length as usize
         }
+    }
 
-        sfnt_table_data_vec.push(sfnt_table_data);
+    // table records must be sorted by tag whatever the data order is
+    sfnt_table_records_vec.sort_by_key(|record| record.table_tag);
+
+    // dropped, repaired or sanitized tables change the checksum of the whole font
+    let is_modified = inflated_entries.len() != declared_tables_count
+        || repairs.iter().any(|repair| !matches!(repair, Repair::WoffLengthMismatch { .. }))
+        || !sanitize_issues.is_empty();
+    if is_modified {
+        update_check_sum_adjustment(&sfnt_offset_table, &mut sfnt_table_records_vec, &inflated_entries, &mut sfnt_table_data_vec);
     }
 
     Ok(DecodedData {
         sfnt_header: sfnt_offset_table,
         table_records: sfnt_table_records_vec,
         data_tables: sfnt_table_data_vec,
-        decompressed_data_size: woff_header.total_sfnt_size,
-        error,
+        // filtered tables make the SFNT smaller than `total_sfnt_size` from WOFF header
+        decompressed_data_size: sfnt_table_offset as u32,
+        repairs,
+        warnings,
+        sanitize_issues,
+        embedding_permissions,
+        error: Error::None,
     })
 }
 
+/// Reads embedding permissions from fsType of 'OS/2' table described by one of `entries`.
+/// Only the beginning of the table up to fsType has to be valid. Returns `None` if there is no 'OS/2' table
+fn read_embedding_permissions(
+    buf: &[u8],
+    entries: &[WoffTableDirectoryEntry],
+    lenient: bool,
+) -> Result<Option<EmbeddingPermissions>, Error> {
+    let Some(entry) = entries.iter().find(|entry| entry.tag == tag(b"OS/2")) else {
+        return Ok(None);
+    };
+    let (data, readable_length) = if lenient {
+        let mut repairs: Vec<Repair> = vec![];
+        let data = repair::inflate_table(buf, &mut entry.clone(), &mut repairs).ok_or(Error::InvalidTableData)?;
+        // zeros which fill cut data don't tell anything about permissions
+        let readable_length = repairs.iter().fold(data.len(), |length, repair| match *repair {
+            Repair::TableDataTruncated { available, .. } => length.min(available as usize),
+            Repair::CompressedStreamTruncated { recovered_length, .. } => length.min(recovered_length as usize),
+            _ => length,
+        });
+        (data, readable_length)
+    } else {
+        let data = inflate_table(buf, entry, &mut vec![])?;
+        let readable_length = data.len();
+        (data, readable_length)
+    };
+    let fs_type = try_slice(&data[..readable_length], OS2_FS_TYPE_OFFSET, 2)?;
+    Ok(Some(EmbeddingPermissions::from_fs_type(u16::from_be_bytes([fs_type[0], fs_type[1]]))))
+}
+
+/// Recalculates checkSumAdjustment of 'head' table (if there is one) for the SFNT assembled from
+/// the header, table records and tables data. `entries` describe `data_tables` in the same order
+fn update_check_sum_adjustment(
+    sfnt_header: &SfntOffsetTable,
+    table_records: &mut [SfntTableRecord],
+    entries: &[WoffTableDirectoryEntry],
+    data_tables: &mut [Vec<u8>],
+) {
+    let head = entries.iter().position(|entry| entry.tag == tag(b"head"));
+    let Some(head) = head.filter(|&index| data_tables[index].len() >= HEAD_CHECK_SUM_ADJUSTMENT_OFFSET + 4) else {
+        return;
+    };
+    // checksum of 'head' table is calculated with zero checkSumAdjustment
+    data_tables[head][HEAD_CHECK_SUM_ADJUSTMENT_OFFSET..HEAD_CHECK_SUM_ADJUSTMENT_OFFSET + 4].fill(0);
+    if let Some(record) = table_records.iter_mut().find(|record| record.table_tag == tag(b"head")) {
+        record.checksum = calculate_table_checksum(&data_tables[head][..record.length as usize]);
+    }
+
+    let mut check_sum = calculate_table_checksum(&sfnt_header.transform_to_u8_vec());
+    for record in table_records.iter() {
+        check_sum = check_sum.wrapping_add(calculate_table_checksum(&record.transform_to_u8_vec()));
+    }
+    for data in data_tables.iter() {
+        check_sum = check_sum.wrapping_add(calculate_table_checksum(data));
+    }
+    let adjustment = HEAD_CHECK_SUM_MAGIC.wrapping_sub(check_sum);
+    data_tables[head][HEAD_CHECK_SUM_ADJUSTMENT_OFFSET..HEAD_CHECK_SUM_ADJUSTMENT_OFFSET + 4].copy_from_slice(&adjustment.to_be_bytes());
+}
+
+/// Reads all WOFF table directory entries in their original order
+/// and checks that each entry points to the data inside of the WOFF buffer
+fn read_woff_table_directory(buf: &[u8], woff_header: &WoffHeader) -> Result<Vec<WoffTableDirectoryEntry>, Error> {
+    let woff_table_directory_size = size_of::<WoffTableDirectoryEntry>();
+    let woff_header_size = size_of::<WoffHeader>();
+    let mut woff_table_dir_entry_container: Vec<WoffTableDirectoryEntry> = Vec::with_capacity(woff_header.num_tables as usize);
+
+    for table_number in 0..woff_header.num_tables as usize {
+        let next_table_offset = woff_header_size + (table_number * woff_table_directory_size);
+        let woff_table_dir_entry = create_woff_table_dir_entry(buf, next_table_offset);
+        // check if dir_entry parameters are correct
+        // and if not return Result with error
+        if (woff_table_dir_entry.orig_length < woff_table_dir_entry.comp_length)
+            || (woff_table_dir_entry.offset as usize > buf.len() - woff_table_dir_entry.comp_length as usize) {
+            return Err(Error::InvalidWoffStructure);
+        }
+        woff_table_dir_entry_container.push(woff_table_dir_entry);
+    }
+    Ok(woff_table_dir_entry_container)
+}
+
+/// Extracts data of the table described by `table_dir_entry` from WOFF data
+/// and decompresses it if the table is compressed
+fn inflate_table(buf: &[u8], table_dir_entry: &WoffTableDirectoryEntry, warnings: &mut Vec<Warning>) -> Result<Vec<u8>, Error> {
+    let source_slice = try_slice(buf, table_dir_entry.offset as usize, table_dir_entry.comp_length as usize)
+        .map_err(|_| Error::InvalidWoffStructure)?;
+    let mut sfnt_table_data: Vec<u8> = Vec::with_capacity(table_dir_entry.orig_length as usize);
+
+    if table_dir_entry.orig_length != table_dir_entry.comp_length {
+        // decompress table data
+        let mut decompressor = Decompress::new(true);
+        // match decompressor result status
+        match decompressor.decompress_vec(source_slice, &mut sfnt_table_data, FlushDecompress::None) {
+            Ok(stat) => {
+                if stat == flate2::Status::Ok {
+                    return Err(Error::OutBufferFull);
+                };
+                if stat == flate2::Status::BufError {
+                    return Err(Error::BuffError);
+                };
+                if (decompressor.total_in() as usize) < source_slice.len() {
+                    warnings.push(Warning::ExtraDataAfterCompressedStream {
+                        tag: table_dir_entry.tag,
+                        offset: table_dir_entry.offset,
+                        extra_bytes: (source_slice.len() - decompressor.total_in() as usize) as u32,
+                    });
+                }
+            }
+            Err(_) => {
+                return Err(Error::DecompressError);
+            }
+        };
+    } else {
+        sfnt_table_data.extend_from_slice(source_slice);
+    }
+    Ok(sfnt_table_data)
+}
+
 /// Function for creating WOFF header from raw data
-fn create_woff_header(buf: &mut [u8]) -> WoffHeader {
+fn create_woff_header(buf: &[u8]) -> WoffHeader {
     let mut buffer = ByteBuffer::from_bytes(buf);
     WoffHeader {
         signature: read_u32_be(&mut buffer),
@@ -491,7 +1181,7 @@ fn create_woff_header(buf: &mut [u8]) -> WoffHeader {
 }
 
 /// function for creating WOFF table directory entry structure
-fn create_woff_table_dir_entry(buf: &mut [u8], next_table_offset: usize) -> WoffTableDirectoryEntry {
+fn create_woff_table_dir_entry(buf: &[u8], next_table_offset: usize) -> WoffTableDirectoryEntry {
     let mut buffer = ByteBuffer::from_bytes(buf);
     buffer.set_rpos(next_table_offset);
     WoffTableDirectoryEntry {
@@ -511,6 +1201,8 @@ fn assemble_sfnt_binary(
     table_records: Vec<SfntTableRecord>,
     data_tables: Vec<Vec<u8>>,
     decompressed_data_size: usize,
+    warnings: &[Warning],
+    repairs: &[Repair],
     error: Error,
 ) -> *mut DecodedResult {
     let mut sfnt_header_data = sfnt_header.transform_to_u8_vec();
@@ -528,12 +1220,17 @@ fn assemble_sfnt_binary(
     }
 
     let data_len = sfnt_data_vec.len();
-    let data = sfnt_data_vec.as_mut_ptr();
-    std::mem::forget(sfnt_data_vec);
+    let data = Box::into_raw(sfnt_data_vec.into_boxed_slice()) as *mut u8;
+    let c_warnings: Box<[CWarning]> = warnings.iter().map(CWarning::from).collect();
+    let c_repairs: Box<[CRepair]> = repairs.iter().map(CRepair::from).collect();
     let result_buffer = DecodedResult {
         decoded_data: data,
         decoded_data_len: data_len,
         error,
+        warnings_count: c_warnings.len(),
+        warnings: Box::into_raw(c_warnings) as *mut CWarning,
+        repairs_count: c_repairs.len(),
+        repairs: Box::into_raw(c_repairs) as *mut CRepair,
     };
     Box::into_raw(Box::new(result_buffer))
 }
@@ -614,13 +1311,14 @@ fn create_sfnt_file_from_vec(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::*;
 
     #[test]
     fn test_decode_to_buffer() {
         let str_path = "test_fonts/noto-sans-tc.woff";
         let mut buf: Vec<u8> = vec![];
         read_file(str_path, &mut buf);
-        let result = DecodedResult::create_result(decode_internal(&mut buf));
+        let result = DecodedResult::create_result(decode_internal(&buf, &DecodeOptions::default()));
         match result {
             Ok(data) => { debug_assert!(!data.is_empty()) }
             Err(err) => { debug_assert!(err == Error::None) }
@@ -645,6 +1343,354 @@ mod tests {
         let str_path = "test_fonts/noto-sans-tc.woff";
         let mut buf: Vec<u8> = vec![];
         read_file(str_path, &mut buf);
-        debug_assert!(sanity_check(&mut buf) == Error::None)
+        debug_assert!(sanity_check(&buf) == Error::None)
+    }
+
+    fn filter_test_woff() -> Vec<u8> {
+        build_woff(0x00010000, &[
+            (tag(b"head"), head_table(1000, 0)),
+            (tag(b"cmap"), cmap_table(&[(0x41, 1)], &[])),
+            (tag(b"hhea"), hhea_table(800, -200, 0, 2)),
+            (tag(b"maxp"), maxp_table(2, 0)),
+            (tag(b"name"), vec![0; 6]),
+        ])
+    }
+
+    #[test]
+    fn test_decode_with_included_tables() {
+        let options = DecodeOptions { include_tables: vec![tag(b"head"), tag(b"cmap")], ..Default::default() };
+        let decoded = decode_internal(&filter_test_woff(), &options).unwrap();
+        let tags: Vec<u32> = decoded.table_records.iter().map(|record| record.table_tag).collect();
+        debug_assert!(tags == vec![tag(b"cmap"), tag(b"head")]);
+        debug_assert!(decoded.sfnt_header.num_tables == 2);
+        debug_assert!(decoded.sfnt_header.search_range == 32);
+        debug_assert!(decoded.sfnt_header.entry_selector == 1);
+        debug_assert!(decoded.sfnt_header.range_shift == 0);
+        debug_assert!(decoded.table_records[0].offset == 12 + 2 * 16);
+
+        let sfnt = decode_to_sfnt_with_options(&filter_test_woff(), &options).unwrap();
+        debug_assert!(sfnt.table_data(tag(b"head")).is_ok());
+        debug_assert!(sfnt.table_data(tag(b"name")).is_err());
+        // checkSumAdjustment is recalculated for the remaining tables
+        debug_assert!(calculate_table_checksum(sfnt.as_bytes()) == HEAD_CHECK_SUM_MAGIC);
+        let head_record = decoded.table_records.iter().find(|record| record.table_tag == tag(b"head")).unwrap();
+        debug_assert!(head_record.checksum == table_checksum(&head_table(1000, 0)));
+    }
+
+    #[test]
+    fn test_decode_with_excluded_tables() {
+        let options = DecodeOptions { exclude_tables: vec![tag(b"name"), tag(b"hhea")], ..Default::default() };
+        let data = decode_from_slice_with_options(&filter_test_woff(), &options).unwrap();
+        let sfnt = Sfnt::from_vec(data).unwrap();
+        debug_assert!(sfnt.table_data(tag(b"maxp")).is_ok());
+        debug_assert!(sfnt.table_data(tag(b"name")).is_err());
+        debug_assert!(sfnt.table_data(tag(b"hhea")).is_err());
+
+        let all = decode_internal(&filter_test_woff(), &DecodeOptions::default()).unwrap();
+        debug_assert!(all.sfnt_header.num_tables == 5);
+
+        let in_path = std::env::temp_dir().join("woffdecoder_test_decode_with_excluded_tables.woff");
+        let out_path = std::env::temp_dir().join("woffdecoder_test_decode_with_excluded_tables.ttf");
+        std::fs::write(&in_path, filter_test_woff()).unwrap();
+        let exclude_tables = [tag(b"name"), tag(b"hhea")];
+        let c_options = CDecodeOptions {
+            include_tables: std::ptr::null(),
+            include_tables_len: 0,
+            exclude_tables: exclude_tables.as_ptr(),
+            exclude_tables_len: exclude_tables.len(),
+            preserve_table_order: false,
+            lenient: false,
+            sanitize: false,
+            enforce_embedding_permissions: false,
+        };
+        let in_path_c = std::ffi::CString::new(in_path.to_str().unwrap()).unwrap();
+        let out_path_c = std::ffi::CString::new(out_path.to_str().unwrap()).unwrap();
+        unsafe {
+            let result = decode_file_to_file_with_options_wrapped(in_path_c.as_ptr(), out_path_c.as_ptr(), &c_options);
+            debug_assert!((*result).error == Error::None);
+            destroy_file_rw_result(result);
+        }
+        debug_assert!(std::fs::read(&out_path).unwrap() == decode_from_slice_with_options(&filter_test_woff(), &options).unwrap());
+        let _ = std::fs::remove_file(in_path);
+        let _ = std::fs::remove_file(out_path);
+    }
+
+    #[test]
+    fn test_load_font() {
+        let tables = vec![(tag(b"head"), head_table(1000, 0)), (tag(b"maxp"), maxp_table(4, 0))];
+        let sfnt = build_sfnt(0x00010000, &tables);
+        debug_assert!(load_font(&sfnt).unwrap().as_bytes() == sfnt.as_slice());
+        debug_assert!(load_font(&build_woff(0x00010000, &tables)).unwrap().as_bytes() == sfnt.as_slice());
+        debug_assert!(load_font(&build_collection(&[&tables])).unwrap().maxp().unwrap().num_glyphs == 4);
+        let eot_header = EotHeader { version: 0x00020001, ..Default::default() };
+        debug_assert!(load_font(&build_eot(&eot_header, &sfnt)).unwrap().as_bytes() == sfnt.as_slice());
+        debug_assert!(load_font(b"wOF2\0\x01\0\0").err() == Some(Error::UnsupportedFontFormat));
+        debug_assert!(load_font(b"").err() == Some(Error::UnsupportedFontFormat));
+
+        // decode functions without options pass SFNT data through too
+        debug_assert!(decode_from_slice(&sfnt).unwrap() == sfnt);
+        debug_assert!(decode_to_sfnt(&build_collection(&[&tables])).unwrap().maxp().unwrap().num_glyphs == 4);
+        debug_assert!(decode_from_slice(b"wOF2\0\x01\0\0").err() == Some(Error::UnsupportedFontFormat));
+        debug_assert!(decode_from_slice(b"").err() == Some(Error::InputBufferIsEmpty));
+        debug_assert!(decode_from_slice_with_options(&sfnt, &DecodeOptions::default()).err() == Some(Error::InvalidWoffSignature));
+
+        unsafe {
+            let result = load_font_from_data_wrapped(sfnt.as_ptr(), sfnt.len());
+            debug_assert!((*result).error == Error::None);
+            debug_assert!(std::slice::from_raw_parts((*result).decoded_data, (*result).decoded_data_len) == sfnt.as_slice());
+            destroy_decoded_result(result);
+
+            let result = decode_from_data_wrapped(sfnt.as_ptr(), sfnt.len());
+            debug_assert!((*result).error == Error::None);
+            debug_assert!(std::slice::from_raw_parts((*result).decoded_data, (*result).decoded_data_len) == sfnt.as_slice());
+            destroy_decoded_result(result);
+        }
+    }
+
+    #[test]
+    fn test_decode_to_eot() {
+        let tables = vec![
+            (tag(b"head"), head_table(1000, 0)),
+            (tag(b"name"), name_table(&[(3, 1, 0x409, 1, utf16_be("Test"))], &[])),
+            (tag(b"OS/2"), os2_table(4, 400, 0)),
+        ];
+        let options = EotOptions { root_strings: vec!["http://example.com".to_string()], ..Default::default() };
+        let eot = decode_from_slice_to_eot(&build_woff(0x00010000, &tables), &options).unwrap();
+        debug_assert!(detect_format(&eot) == FontFormat::Eot);
+        debug_assert!(decode_eot_from_slice(&eot).unwrap() == build_sfnt(0x00010000, &tables));
+
+        let in_path = std::env::temp_dir().join("woffdecoder_test_decode_to_eot.woff");
+        let out_path = std::env::temp_dir().join("woffdecoder_test_decode_to_eot.eot");
+        std::fs::write(&in_path, build_woff(0x00010000, &tables)).unwrap();
+        let error = decode_from_file_to_eot_file(in_path.to_str().unwrap(), out_path.to_str().unwrap(), &options);
+        debug_assert!(error == Error::None);
+        debug_assert!(std::fs::read(&out_path).unwrap() == eot);
+        let _ = std::fs::remove_file(in_path);
+        let _ = std::fs::remove_file(out_path);
+    }
+
+    #[test]
+    fn test_lenient_decode() {
+        let tables = vec![
+            (tag(b"cmap"), cmap_table(&[(0x41, 1)], &[])),
+            (tag(b"head"), head_table(1000, 0)),
+            (tag(b"maxp"), maxp_table(2, 0)),
+        ];
+        let mut woff = build_woff(0x00010000, &tables);
+        // wrong `length` and trailing garbage
+        woff.extend_from_slice(&[0xFF; 8]);
+        debug_assert!(decode_from_slice(&woff).err() == Some(Error::InvalidWoffSize));
+
+        let options = DecodeOptions { lenient: true, ..Default::default() };
+        let output = decode_from_slice_with_report(&woff, &options).unwrap();
+        debug_assert!(output.data == build_sfnt(0x00010000, &tables));
+        debug_assert!(output.repairs == vec![Repair::WoffLengthMismatch {
+            declared: woff.len() as u32 - 8,
+            actual: woff.len() as u32,
+        }]);
+
+        // data of the last table is cut off, it's padded with zeros
+        woff.truncate(woff.len() - 8 - 4);
+        let output = decode_from_slice_with_report(&woff, &options).unwrap();
+        let sfnt = Sfnt::from_vec(output.data).unwrap();
+        debug_assert!(sfnt.maxp().is_ok() && sfnt.cmap().is_ok() && sfnt.head().is_ok());
+        debug_assert!(output.repairs.iter().any(|repair| matches!(repair, Repair::TableDataTruncated { tag: table_tag, .. } if *table_tag == tag(b"maxp"))));
+
+        // table data outside of the file is dropped
+        let offset_position = 44 + 2 * 20 + 4;
+        woff[offset_position..offset_position + 4].copy_from_slice(&0x00FF0000u32.to_be_bytes());
+        let output = decode_from_slice_with_report(&woff, &options).unwrap();
+        let sfnt = Sfnt::from_vec(output.data).unwrap();
+        debug_assert!(sfnt.maxp().is_err() && sfnt.cmap().is_ok());
+        debug_assert!(output.repairs.contains(&Repair::TableDropped { tag: tag(b"maxp"), offset: 0x00FF0000 }));
+
+        let c_options = CDecodeOptions {
+            include_tables: std::ptr::null(),
+            include_tables_len: 0,
+            exclude_tables: std::ptr::null(),
+            exclude_tables_len: 0,
+            preserve_table_order: false,
+            lenient: true,
+            sanitize: false,
+            enforce_embedding_permissions: false,
+        };
+        unsafe {
+            let result = decode_from_data_with_options_wrapped(woff.as_ptr(), woff.len(), &c_options);
+            debug_assert!((*result).error == Error::None);
+            debug_assert!(decoded_result_repairs_count(result) == output.repairs.len());
+            let mut repair = CRepair::from(&output.repairs[0]);
+            debug_assert!(!decoded_result_repair(result, output.repairs.len(), &mut repair));
+            let c_repairs: Vec<CRepair> = (0..output.repairs.len())
+                .map(|index| {
+                    debug_assert!(decoded_result_repair(result, index, &mut repair));
+                    repair
+                })
+                .collect();
+            debug_assert!(c_repairs.contains(&CRepair {
+                kind: RepairKind::TableDropped,
+                tag: tag(b"maxp"),
+                offset: 0x00FF0000,
+                declared: 0,
+                actual: 0,
+            }));
+            destroy_decoded_result(result);
+        }
+
+        // strict decoding stays as it was
+        let output = decode_from_slice_with_report(&build_woff(0x00010000, &tables), &DecodeOptions::default()).unwrap();
+        debug_assert!(output.repairs.is_empty());
+    }
+
+    #[test]
+    fn test_decode_warnings() {
+        let tables = vec![
+            (tag(b"head"), head_table(1000, 0)),
+            (tag(b"cmap"), cmap_table(&[(0x41, 1)], &[])),
+            (tag(b"post"), vec![0; 100]),
+        ];
+        let mut woff = build_woff(0x00010000, &tables);
+        // non-zero reserved field
+        woff[14..16].copy_from_slice(&1u16.to_be_bytes());
+        // two extra bytes after zlib stream of the last table
+        let entry = 44 + 2 * 20;
+        let comp_length = u32::from_be_bytes(woff[entry + 8..entry + 12].try_into().unwrap());
+        debug_assert!(comp_length < 100);
+        woff[entry + 8..entry + 12].copy_from_slice(&(comp_length + 2).to_be_bytes());
+        let table_end = u32::from_be_bytes(woff[entry + 4..entry + 8].try_into().unwrap()) + comp_length;
+        woff.truncate(table_end as usize);
+        woff.extend_from_slice(&[0, 0]);
+        let length = woff.len() as u32;
+        woff[8..12].copy_from_slice(&length.to_be_bytes());
+
+        let post_offset = u32::from_be_bytes(woff[entry + 4..entry + 8].try_into().unwrap());
+        let expected = vec![
+            Warning::NonZeroReserved { value: 1 },
+            Warning::UnsortedTableDirectory { tag: tag(b"cmap"), offset: 64 },
+            Warning::MissingRequiredTable { tag: tag(b"hhea") },
+            Warning::MissingRequiredTable { tag: tag(b"hmtx") },
+            Warning::MissingRequiredTable { tag: tag(b"maxp") },
+            Warning::MissingRequiredTable { tag: tag(b"name") },
+            Warning::MissingRequiredTable { tag: tag(b"OS/2") },
+            Warning::MissingRequiredTable { tag: tag(b"glyf") },
+            Warning::MissingRequiredTable { tag: tag(b"loca") },
+            Warning::ExtraDataAfterCompressedStream { tag: tag(b"post"), offset: post_offset, extra_bytes: 2 },
+        ];
+        let output = decode_from_slice_with_report(&woff, &DecodeOptions::default()).unwrap();
+        debug_assert!(output.data == build_sfnt(0x00010000, &tables));
+        debug_assert!(output.warnings == expected);
+
+        unsafe {
+            let result = decode_from_data_wrapped(woff.as_ptr(), woff.len());
+            debug_assert!(decoded_result_warnings_count(result) == expected.len());
+            let mut warning = CWarning { kind: WarningKind::NonZeroReserved, tag: 0, offset: 0, declared: 0, actual: 0 };
+            debug_assert!(decoded_result_warning(result, 1, &mut warning));
+            debug_assert!(warning == CWarning::from(&expected[1]));
+            debug_assert!(warning.kind == WarningKind::UnsortedTableDirectory && warning.offset == 64);
+            debug_assert!(!decoded_result_warning(result, expected.len(), &mut warning));
+            destroy_decoded_result(result);
+        }
+    }
+
+    #[test]
+    fn test_decode_with_sanitize() {
+        let mut tables = truetype_font_tables();
+        let options = DecodeOptions { sanitize: true, ..Default::default() };
+        let output = decode_from_slice_with_report(&build_woff(0x00010000, &tables), &options).unwrap();
+        debug_assert!(output.sanitize_issues.is_empty());
+
+        // 'hmtx' is too short for numberOfHMetrics of 'hhea'
+        tables.iter_mut().find(|(table_tag, _)| *table_tag == tag(b"hmtx")).unwrap().1.truncate(2);
+        let woff = build_woff(0x00010000, &tables);
+        debug_assert!(decode_from_slice(&woff).is_ok());
+        debug_assert!(decode_from_slice_with_report(&woff, &options).err() == Some(Error::SanitizeRejected));
+        let report = sanitize(&decode_to_sfnt(&woff).unwrap());
+        debug_assert!(report.font.is_none() && report.issues[0].tag == tag(b"hmtx"));
+    }
+
+    #[test]
+    fn test_enforce_embedding_permissions() {
+        let mut tables = truetype_font_tables();
+        tables.iter_mut().find(|(table_tag, _)| *table_tag == tag(b"OS/2")).unwrap().1 = os2_table(4, 400, 0x0102);
+        let woff = build_woff(0x00010000, &tables);
+        let output = decode_from_slice_with_report(&woff, &DecodeOptions::default()).unwrap();
+        debug_assert!(output.embedding_permissions == Some(EmbeddingPermissions {
+            level: EmbeddingLevel::Restricted,
+            no_subsetting: true,
+            bitmap_only: false,
+        }));
+
+        // 'OS/2' is checked even if it's filtered out
+        let options = DecodeOptions {
+            exclude_tables: vec![tag(b"OS/2")],
+            enforce_embedding_permissions: true,
+            ..Default::default()
+        };
+        debug_assert!(decode_from_slice_with_options(&woff, &options) == Err(Error::EmbeddingRestricted));
+        let woff = build_woff(0x00010000, &truetype_font_tables());
+        debug_assert!(decode_from_slice_with_options(&woff, &options).is_ok());
+
+        // only the beginning of 'OS/2' table up to fsType is needed
+        let enforce = DecodeOptions { enforce_embedding_permissions: true, ..Default::default() };
+        let short_os2 = [vec![0; 8], 0x0002u16.to_be_bytes().to_vec()].concat();
+        let woff = build_woff(0x00010000, &[(tag(b"head"), head_table(1000, 0)), (tag(b"OS/2"), short_os2)]);
+        debug_assert!(decode_from_slice_with_options(&woff, &enforce) == Err(Error::EmbeddingRestricted));
+
+        // unreadable fsType is reported without enforcement and rejects the font with it
+        let woff = build_woff(0x00010000, &[(tag(b"head"), head_table(1000, 0)), (tag(b"OS/2"), vec![0; 6])]);
+        debug_assert!(decode_from_slice_with_report(&woff, &DecodeOptions::default()).unwrap().embedding_permissions.is_none());
+        debug_assert!(decode_from_slice_with_options(&woff, &enforce) == Err(Error::InvalidTableData));
+
+        // 'OS/2' data cut by the end of WOFF data
+        let mut woff = build_woff(0x00010000, &[(tag(b"head"), head_table(1000, 0)), (tag(b"OS/2"), os2_table(4, 400, 0x0002))]);
+        let os2_offset = u32::from_be_bytes(woff[44 + 20 + 4..44 + 20 + 8].try_into().unwrap()) as usize;
+        woff.truncate(os2_offset + 5);
+        let lenient = DecodeOptions { lenient: true, ..Default::default() };
+        let output = decode_from_slice_with_report(&woff, &lenient).unwrap();
+        debug_assert!(output.embedding_permissions.is_none());
+        let lenient_enforce = DecodeOptions { enforce_embedding_permissions: true, ..lenient };
+        debug_assert!(decode_from_slice_with_options(&woff, &lenient_enforce) == Err(Error::InvalidTableData));
+    }
+
+    fn unordered_test_tables() -> Vec<(u32, Vec<u8>)> {
+        vec![
+            (tag(b"head"), head_table(1000, 0)),
+            (tag(b"hhea"), hhea_table(800, -200, 0, 2)),
+            (tag(b"maxp"), maxp_table(2, 0)),
+            (tag(b"OS/2"), vec![1; 10]),
+            (tag(b"cmap"), cmap_table(&[(0x41, 1), (0x42, 2)], &[])),
+            (tag(b"name"), vec![0; 6]),
+        ]
+    }
+
+    #[test]
+    fn test_decode_preserves_table_order() {
+        let options = DecodeOptions { preserve_table_order: true, ..Default::default() };
+        let original = build_sfnt_in_order(0x00010000, &unordered_test_tables());
+        let decoded = decode_from_slice_with_options(&sfnt_to_woff(&original), &options).unwrap();
+        debug_assert!(decoded == original);
+
+        // tag-ordered layout is reproduced as well
+        let original = build_sfnt(0x00010000, &unordered_test_tables());
+        let decoded = decode_from_slice_with_options(&sfnt_to_woff(&original), &options).unwrap();
+        debug_assert!(decoded == original);
+    }
+
+    #[test]
+    fn test_decode_preserves_table_order_with_filtering() {
+        let options = DecodeOptions {
+            exclude_tables: vec![tag(b"hhea")],
+            preserve_table_order: true,
+            ..Default::default()
+        };
+        let mut tables = unordered_test_tables();
+        let woff = sfnt_to_woff(&build_sfnt_in_order(0x00010000, &tables));
+        tables.retain(|(table_tag, _)| *table_tag != tag(b"hhea"));
+        let mut expected = build_sfnt_in_order(0x00010000, &tables);
+        set_check_sum_adjustment(&mut expected);
+        debug_assert!(decode_from_slice_with_options(&woff, &options).unwrap() == expected);
+
+        // default mode still lays out data in tag order
+        let decoded = decode_from_slice(&woff).unwrap();
+        debug_assert!(decoded == build_sfnt(0x00010000, &unordered_test_tables()));
     }
 }
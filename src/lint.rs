@@ -0,0 +1,422 @@
+//! Font quality checks (lint rules) over decoded tables
+
+use crate::font::FontTables;
+use crate::structures::SfntHeaderTable;
+use crate::tables::hhea::HheaTable;
+use crate::tables::maxp::MaxpTable;
+use crate::tables::name::NameTable;
+use crate::tables::os2::{EmbeddingLevel, Os2Table};
+use crate::tables::post::{PostGlyphNames, NUM_STANDARD_NAMES, POST_VERSION_2_0};
+use crate::utils::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Severity of lint finding
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Lowercase name used in JSON report
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// Single problem found by lint rule
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LintFinding {
+    pub rule_id: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Lint rule: checks font tables and returns findings
+pub struct LintRule {
+    pub id: &'static str,
+    pub description: &'static str,
+    check: fn(&LintContext) -> Vec<LintFinding>,
+}
+
+/// All findings of lint run
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LintReport {
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    /// Checks if there is at least one finding with `Error` severity
+    pub fn has_errors(&self) -> bool {
+        self.findings.iter().any(|finding| finding.severity == Severity::Error)
+    }
+
+    /// Serializes report to JSON:
+    /// `{"findings":[{"rule_id":"...","severity":"warning","message":"..."}]}`
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"findings\":[");
+        for (index, finding) in self.findings.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"rule_id\":");
+            write_json_string(&mut json, finding.rule_id);
+            json.push_str(",\"severity\":");
+            write_json_string(&mut json, finding.severity.as_str());
+            json.push_str(",\"message\":");
+            write_json_string(&mut json, &finding.message);
+            json.push('}');
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+/// Writes quoted and escaped JSON string
+fn write_json_string(json: &mut String, value: &str) {
+    json.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", ch as u32);
+            }
+            ch => json.push(ch),
+        }
+    }
+    json.push('"');
+}
+
+/// Tables used by lint rules. Missing or broken tables are `None`
+struct LintContext {
+    head: Option<SfntHeaderTable>,
+    hhea: Option<HheaTable>,
+    maxp: Option<MaxpTable>,
+    os2: Option<Os2Table>,
+    name: Option<NameTable>,
+    post_data: Option<Vec<u8>>,
+    // outline data of .notdef glyph, `None` if it can't be found
+    notdef_outline_len: Option<usize>,
+}
+
+/// Rules run by `lint` in this order
+pub const LINT_RULES: &[LintRule] = &[
+    LintRule {
+        id: "vertical-metrics",
+        description: "'hhea' ascender, descender and line gap match typographic metrics of 'OS/2'",
+        check: check_vertical_metrics,
+    },
+    LintRule {
+        id: "weight-class",
+        description: "usWeightClass of 'OS/2' matches the style name",
+        check: check_weight_class,
+    },
+    LintRule {
+        id: "fstype",
+        description: "fsType of 'OS/2' allows installable embedding",
+        check: check_fs_type,
+    },
+    LintRule {
+        id: "post-glyph-names",
+        description: "'post' glyph names are present for every glyph, valid and unique",
+        check: check_post_glyph_names,
+    },
+    LintRule {
+        id: "notdef",
+        description: "glyph 0 is '.notdef' and has an outline",
+        check: check_notdef,
+    },
+    LintRule {
+        id: "duplicate-name-records",
+        description: "'name' has no records with the same platform, encoding, language and name ID",
+        check: check_duplicate_name_records,
+    },
+    LintRule {
+        id: "units-per-em",
+        description: "unitsPerEm of 'head' is a power of two in 16..16384 range",
+        check: check_units_per_em,
+    },
+];
+
+/// Runs all lint rules over the font tables
+pub fn lint<F: FontTables + ?Sized>(font: &F) -> LintReport {
+    let notdef_outline_len = if font.has_table(tag(b"glyf")) {
+        font.truetype_glyphs().ok().and_then(|glyphs| glyphs.glyph_data(0).ok().map(|data| data.len()))
+    } else {
+        None
+    };
+    let context = LintContext {
+        head: font.head().ok(),
+        hhea: font.hhea().ok(),
+        maxp: font.maxp().ok(),
+        os2: font.os2().ok(),
+        name: font.name().ok(),
+        post_data: font.table_data(tag(b"post")).ok().map(|data| data.into_owned()),
+        notdef_outline_len,
+    };
+    LintReport {
+        findings: LINT_RULES.iter().flat_map(|rule| (rule.check)(&context)).collect(),
+    }
+}
+
+fn finding(rule_id: &'static str, severity: Severity, message: String) -> LintFinding {
+    LintFinding { rule_id, severity, message }
+}
+
+fn check_vertical_metrics(context: &LintContext) -> Vec<LintFinding> {
+    let (hhea, os2) = match (&context.hhea, &context.os2) {
+        (Some(hhea), Some(os2)) => (hhea, os2),
+        _ => return vec![],
+    };
+    let (typo_ascender, typo_descender, typo_line_gap) =
+        match (os2.s_typo_ascender, os2.s_typo_descender, os2.s_typo_line_gap) {
+            (Some(ascender), Some(descender), Some(line_gap)) => (ascender, descender, line_gap),
+            _ => return vec![],
+        };
+    [
+        ("ascender", hhea.ascender, "sTypoAscender", typo_ascender),
+        ("descender", hhea.descender, "sTypoDescender", typo_descender),
+        ("lineGap", hhea.line_gap, "sTypoLineGap", typo_line_gap),
+    ]
+    .iter()
+    .filter(|(_, hhea_value, _, os2_value)| hhea_value != os2_value)
+    .map(|(hhea_name, hhea_value, os2_name, os2_value)| {
+        finding("vertical-metrics", Severity::Warning,
+                format!("'hhea' {} is {} but 'OS/2' {} is {}", hhea_name, hhea_value, os2_name, os2_value))
+    })
+    .collect()
+}
+
+// style name keywords and weight classes they imply; longer keywords go first
+const WEIGHT_KEYWORDS: [(&str, u16); 16] = [
+    ("extralight", 200), ("ultralight", 200), ("semibold", 600), ("demibold", 600),
+    ("extrabold", 800), ("ultrabold", 800), ("hairline", 100), ("regular", 400),
+    ("normal", 400), ("medium", 500), ("black", 900), ("heavy", 900),
+    ("light", 300), ("thin", 100), ("bold", 700), ("book", 400),
+];
+
+fn check_weight_class(context: &LintContext) -> Vec<LintFinding> {
+    let (os2, name) = match (&context.os2, &context.name) {
+        (Some(os2), Some(name)) => (os2, name),
+        _ => return vec![],
+    };
+    let style = match name.subfamily_name(&["en-US", "en"]) {
+        Some(style) => style.to_ascii_lowercase().replace([' ', '-'], ""),
+        None => return vec![],
+    };
+    // "Italic" alone means regular weight
+    let expected = WEIGHT_KEYWORDS
+        .iter()
+        .find(|(keyword, _)| style.contains(keyword))
+        .map(|(_, weight)| *weight)
+        .or(if style == "italic" || style == "oblique" { Some(400) } else { None });
+    match expected {
+        Some(weight) if weight != os2.us_weight_class => vec![finding(
+            "weight-class",
+            Severity::Warning,
+            format!("usWeightClass is {} but style name '{}' implies {}", os2.us_weight_class, style, weight),
+        )],
+        _ => vec![],
+    }
+}
+
+fn check_fs_type(context: &LintContext) -> Vec<LintFinding> {
+    let os2 = match &context.os2 {
+        Some(os2) => os2,
+        None => return vec![],
+    };
+    match os2.embedding_permissions().level {
+        EmbeddingLevel::Restricted => vec![finding("fstype", Severity::Error, format!(
+            "fsType is 0x{:04X}: restricted license embedding, the font must not be embedded", os2.fs_type))],
+        EmbeddingLevel::PreviewAndPrint | EmbeddingLevel::Editable => vec![finding("fstype", Severity::Warning, format!(
+            "fsType is 0x{:04X}: embedding is restricted, web fonts should use 0 (installable)", os2.fs_type))],
+        EmbeddingLevel::Installable => vec![],
+    }
+}
+
+/// Checks if the glyph name has only characters allowed by AGL specification
+fn is_valid_glyph_name(name: &[u8]) -> bool {
+    !name.is_empty()
+        && name.len() <= 63
+        && !name[0].is_ascii_digit()
+        && name.iter().all(|byte| byte.is_ascii_alphanumeric() || *byte == b'.' || *byte == b'_' || *byte == b'-')
+}
+
+fn check_post_glyph_names(context: &LintContext) -> Vec<LintFinding> {
+    let data = match &context.post_data {
+        Some(data) if try_read_u32_at(data, 0) == Some(POST_VERSION_2_0) => data,
+        _ => return vec![],
+    };
+    let rule = "post-glyph-names";
+    let names = match PostGlyphNames::parse(data) {
+        Ok(names) => names,
+        Err(_) => return vec![finding(rule, Severity::Error, "'post' glyph names data is broken".to_string())],
+    };
+
+    let mut findings: Vec<LintFinding> = vec![];
+    if let Some(maxp) = &context.maxp {
+        if names.name_indices.len() != maxp.num_glyphs as usize {
+            findings.push(finding(rule, Severity::Error, format!(
+                "'post' has names for {} glyphs but 'maxp' numGlyphs is {}", names.name_indices.len(), maxp.num_glyphs)));
+        }
+    }
+    let mut seen: BTreeMap<u16, u16> = BTreeMap::new();
+    for (glyph_id, &index) in names.name_indices.iter().enumerate() {
+        if index >= NUM_STANDARD_NAMES {
+            match names.names.get((index - NUM_STANDARD_NAMES) as usize) {
+                Some(name) if !is_valid_glyph_name(name) => findings.push(finding(rule, Severity::Warning, format!(
+                    "glyph {} has invalid name '{}'", glyph_id, String::from_utf8_lossy(name)))),
+                Some(_) => {}
+                None => {
+                    findings.push(finding(rule, Severity::Error, format!(
+                        "glyph {} refers to missing custom name {}", glyph_id, index - NUM_STANDARD_NAMES)));
+                    continue;
+                }
+            }
+        }
+        // the same name index means the same name
+        if let Some(first) = seen.insert(index, glyph_id as u16) {
+            findings.push(finding(rule, Severity::Warning,
+                                  format!("glyphs {} and {} have the same name", first, glyph_id)));
+            seen.insert(index, first);
+        }
+    }
+    let custom: Vec<&Vec<u8>> = names.names.iter().collect();
+    let unique: BTreeSet<&Vec<u8>> = custom.iter().copied().collect();
+    if unique.len() != custom.len() {
+        findings.push(finding(rule, Severity::Warning, "'post' has duplicate custom glyph names".to_string()));
+    }
+    findings
+}
+
+fn check_notdef(context: &LintContext) -> Vec<LintFinding> {
+    let mut findings: Vec<LintFinding> = vec![];
+    if let Some(data) = context.post_data.as_ref().filter(|data| try_read_u32_at(data, 0) == Some(POST_VERSION_2_0)) {
+        if let Ok(names) = PostGlyphNames::parse(data) {
+            let is_notdef = match names.name_indices.first() {
+                // standard name 0 is .notdef
+                Some(0) => true,
+                Some(&index) if index >= NUM_STANDARD_NAMES => {
+                    names.names.get((index - NUM_STANDARD_NAMES) as usize).map(|name| name == b".notdef").unwrap_or(false)
+                }
+                _ => false,
+            };
+            if !is_notdef {
+                findings.push(finding("notdef", Severity::Error, "glyph 0 is not named '.notdef'".to_string()));
+            }
+        }
+    }
+    if context.notdef_outline_len == Some(0) {
+        findings.push(finding("notdef", Severity::Warning, "'.notdef' glyph has no outline".to_string()));
+    }
+    findings
+}
+
+fn check_duplicate_name_records(context: &LintContext) -> Vec<LintFinding> {
+    let name = match &context.name {
+        Some(name) => name,
+        None => return vec![],
+    };
+    let mut seen: BTreeSet<(u16, u16, u16, u16)> = BTreeSet::new();
+    name.records
+        .iter()
+        .filter(|record| !seen.insert((record.platform_id, record.encoding_id, record.language_id, record.name_id)))
+        .map(|record| finding("duplicate-name-records", Severity::Error, format!(
+            "duplicate name record: platform {}, encoding {}, language 0x{:04X}, name ID {}",
+            record.platform_id, record.encoding_id, record.language_id, record.name_id)))
+        .collect()
+}
+
+fn check_units_per_em(context: &LintContext) -> Vec<LintFinding> {
+    let units_per_em = match &context.head {
+        Some(head) => head.units_per_em,
+        None => return vec![],
+    };
+    if !(16..=16384).contains(&units_per_em) {
+        vec![finding("units-per-em", Severity::Error,
+                      format!("unitsPerEm {} is outside of 16..16384 range", units_per_em))]
+    } else if !units_per_em.is_power_of_two() {
+        vec![finding("units-per-em", Severity::Info,
+                      format!("unitsPerEm {} is not a power of two", units_per_em))]
+    } else {
+        vec![]
+    }
+}
+
+/// Reads big-endian u32 value at `offset`
+fn try_read_u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    try_slice(data, offset, 4).ok().map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Sfnt;
+    use crate::test_utils::*;
+
+    fn post_v2_table(name_indices: &[u16], names: &[&[u8]]) -> Vec<u8> {
+        let mut data = post_table(0, -100);
+        data[..4].copy_from_slice(&POST_VERSION_2_0.to_be_bytes());
+        let glyph_names = PostGlyphNames {
+            name_indices: name_indices.to_vec(),
+            names: names.iter().map(|name| name.to_vec()).collect(),
+        };
+        data.extend(glyph_names.transform_to_u8_vec());
+        data
+    }
+
+    fn lint_tables(tables: Vec<(u32, Vec<u8>)>) -> LintReport {
+        lint(&Sfnt::from_tables(0x00010000, tables).unwrap())
+    }
+
+    fn rule_ids(report: &LintReport) -> Vec<&'static str> {
+        report.findings.iter().map(|finding| finding.rule_id).collect()
+    }
+
+    #[test]
+    fn test_lint_clean_font() {
+        let report = lint_tables(vec![
+            (tag(b"head"), head_table(1024, 1)),
+            (tag(b"hhea"), hhea_table(800, -200, 100, 2)),
+            (tag(b"maxp"), maxp_table(2, 10)),
+            (tag(b"OS/2"), os2_table(4, 700, 0)),
+            (tag(b"name"), name_table(&[(3, 1, 0x409, 2, utf16_be("Bold"))], &[])),
+            (tag(b"post"), post_v2_table(&[0, NUM_STANDARD_NAMES], &[b"uni4E00"])),
+        ]);
+        debug_assert!(report.findings.is_empty());
+        debug_assert!(report.to_json() == "{\"findings\":[]}");
+    }
+
+    #[test]
+    fn test_lint_findings() {
+        let report = lint_tables(vec![
+            (tag(b"head"), head_table(1000, 1)),
+            (tag(b"hhea"), hhea_table(900, -200, 0, 3)),
+            (tag(b"maxp"), maxp_table(3, 10)),
+            (tag(b"OS/2"), os2_table(4, 400, 0x0002)),
+            (tag(b"name"), name_table(&[
+                (3, 1, 0x409, 2, utf16_be("Semi Bold")),
+                (3, 1, 0x409, 2, utf16_be("Semi Bold")),
+            ], &[])),
+            (tag(b"post"), post_v2_table(&[NUM_STANDARD_NAMES, 3, 3], &[b"1\"bad"])),
+        ]);
+        debug_assert!(rule_ids(&report) == vec![
+            "vertical-metrics", "vertical-metrics", "weight-class", "fstype", "post-glyph-names",
+            "post-glyph-names", "notdef", "duplicate-name-records", "units-per-em",
+        ]);
+        debug_assert!(report.has_errors());
+        debug_assert!(report.findings[8].severity == Severity::Info);
+        debug_assert!(report.to_json().contains("\"message\":\"glyph 0 has invalid name '1\\\"bad'\""));
+    }
+}
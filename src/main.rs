@@ -23,6 +23,15 @@ fn handle_error(err: Error) {
         Error::ReadFromFileError => { println!("Read from file error") }
         Error::InputPathError => { println!("Input file path error") }
         Error::OutputPathError => { println!("Output file path error") }
+        Error::TableNotFound => { println!("Table not found") }
+        Error::InvalidTableData => { println!("Invalid table data") }
+        Error::InvalidHeadMagicNumber => { println!("Invalid 'head' table magic number") }
+        Error::InvalidSfntStructure => { println!("Invalid SFNT structure") }
+        Error::UnsupportedFontFormat => { println!("Unsupported font format") }
+        Error::InvalidEotStructure => { println!("Invalid EOT structure") }
+        Error::UnsupportedEotCompression => { println!("Unsupported EOT compression") }
+        Error::SanitizeRejected => { println!("Font is rejected by sanitizer") }
+        Error::EmbeddingRestricted => { println!("Font embedding is restricted by its license") }
     }
 }
 
@@ -0,0 +1,132 @@
+//! Summary of font metrics needed to lay out text before the font itself is loaded
+
+use crate::font::FontTables;
+use crate::tables::os2::FS_SELECTION_USE_TYPO_METRICS;
+use crate::Error;
+
+/// Font metrics combined from 'head', 'hhea', 'maxp', 'OS/2' and 'post' tables.
+/// All values are in font units. Values from optional tables (or table versions) are optional
+#[derive(Clone, Debug, PartialEq)]
+pub struct FontMetrics {
+    pub units_per_em: u16,
+    // 'hhea' ascender, descender and line gap
+    pub ascender: i16,
+    pub descender: i16,
+    pub line_gap: i16,
+    // 'OS/2' typographic metrics
+    pub typo_ascender: Option<i16>,
+    pub typo_descender: Option<i16>,
+    pub typo_line_gap: Option<i16>,
+    // Set if 'OS/2' fsSelection USE_TYPO_METRICS bit is set
+    pub use_typo_metrics: bool,
+    // 'OS/2' Windows metrics
+    pub win_ascent: Option<u16>,
+    pub win_descent: Option<u16>,
+    pub x_height: Option<i16>,
+    pub cap_height: Option<i16>,
+    pub weight_class: Option<u16>,
+    pub width_class: Option<u16>,
+    // In counter-clockwise degrees from the vertical
+    pub italic_angle: Option<f32>,
+    pub underline_position: Option<i16>,
+    pub underline_thickness: Option<i16>,
+    pub strikeout_position: Option<i16>,
+    pub strikeout_size: Option<i16>,
+    pub num_glyphs: u16,
+}
+
+impl FontMetrics {
+    /// Collects metrics from font tables. 'head', 'hhea' and 'maxp' tables are required
+    pub(crate) fn from_font<F: FontTables + ?Sized>(font: &F) -> Result<Self, Error> {
+        let head = font.head()?;
+        let hhea = font.hhea()?;
+        let maxp = font.maxp()?;
+        let os2 = match font.os2() {
+            Ok(os2) => Some(os2),
+            Err(Error::TableNotFound) => None,
+            Err(err) => return Err(err),
+        };
+        let post = match font.post() {
+            Ok(post) => Some(post),
+            Err(Error::TableNotFound) => None,
+            Err(err) => return Err(err),
+        };
+
+        Ok(FontMetrics {
+            units_per_em: head.units_per_em,
+            ascender: hhea.ascender,
+            descender: hhea.descender,
+            line_gap: hhea.line_gap,
+            typo_ascender: os2.as_ref().and_then(|os2| os2.s_typo_ascender),
+            typo_descender: os2.as_ref().and_then(|os2| os2.s_typo_descender),
+            typo_line_gap: os2.as_ref().and_then(|os2| os2.s_typo_line_gap),
+            use_typo_metrics: os2.as_ref().is_some_and(|os2| os2.fs_selection & FS_SELECTION_USE_TYPO_METRICS != 0),
+            win_ascent: os2.as_ref().and_then(|os2| os2.us_win_ascent),
+            win_descent: os2.as_ref().and_then(|os2| os2.us_win_descent),
+            x_height: os2.as_ref().and_then(|os2| os2.sx_height),
+            cap_height: os2.as_ref().and_then(|os2| os2.s_cap_height),
+            weight_class: os2.as_ref().map(|os2| os2.us_weight_class),
+            width_class: os2.as_ref().map(|os2| os2.us_width_class),
+            italic_angle: post.as_ref().map(|post| post.italic_angle),
+            underline_position: post.as_ref().map(|post| post.underline_position),
+            underline_thickness: post.as_ref().map(|post| post.underline_thickness),
+            strikeout_position: os2.as_ref().map(|os2| os2.y_strikeout_position),
+            strikeout_size: os2.as_ref().map(|os2| os2.y_strikeout_size),
+            num_glyphs: maxp.num_glyphs,
+        })
+    }
+
+    /// Line height (ascender - descender + line gap) which browsers use for this font:
+    /// typographic metrics if USE_TYPO_METRICS flag is set and 'hhea' metrics otherwise
+    pub fn line_height(&self) -> i32 {
+        match (self.use_typo_metrics, self.typo_ascender, self.typo_descender, self.typo_line_gap) {
+            (true, Some(ascender), Some(descender), Some(line_gap)) => {
+                ascender as i32 - descender as i32 + line_gap as i32
+            }
+            _ => self.ascender as i32 - self.descender as i32 + self.line_gap as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+    use crate::utils::tag;
+    use crate::{decode_to_sfnt, Error, FontTables, WoffFont};
+
+    #[test]
+    fn test_metrics_from_woff_and_sfnt() {
+        let woff = build_woff(0x00010000, &[
+            (tag(b"head"), head_table(1000, 0)),
+            (tag(b"hhea"), hhea_table(900, -250, 50, 3)),
+            (tag(b"maxp"), maxp_table(3, 10)),
+            (tag(b"OS/2"), os2_table(4, 700, 0)),
+            (tag(b"post"), post_table(-12 << 16, -75)),
+        ]);
+        let metrics = WoffFont::new(&woff).unwrap().metrics().unwrap();
+        debug_assert!(metrics == decode_to_sfnt(&woff).unwrap().metrics().unwrap());
+        debug_assert!(metrics.units_per_em == 1000 && metrics.num_glyphs == 3);
+        debug_assert!((metrics.ascender, metrics.descender, metrics.line_gap) == (900, -250, 50));
+        debug_assert!(metrics.typo_ascender == Some(800) && metrics.win_descent == Some(300));
+        debug_assert!(metrics.x_height == Some(500) && metrics.cap_height == Some(700));
+        debug_assert!(metrics.weight_class == Some(700) && metrics.width_class == Some(5));
+        debug_assert!(metrics.italic_angle == Some(-12.0) && metrics.underline_position == Some(-75));
+        debug_assert!(metrics.use_typo_metrics && metrics.line_height() == 1100);
+    }
+
+    #[test]
+    fn test_metrics_without_optional_tables() {
+        let woff = build_woff(0x00010000, &[
+            (tag(b"head"), head_table(2048, 0)),
+            (tag(b"hhea"), hhea_table(1900, -500, 0, 1)),
+            (tag(b"maxp"), maxp_table(1, 0)),
+            (tag(b"OS/2"), os2_table(0, 400, 0)),
+        ]);
+        let metrics = WoffFont::new(&woff).unwrap().metrics().unwrap();
+        debug_assert!(metrics.weight_class == Some(400) && metrics.typo_ascender.is_none());
+        debug_assert!(metrics.italic_angle.is_none() && metrics.line_height() == 2400);
+
+        let woff = build_woff(0x00010000, &[(tag(b"head"), head_table(2048, 0))]);
+        debug_assert!(WoffFont::new(&woff).unwrap().metrics() == Err(Error::TableNotFound));
+    }
+}
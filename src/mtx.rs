@@ -0,0 +1,99 @@
+//! MicroType Express (MTX) decompression of EOT font data.
+//!
+//! MTX data has a 10 bytes header followed by three LZCOMP compressed blocks: the font in
+//! compact table format (CTF), push values of TrueType instructions and the rest of instructions.
+//! The blocks are decompressed separately and the font is rebuilt to plain SFNT from them
+
+pub(crate) mod ctf;
+pub(crate) mod lzcomp;
+
+use crate::Error;
+
+/// The only supported number of blocks: font data, push values and instructions
+pub(crate) const MTX_NUM_BLOCKS: u8 = 3;
+/// numBlocks (8 bits), copyLimit, offset2 and offset3 (24 bits each)
+pub(crate) const MTX_HEADER_SIZE: usize = 10;
+
+/// Decompresses MTX data to SFNT data. Returns `UnsupportedEotCompression` error
+/// for unknown number of blocks and `InvalidEotStructure` error for broken compressed data
+pub(crate) fn decompress_mtx(data: &[u8]) -> Result<Vec<u8>, Error> {
+    if data.len() < MTX_HEADER_SIZE {
+        return Err(Error::InvalidEotStructure);
+    }
+    if data[0] != MTX_NUM_BLOCKS {
+        return Err(Error::UnsupportedEotCompression);
+    }
+    let read_u24 = |offset: usize| u32::from_be_bytes([0, data[offset], data[offset + 1], data[offset + 2]]) as usize;
+    // the longest copy distance the blocks were compressed with
+    let copy_limit = read_u24(1);
+    let offset2 = read_u24(4);
+    let offset3 = read_u24(7);
+    if offset2 < MTX_HEADER_SIZE || offset3 < offset2 || offset3 > data.len() {
+        return Err(Error::InvalidEotStructure);
+    }
+
+    let font_data = lzcomp::decompress(&data[MTX_HEADER_SIZE..offset2], copy_limit)?;
+    let push_data = lzcomp::decompress(&data[offset2..offset3], copy_limit)?;
+    let code_data = lzcomp::decompress(&data[offset3..], copy_limit)?;
+    ctf::reconstruct_font(font_data, &push_data, &code_data)
+}
+
+/// Reader of bits from the most significant bit of every byte
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        BitReader { data, position: 0 }
+    }
+
+    pub(crate) fn read_bit(&mut self) -> Result<bool, Error> {
+        let byte = self.data.get(self.position / 8).ok_or(Error::InvalidEotStructure)?;
+        let bit = byte & (0x80 >> (self.position % 8)) != 0;
+        self.position += 1;
+        Ok(bit)
+    }
+
+    /// Reads `bit_count` bits value, the most significant bit first
+    pub(crate) fn read_value(&mut self, bit_count: u32) -> Result<u32, Error> {
+        let mut value = 0u32;
+        for _ in 0..bit_count {
+            value = (value << 1) | self.read_bit()? as u32;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Sfnt;
+    use crate::test_utils::*;
+    use crate::utils::tag;
+
+    #[test]
+    fn test_decompress_mtx_errors() {
+        let sfnt = Sfnt::from_tables(0x00010000, vec![(tag(b"head"), head_table(1000, 0))]).unwrap().into_vec();
+        let mut mtx = mtx_data(&sfnt, false);
+        debug_assert!(decompress_mtx(&mtx).unwrap() == sfnt);
+        debug_assert!(decompress_mtx(&mtx[..MTX_HEADER_SIZE - 1]).err() == Some(Error::InvalidEotStructure));
+        debug_assert!(decompress_mtx(&mtx[..mtx.len() - 1]).err() == Some(Error::InvalidEotStructure));
+
+        // offset3 points outside of the data
+        mtx[7] = 0xFF;
+        debug_assert!(decompress_mtx(&mtx).err() == Some(Error::InvalidEotStructure));
+        mtx[0] = 2;
+        debug_assert!(decompress_mtx(&mtx).err() == Some(Error::UnsupportedEotCompression));
+    }
+
+    #[test]
+    fn test_bit_reader() {
+        let mut bits = BitReader::new(&[0b1010_0000, 0xFF]);
+        debug_assert!(bits.read_bit().unwrap() && !bits.read_bit().unwrap());
+        debug_assert!(bits.read_value(10).unwrap() == 0b10_0000_1111);
+        debug_assert!(bits.read_value(4).unwrap() == 0xF);
+        debug_assert!(bits.read_bit().err() == Some(Error::InvalidEotStructure));
+    }
+}
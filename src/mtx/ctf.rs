@@ -0,0 +1,491 @@
+//! Compact table format (CTF) of MTX font data.
+//!
+//! 'glyf' table stores glyphs with triplet encoded coordinates, their push values and the rest of
+//! instructions are in the separate blocks. 'loca' table is rebuilt from the glyphs, 'cvt ' values
+//! are delta encoded, 'hdmx' widths and 'VDMX' heights are stored as differences from the values
+//! predicted from 'hmtx' and 'head' tables. Other tables are stored as they are
+
+use crate::font::{FontTables, Sfnt};
+use crate::mtx::BitReader;
+use crate::outline::GlyphPoint;
+use crate::structures::{SfntHeaderTable, HEAD_INDEX_TO_LOC_FORMAT_OFFSET};
+use crate::tables::glyf::*;
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+// 255UShort codes: 16-bit value follows, one byte follows which is added to 253 or 506
+pub(crate) const WORD_CODE: u8 = 253;
+pub(crate) const ONE_MORE_BYTE_CODE1: u8 = 255;
+pub(crate) const ONE_MORE_BYTE_CODE2: u8 = 254;
+pub(crate) const LOWEST_U_CODE: u16 = 253;
+// 255Short codes: negative value follows, one byte follows which is added to 250 or 500
+pub(crate) const FLIP_SIGN_CODE: u8 = 250;
+pub(crate) const LOWEST_S_CODE: i16 = 250;
+/// Push values A X A are stored as A, HOP3_CODE, X
+pub(crate) const HOP3_CODE: u8 = 251;
+/// Push values A X A Y A are stored as A, HOP4_CODE, X, Y
+pub(crate) const HOP4_CODE: u8 = 252;
+/// Bit of the triplet flag which is set for off-curve points
+pub(crate) const OFF_CURVE_FLAG: u8 = 0x80;
+// 'cvt ' delta codes: positive delta CVT_STEP * (code - CVT_POS1 + 1) + next byte,
+// negative delta -(CVT_STEP * (code - CVT_NEG0) + next byte) and 16-bit delta
+pub(crate) const CVT_POS1: u8 = 238;
+pub(crate) const CVT_NEG0: u8 = 246;
+pub(crate) const CVT_NEG8: u8 = 254;
+pub(crate) const CVT_WORD_CODE: u8 = 255;
+pub(crate) const CVT_STEP: i32 = 238;
+// TrueType push instructions
+const NPUSHB: u8 = 0x40;
+const NPUSHW: u8 = 0x41;
+const PUSHB: u8 = 0xB0;
+const PUSHW: u8 = 0xB8;
+// PUSHB and PUSHW push up to 8 values
+const MAX_SHORT_PUSH: usize = 8;
+/// Magnitude of 'hdmx' width difference is stored in unary
+const MAX_WIDTH_DIFFERENCE: i32 = 255;
+
+/// Rebuilds SFNT data from CTF font data, push values and instructions blocks
+pub(crate) fn reconstruct_font(font_data: Vec<u8>, push_data: &[u8], code_data: &[u8]) -> Result<Vec<u8>, Error> {
+    let font = Sfnt::from_vec(font_data)?;
+    let glyf = if font.has_table(tag(b"glyf")) {
+        Some(decode_glyf(&font.table_data(tag(b"glyf"))?, font.maxp()?.num_glyphs, push_data, code_data)?)
+    } else {
+        None
+    };
+
+    let mut tables: Vec<(u32, Vec<u8>)> = vec![];
+    for table_tag in font.table_tags() {
+        let data = font.table_data(table_tag)?;
+        let table = match &glyf {
+            // 'loca' is rebuilt along with 'glyf'
+            _ if table_tag == tag(b"loca") => continue,
+            Some((glyf, _, _)) if table_tag == tag(b"glyf") => glyf.clone(),
+            Some((_, _, index_to_loc_format)) if table_tag == tag(b"head") => {
+                font.head()?;
+                let mut head = data.into_owned();
+                head[HEAD_INDEX_TO_LOC_FORMAT_OFFSET..HEAD_INDEX_TO_LOC_FORMAT_OFFSET + 2]
+                    .copy_from_slice(&index_to_loc_format.to_be_bytes());
+                head
+            }
+            _ if table_tag == tag(b"cvt ") => decode_cvt(&data)?,
+            _ if table_tag == tag(b"hdmx") => decode_hdmx(&data, font.head()?.units_per_em, &font.hmtx()?.advances)?,
+            _ if table_tag == tag(b"VDMX") => decode_vdmx(&data, &font.head()?)?,
+            _ => data.into_owned(),
+        };
+        tables.push((table_tag, table));
+    }
+    if let Some((_, loca, _)) = glyf {
+        tables.push((tag(b"loca"), loca));
+    }
+    Sfnt::from_tables(font.version(), tables).map(Sfnt::into_vec)
+}
+
+/// Push values and instructions blocks which glyphs take their instructions from in order
+struct InstructionStreams<'a> {
+    pushes: ByteReader<'a>,
+    code: ByteReader<'a>,
+}
+
+impl InstructionStreams<'_> {
+    /// Reads numbers of push values and instruction bytes of the glyph and rebuilds its instructions
+    fn read_instructions(&mut self, glyph: &mut ByteReader) -> Result<Vec<u8>, Error> {
+        let push_count = read_255_ushort(glyph)? as usize;
+        let code_size = read_255_ushort(glyph)? as usize;
+        let mut instructions = encode_pushes(&read_push_values(&mut self.pushes, push_count)?);
+        instructions.extend(self.code.read_bytes(code_size).map_err(|_| Error::InvalidTableData)?);
+        if instructions.len() > u16::MAX as usize {
+            return Err(Error::InvalidTableData);
+        }
+        Ok(instructions)
+    }
+}
+
+/// Rebuilds 'glyf' and 'loca' tables and returns them with indexToLocFormat of 'loca'
+fn decode_glyf(data: &[u8], num_glyphs: u16, push_data: &[u8], code_data: &[u8]) -> Result<(Vec<u8>, Vec<u8>, i16), Error> {
+    let mut buffer = ByteReader::from_bytes(data);
+    let mut streams = InstructionStreams {
+        pushes: ByteReader::from_bytes(push_data),
+        code: ByteReader::from_bytes(code_data),
+    };
+    let mut glyphs: Vec<Vec<u8>> = Vec::with_capacity(num_glyphs as usize);
+    for _ in 0..num_glyphs {
+        let number_of_contours = try_read_i16_be(&mut buffer)?;
+        let glyph = match number_of_contours {
+            0 => vec![],
+            1.. => decode_simple_glyph(&mut buffer, number_of_contours as usize, &mut streams)?,
+            _ => decode_composite_glyph(data, &mut buffer, &mut streams)?,
+        };
+        glyphs.push(glyph);
+    }
+    Ok(build_glyf_and_loca(&glyphs))
+}
+
+/// Reads point counts of contours, flags and triplet encoded coordinates of simple glyph.
+/// Glyph bounds are calculated from the points
+fn decode_simple_glyph(buffer: &mut ByteReader, number_of_contours: usize, streams: &mut InstructionStreams) -> Result<Vec<u8>, Error> {
+    let mut end_pts_of_contours: Vec<u16> = Vec::with_capacity(number_of_contours);
+    let mut num_points = 0usize;
+    for _ in 0..number_of_contours {
+        let point_count = read_255_ushort(buffer)? as usize;
+        num_points += point_count;
+        if point_count == 0 || num_points > u16::MAX as usize + 1 {
+            return Err(Error::InvalidTableData);
+        }
+        end_pts_of_contours.push((num_points - 1) as u16);
+    }
+    let flags = buffer.read_bytes(num_points).map_err(|_| Error::InvalidTableData)?;
+
+    let mut points: Vec<GlyphPoint> = Vec::with_capacity(num_points);
+    let mut bounds = [i16::MAX, i16::MAX, i16::MIN, i16::MIN];
+    let (mut x, mut y) = (0i16, 0i16);
+    for flag in flags {
+        let (dx, dy) = read_triplet(buffer, flag & !OFF_CURVE_FLAG)?;
+        x = x.wrapping_add(dx);
+        y = y.wrapping_add(dy);
+        bounds = [bounds[0].min(x), bounds[1].min(y), bounds[2].max(x), bounds[3].max(y)];
+        points.push(GlyphPoint {
+            x: x as f32,
+            y: y as f32,
+            on_curve: flag & OFF_CURVE_FLAG == 0,
+        });
+    }
+    let instructions = streams.read_instructions(buffer)?;
+    let glyph = SimpleGlyph {
+        bounds,
+        end_pts_of_contours,
+        instructions,
+        points,
+        overlap: false,
+    };
+    Ok(glyph.transform_to_u8_vec())
+}
+
+/// Reads (dx, dy) of a point coded by the triplet flag without the off-curve bit.
+/// Bit 0 of the flag is the sign of the first value and bit 1 is the sign of y if both are stored
+fn read_triplet(buffer: &mut ByteReader, flag: u8) -> Result<(i16, i16), Error> {
+    let with_sign = |flag: u8, value: i32| if flag & 1 != 0 { value } else { -value };
+    let mut byte = || try_read_u8(buffer).map(|byte| byte as i32);
+    let flag_value = flag as i32;
+    let (dx, dy) = match flag {
+        0..10 => (0, with_sign(flag, ((flag_value & 14) << 7) + byte()?)),
+        10..20 => (with_sign(flag, (((flag_value - 10) & 14) << 7) + byte()?), 0),
+        20..84 => {
+            let (high, low) = (flag_value - 20, byte()?);
+            (with_sign(flag, 1 + (high & 0x30) + (low >> 4)), with_sign(flag >> 1, 1 + ((high & 0x0C) << 2) + (low & 0x0F)))
+        }
+        84..120 => {
+            let high = flag_value - 84;
+            let dx = with_sign(flag, 1 + ((high / 12) << 8) + byte()?);
+            (dx, with_sign(flag >> 1, 1 + (((high % 12) >> 2) << 8) + byte()?))
+        }
+        120..124 => {
+            let (first, second, third) = (byte()?, byte()?, byte()?);
+            (with_sign(flag, (first << 4) + (second >> 4)), with_sign(flag >> 1, ((second & 0x0F) << 8) + third))
+        }
+        _ => {
+            let (first, second, third, fourth) = (byte()?, byte()?, byte()?, byte()?);
+            (with_sign(flag, (first << 8) + second), with_sign(flag >> 1, (third << 8) + fourth))
+        }
+    };
+    Ok((dx as i16, dy as i16))
+}
+
+/// Copies header and components of composite glyph stored as in 'glyf' table
+/// and rebuilds its instructions
+fn decode_composite_glyph(data: &[u8], buffer: &mut ByteReader, streams: &mut InstructionStreams) -> Result<Vec<u8>, Error> {
+    let start = buffer.get_rpos() - 2;
+    // bounds
+    try_slice(data, buffer.get_rpos(), 8)?;
+    buffer.set_rpos(buffer.get_rpos() + 8);
+    let mut has_instructions = false;
+    loop {
+        let flags = try_read_u16_be(buffer)?;
+        let _glyph_id = try_read_u16_be(buffer)?;
+        let args_size = if flags & ARG_1_AND_2_ARE_WORDS != 0 { 4 } else { 2 };
+        let transform_size = if flags & WE_HAVE_A_SCALE != 0 {
+            2
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            4
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            8
+        } else {
+            0
+        };
+        try_slice(data, buffer.get_rpos(), args_size + transform_size)?;
+        buffer.set_rpos(buffer.get_rpos() + args_size + transform_size);
+        has_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+
+    let mut glyph = data[start..buffer.get_rpos()].to_vec();
+    if has_instructions {
+        let instructions = streams.read_instructions(buffer)?;
+        glyph.extend_from_slice(&(instructions.len() as u16).to_be_bytes());
+        glyph.extend_from_slice(&instructions);
+    }
+    Ok(glyph)
+}
+
+/// Reads 255UShort value: one byte for values below 253, two or three bytes for the others
+fn read_255_ushort(buffer: &mut ByteReader) -> Result<u16, Error> {
+    match try_read_u8(buffer)? {
+        WORD_CODE => try_read_u16_be(buffer),
+        ONE_MORE_BYTE_CODE1 => Ok(LOWEST_U_CODE + try_read_u8(buffer)? as u16),
+        ONE_MORE_BYTE_CODE2 => Ok(LOWEST_U_CODE * 2 + try_read_u8(buffer)? as u16),
+        code => Ok(code as u16),
+    }
+}
+
+/// Reads 255Short value which starts with `code`
+fn read_255_short(code: u8, buffer: &mut ByteReader) -> Result<i16, Error> {
+    let magnitude = |code: u8, buffer: &mut ByteReader| match code {
+        ONE_MORE_BYTE_CODE1 => Ok(LOWEST_S_CODE + try_read_u8(buffer)? as i16),
+        ONE_MORE_BYTE_CODE2 => Ok(LOWEST_S_CODE * 2 + try_read_u8(buffer)? as i16),
+        FLIP_SIGN_CODE..=WORD_CODE => Err(Error::InvalidTableData),
+        code => Ok(code as i16),
+    };
+    match code {
+        WORD_CODE => try_read_i16_be(buffer),
+        FLIP_SIGN_CODE => {
+            let code = try_read_u8(buffer)?;
+            magnitude(code, buffer).map(|value| -value)
+        }
+        code => magnitude(code, buffer),
+    }
+}
+
+/// Reads `count` push values of a glyph expanding hop codes
+fn read_push_values(buffer: &mut ByteReader, count: usize) -> Result<Vec<i16>, Error> {
+    let mut values: Vec<i16> = Vec::with_capacity(count);
+    while values.len() < count {
+        let code = try_read_u8(buffer)?;
+        if code == HOP3_CODE || code == HOP4_CODE {
+            let repeated = *values.last().ok_or(Error::InvalidTableData)?;
+            values.push(read_255_short(try_read_u8(buffer)?, buffer)?);
+            values.push(repeated);
+            if code == HOP4_CODE {
+                values.push(read_255_short(try_read_u8(buffer)?, buffer)?);
+                values.push(repeated);
+            }
+        } else {
+            values.push(read_255_short(code, buffer)?);
+        }
+    }
+    if values.len() != count {
+        return Err(Error::InvalidTableData);
+    }
+    Ok(values)
+}
+
+/// Builds push instructions for the values: runs of byte values are pushed with PUSHB or NPUSHB,
+/// the other runs with PUSHW or NPUSHW
+fn encode_pushes(values: &[i16]) -> Vec<u8> {
+    let is_byte = |value: i16| (0..=255).contains(&value);
+    let mut instructions: Vec<u8> = vec![];
+    let mut start = 0usize;
+    while start < values.len() {
+        let bytes = is_byte(values[start]);
+        let count = values[start..].iter().take(u8::MAX as usize).take_while(|&&value| is_byte(value) == bytes).count();
+        match (bytes, count <= MAX_SHORT_PUSH) {
+            (true, true) => instructions.push(PUSHB + count as u8 - 1),
+            (false, true) => instructions.push(PUSHW + count as u8 - 1),
+            (true, false) => instructions.extend_from_slice(&[NPUSHB, count as u8]),
+            (false, false) => instructions.extend_from_slice(&[NPUSHW, count as u8]),
+        }
+        for &value in &values[start..start + count] {
+            if bytes {
+                instructions.push(value as u8);
+            } else {
+                instructions.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        start += count;
+    }
+    instructions
+}
+
+/// Rebuilds 'cvt ' values from number of values and their deltas
+fn decode_cvt(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut buffer = ByteReader::from_bytes(data);
+    let count = try_read_u16_be(&mut buffer)?;
+    let mut cvt: Vec<u8> = Vec::with_capacity(count as usize * 2);
+    let mut value = 0i16;
+    for _ in 0..count {
+        let code = try_read_u8(&mut buffer)?;
+        let delta = match code {
+            0..CVT_POS1 => code as i32,
+            CVT_POS1..CVT_NEG0 => CVT_STEP * (code - CVT_POS1 + 1) as i32 + try_read_u8(&mut buffer)? as i32,
+            CVT_NEG0..=CVT_NEG8 => -(CVT_STEP * (code - CVT_NEG0) as i32 + try_read_u8(&mut buffer)? as i32),
+            CVT_WORD_CODE => try_read_i16_be(&mut buffer)? as i32,
+        };
+        value = value.wrapping_add(delta as i16);
+        cvt.extend_from_slice(&value.to_be_bytes());
+    }
+    Ok(cvt)
+}
+
+/// Advance width in whole pixels at `ppem` pixels per em as rounded by TrueType rasterizers
+pub(crate) fn predicted_width(advance: u16, ppem: u8, units_per_em: u16) -> i32 {
+    let units_per_em = units_per_em as u64;
+    let scaled = (64 * ppem as u64 * advance as u64 + units_per_em / 2) / units_per_em;
+    ((scaled + 32) / 64) as i32
+}
+
+/// Rebuilds 'hdmx' table. Header and (pixel size, max width) of device records are stored
+/// as they are, followed by the bits of width differences from `predicted_width`
+fn decode_hdmx(data: &[u8], units_per_em: u16, advances: &[u16]) -> Result<Vec<u8>, Error> {
+    let mut buffer = ByteReader::from_bytes(data);
+    let version = try_read_u16_be(&mut buffer)?;
+    let num_records = try_read_u16_be(&mut buffer)?;
+    let size_device_record = try_read_u32_be(&mut buffer)? as usize;
+    let record_size = advances.len() + 2;
+    if units_per_em == 0 || size_device_record < record_size || size_device_record > calculate_padded_len(record_size as u32) as usize {
+        return Err(Error::InvalidTableData);
+    }
+    let sizes = buffer.read_bytes(num_records as usize * 2).map_err(|_| Error::InvalidTableData)?;
+    let width_data = &data[buffer.get_rpos()..];
+    // every width difference takes at least one bit
+    if width_data.len() * 8 < num_records as usize * advances.len() {
+        return Err(Error::InvalidTableData);
+    }
+    let mut bits = BitReader::new(width_data);
+
+    let mut hdmx: Vec<u8> = Vec::with_capacity(8 + num_records as usize * size_device_record);
+    hdmx.extend_from_slice(&version.to_be_bytes());
+    hdmx.extend_from_slice(&num_records.to_be_bytes());
+    hdmx.extend_from_slice(&(size_device_record as u32).to_be_bytes());
+    for record in sizes.chunks(2) {
+        let record_start = hdmx.len();
+        hdmx.extend_from_slice(record);
+        for &advance in advances {
+            let width = predicted_width(advance, record[0], units_per_em) + read_magnitude_dependent(&mut bits)?;
+            hdmx.push(u8::try_from(width).map_err(|_| Error::InvalidTableData)?);
+        }
+        hdmx.resize(record_start + size_device_record, 0);
+    }
+    Ok(hdmx)
+}
+
+/// Reads value coded with magnitude dependent encoding: the magnitude in unary
+/// (ones ended by zero) followed by the sign bit if the value isn't 0
+fn read_magnitude_dependent(bits: &mut BitReader) -> Result<i32, Error> {
+    let mut magnitude = 0i32;
+    while bits.read_bit().map_err(|_| Error::InvalidTableData)? {
+        magnitude += 1;
+        if magnitude > MAX_WIDTH_DIFFERENCE {
+            return Err(Error::InvalidTableData);
+        }
+    }
+    if magnitude != 0 && bits.read_bit().map_err(|_| Error::InvalidTableData)? {
+        return Ok(-magnitude);
+    }
+    Ok(magnitude)
+}
+
+/// yMax and yMin of 'head' scaled to `ppem` pixels per em, rounded outwards
+pub(crate) fn predicted_heights(head: &SfntHeaderTable, ppem: u16) -> (i32, i32) {
+    let units_per_em = head.units_per_em as i32;
+    let y_max = (head.y_max as i32 * ppem as i32 + units_per_em - 1).div_euclid(units_per_em);
+    let y_min = (head.y_min as i32 * ppem as i32).div_euclid(units_per_em);
+    (y_max, y_min)
+}
+
+/// Rebuilds 'VDMX' table. Header, ratios and group offsets are stored as they are. Every group
+/// has its number of records, startsz and endsz followed by records: yPelHeight as 255UShort delta
+/// from the previous one (startsz for the first record), yMax and yMin as 255Short differences
+/// from `predicted_heights`
+fn decode_vdmx(data: &[u8], head: &SfntHeaderTable) -> Result<Vec<u8>, Error> {
+    if head.units_per_em == 0 {
+        return Err(Error::InvalidTableData);
+    }
+    let mut buffer = ByteReader::from_bytes(data);
+    let _version = try_read_u16_be(&mut buffer)?;
+    let num_recs = try_read_u16_be(&mut buffer)?;
+    let num_ratios = try_read_u16_be(&mut buffer)? as usize;
+    let header_size = 6 + num_ratios * 4 + num_ratios * 2;
+    let mut vdmx = try_slice(data, 0, header_size)?.to_vec();
+    buffer.set_rpos(header_size);
+
+    for _ in 0..num_recs {
+        let recs = try_read_u16_be(&mut buffer)?;
+        let start_size = try_read_u8(&mut buffer)?;
+        let end_size = try_read_u8(&mut buffer)?;
+        vdmx.extend_from_slice(&recs.to_be_bytes());
+        vdmx.extend_from_slice(&[start_size, end_size]);
+        let mut y_pel_height = start_size as u16;
+        for _ in 0..recs {
+            y_pel_height = y_pel_height.checked_add(read_255_ushort(&mut buffer)?).ok_or(Error::InvalidTableData)?;
+            let (predicted_max, predicted_min) = predicted_heights(head, y_pel_height);
+            let y_max = predicted_max + read_255_short(try_read_u8(&mut buffer)?, &mut buffer)? as i32;
+            let y_min = predicted_min + read_255_short(try_read_u8(&mut buffer)?, &mut buffer)? as i32;
+            vdmx.extend_from_slice(&y_pel_height.to_be_bytes());
+            for value in [y_max, y_min] {
+                vdmx.extend_from_slice(&i16::try_from(value).map_err(|_| Error::InvalidTableData)?.to_be_bytes());
+            }
+        }
+    }
+    Ok(vdmx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_read_255_values() {
+        let data = [100, 255, 7, 254, 0, 253, 0x12, 0x34];
+        let mut buffer = ByteReader::from_bytes(&data);
+        let values: Vec<u16> = (0..4).map(|_| read_255_ushort(&mut buffer).unwrap()).collect();
+        debug_assert!(values == vec![100, 260, 506, 0x1234]);
+
+        for value in [0i16, 1, -1, 249, -250, 499, 500, -755, 756, i16::MIN, i16::MAX] {
+            let mut data: Vec<u8> = vec![];
+            write_255_short(value, &mut data);
+            let mut buffer = ByteReader::from_bytes(&data);
+            debug_assert!(read_255_short(try_read_u8(&mut buffer).unwrap(), &mut buffer).unwrap() == value);
+        }
+        debug_assert!(read_255_short(FLIP_SIGN_CODE, &mut ByteReader::from_bytes(&[FLIP_SIGN_CODE])).is_err());
+    }
+
+    #[test]
+    fn test_push_values() {
+        let data = [10, HOP3_CODE, 20, HOP4_CODE, FLIP_SIGN_CODE, 5, 255, 50];
+        let values = read_push_values(&mut ByteReader::from_bytes(&data), 7).unwrap();
+        debug_assert!(values == vec![10, 20, 10, -5, 10, 300, 10]);
+        // hop code expands past the number of values
+        debug_assert!(read_push_values(&mut ByteReader::from_bytes(&data), 2).is_err());
+        debug_assert!(read_push_values(&mut ByteReader::from_bytes(&[HOP3_CODE, 1]), 2).is_err());
+
+        debug_assert!(encode_pushes(&[1, 2, 300, -1, 3]) == vec![PUSHB + 1, 1, 2, PUSHW + 1, 0x01, 0x2C, 0xFF, 0xFF, PUSHB, 3]);
+        let many = vec![7i16; 300];
+        let instructions = encode_pushes(&many);
+        debug_assert!(instructions[..2] == [NPUSHB, 255] && instructions[257..259] == [NPUSHB, 45] && instructions.len() == 304);
+    }
+
+    #[test]
+    fn test_decode_cvt() {
+        let values = [0i16, 237, 500, 3000, -5, -300, 100, -2000, 141];
+        debug_assert!(decode_cvt(&ctf_cvt(&values.iter().flat_map(|value| value.to_be_bytes()).collect::<Vec<u8>>())).unwrap()
+            == values.iter().flat_map(|value| value.to_be_bytes()).collect::<Vec<u8>>());
+        debug_assert!(decode_cvt(&[0, 1, CVT_NEG8, 10]).unwrap() == (-(CVT_STEP * 8 + 10) as i16).to_be_bytes());
+        debug_assert!(decode_cvt(&[0, 1, CVT_NEG8]).is_err());
+        debug_assert!(decode_cvt(&[0, 2, 1]).is_err());
+    }
+
+    #[test]
+    fn test_read_triplet() {
+        for (dx, dy) in [(0, 0), (0, -1000), (300, 0), (-64, 64), (700, -5), (-4000, 4000), (10000, -20000)] {
+            for on_curve in [true, false] {
+                let (flag, data) = triplet(dx, dy, on_curve);
+                debug_assert!((flag & OFF_CURVE_FLAG == 0) == on_curve);
+                let mut buffer = ByteReader::from_bytes(&data);
+                debug_assert!(read_triplet(&mut buffer, flag & !OFF_CURVE_FLAG).unwrap() == (dx, dy));
+                debug_assert!(buffer.get_rpos() == data.len());
+            }
+        }
+    }
+}
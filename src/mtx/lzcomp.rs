@@ -0,0 +1,341 @@
+//! LZCOMP decompression of MTX blocks.
+//!
+//! Data is LZ77 compressed over a history preloaded with a fixed pattern. Literals and copy
+//! commands, chunks of copy lengths and chunks of copy distances are coded by three adaptive
+//! Huffman trees. The data may be run length encoded before the compression
+
+use crate::mtx::BitReader;
+use crate::Error;
+
+/// Bits of a copy length chunk: the flag of one more chunk and the value bits
+pub(crate) const LEN_WIDTH: u32 = 3;
+pub(crate) const LEN_VALUE_BITS: u32 = LEN_WIDTH - 1;
+pub(crate) const LEN_MORE_CHUNKS: u16 = 1 << LEN_VALUE_BITS;
+/// Bits of a copy distance chunk
+pub(crate) const DIST_WIDTH: u32 = 3;
+pub(crate) const LEN_MIN: usize = 2;
+pub(crate) const DIST_MIN: usize = 1;
+/// Copies farther than this distance are at least 3 bytes long, so their length is stored minus 3
+pub(crate) const MAX_2BYTE_DIST: usize = 512;
+/// History preloaded before decoding: pairs (0..32, 0..96) followed by 4 copies of every byte value
+pub(crate) const PRELOAD_SIZE: usize = 2 * 32 * 96 + 4 * 256;
+/// Bits of the decompressed data length stored before the symbols
+pub(crate) const LENGTH_BITS: u32 = 24;
+/// Limit of the data expanded by run length decoding
+const MAX_RUN_LENGTH_OUTPUT: usize = 1 << 28;
+/// Symbols of one byte copies from distances 2, 4 and 6 follow the copy symbols
+const DUP_DISTANCES: [usize; 3] = [2, 4, 6];
+// node index of the root of adaptive Huffman tree
+const ROOT: usize = 1;
+
+/// Adaptive Huffman tree. Nodes are numbered from the root in the order of non-increasing weights
+/// and the tree is rebalanced after every coded symbol. All symbols start with weight 1
+pub(crate) struct AdaptiveHuffman {
+    weights: Vec<u32>,
+    parents: Vec<usize>,
+    // (left, right) children of internal nodes, `None` for leaves
+    children: Vec<Option<(usize, usize)>>,
+    // symbols of leaves
+    symbols: Vec<u16>,
+    // leaf of every symbol
+    leaves: Vec<usize>,
+}
+
+impl AdaptiveHuffman {
+    /// Creates balanced tree of `range` symbols, `range` has to be at least 2
+    pub(crate) fn new(range: usize) -> Self {
+        let node_count = 2 * range;
+        let mut tree = AdaptiveHuffman {
+            weights: vec![0; node_count],
+            parents: vec![0; node_count],
+            children: vec![None; node_count],
+            symbols: vec![0; node_count],
+            leaves: vec![0; range],
+        };
+        for node in (ROOT..node_count).rev() {
+            if node >= range {
+                tree.weights[node] = 1;
+                tree.symbols[node] = (node - range) as u16;
+                tree.leaves[node - range] = node;
+            } else {
+                tree.children[node] = Some((2 * node, 2 * node + 1));
+                tree.weights[node] = tree.weights[2 * node] + tree.weights[2 * node + 1];
+                tree.parents[2 * node] = node;
+                tree.parents[2 * node + 1] = node;
+            }
+        }
+        tree
+    }
+
+    /// Reads bits of one symbol from the root to the leaf and updates the tree
+    pub(crate) fn read_symbol(&mut self, bits: &mut BitReader) -> Result<u16, Error> {
+        let mut node = ROOT;
+        while let Some((left, right)) = self.children[node] {
+            node = if bits.read_bit()? { right } else { left };
+        }
+        let symbol = self.symbols[node];
+        self.update(symbol);
+        Ok(symbol)
+    }
+
+    /// Appends bits of the symbol to `bits` and updates the tree
+    #[cfg(test)]
+    pub(crate) fn write_symbol(&mut self, symbol: u16, bits: &mut Vec<bool>) {
+        let mut code: Vec<bool> = vec![];
+        let mut node = self.leaves[symbol as usize];
+        while node != ROOT {
+            let parent = self.parents[node];
+            code.push(self.children[parent].is_some_and(|(_, right)| right == node));
+            node = parent;
+        }
+        bits.extend(code.iter().rev());
+        self.update(symbol);
+    }
+
+    /// Increments weights from the leaf of the symbol to the root. Every node is swapped
+    /// with the first node of the same weight before the increment to keep the order of weights
+    fn update(&mut self, symbol: u16) {
+        let mut node = self.leaves[symbol as usize];
+        loop {
+            let weight = self.weights[node];
+            let mut leader = node;
+            while leader > ROOT && self.weights[leader - 1] == weight {
+                leader -= 1;
+            }
+            // ancestors are heavier than the node, so the leader is never one of them
+            if leader != node {
+                self.swap(node, leader);
+                node = leader;
+            }
+            self.weights[node] += 1;
+            if node == ROOT {
+                break;
+            }
+            node = self.parents[node];
+        }
+    }
+
+    /// Swaps subtrees of two nodes with the same weight
+    fn swap(&mut self, first: usize, second: usize) {
+        self.children.swap(first, second);
+        self.symbols.swap(first, second);
+        for node in [first, second] {
+            match self.children[node] {
+                Some((left, right)) => {
+                    self.parents[left] = node;
+                    self.parents[right] = node;
+                }
+                None => self.leaves[self.symbols[node] as usize] = node,
+            }
+        }
+    }
+}
+
+/// Trees of symbols, length chunks and distance chunks of one block
+pub(crate) struct LzcompModel {
+    pub(crate) symbols: AdaptiveHuffman,
+    pub(crate) lengths: AdaptiveHuffman,
+    pub(crate) distances: AdaptiveHuffman,
+    // number of distance chunks which cover the longest copy distance
+    pub(crate) dist_ranges: usize,
+}
+
+impl LzcompModel {
+    /// Creates the trees for copies up to `max_copy_distance` bytes back.
+    /// Symbols are 256 literals, copy symbols for every number of distance chunks and one byte copies
+    pub(crate) fn new(max_copy_distance: usize) -> Self {
+        let mut dist_ranges = 1;
+        while DIST_MIN + (1 << (DIST_WIDTH as usize * dist_ranges)) - 1 < max_copy_distance {
+            dist_ranges += 1;
+        }
+        LzcompModel {
+            symbols: AdaptiveHuffman::new(256 + (1 << LEN_WIDTH) * dist_ranges + DUP_DISTANCES.len()),
+            lengths: AdaptiveHuffman::new(1 << LEN_WIDTH),
+            distances: AdaptiveHuffman::new(1 << DIST_WIDTH),
+            dist_ranges,
+        }
+    }
+
+    /// The first one byte copy symbol
+    pub(crate) fn dup_symbol(&self) -> u16 {
+        (256 + (1 << LEN_WIDTH) * self.dist_ranges) as u16
+    }
+
+    /// Reads copy length which starts with the chunk stored in the copy symbol
+    fn read_length(&mut self, first_chunk: u16, bits: &mut BitReader) -> Result<usize, Error> {
+        let mut chunk = first_chunk;
+        let mut value = 0usize;
+        loop {
+            value = (value << LEN_VALUE_BITS) | (chunk & (LEN_MORE_CHUNKS - 1)) as usize;
+            if chunk & LEN_MORE_CHUNKS == 0 {
+                return Ok(value + LEN_MIN);
+            }
+            if value >= 1 << LENGTH_BITS {
+                return Err(Error::InvalidEotStructure);
+            }
+            chunk = self.lengths.read_symbol(bits)?;
+        }
+    }
+
+    /// Reads copy distance made of `chunk_count` chunks
+    fn read_distance(&mut self, chunk_count: usize, bits: &mut BitReader) -> Result<usize, Error> {
+        let mut value = 0usize;
+        for _ in 0..chunk_count {
+            value = (value << DIST_WIDTH) | self.distances.read_symbol(bits)? as usize;
+        }
+        Ok(value + DIST_MIN)
+    }
+}
+
+/// History preloaded before decoding, copies may refer to it
+pub(crate) fn preload() -> Vec<u8> {
+    let mut history: Vec<u8> = Vec::with_capacity(PRELOAD_SIZE);
+    for first in 0..32u8 {
+        for second in 0..96u8 {
+            history.extend_from_slice(&[first, second]);
+        }
+    }
+    for value in 0..=255u8 {
+        history.extend_from_slice(&[value; 4]);
+    }
+    history
+}
+
+/// Decompresses one block. Empty block stays empty, copies may be up to `max_copy_distance` bytes back
+pub(crate) fn decompress(data: &[u8], max_copy_distance: usize) -> Result<Vec<u8>, Error> {
+    if data.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut bits = BitReader::new(data);
+    let is_run_length_encoded = bits.read_bit()?;
+    let length = bits.read_value(LENGTH_BITS)? as usize;
+    let mut model = LzcompModel::new(max_copy_distance);
+    let dup_symbol = model.dup_symbol();
+
+    let mut history = preload();
+    history.reserve(length);
+    while history.len() < PRELOAD_SIZE + length {
+        let symbol = model.symbols.read_symbol(&mut bits)?;
+        let (distance, count) = if symbol < 256 {
+            history.push(symbol as u8);
+            continue;
+        } else if symbol >= dup_symbol {
+            (DUP_DISTANCES[(symbol - dup_symbol) as usize], 1)
+        } else {
+            let copy = symbol - 256;
+            let length = model.read_length(copy % (1 << LEN_WIDTH), &mut bits)?;
+            let distance = model.read_distance(copy as usize / (1 << LEN_WIDTH) + 1, &mut bits)?;
+            (distance, if distance > MAX_2BYTE_DIST { length + 1 } else { length })
+        };
+        if distance > history.len() || history.len() + count > PRELOAD_SIZE + length {
+            return Err(Error::InvalidEotStructure);
+        }
+        // the source may overlap the copied bytes
+        for _ in 0..count {
+            history.push(history[history.len() - distance]);
+        }
+    }
+
+    let output = history.split_off(PRELOAD_SIZE);
+    if is_run_length_encoded {
+        run_length_decode(&output)
+    } else {
+        Ok(output)
+    }
+}
+
+/// Expands run length encoded data. The first byte is the escape byte: escape, count, byte is
+/// a run of `count` bytes and escape, 0 is the escape byte itself
+fn run_length_decode(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let Some((&escape, mut data)) = data.split_first() else {
+        return Ok(vec![]);
+    };
+    let mut output: Vec<u8> = Vec::with_capacity(data.len());
+    while let Some((&byte, rest)) = data.split_first() {
+        data = rest;
+        if byte != escape {
+            output.push(byte);
+            continue;
+        }
+        match data {
+            [0, rest @ ..] => {
+                output.push(escape);
+                data = rest;
+            }
+            [count, value, rest @ ..] => {
+                if output.len() + *count as usize > MAX_RUN_LENGTH_OUTPUT {
+                    return Err(Error::InvalidEotStructure);
+                }
+                output.resize(output.len() + *count as usize, *value);
+                data = rest;
+            }
+            _ => return Err(Error::InvalidEotStructure),
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_adaptive_huffman() {
+        let mut encoder = AdaptiveHuffman::new(5);
+        let mut code: Vec<bool> = vec![];
+        let symbols = [4u16, 4, 4, 0, 4, 2, 4, 4, 1, 3, 4];
+        symbols.iter().for_each(|&symbol| encoder.write_symbol(symbol, &mut code));
+        // the frequent symbol gets the shortest code
+        let mut frequent: Vec<bool> = vec![];
+        encoder.write_symbol(4, &mut frequent);
+        debug_assert!(frequent.len() == 1);
+
+        let packed = pack_bits(&code);
+        let mut bits = BitReader::new(&packed);
+        let mut decoder = AdaptiveHuffman::new(5);
+        let decoded: Vec<u16> = symbols.iter().map(|_| decoder.read_symbol(&mut bits).unwrap()).collect();
+        debug_assert!(decoded == symbols);
+    }
+
+    #[test]
+    fn test_decompress() {
+        let text = b"the glyph data of the glyph table, the glyph data of the font".to_vec();
+        // copies from the preloaded history and one byte copies
+        let mut data = [0u8, 1, 0, 2, 0, 3, 0, 4, 7, 7, 7, 7, 7, 9].to_vec();
+        data.extend_from_slice(&text);
+        data.extend((0..600).map(|index| (index % 7) as u8));
+        data.extend_from_slice(&text);
+        for is_run_length_encoded in [false, true] {
+            let compressed = lzcomp_compress(&data, 1 << 16, is_run_length_encoded);
+            debug_assert!(compressed.len() < data.len());
+            debug_assert!(decompress(&compressed, 1 << 16).unwrap() == data);
+        }
+        debug_assert!(decompress(&lzcomp_compress(&[], 100, false), 100).unwrap().is_empty());
+        debug_assert!(decompress(&[], 100).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_decompress_errors() {
+        let data: Vec<u8> = (0..200).map(|index| (index % 13) as u8).collect();
+        let compressed = lzcomp_compress(&data, 1 << 16, false);
+        debug_assert!(decompress(&compressed[..compressed.len() / 2], 1 << 16).err() == Some(Error::InvalidEotStructure));
+
+        // copy from before the preloaded history
+        let mut model = LzcompModel::new(PRELOAD_SIZE * 2);
+        let mut code = vec![false];
+        code.extend((0..LENGTH_BITS).map(|bit| bit == LENGTH_BITS - 2));
+        let far = PRELOAD_SIZE + 1 - DIST_MIN;
+        let chunks: Vec<u16> = (0..model.dist_ranges).rev().map(|index| ((far >> (3 * index)) & 7) as u16).collect();
+        model.symbols.write_symbol(256 + 8 * (model.dist_ranges as u16 - 1), &mut code);
+        chunks.iter().for_each(|&chunk| model.distances.write_symbol(chunk, &mut code));
+        debug_assert!(decompress(&pack_bits(&code), PRELOAD_SIZE * 2).err() == Some(Error::InvalidEotStructure));
+    }
+
+    #[test]
+    fn test_run_length_decode() {
+        debug_assert!(run_length_decode(&[0xAA, 1, 0xAA, 3, 2, 0xAA, 0, 5]).unwrap() == vec![1, 2, 2, 2, 0xAA, 5]);
+        debug_assert!(run_length_decode(&[]).unwrap().is_empty());
+        debug_assert!(run_length_decode(&[0xAA, 1, 0xAA, 3]).err() == Some(Error::InvalidEotStructure));
+    }
+}
@@ -0,0 +1,134 @@
+//! Glyph outlines and the path builder interface used to emit them
+
+use std::fmt::Write;
+
+/// Receiver of glyph path commands. Implement it to emit SVG, canvas or tessellator commands.
+/// Coordinates are in font units with Y axis pointing up
+pub trait PathBuilder {
+    fn move_to(&mut self, x: f32, y: f32);
+    fn line_to(&mut self, x: f32, y: f32);
+    // Quadratic Bézier curve (TrueType outlines)
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32);
+    // Cubic Bézier curve (CFF outlines)
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32);
+    fn close(&mut self);
+}
+
+/// Point of a glyph contour
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlyphPoint {
+    pub x: f32,
+    pub y: f32,
+    // Off-curve points of TrueType contours are quadratic control points
+    pub on_curve: bool,
+}
+
+/// Glyph outline made of closed quadratic contours
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GlyphOutline {
+    pub contours: Vec<Vec<GlyphPoint>>,
+}
+
+impl GlyphOutline {
+    /// Emits outline contours to the path builder
+    pub fn draw<B: PathBuilder + ?Sized>(&self, builder: &mut B) {
+        for contour in self.contours.iter() {
+            draw_quadratic_contour(contour, builder);
+        }
+    }
+
+    /// Returns bounding box of the outline points (x_min, y_min, x_max, y_max)
+    /// or None if the outline is empty
+    pub fn bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        let mut points = self.contours.iter().flatten();
+        let first = points.next()?;
+        Some(points.fold((first.x, first.y, first.x, first.y), |bounds, point| {
+            (bounds.0.min(point.x), bounds.1.min(point.y), bounds.2.max(point.x), bounds.3.max(point.y))
+        }))
+    }
+}
+
+/// Emits one closed quadratic contour. Two consecutive off-curve points have
+/// an implied on-curve point in the middle between them
+fn draw_quadratic_contour<B: PathBuilder + ?Sized>(contour: &[GlyphPoint], builder: &mut B) {
+    let (Some(first), Some(last)) = (contour.first(), contour.last()) else {
+        return;
+    };
+    let midpoint = |first: &GlyphPoint, second: &GlyphPoint| GlyphPoint {
+        x: (first.x + second.x) / 2.0,
+        y: (first.y + second.y) / 2.0,
+        on_curve: true,
+    };
+    // contour has to start from an on-curve point
+    let (start, rest) = match contour.iter().position(|point| point.on_curve) {
+        Some(index) => (contour[index], [&contour[index + 1..], &contour[..index]].concat()),
+        None => (midpoint(last, first), contour.to_vec()),
+    };
+    builder.move_to(start.x, start.y);
+
+    let mut control: Option<GlyphPoint> = None;
+    for point in rest {
+        match (control, point.on_curve) {
+            (None, true) => builder.line_to(point.x, point.y),
+            (None, false) => control = Some(point),
+            (Some(control_point), true) => {
+                builder.quad_to(control_point.x, control_point.y, point.x, point.y);
+                control = None;
+            }
+            (Some(control_point), false) => {
+                let middle = midpoint(&control_point, &point);
+                builder.quad_to(control_point.x, control_point.y, middle.x, middle.y);
+                control = Some(point);
+            }
+        }
+    }
+    if let Some(control_point) = control {
+        builder.quad_to(control_point.x, control_point.y, start.x, start.y);
+    }
+    builder.close();
+}
+
+/// Path builder which collects commands to SVG path data string.
+/// Y axis is flipped to make the path usable in SVG coordinate system directly
+#[derive(Clone, Debug, Default)]
+pub struct SvgPathBuilder {
+    path: String,
+}
+
+impl SvgPathBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns collected SVG path data
+    pub fn finish(self) -> String {
+        self.path.trim_end().to_string()
+    }
+}
+
+impl PathBuilder for SvgPathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let _ = write!(self.path, "M{} {} ", x, flip(y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let _ = write!(self.path, "L{} {} ", x, flip(y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let _ = write!(self.path, "Q{} {} {} {} ", x1, flip(y1), x, flip(y));
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let _ = write!(self.path, "C{} {} {} {} {} {} ", x1, flip(y1), x2, flip(y2), x, flip(y));
+    }
+
+    fn close(&mut self) {
+        self.path.push_str("Z ");
+    }
+}
+
+/// Flips Y coordinate for SVG coordinate system (without producing negative zero)
+fn flip(y: f32) -> f32 {
+    0.0 - y
+}
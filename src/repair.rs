@@ -0,0 +1,373 @@
+//! Best-effort recovery of damaged WOFF data used by lenient decoding
+
+use crate::structures::{WoffHeader, WoffTableDirectoryEntry, HEAD_CHECK_SUM_ADJUSTMENT_OFFSET};
+use crate::utils::*;
+use crate::Error;
+use flate2::{Decompress, FlushDecompress, Status};
+use std::mem::size_of;
+
+/// Repair made by lenient decoding. `tag` and `offset` are the tag of the table
+/// and the offset of its data from the beginning of WOFF data
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Repair {
+    // `length` field of WOFF header doesn't match the size of the data
+    WoffLengthMismatch { declared: u32, actual: u32 },
+    // table directory doesn't fit in the data, only complete entries are used
+    TableDirectoryTruncated { declared_tables: u16, recovered_tables: u16 },
+    // table data starts outside of the data or can't be decompressed at all, the table is dropped
+    TableDropped { tag: u32, offset: u32 },
+    // table data is cut by the end of the data
+    TableDataTruncated { tag: u32, offset: u32, comp_length: u32, available: u32 },
+    // compressed table data is raw deflate stream without zlib framing
+    RawDeflateStream { tag: u32, offset: u32 },
+    // compressed stream is broken or ends too early, the rest of the table is filled with zeros
+    CompressedStreamTruncated { tag: u32, offset: u32, recovered_length: u32 },
+    // compressed stream produces more data than `orig_length`, extra data is dropped
+    ExtraDecompressedData { tag: u32, offset: u32 },
+    // `orig_length` doesn't match the size of decompressed data, the actual size is used
+    OrigLengthMismatch { tag: u32, offset: u32, declared: u32, actual: u32 },
+    // table data is stored uncompressed but `comp_length` is bigger than `orig_length`
+    CompLengthMismatch { tag: u32, offset: u32, declared: u32, actual: u32 },
+}
+
+/// Kind of `Repair` for using with C wrapper
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RepairKind {
+    WoffLengthMismatch,
+    TableDirectoryTruncated,
+    TableDropped,
+    TableDataTruncated,
+    RawDeflateStream,
+    CompressedStreamTruncated,
+    ExtraDecompressedData,
+    OrigLengthMismatch,
+    CompLengthMismatch,
+}
+
+/// `Repair` for using with C wrapper. Fields which are not used by the kind of repair are zero
+///
+/// #Fields
+///
+/// `kind` - kind of repair
+/// `tag` - tag of the table
+/// `offset` - offset of the table data from the beginning of WOFF data
+/// `declared` - declared value (`length`, number of tables, `comp_length`, `orig_length`)
+/// `actual` - actual value (size of WOFF data, recovered number of tables, available or recovered length)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CRepair {
+    pub kind: RepairKind,
+    pub tag: u32,
+    pub offset: u32,
+    pub declared: u32,
+    pub actual: u32,
+}
+
+impl From<&Repair> for CRepair {
+    fn from(repair: &Repair) -> Self {
+        let c_repair = |kind: RepairKind| CRepair { kind, tag: 0, offset: 0, declared: 0, actual: 0 };
+        match *repair {
+            Repair::WoffLengthMismatch { declared, actual } => CRepair {
+                declared,
+                actual,
+                ..c_repair(RepairKind::WoffLengthMismatch)
+            },
+            Repair::TableDirectoryTruncated { declared_tables, recovered_tables } => CRepair {
+                declared: declared_tables as u32,
+                actual: recovered_tables as u32,
+                ..c_repair(RepairKind::TableDirectoryTruncated)
+            },
+            Repair::TableDropped { tag, offset } => CRepair { tag, offset, ..c_repair(RepairKind::TableDropped) },
+            Repair::TableDataTruncated { tag, offset, comp_length, available } => CRepair {
+                tag,
+                offset,
+                declared: comp_length,
+                actual: available,
+                ..c_repair(RepairKind::TableDataTruncated)
+            },
+            Repair::RawDeflateStream { tag, offset } => CRepair { tag, offset, ..c_repair(RepairKind::RawDeflateStream) },
+            Repair::CompressedStreamTruncated { tag, offset, recovered_length } => CRepair {
+                tag,
+                offset,
+                actual: recovered_length,
+                ..c_repair(RepairKind::CompressedStreamTruncated)
+            },
+            Repair::ExtraDecompressedData { tag, offset } => CRepair { tag, offset, ..c_repair(RepairKind::ExtraDecompressedData) },
+            Repair::OrigLengthMismatch { tag, offset, declared, actual } => CRepair {
+                tag,
+                offset,
+                declared,
+                actual,
+                ..c_repair(RepairKind::OrigLengthMismatch)
+            },
+            Repair::CompLengthMismatch { tag, offset, declared, actual } => CRepair {
+                tag,
+                offset,
+                declared,
+                actual,
+                ..c_repair(RepairKind::CompLengthMismatch)
+            },
+        }
+    }
+}
+
+// `orig_length` may be off by padding, so a bit more data is accepted from the compressed stream
+const ORIG_LENGTH_TOLERANCE: usize = 4;
+// deflate can't expand data more than 1032 times, so bigger `orig_length` is not trusted
+const MAX_COMPRESSION_RATIO: usize = 1032;
+// recovered table data never grows bigger than this whatever the header says
+const MAX_TABLE_LENGTH: usize = 1 << 28;
+// initial buffer size of decompression, the buffer grows as the data is decompressed
+const INFLATE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Lenient variant of the sanity check: only the header and the signature are required
+pub(crate) fn sanity_check(buf: &[u8], repairs: &mut Vec<Repair>) -> Result<(), Error> {
+    if buf.is_empty() { return Err(Error::InputBufferIsEmpty); }
+    if buf.len() < size_of::<WoffHeader>() { return Err(Error::InvalidWoffSize); }
+    if try_read_u32(buf, 0)? != tag(b"wOFF") { return Err(Error::InvalidWoffSignature); }
+
+    let length = try_read_u32(buf, 8)?;
+    if length as usize != buf.len() {
+        repairs.push(Repair::WoffLengthMismatch { declared: length, actual: buf.len() as u32 });
+    }
+    Ok(())
+}
+
+/// Number of complete table directory entries which fit in the data
+pub(crate) fn directory_entries_count(buf: &[u8], woff_header: &WoffHeader, repairs: &mut Vec<Repair>) -> u16 {
+    let available = (buf.len() - size_of::<WoffHeader>()) / size_of::<WoffTableDirectoryEntry>();
+    if available < woff_header.num_tables as usize {
+        repairs.push(Repair::TableDirectoryTruncated {
+            declared_tables: woff_header.num_tables,
+            recovered_tables: available as u16,
+        });
+        available as u16
+    } else {
+        woff_header.num_tables
+    }
+}
+
+/// Recovers as much table data as possible. `entry` is updated with the actual length and checksum
+/// of the recovered data. Returns `None` if nothing can be recovered and the table has to be dropped
+pub(crate) fn inflate_table(buf: &[u8], entry: &mut WoffTableDirectoryEntry, repairs: &mut Vec<Repair>) -> Option<Vec<u8>> {
+    let (tag, offset) = (entry.tag, entry.offset);
+    let start_offset = offset as usize;
+    if start_offset >= buf.len() {
+        repairs.push(Repair::TableDropped { tag, offset });
+        return None;
+    }
+    let available = buf.len() - start_offset;
+    let comp_length = (entry.comp_length as usize).min(available);
+    if comp_length < entry.comp_length as usize {
+        repairs.push(Repair::TableDataTruncated {
+            tag,
+            offset,
+            comp_length: entry.comp_length,
+            available: available as u32,
+        });
+    }
+    let source = &buf[start_offset..start_offset + comp_length];
+    // declared length is used for allocations only as far as the available data can explain it
+    let orig_length = (entry.orig_length as usize).min(comp_length.saturating_mul(MAX_COMPRESSION_RATIO)).min(MAX_TABLE_LENGTH);
+    let repairs_count = repairs.len();
+
+    let mut data = if entry.comp_length == entry.orig_length {
+        source.to_vec()
+    } else {
+        let limit = orig_length + ORIG_LENGTH_TOLERANCE;
+        let mut inflated = inflate(source, true, limit);
+        if inflated.data.is_empty() && !inflated.complete {
+            let raw = inflate(source, false, limit);
+            if !raw.data.is_empty() {
+                repairs.push(Repair::RawDeflateStream { tag, offset });
+                inflated = raw;
+            }
+        }
+        if inflated.data.is_empty() && !inflated.complete {
+            if entry.comp_length < entry.orig_length {
+                repairs.push(Repair::TableDropped { tag, offset });
+                return None;
+            }
+            // neither zlib nor deflate, but there is enough data to treat it as stored
+            repairs.push(Repair::CompLengthMismatch {
+                tag,
+                offset,
+                declared: entry.comp_length,
+                actual: entry.orig_length,
+            });
+            source[..orig_length.min(source.len())].to_vec()
+        } else if inflated.complete {
+            if inflated.data.len() != entry.orig_length as usize {
+                repairs.push(Repair::OrigLengthMismatch {
+                    tag,
+                    offset,
+                    declared: entry.orig_length,
+                    actual: inflated.data.len() as u32,
+                });
+            }
+            inflated.data
+        } else if inflated.data.len() >= limit {
+            repairs.push(Repair::ExtraDecompressedData { tag, offset });
+            inflated.data.truncate(orig_length);
+            inflated.data
+        } else {
+            repairs.push(Repair::CompressedStreamTruncated {
+                tag,
+                offset,
+                recovered_length: inflated.data.len() as u32,
+            });
+            inflated.data
+        }
+    };
+
+    // stored data cut by the end of WOFF data or broken compressed stream
+    let is_cut = repairs[repairs_count..].iter().any(|repair| matches!(repair,
+        Repair::TableDataTruncated { .. } | Repair::CompressedStreamTruncated { .. }));
+    if is_cut && data.len() < orig_length {
+        data.resize(orig_length, 0);
+    }
+
+    if repairs.len() > repairs_count {
+        entry.orig_length = data.len() as u32;
+        entry.comp_length = comp_length as u32;
+        entry.orig_checksum = table_checksum(tag, &data);
+    }
+    Some(data)
+}
+
+/// Checksum of the table data; checkSumAdjustment of 'head' table is not counted
+fn table_checksum(table_tag: u32, data: &[u8]) -> u32 {
+    if table_tag == tag(b"head") && data.len() >= HEAD_CHECK_SUM_ADJUSTMENT_OFFSET + 4 {
+        let mut head = data.to_vec();
+        head[HEAD_CHECK_SUM_ADJUSTMENT_OFFSET..HEAD_CHECK_SUM_ADJUSTMENT_OFFSET + 4].fill(0);
+        calculate_table_checksum(&head)
+    } else {
+        calculate_table_checksum(data)
+    }
+}
+
+/// Result of decompression which doesn't stop on errors
+struct Inflated {
+    // all data decompressed before the stream ended or broke
+    data: Vec<u8>,
+    // the stream ended properly
+    complete: bool,
+}
+
+/// Decompresses zlib (or raw deflate if `zlib_header` is false) stream up to `limit` bytes
+fn inflate(source: &[u8], zlib_header: bool, limit: usize) -> Inflated {
+    let mut decompressor = Decompress::new(zlib_header);
+    let mut data: Vec<u8> = Vec::with_capacity(limit.min(INFLATE_CHUNK_SIZE));
+    loop {
+        // decompressed data is written to spare capacity only, so the buffer is doubled when it's full
+        if data.len() == data.capacity() {
+            data.reserve_exact(data.capacity().min(limit - data.len()));
+        }
+        let (total_in, total_out) = (decompressor.total_in(), decompressor.total_out());
+        let input = &source[total_in as usize..];
+        match decompressor.decompress_vec(input, &mut data, FlushDecompress::None) {
+            Ok(Status::StreamEnd) => return Inflated { data, complete: true },
+            Ok(_) => {
+                let no_progress = decompressor.total_in() == total_in && decompressor.total_out() == total_out;
+                if no_progress || data.len() >= limit {
+                    return Inflated { data, complete: false };
+                }
+            }
+            Err(_) => return Inflated { data, complete: false },
+        }
+    }
+}
+
+/// Reads big-endian u32 value at `offset`
+fn try_read_u32(buf: &[u8], offset: usize) -> Result<u32, Error> {
+    let bytes = try_slice(buf, offset, 4)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn entry(offset: u32, comp_length: u32, orig_length: u32) -> WoffTableDirectoryEntry {
+        WoffTableDirectoryEntry { tag: tag(b"test"), offset, comp_length, orig_length, orig_checksum: 0 }
+    }
+
+    fn table() -> Vec<u8> {
+        (0..200u32).map(|value| (value % 7) as u8).collect()
+    }
+
+    #[test]
+    fn test_inflate_raw_deflate() {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&table()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut repairs = vec![];
+        let mut dir_entry = entry(0, compressed.len() as u32, 200);
+        debug_assert!(inflate_table(&compressed, &mut dir_entry, &mut repairs).unwrap() == table());
+        debug_assert!(repairs == vec![Repair::RawDeflateStream { tag: tag(b"test"), offset: 0 }]);
+    }
+
+    #[test]
+    fn test_inflate_truncated_and_off_by_padding() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&table()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut repairs = vec![];
+        let mut dir_entry = entry(0, compressed.len() as u32, 203);
+        debug_assert!(inflate_table(&compressed, &mut dir_entry, &mut repairs).unwrap() == table());
+        debug_assert!(dir_entry.orig_length == 200 && dir_entry.orig_checksum == calculate_table_checksum(&table()));
+        debug_assert!(repairs == vec![Repair::OrigLengthMismatch { tag: tag(b"test"), offset: 0, declared: 203, actual: 200 }]);
+
+        let mut repairs = vec![];
+        let truncated = &compressed[..compressed.len() - 6];
+        let mut dir_entry = entry(0, compressed.len() as u32, 200);
+        let data = inflate_table(truncated, &mut dir_entry, &mut repairs).unwrap();
+        debug_assert!(data.len() == 200 && repairs.len() == 2);
+        let recovered_length = match repairs[1] {
+            Repair::CompressedStreamTruncated { recovered_length, .. } => recovered_length as usize,
+            _ => 0,
+        };
+        debug_assert!(recovered_length > 0 && data[..recovered_length] == table()[..recovered_length]);
+        debug_assert!(data[recovered_length..].iter().all(|byte| *byte == 0));
+
+        let mut repairs = vec![];
+        let mut dir_entry = entry(300, 10, 20);
+        debug_assert!(inflate_table(&compressed, &mut dir_entry, &mut repairs).is_none());
+        debug_assert!(repairs == vec![Repair::TableDropped { tag: tag(b"test"), offset: 300 }]);
+    }
+
+    #[test]
+    fn test_inflate_with_huge_orig_length() {
+        // data bigger than the initial buffer is decompressed completely
+        let big_table: Vec<u8> = table().into_iter().cycle().take(3 * INFLATE_CHUNK_SIZE + 5).collect();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&big_table).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut repairs = vec![];
+        let mut dir_entry = entry(0, compressed.len() as u32, big_table.len() as u32);
+        debug_assert!(inflate_table(&compressed, &mut dir_entry, &mut repairs).unwrap() == big_table);
+        debug_assert!(repairs.is_empty());
+
+        // zero padding of the cut stream is limited by the size of available data
+        let truncated = &compressed[..100];
+        let mut dir_entry = entry(0, compressed.len() as u32, u32::MAX);
+        let data = inflate_table(truncated, &mut dir_entry, &mut repairs).unwrap();
+        debug_assert!(data.len() == 100 * MAX_COMPRESSION_RATIO && dir_entry.orig_length == data.len() as u32);
+
+        let mut repairs = vec![];
+        let mut dir_entry = entry(0, compressed.len() as u32, u32::MAX);
+        debug_assert!(inflate_table(&compressed, &mut dir_entry, &mut repairs).unwrap() == big_table);
+        debug_assert!(repairs == vec![Repair::OrigLengthMismatch {
+            tag: tag(b"test"),
+            offset: 0,
+            declared: u32::MAX,
+            actual: big_table.len() as u32,
+        }]);
+    }
+}
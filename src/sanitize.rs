@@ -0,0 +1,382 @@
+//! Validation of table contents in the spirit of OpenType Sanitizer (OTS)
+
+use crate::font::{FontTables, Sfnt};
+use crate::structures::SfntHeaderTable;
+use crate::tables::hhea::HheaTable;
+use crate::tables::loca::LocaTable;
+use crate::tables::maxp::{MaxpTable, MAXP_VERSION_1_0};
+use crate::utils::*;
+
+/// What was found by the sanitize pass
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SanitizeIssueKind {
+    // table is too short or its header is invalid
+    MalformedTable,
+    // 'loca' is shorter than `num_glyphs + 1` offsets of 'maxp'
+    LocaTooShort { num_glyphs: u16, expected: u32, actual: u32 },
+    // end offset of the glyph is less than its start offset
+    LocaNotMonotonic { glyph_id: u16 },
+    // end of the glyph is outside of 'glyf' table
+    LocaOffsetOutOfBounds { glyph_id: u16, offset: u32, glyf_length: u32 },
+    // numberOfHMetrics of 'hhea' is zero or bigger than number of glyphs
+    InvalidNumberOfHMetrics { number_of_h_metrics: u16, num_glyphs: u16 },
+    // 'hmtx' is shorter than numberOfHMetrics of 'hhea' and number of glyphs require
+    HmtxTooShort { expected: u32, actual: u32 },
+    // 'cmap' subtable is outside of the table
+    CmapSubtableOutOfBounds { platform_id: u16, encoding_id: u16, offset: u32 },
+    // glyph header or contour end points are broken
+    MalformedGlyph { glyph_id: u16 },
+    // simple glyph has more points than maxPoints of 'maxp'
+    GlyphPointsExceedMaxp { glyph_id: u16, points: u32, max_points: u16 },
+    // simple glyph has more contours than maxContours of 'maxp'
+    GlyphContoursExceedMaxp { glyph_id: u16, contours: u16, max_contours: u16 },
+}
+
+/// How the sanitize pass dealt with the issue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SanitizeAction {
+    // the font can't be used safely
+    FontRejected,
+    // broken subtable was removed from the table
+    SubtableDropped,
+}
+
+/// Issue found in the table with `tag`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SanitizeIssue {
+    pub tag: u32,
+    pub kind: SanitizeIssueKind,
+    pub action: SanitizeAction,
+}
+
+/// Result of `sanitize`: the font with broken subtables dropped (`None` if the font is rejected)
+/// and every issue found
+pub struct SanitizeReport {
+    pub font: Option<Sfnt>,
+    pub issues: Vec<SanitizeIssue>,
+}
+
+/// Validates table contents of the font. Issues which can't be fixed by dropping broken data
+/// reject the whole font
+pub fn sanitize(font: &Sfnt) -> SanitizeReport {
+    let mut tables: Vec<(u32, Vec<u8>)> = font
+        .table_tags()
+        .into_iter()
+        .filter_map(|table_tag| font.table_data(table_tag).ok().map(|data| (table_tag, data.into_owned())))
+        .collect();
+    let issues = sanitize_tables(&mut tables);
+    let font = if is_rejected(&issues) {
+        None
+    } else if issues.is_empty() {
+        Sfnt::from_vec(font.as_bytes().to_vec()).ok()
+    } else {
+        Sfnt::from_tables(font.version(), tables).ok()
+    };
+    SanitizeReport { font, issues }
+}
+
+/// Checks if any of the issues rejects the font
+pub(crate) fn is_rejected(issues: &[SanitizeIssue]) -> bool {
+    issues.iter().any(|issue| issue.action == SanitizeAction::FontRejected)
+}
+
+/// Validates tables and drops broken subtables in place
+pub(crate) fn sanitize_tables(tables: &mut [(u32, Vec<u8>)]) -> Vec<SanitizeIssue> {
+    let mut issues: Vec<SanitizeIssue> = vec![];
+    let find = |tables: &[(u32, Vec<u8>)], table_tag: &[u8; 4]| {
+        tables.iter().position(|(current, _)| *current == tag(table_tag))
+    };
+    let reject = |table_tag: &[u8; 4], kind: SanitizeIssueKind| SanitizeIssue {
+        tag: tag(table_tag),
+        kind,
+        action: SanitizeAction::FontRejected,
+    };
+
+    let maxp = match find(tables, b"maxp").map(|index| MaxpTable::parse(&tables[index].1)) {
+        Some(Ok(maxp)) => Some(maxp),
+        Some(Err(_)) => {
+            issues.push(reject(b"maxp", SanitizeIssueKind::MalformedTable));
+            None
+        }
+        None => None,
+    };
+
+    if let Some(maxp) = &maxp {
+        if let (Some(loca), Some(glyf), Some(head)) = (find(tables, b"loca"), find(tables, b"glyf"), find(tables, b"head")) {
+            match SfntHeaderTable::parse(&tables[head].1) {
+                Ok(head) => issues.extend(check_glyphs(&tables[loca].1, &tables[glyf].1, head.index_to_loc_format, maxp)),
+                Err(_) => issues.push(reject(b"head", SanitizeIssueKind::MalformedTable)),
+            }
+        }
+        if let (Some(hhea), Some(hmtx)) = (find(tables, b"hhea"), find(tables, b"hmtx")) {
+            match HheaTable::parse(&tables[hhea].1) {
+                Ok(hhea) => issues.extend(check_hmtx(&tables[hmtx].1, hhea.number_of_h_metrics, maxp.num_glyphs)),
+                Err(_) => issues.push(reject(b"hhea", SanitizeIssueKind::MalformedTable)),
+            }
+        }
+    }
+
+    if let Some(cmap) = find(tables, b"cmap") {
+        issues.extend(sanitize_cmap(&mut tables[cmap].1));
+    }
+    issues
+}
+
+/// Checks 'loca' against 'maxp' and 'glyf' and simple glyphs against 'maxp' maxima
+fn check_glyphs(loca_data: &[u8], glyf: &[u8], index_to_loc_format: i16, maxp: &MaxpTable) -> Vec<SanitizeIssue> {
+    let reject = |table_tag: &[u8; 4], kind: SanitizeIssueKind| SanitizeIssue {
+        tag: tag(table_tag),
+        kind,
+        action: SanitizeAction::FontRejected,
+    };
+    let offset_size = if index_to_loc_format == 0 { 2 } else { 4 };
+    let expected = (maxp.num_glyphs as u32 + 1) * offset_size;
+    let loca = match LocaTable::parse(loca_data, index_to_loc_format, maxp.num_glyphs) {
+        Ok(loca) => loca,
+        Err(_) => {
+            let kind = if (0..=1).contains(&index_to_loc_format) {
+                SanitizeIssueKind::LocaTooShort { num_glyphs: maxp.num_glyphs, expected, actual: loca_data.len() as u32 }
+            } else {
+                SanitizeIssueKind::MalformedTable
+            };
+            return vec![reject(if kind == SanitizeIssueKind::MalformedTable { b"head" } else { b"loca" }, kind)];
+        }
+    };
+
+    let mut issues: Vec<SanitizeIssue> = vec![];
+    for glyph_id in 0..maxp.num_glyphs {
+        let (start, end) = (loca.offsets[glyph_id as usize], loca.offsets[glyph_id as usize + 1]);
+        if end < start {
+            issues.push(reject(b"loca", SanitizeIssueKind::LocaNotMonotonic { glyph_id }));
+            return issues;
+        }
+        if end as usize > glyf.len() {
+            issues.push(reject(b"loca", SanitizeIssueKind::LocaOffsetOutOfBounds {
+                glyph_id,
+                offset: end,
+                glyf_length: glyf.len() as u32,
+            }));
+            return issues;
+        }
+        if start == end {
+            continue;
+        }
+        if let Some(kind) = check_simple_glyph(&glyf[start as usize..end as usize], glyph_id, maxp) {
+            issues.push(reject(b"glyf", kind));
+        }
+    }
+    issues
+}
+
+/// Checks header and contour end points of a simple glyph. Composite glyphs are not checked
+fn check_simple_glyph(data: &[u8], glyph_id: u16, maxp: &MaxpTable) -> Option<SanitizeIssueKind> {
+    // numberOfContours and bounding box
+    const GLYPH_HEADER_SIZE: usize = 10;
+    if data.len() < GLYPH_HEADER_SIZE {
+        return Some(SanitizeIssueKind::MalformedGlyph { glyph_id });
+    }
+    let number_of_contours = i16::from_be_bytes([data[0], data[1]]);
+    if number_of_contours < 0 {
+        return None;
+    }
+    let contours = number_of_contours as u16;
+    let end_points = match try_slice(data, GLYPH_HEADER_SIZE, contours as usize * 2) {
+        Ok(end_points) => end_points,
+        Err(_) => return Some(SanitizeIssueKind::MalformedGlyph { glyph_id }),
+    };
+    let mut points: u32 = 0;
+    for end_point in end_points.chunks(2) {
+        let end_point = u16::from_be_bytes([end_point[0], end_point[1]]) as u32 + 1;
+        if end_point <= points {
+            return Some(SanitizeIssueKind::MalformedGlyph { glyph_id });
+        }
+        points = end_point;
+    }
+    if maxp.version != MAXP_VERSION_1_0 {
+        return None;
+    }
+    if contours > maxp.max_contours {
+        return Some(SanitizeIssueKind::GlyphContoursExceedMaxp { glyph_id, contours, max_contours: maxp.max_contours });
+    }
+    if points > maxp.max_points as u32 {
+        return Some(SanitizeIssueKind::GlyphPointsExceedMaxp { glyph_id, points, max_points: maxp.max_points });
+    }
+    None
+}
+
+/// Checks 'hmtx' size against numberOfHMetrics of 'hhea' and number of glyphs
+fn check_hmtx(hmtx: &[u8], number_of_h_metrics: u16, num_glyphs: u16) -> Vec<SanitizeIssue> {
+    if number_of_h_metrics == 0 || number_of_h_metrics > num_glyphs {
+        return vec![SanitizeIssue {
+            tag: tag(b"hhea"),
+            kind: SanitizeIssueKind::InvalidNumberOfHMetrics { number_of_h_metrics, num_glyphs },
+            action: SanitizeAction::FontRejected,
+        }];
+    }
+    let expected = number_of_h_metrics as u32 * 4 + (num_glyphs - number_of_h_metrics) as u32 * 2;
+    if (hmtx.len() as u32) < expected {
+        return vec![SanitizeIssue {
+            tag: tag(b"hmtx"),
+            kind: SanitizeIssueKind::HmtxTooShort { expected, actual: hmtx.len() as u32 },
+            action: SanitizeAction::FontRejected,
+        }];
+    }
+    vec![]
+}
+
+/// Drops 'cmap' encoding records which point to subtables outside of the table.
+/// Remaining records are moved to the beginning of the records array, subtables data stays in place
+fn sanitize_cmap(cmap: &mut [u8]) -> Vec<SanitizeIssue> {
+    let reject = |kind: SanitizeIssueKind| SanitizeIssue { tag: tag(b"cmap"), kind, action: SanitizeAction::FontRejected };
+    // version, numTables
+    const CMAP_HEADER_SIZE: usize = 4;
+    // platformID, encodingID, subtableOffset
+    const ENCODING_RECORD_SIZE: usize = 8;
+    if cmap.len() < CMAP_HEADER_SIZE {
+        return vec![reject(SanitizeIssueKind::MalformedTable)];
+    }
+    let num_tables = u16::from_be_bytes([cmap[2], cmap[3]]) as usize;
+    let records = match try_slice(cmap, CMAP_HEADER_SIZE, num_tables * ENCODING_RECORD_SIZE) {
+        Ok(records) => records.to_vec(),
+        Err(_) => return vec![reject(SanitizeIssueKind::MalformedTable)],
+    };
+
+    let mut issues: Vec<SanitizeIssue> = vec![];
+    let mut kept: Vec<&[u8]> = vec![];
+    for record in records.chunks(ENCODING_RECORD_SIZE) {
+        let offset = u32::from_be_bytes([record[4], record[5], record[6], record[7]]);
+        if subtable_length(cmap, offset as usize).is_some() {
+            kept.push(record);
+        } else {
+            issues.push(SanitizeIssue {
+                tag: tag(b"cmap"),
+                kind: SanitizeIssueKind::CmapSubtableOutOfBounds {
+                    platform_id: u16::from_be_bytes([record[0], record[1]]),
+                    encoding_id: u16::from_be_bytes([record[2], record[3]]),
+                    offset,
+                },
+                action: SanitizeAction::SubtableDropped,
+            });
+        }
+    }
+    if issues.is_empty() {
+        return issues;
+    }
+    if kept.is_empty() {
+        issues.iter_mut().for_each(|issue| issue.action = SanitizeAction::FontRejected);
+        return issues;
+    }
+    cmap[2..4].copy_from_slice(&(kept.len() as u16).to_be_bytes());
+    let kept = kept.concat();
+    cmap[CMAP_HEADER_SIZE..CMAP_HEADER_SIZE + kept.len()].copy_from_slice(&kept);
+    issues
+}
+
+/// Length of 'cmap' subtable if the whole subtable is inside of the table
+fn subtable_length(cmap: &[u8], offset: usize) -> Option<usize> {
+    let format = u16::from_be_bytes(try_slice(cmap, offset, 2).ok()?.try_into().ok()?);
+    let length = match format {
+        0 | 2 | 4 | 6 => u16::from_be_bytes(try_slice(cmap, offset + 2, 2).ok()?.try_into().ok()?) as usize,
+        8 | 10 | 12 | 13 => u32::from_be_bytes(try_slice(cmap, offset + 4, 4).ok()?.try_into().ok()?) as usize,
+        14 => u32::from_be_bytes(try_slice(cmap, offset + 2, 4).ok()?.try_into().ok()?) as usize,
+        _ => return None,
+    };
+    try_slice(cmap, offset, length).ok().map(|_| length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn sanitize_test_tables() -> Vec<(u32, Vec<u8>)> {
+        let glyphs = vec![vec![], simple_glyph(&[&[(0, 0, true), (100, 0, true), (100, 100, true)]])];
+        let (glyf, loca) = glyf_and_loca(&glyphs);
+        vec![
+            (tag(b"head"), head_table(1000, 1)),
+            (tag(b"maxp"), maxp_table(2, 3)),
+            (tag(b"hhea"), hhea_table(800, -200, 0, 1)),
+            (tag(b"hmtx"), vec![0, 100, 0, 0, 0, 0]),
+            (tag(b"cmap"), cmap_table(&[(0x41, 1)], &[])),
+            (tag(b"glyf"), glyf),
+            (tag(b"loca"), loca),
+        ]
+    }
+
+    fn replace(tables: &mut [(u32, Vec<u8>)], table_tag: &[u8; 4], data: Vec<u8>) {
+        tables.iter_mut().find(|(current, _)| *current == tag(table_tag)).unwrap().1 = data;
+    }
+
+    #[test]
+    fn test_sanitize_valid_font() {
+        let font = Sfnt::from_tables(0x00010000, sanitize_test_tables()).unwrap();
+        let report = sanitize(&font);
+        debug_assert!(report.issues.is_empty());
+        debug_assert!(report.font.unwrap().as_bytes() == font.as_bytes());
+    }
+
+    #[test]
+    fn test_sanitize_rejects_glyph_tables() {
+        let mut tables = sanitize_test_tables();
+        replace(&mut tables, b"maxp", maxp_table(2, 2));
+        let issues = sanitize_tables(&mut tables);
+        debug_assert!(issues == vec![SanitizeIssue {
+            tag: tag(b"glyf"),
+            kind: SanitizeIssueKind::GlyphPointsExceedMaxp { glyph_id: 1, points: 3, max_points: 2 },
+            action: SanitizeAction::FontRejected,
+        }]);
+
+        let mut tables = sanitize_test_tables();
+        replace(&mut tables, b"maxp", maxp_table(3, 3));
+        let issues = sanitize_tables(&mut tables);
+        debug_assert!(issues[0].kind == SanitizeIssueKind::LocaTooShort { num_glyphs: 3, expected: 16, actual: 12 });
+
+        let mut tables = sanitize_test_tables();
+        let glyf_length = tables[5].1.len() as u32;
+        replace(&mut tables, b"loca", [0u32, 0, glyf_length + 4].iter().flat_map(|offset| offset.to_be_bytes()).collect());
+        let issues = sanitize_tables(&mut tables);
+        debug_assert!(issues[0].kind == SanitizeIssueKind::LocaOffsetOutOfBounds { glyph_id: 1, offset: glyf_length + 4, glyf_length });
+
+        let mut tables = sanitize_test_tables();
+        replace(&mut tables, b"loca", [0u32, 12, 4].iter().flat_map(|offset| offset.to_be_bytes()).collect());
+        debug_assert!(sanitize_tables(&mut tables)[0].kind == SanitizeIssueKind::LocaNotMonotonic { glyph_id: 1 });
+
+        let mut tables = sanitize_test_tables();
+        replace(&mut tables, b"hmtx", vec![0, 100, 0]);
+        let issues = sanitize_tables(&mut tables);
+        debug_assert!(issues[0].kind == SanitizeIssueKind::HmtxTooShort { expected: 6, actual: 3 });
+
+        let mut tables = sanitize_test_tables();
+        replace(&mut tables, b"hhea", hhea_table(800, -200, 0, 3));
+        let issues = sanitize_tables(&mut tables);
+        debug_assert!(issues[0].kind == SanitizeIssueKind::InvalidNumberOfHMetrics { number_of_h_metrics: 3, num_glyphs: 2 });
+        debug_assert!(is_rejected(&issues));
+    }
+
+    #[test]
+    fn test_sanitize_drops_cmap_subtable() {
+        let mut tables = sanitize_test_tables();
+        let mut cmap = cmap_table(&[(0x41, 1)], &[]);
+        // add the second encoding record pointing outside of the table
+        let num_tables = u16::from_be_bytes([cmap[2], cmap[3]]);
+        let mut broken = cmap[..4 + num_tables as usize * 8].to_vec();
+        broken[2..4].copy_from_slice(&(num_tables + 1).to_be_bytes());
+        broken.extend_from_slice(&[0, 0, 0, 3, 0, 0, 0xFF, 0xFF]);
+        for record in broken[4..4 + num_tables as usize * 8].chunks_mut(8) {
+            let offset = u32::from_be_bytes([record[4], record[5], record[6], record[7]]) + 8;
+            record[4..8].copy_from_slice(&offset.to_be_bytes());
+        }
+        broken.extend_from_slice(&cmap.split_off(4 + num_tables as usize * 8));
+        replace(&mut tables, b"cmap", broken);
+
+        let font = Sfnt::from_tables(0x00010000, tables).unwrap();
+        let report = sanitize(&font);
+        debug_assert!(report.issues == vec![SanitizeIssue {
+            tag: tag(b"cmap"),
+            kind: SanitizeIssueKind::CmapSubtableOutOfBounds { platform_id: 0, encoding_id: 3, offset: 0xFFFF },
+            action: SanitizeAction::SubtableDropped,
+        }]);
+        let font = report.font.unwrap();
+        debug_assert!(font.cmap().unwrap().glyph_for_char('A') == Some(1));
+    }
+}
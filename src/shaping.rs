@@ -0,0 +1,245 @@
+//! Simple text shaping for measuring strings: character mapping, 'GSUB' single and ligature substitutions
+//! of the default features, 'GPOS' pair kerning with 'kern' table fallback and advances from 'hmtx'.
+//!
+//! Text is shaped left to right as a single run, lookup flags and contextual lookups aren't supported.
+
+use crate::font::FontTables;
+use crate::layout::optional;
+use crate::tables::gpos::{pair_adjustment, ValueRecord, PAIR_ADJUSTMENT};
+use crate::tables::gsub::{ligature_substitute, single_substitute, LIGATURE_SUBSTITUTION, SINGLE_SUBSTITUTION};
+use crate::tables::layout::{LayoutTable, DEFAULT_SCRIPT_TAG};
+use crate::utils::tag;
+use crate::Error;
+
+/// 'GSUB' features applied by default
+pub const DEFAULT_GSUB_FEATURES: [u32; 5] = [tag(b"ccmp"), tag(b"locl"), tag(b"rlig"), tag(b"liga"), tag(b"clig")];
+/// 'GPOS' features applied by default
+pub const DEFAULT_GPOS_FEATURES: [u32; 1] = [tag(b"kern")];
+
+/// Script, language and features used for shaping
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShapingOptions {
+    pub script: u32,
+    // `None` for the default language system of the script
+    pub language: Option<u32>,
+    // Features applied in addition to the default ones
+    pub features: Vec<u32>,
+    // Normalized variation coordinates used to select feature variations
+    pub coordinates: Vec<f32>,
+}
+
+impl Default for ShapingOptions {
+    fn default() -> Self {
+        ShapingOptions { script: DEFAULT_SCRIPT_TAG, language: None, features: vec![], coordinates: vec![] }
+    }
+}
+
+/// Glyph of a shaped run, all values are in font units
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PositionedGlyph {
+    pub glyph_id: u16,
+    // Index of the first character of the text the glyph is made of
+    pub cluster: usize,
+    pub x_advance: i32,
+    pub x_offset: i32,
+    pub y_offset: i32,
+}
+
+/// Shaped glyphs in visual order with the total advance
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GlyphRun {
+    pub glyphs: Vec<PositionedGlyph>,
+    // Sum of glyph advances in font units
+    pub advance: i32,
+    pub units_per_em: u16,
+}
+
+impl GlyphRun {
+    /// Total advance in pixels at `pixel_size` pixels per em
+    pub fn advance_at(&self, pixel_size: f32) -> f32 {
+        self.to_pixels(self.advance, pixel_size)
+    }
+
+    /// (x, y) origins of the glyphs in pixels at `pixel_size` pixels per em, y axis goes up
+    pub fn positions_at(&self, pixel_size: f32) -> Vec<(f32, f32)> {
+        let mut pen = 0;
+        self.glyphs.iter().map(|glyph| {
+            let position = (self.to_pixels(pen + glyph.x_offset, pixel_size), self.to_pixels(glyph.y_offset, pixel_size));
+            pen += glyph.x_advance;
+            position
+        }).collect()
+    }
+
+    fn to_pixels(&self, value: i32, pixel_size: f32) -> f32 {
+        if self.units_per_em == 0 {
+            return 0.0;
+        }
+        value as f32 * pixel_size / self.units_per_em as f32
+    }
+}
+
+/// Shapes the text with the font, see `ShapingOptions`. Characters missing in 'cmap' are mapped to glyph 0
+pub(crate) fn shape_text<F: FontTables + ?Sized>(font: &F, text: &str, options: &ShapingOptions) -> Result<GlyphRun, Error> {
+    let units_per_em = font.head()?.units_per_em;
+    let cmap = font.cmap()?;
+    let hmtx = font.hmtx()?;
+    let mut glyphs: Vec<(u16, usize)> =
+        text.chars().enumerate().map(|(cluster, ch)| (cmap.glyph_for_char(ch).unwrap_or(0), cluster)).collect();
+
+    if let Some(gsub) = optional(font.gsub())? {
+        let feature_tags = [&DEFAULT_GSUB_FEATURES[..], &options.features].concat();
+        for lookup_index in gsub.lookups_for_features(options.script, options.language, &feature_tags, &options.coordinates) {
+            apply_substitution(&gsub, lookup_index, &mut glyphs)?;
+        }
+    }
+
+    let mut positioned: Vec<PositionedGlyph> = glyphs.iter().map(|&(glyph_id, cluster)| PositionedGlyph {
+        glyph_id,
+        cluster,
+        x_advance: hmtx.advance(glyph_id).unwrap_or(0) as i32,
+        ..PositionedGlyph::default()
+    }).collect();
+
+    // 'kern' table is used only if 'GPOS' has no pair adjustments for the features
+    let mut is_kerned = false;
+    if let Some(gpos) = optional(font.gpos())? {
+        let feature_tags = [&DEFAULT_GPOS_FEATURES[..], &options.features].concat();
+        for lookup_index in gpos.lookups_for_features(options.script, options.language, &feature_tags, &options.coordinates) {
+            is_kerned |= apply_pair_adjustment(&gpos, lookup_index, &mut positioned)?;
+        }
+    }
+    if !is_kerned {
+        if let Some(kern) = optional(font.kern())? {
+            for index in 1..positioned.len() {
+                positioned[index - 1].x_advance += kern.kerning(positioned[index - 1].glyph_id, positioned[index].glyph_id) as i32;
+            }
+        }
+    }
+
+    let advance = positioned.iter().map(|glyph| glyph.x_advance).sum();
+    Ok(GlyphRun { glyphs: positioned, advance, units_per_em })
+}
+
+/// Applies single or ligature substitution lookup to the glyphs, other lookup types are skipped.
+/// Ligature keeps the cluster of its first component
+fn apply_substitution(gsub: &LayoutTable, lookup_index: u16, glyphs: &mut Vec<(u16, usize)>) -> Result<(), Error> {
+    let lookup = &gsub.lookups[lookup_index as usize];
+    if lookup.lookup_type != SINGLE_SUBSTITUTION && lookup.lookup_type != LIGATURE_SUBSTITUTION {
+        return Ok(());
+    }
+    let mut index = 0;
+    while index < glyphs.len() {
+        // the first subtable which applies to the glyph is used
+        for subtable_index in 0..lookup.subtable_count() {
+            let subtable = gsub.subtable_data(lookup_index, subtable_index).ok_or(Error::InvalidTableData)?;
+            if lookup.lookup_type == SINGLE_SUBSTITUTION {
+                if let Some(substitute) = single_substitute(subtable, glyphs[index].0)? {
+                    glyphs[index].0 = substitute;
+                    break;
+                }
+            } else {
+                let components: Vec<u16> = glyphs[index..].iter().map(|(glyph_id, _)| *glyph_id).collect();
+                if let Some((ligature, component_count)) = ligature_substitute(subtable, &components)? {
+                    glyphs[index].0 = ligature;
+                    glyphs.drain(index + 1..index + component_count);
+                    break;
+                }
+            }
+        }
+        index += 1;
+    }
+    Ok(())
+}
+
+/// Applies pair adjustment lookup to the glyphs, other lookup types are skipped.
+/// Returns `true` if the lookup is a pair adjustment one
+fn apply_pair_adjustment(gpos: &LayoutTable, lookup_index: u16, glyphs: &mut [PositionedGlyph]) -> Result<bool, Error> {
+    let lookup = &gpos.lookups[lookup_index as usize];
+    if lookup.lookup_type != PAIR_ADJUSTMENT {
+        return Ok(false);
+    }
+    let mut index = 0;
+    while index + 1 < glyphs.len() {
+        let mut step = 1;
+        for subtable_index in 0..lookup.subtable_count() {
+            let subtable = gpos.subtable_data(lookup_index, subtable_index).ok_or(Error::InvalidTableData)?;
+            if let Some((first, second, has_second_value)) = pair_adjustment(subtable, glyphs[index].glyph_id, glyphs[index + 1].glyph_id)? {
+                adjust(&mut glyphs[index], &first);
+                adjust(&mut glyphs[index + 1], &second);
+                // the second glyph isn't the first one of the next pair if it has been adjusted
+                if has_second_value {
+                    step = 2;
+                }
+                break;
+            }
+        }
+        index += step;
+    }
+    Ok(true)
+}
+
+fn adjust(glyph: &mut PositionedGlyph, value: &ValueRecord) {
+    glyph.x_advance += value.x_advance as i32;
+    glyph.x_offset += value.x_placement as i32;
+    glyph.y_offset += value.y_placement as i32;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Sfnt;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_shape_without_layout_tables() {
+        let mut tables = truetype_font_tables();
+        let font = Sfnt::from_tables(0x00010000, tables.clone()).unwrap();
+        let run = font.shape("AB C", &ShapingOptions::default()).unwrap();
+        debug_assert!(run.glyphs.iter().map(|glyph| glyph.glyph_id).collect::<Vec<u16>>() == vec![1, 2, 3, 0]);
+        debug_assert!(run.advance == 700 + 400 + 250 + 500 && run.glyphs[3].cluster == 3);
+        debug_assert!(run.advance_at(20.0) == 37.0);
+        debug_assert!(run.positions_at(10.0) == vec![(0.0, 0.0), (7.0, 0.0), (11.0, 0.0), (13.5, 0.0)]);
+
+        tables.push((tag(b"kern"), kern_table(&[(1, 2, -100)])));
+        let font = Sfnt::from_tables(0x00010000, tables).unwrap();
+        let run = font.shape("AB", &ShapingOptions::default()).unwrap();
+        debug_assert!(run.glyphs[0].x_advance == 600 && run.advance == 1000);
+        debug_assert!(font.shape("", &ShapingOptions::default()).unwrap().advance == 0);
+    }
+
+    #[test]
+    fn test_shape_with_layout_tables() {
+        let mut tables = truetype_font_tables();
+        tables.push((tag(b"GSUB"), layout_table(
+            &[(tag(b"latn"), vec![(None, None, vec![0, 1])])],
+            &[(tag(b"liga"), vec![1]), (tag(b"smcp"), vec![0])],
+            &[(1, vec![single_substitution_subtable(&[(3, 2)])]), (4, vec![ligature_substitution_subtable(&[(&[1, 2], 4)])])],
+            &[],
+        )));
+        tables.push((tag(b"GPOS"), layout_table(
+            &[(tag(b"latn"), vec![(None, None, vec![0])])],
+            &[(tag(b"kern"), vec![0])],
+            &[(2, vec![pair_adjustment_subtable(&[(4, 1, -30)])])],
+            &[],
+        )));
+        // 'kern' table is ignored as 'GPOS' has kerning
+        tables.push((tag(b"kern"), kern_table(&[(4, 1, -100)])));
+        let font = Sfnt::from_tables(0x00010000, tables).unwrap();
+        let latin = ShapingOptions { script: tag(b"latn"), ..ShapingOptions::default() };
+        let run = font.shape("ABA B", &latin).unwrap();
+        debug_assert!(run.glyphs == vec![
+            PositionedGlyph { glyph_id: 4, cluster: 0, x_advance: 770, x_offset: 0, y_offset: 0 },
+            PositionedGlyph { glyph_id: 1, cluster: 2, x_advance: 700, x_offset: 0, y_offset: 0 },
+            PositionedGlyph { glyph_id: 3, cluster: 3, x_advance: 250, x_offset: 0, y_offset: 0 },
+            PositionedGlyph { glyph_id: 2, cluster: 4, x_advance: 400, x_offset: 0, y_offset: 0 },
+        ]);
+        debug_assert!(run.advance == 2120);
+        // the font has only 'latn' script which is used as the fallback of the default script
+        debug_assert!(font.shape("ABA B", &ShapingOptions::default()).unwrap() == run);
+
+        let small_caps = ShapingOptions { features: vec![tag(b"smcp")], ..latin };
+        let run = font.shape("A B", &small_caps).unwrap();
+        // space is substituted before the ligature is formed, lookups are applied in the order of the lookup list
+        debug_assert!(run.glyphs.iter().map(|glyph| (glyph.glyph_id, glyph.cluster)).collect::<Vec<(u16, usize)>>() == vec![(4, 0), (2, 2)]);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::utils::{u32_to_u8_array, u16_to_u8_array};
+use crate::utils::{u32_to_u8_array, u16_to_u8_array, try_read_u16_be, try_read_u32_be, try_read_i16_be};
+use crate::Error;
+use bytebuffer::ByteReader;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// WOFF header (44 bytes length)
 #[allow(dead_code)]
@@ -31,6 +34,7 @@ pub(crate) struct WoffHeader {
 }
 
 /// WOFF table directory
+#[derive(Clone)]
 pub(crate) struct WoffTableDirectoryEntry {
     // 4-byte sfnt table identifier
     pub(crate) tag: u32,
@@ -89,42 +93,106 @@ impl SfntTableRecord {
     }
 }
 
-/// SFNT header table
-#[allow(dead_code)]
-pub(crate) struct SfntHeaderTable {
+/// SFNT header table ('head', 54 bytes length)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SfntHeaderTable {
     // Major version number of the font header table — set to 1
-    pub(crate) major_version: u16,
+    pub major_version: u16,
     // Minor version number of the font header table — set to 0
-    pub(crate) minor_version: u16,
+    pub minor_version: u16,
     // Set by font manufacturer
-    pub(crate) font_revision: u32,
-    pub(crate) check_sum_adjustment: u32,
-    // Set to 0x5F0F3CF5 "OTTO"
-    pub(crate) magic_number: u32,
-    pub(crate) flags: u16,
+    pub font_revision: u32,
+    pub check_sum_adjustment: u32,
+    // Set to 0x5F0F3CF5
+    pub magic_number: u32,
+    pub flags: u16,
     // Set to a value from 16 to 16384. Any value in this range is valid
-    pub(crate) units_per_em: u16,
+    pub units_per_em: u16,
     // Number of seconds since 12:00 midnight that started January 1st 1904 in GMT/UTC time zone.
     // 64-bit integer
-    pub(crate) created: [u32; 2],
+    pub created: [u32; 2],
     // Number of seconds since 12:00 midnight that started January 1st 1904 in GMT/UTC time zone.
     // 64-bit integer
-    pub(crate) modified: [u32; 2],
+    pub modified: [u32; 2],
     // For all glyph bounding boxes
-    pub(crate) x_min: i16,
-    pub(crate) y_min: i16,
-    pub(crate) x_max: i16,
-    pub(crate) y_max: i16,
+    pub x_min: i16,
+    pub y_min: i16,
+    pub x_max: i16,
+    pub y_max: i16,
     // Bit 0: Bold (if set to 1); Bit 1: Italic (if set to 1) Bit 2: Underline (if set to 1)
     // Bit 3: Outline (if set to 1) Bit 4: Shadow (if set to 1) Bit 5: Condensed (if set to 1)
     // Bit 6: Extended (if set to 1) Bits 7–15: Reserved (set to 0).
-    pub(crate) mac_style: u16,
+    pub mac_style: u16,
     // Smallest readable size in pixels.
-    pub(crate) lowest_rec_ppem: u16,
+    pub lowest_rec_ppem: u16,
     // Deprecated. Only strongly left to right but also contains neutrals
-    pub(crate) font_direction_hint: i16,
+    pub font_direction_hint: i16,
     // 0 for short offsets (Offset16), 1 for long (Offset32).
-    pub(crate) index_to_loc_format: i16,
+    pub index_to_loc_format: i16,
     // 0 for current format.
-    pub(crate) glyph_data_format: i16,
-}
\ No newline at end of file
+    pub glyph_data_format: i16,
+}
+
+/// Magic number every valid 'head' table contains
+pub(crate) const HEAD_MAGIC_NUMBER: u32 = 0x5F0F3CF5;
+/// Checksum of the whole font with correct 'head' checkSumAdjustment value
+pub(crate) const HEAD_CHECK_SUM_MAGIC: u32 = 0xB1B0AFBA;
+/// Offset of checkSumAdjustment field in 'head' table
+pub(crate) const HEAD_CHECK_SUM_ADJUSTMENT_OFFSET: usize = 8;
+/// Offset of indexToLocFormat field in 'head' table
+pub(crate) const HEAD_INDEX_TO_LOC_FORMAT_OFFSET: usize = 50;
+
+/// Seconds between 1904-01-01 (SFNT LONGDATETIME epoch) and 1970-01-01 (UNIX epoch)
+const SECONDS_FROM_1904_TO_1970: i64 = 2_082_844_800;
+
+impl SfntHeaderTable {
+    /// Parses raw 'head' table data and validates its magic number
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let head = SfntHeaderTable {
+            major_version: try_read_u16_be(&mut buffer)?,
+            minor_version: try_read_u16_be(&mut buffer)?,
+            font_revision: try_read_u32_be(&mut buffer)?,
+            check_sum_adjustment: try_read_u32_be(&mut buffer)?,
+            magic_number: try_read_u32_be(&mut buffer)?,
+            flags: try_read_u16_be(&mut buffer)?,
+            units_per_em: try_read_u16_be(&mut buffer)?,
+            created: [try_read_u32_be(&mut buffer)?, try_read_u32_be(&mut buffer)?],
+            modified: [try_read_u32_be(&mut buffer)?, try_read_u32_be(&mut buffer)?],
+            x_min: try_read_i16_be(&mut buffer)?,
+            y_min: try_read_i16_be(&mut buffer)?,
+            x_max: try_read_i16_be(&mut buffer)?,
+            y_max: try_read_i16_be(&mut buffer)?,
+            mac_style: try_read_u16_be(&mut buffer)?,
+            lowest_rec_ppem: try_read_u16_be(&mut buffer)?,
+            font_direction_hint: try_read_i16_be(&mut buffer)?,
+            index_to_loc_format: try_read_i16_be(&mut buffer)?,
+            glyph_data_format: try_read_i16_be(&mut buffer)?,
+        };
+        if head.magic_number != HEAD_MAGIC_NUMBER {
+            return Err(Error::InvalidHeadMagicNumber);
+        }
+        Ok(head)
+    }
+
+    /// Font creation time converted from the 1904-based LONGDATETIME
+    pub fn created_time(&self) -> SystemTime {
+        long_date_time_to_system_time(self.created)
+    }
+
+    /// Font modification time converted from the 1904-based LONGDATETIME
+    pub fn modified_time(&self) -> SystemTime {
+        long_date_time_to_system_time(self.modified)
+    }
+}
+
+/// Converts LONGDATETIME stored as two big endian halves to `SystemTime`
+fn long_date_time_to_system_time(value: [u32; 2]) -> SystemTime {
+    let seconds = (((value[0] as u64) << 32) | value[1] as u64) as i64;
+    let unix_seconds = seconds.saturating_sub(SECONDS_FROM_1904_TO_1970);
+    if unix_seconds >= 0 {
+        UNIX_EPOCH + Duration::from_secs(unix_seconds as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(unix_seconds.unsigned_abs())
+    }
+}
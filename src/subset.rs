@@ -0,0 +1,525 @@
+//! Font subsetting: keeps only requested glyphs and rewrites tables which refer to glyph IDs.
+//!
+//! Glyphs are renumbered in their original order, '.notdef' (glyph 0) is always kept.
+//! Tables which aren't rewritten and don't depend on glyph IDs are copied as is,
+//! all other tables (layout, kerning, vertical metrics, etc.) are dropped.
+
+mod cff;
+
+use crate::font::{FontTables, Sfnt};
+use crate::structures::HEAD_INDEX_TO_LOC_FORMAT_OFFSET;
+use crate::tables::cff::CffGlyphs;
+use crate::tables::charstring::seac_components;
+use crate::tables::cmap::build_unicode_cmap;
+use crate::tables::glyf::{build_glyf_and_loca, remap_component_glyph_ids, Glyph, TrueTypeGlyphs, MAX_COMPONENT_DEPTH};
+use crate::tables::hmtx::HmtxTable;
+use crate::tables::maxp::{MaxpTable, MAXP_VERSION_1_0};
+use crate::tables::name::name_id;
+use crate::tables::post::*;
+use crate::utils::*;
+use crate::Error;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Version of SFNT with TrueType outlines
+const SFNT_VERSION_TRUETYPE: u32 = 0x00010000;
+/// Version of SFNT with CFF outlines ('OTTO')
+const SFNT_VERSION_CFF: u32 = 0x4F54544F;
+
+/// Offsets of advanceWidthMax and numberOfHMetrics fields in 'hhea' table
+const HHEA_ADVANCE_WIDTH_MAX_OFFSET: usize = 10;
+const HHEA_NUMBER_OF_H_METRICS_OFFSET: usize = 34;
+/// Offset of usFirstCharIndex field in 'OS/2' table (usLastCharIndex follows it)
+const OS2_FIRST_CHAR_INDEX_OFFSET: usize = 64;
+
+/// Tables which don't refer to glyph IDs and are copied to the subset without changes
+const PASSTHROUGH_TABLES: [u32; 5] = [tag(b"OS/2"), tag(b"cvt "), tag(b"fpgm"), tag(b"gasp"), tag(b"prep")];
+
+/// Names which are kept in subset 'name' table
+const SUBSET_NAME_IDS: [u16; 11] = [
+    name_id::COPYRIGHT,
+    name_id::FAMILY,
+    name_id::SUBFAMILY,
+    name_id::UNIQUE_ID,
+    name_id::FULL_NAME,
+    name_id::VERSION,
+    name_id::POSTSCRIPT_NAME,
+    name_id::LICENSE,
+    name_id::LICENSE_URL,
+    name_id::TYPOGRAPHIC_FAMILY,
+    name_id::TYPOGRAPHIC_SUBFAMILY,
+];
+
+/// Glyphs which have to be kept in a subset
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubsetGlyphs {
+    // Glyphs mapped to Unicode codepoints by 'cmap' table; only these codepoints stay in subset 'cmap'
+    Codepoints(BTreeSet<u32>),
+    // Glyph IDs; all codepoints mapped to these glyphs stay in subset 'cmap'
+    GlyphIds(BTreeSet<u16>),
+}
+
+/// Subset font
+pub struct FontSubset {
+    pub sfnt: Sfnt,
+    // Original glyph ID of every subset glyph, index is the new glyph ID
+    pub old_glyph_ids: Vec<u16>,
+}
+
+/// Glyph outlines of the font being subset
+enum Outlines<'a> {
+    TrueType(TrueTypeGlyphs<'a>),
+    Cff(Box<CffGlyphs<'a>>),
+}
+
+/// Creates subset of a font with TrueType ('glyf') or CFF ('CFF ') outlines which contains
+/// only `glyphs` and glyphs used by them as composite glyph components or seac base and accent glyphs.
+///
+/// 'glyf', 'loca', 'CFF ', 'hmtx', 'cmap', 'maxp', 'post', 'name', 'head' and 'hhea' tables are rewritten,
+/// 'OS/2', 'cvt ', 'fpgm', 'gasp' and 'prep' tables are copied, all other tables are dropped.
+/// The result is a valid SFNT with recalculated checksums.
+///
+/// Fonts with 'CFF2' outlines aren't supported and fail with `Error::TableNotFound`
+pub fn subset_font<F: FontTables + ?Sized>(font: &F, glyphs: &SubsetGlyphs) -> Result<FontSubset, Error> {
+    let maxp = font.maxp()?;
+    let cmap = font.cmap()?;
+    let outlines = if font.has_table(tag(b"glyf")) {
+        Outlines::TrueType(font.truetype_glyphs()?)
+    } else {
+        Outlines::Cff(Box::new(CffGlyphs::parse_cff(font.table_data(tag(b"CFF "))?)?))
+    };
+    let num_glyphs = match &outlines {
+        Outlines::TrueType(glyphs) => glyphs.num_glyphs(),
+        Outlines::Cff(glyphs) => glyphs.num_glyphs().min(maxp.num_glyphs),
+    };
+
+    // character codes of the subset with their original glyph IDs
+    let mut mappings: BTreeMap<u32, u16> = BTreeMap::new();
+    let mut glyph_set: BTreeSet<u16> = BTreeSet::from([0]);
+    if let Some(subtable) = cmap.unicode_subtable() {
+        match glyphs {
+            SubsetGlyphs::Codepoints(codepoints) => {
+                for &code in codepoints.iter() {
+                    if let Some(glyph_id) = subtable.glyph_id(code).filter(|&glyph_id| glyph_id < num_glyphs) {
+                        mappings.insert(code, glyph_id);
+                        glyph_set.insert(glyph_id);
+                    }
+                }
+            }
+            SubsetGlyphs::GlyphIds(glyph_ids) => {
+                glyph_set.extend(glyph_ids.iter().filter(|&&glyph_id| glyph_id < num_glyphs));
+                subtable.for_each_mapping(|code, glyph_id| {
+                    if glyph_set.contains(&glyph_id) {
+                        mappings.insert(code, glyph_id);
+                    }
+                });
+            }
+        }
+    }
+    match &outlines {
+        Outlines::TrueType(truetype_glyphs) => add_composite_components(truetype_glyphs, &mut glyph_set)?,
+        Outlines::Cff(cff_glyphs) => add_seac_components(cff_glyphs, &mut glyph_set)?,
+    }
+
+    let old_glyph_ids: Vec<u16> = glyph_set.into_iter().collect();
+    let new_glyph_ids: BTreeMap<u16, u16> =
+        old_glyph_ids.iter().enumerate().map(|(new_id, &old_id)| (old_id, new_id as u16)).collect();
+    for glyph_id in mappings.values_mut() {
+        *glyph_id = new_glyph_ids[glyph_id];
+    }
+
+    // parse 'head' first to be sure the table is valid and long enough
+    font.head()?;
+    let mut head = font.table_data(tag(b"head"))?.into_owned();
+    let mut tables: Vec<(u32, Vec<u8>)> = vec![];
+    let (version, maxp) = match &outlines {
+        Outlines::TrueType(truetype_glyphs) => {
+            let (glyf, loca, index_to_loc_format) = subset_glyf(truetype_glyphs, &old_glyph_ids, &new_glyph_ids)?;
+            tables.push((tag(b"glyf"), glyf));
+            tables.push((tag(b"loca"), loca));
+            head[HEAD_INDEX_TO_LOC_FORMAT_OFFSET..HEAD_INDEX_TO_LOC_FORMAT_OFFSET + 2]
+                .copy_from_slice(&index_to_loc_format.to_be_bytes());
+            (SFNT_VERSION_TRUETYPE, subset_maxp(truetype_glyphs, maxp, &old_glyph_ids)?)
+        }
+        Outlines::Cff(cff_glyphs) => {
+            tables.push((tag(b"CFF "), cff::subset_cff(cff_glyphs, &old_glyph_ids)?));
+            let maxp = MaxpTable {
+                num_glyphs: old_glyph_ids.len() as u16,
+                ..maxp
+            };
+            (SFNT_VERSION_CFF, maxp)
+        }
+    };
+    tables.push((tag(b"head"), head));
+    tables.push((tag(b"maxp"), maxp.transform_to_u8_vec()));
+
+    let hmtx = font.hmtx()?;
+    let subset_hmtx = HmtxTable {
+        advances: old_glyph_ids.iter().map(|&glyph_id| hmtx.advances[glyph_id as usize]).collect(),
+        left_side_bearings: old_glyph_ids.iter().map(|&glyph_id| hmtx.left_side_bearings[glyph_id as usize]).collect(),
+    };
+    let advance_width_max = subset_hmtx.advances.iter().copied().max().unwrap_or_default();
+    let (hmtx, number_of_h_metrics) = subset_hmtx.transform_to_u8_vec();
+    // parse 'hhea' first to be sure the table is long enough
+    font.hhea()?;
+    let mut hhea = font.table_data(tag(b"hhea"))?.into_owned();
+    hhea[HHEA_ADVANCE_WIDTH_MAX_OFFSET..HHEA_ADVANCE_WIDTH_MAX_OFFSET + 2].copy_from_slice(&advance_width_max.to_be_bytes());
+    hhea[HHEA_NUMBER_OF_H_METRICS_OFFSET..HHEA_NUMBER_OF_H_METRICS_OFFSET + 2]
+        .copy_from_slice(&number_of_h_metrics.to_be_bytes());
+    tables.push((tag(b"hmtx"), hmtx));
+    tables.push((tag(b"hhea"), hhea));
+    tables.push((tag(b"cmap"), build_unicode_cmap(&mappings)));
+
+    if font.has_table(tag(b"post")) {
+        tables.push((tag(b"post"), subset_post(&font.table_data(tag(b"post"))?, &old_glyph_ids)?));
+    }
+    if font.has_table(tag(b"name")) {
+        let mut name = font.name()?;
+        name.records.retain(|record| SUBSET_NAME_IDS.contains(&record.name_id));
+        tables.push((tag(b"name"), name.transform_to_u8_vec()));
+    }
+    for table_tag in PASSTHROUGH_TABLES {
+        if font.has_table(table_tag) {
+            tables.push((table_tag, font.table_data(table_tag)?.into_owned()));
+        }
+    }
+    update_os2_char_indices(&mut tables, &mappings);
+
+    Ok(FontSubset {
+        sfnt: Sfnt::from_tables(version, tables)?,
+        old_glyph_ids,
+    })
+}
+
+/// Adds components of composite glyphs from `glyph_set` (and components of their components) to the set
+fn add_composite_components(glyphs: &TrueTypeGlyphs, glyph_set: &mut BTreeSet<u16>) -> Result<(), Error> {
+    let mut pending: Vec<u16> = glyph_set.iter().copied().collect();
+    while let Some(glyph_id) = pending.pop() {
+        if let Glyph::Composite(composite) = glyphs.glyph(glyph_id)? {
+            for component in composite.components {
+                if component.glyph_id >= glyphs.num_glyphs() {
+                    return Err(Error::InvalidTableData);
+                }
+                if glyph_set.insert(component.glyph_id) {
+                    pending.push(component.glyph_id);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Adds base and accent glyphs of seac accented characters to the glyph set.
+/// Base and accent glyphs can't be accented characters themselves
+fn add_seac_components(glyphs: &CffGlyphs, glyph_set: &mut BTreeSet<u16>) -> Result<(), Error> {
+    let mut components: Vec<u16> = vec![];
+    for &glyph_id in glyph_set.iter() {
+        if let Some((base, accent)) = seac_components(glyphs, glyph_id)? {
+            components.extend([base, accent]);
+        }
+    }
+    glyph_set.extend(components);
+    Ok(())
+}
+
+/// Builds 'glyf' and 'loca' tables with glyphs `old_glyph_ids` and component glyph IDs replaced
+/// with the new ones. Returns tables data and 'loca' format (short one is used when it's possible)
+fn subset_glyf(
+    glyphs: &TrueTypeGlyphs,
+    old_glyph_ids: &[u16],
+    new_glyph_ids: &BTreeMap<u16, u16>,
+) -> Result<(Vec<u8>, Vec<u8>, i16), Error> {
+    let mut glyph_data: Vec<Vec<u8>> = Vec::with_capacity(old_glyph_ids.len());
+    for &glyph_id in old_glyph_ids {
+        let mut data = glyphs.glyph_data(glyph_id)?.to_vec();
+        remap_component_glyph_ids(&mut data, |component_id| new_glyph_ids[&component_id])?;
+        glyph_data.push(data);
+    }
+    Ok(build_glyf_and_loca(&glyph_data))
+}
+
+/// Updates number of glyphs and recalculates glyph maxima of version 1.0 'maxp' table
+fn subset_maxp(glyphs: &TrueTypeGlyphs, maxp: MaxpTable, old_glyph_ids: &[u16]) -> Result<MaxpTable, Error> {
+    let mut maxp = MaxpTable {
+        num_glyphs: old_glyph_ids.len() as u16,
+        ..maxp
+    };
+    if maxp.version != MAXP_VERSION_1_0 {
+        return Ok(maxp);
+    }
+    maxp.max_points = 0;
+    maxp.max_contours = 0;
+    maxp.max_composite_points = 0;
+    maxp.max_composite_contours = 0;
+    maxp.max_component_elements = 0;
+    maxp.max_component_depth = 0;
+    let mut totals: BTreeMap<u16, GlyphTotals> = BTreeMap::new();
+    for &glyph_id in old_glyph_ids {
+        match glyphs.glyph(glyph_id)? {
+            Glyph::Empty => {}
+            Glyph::Simple(simple) => {
+                maxp.max_points = maxp.max_points.max(simple.points.len() as u16);
+                maxp.max_contours = maxp.max_contours.max(simple.end_pts_of_contours.len() as u16);
+            }
+            Glyph::Composite(composite) => {
+                let glyph_totals = glyph_totals(glyphs, glyph_id, 0, &mut totals)?;
+                maxp.max_composite_points = maxp.max_composite_points.max(glyph_totals.points.min(u16::MAX as usize) as u16);
+                maxp.max_composite_contours = maxp.max_composite_contours.max(glyph_totals.contours.min(u16::MAX as usize) as u16);
+                maxp.max_component_elements = maxp.max_component_elements.max(composite.components.len() as u16);
+                maxp.max_component_depth = maxp.max_component_depth.max(glyph_totals.depth);
+            }
+        }
+    }
+    Ok(maxp)
+}
+
+/// Totals of a glyph with all its components resolved
+#[derive(Clone, Copy, Debug, Default)]
+struct GlyphTotals {
+    points: usize,
+    contours: usize,
+    // Nesting level of composite glyph: 1 if all its components are simple glyphs, 0 for simple glyphs
+    depth: u16,
+}
+
+/// Calculates totals of the glyph. Totals of every glyph are calculated once and kept in `totals`,
+/// so glyphs referenced many times don't make the calculation exponential
+fn glyph_totals(
+    glyphs: &TrueTypeGlyphs,
+    glyph_id: u16,
+    depth: usize,
+    totals: &mut BTreeMap<u16, GlyphTotals>,
+) -> Result<GlyphTotals, Error> {
+    if let Some(glyph_totals) = totals.get(&glyph_id) {
+        return Ok(*glyph_totals);
+    }
+    if depth > MAX_COMPONENT_DEPTH {
+        return Err(Error::InvalidTableData);
+    }
+    let glyph_totals = match glyphs.glyph(glyph_id)? {
+        Glyph::Empty => GlyphTotals::default(),
+        Glyph::Simple(simple) => GlyphTotals {
+            points: simple.points.len(),
+            contours: simple.end_pts_of_contours.len(),
+            depth: 0,
+        },
+        Glyph::Composite(composite) => {
+            let mut composite_totals = GlyphTotals::default();
+            for component in composite.components {
+                let component_totals = glyph_totals(glyphs, component.glyph_id, depth + 1, totals)?;
+                composite_totals.points = composite_totals.points.saturating_add(component_totals.points);
+                composite_totals.contours = composite_totals.contours.saturating_add(component_totals.contours);
+                composite_totals.depth = composite_totals.depth.max(component_totals.depth + 1);
+            }
+            composite_totals
+        }
+    };
+    totals.insert(glyph_id, glyph_totals);
+    Ok(glyph_totals)
+}
+
+/// Rewrites 'post' table for the subset glyphs. Glyph names of version 2.0 table are kept
+/// (unused custom names are removed), version 1.0 table is converted to version 2.0 one
+/// with standard names and tables of other versions are converted to version 3.0 (no glyph names)
+fn subset_post(data: &[u8], old_glyph_ids: &[u16]) -> Result<Vec<u8>, Error> {
+    let mut post = try_slice(data, 0, POST_HEADER_SIZE)?.to_vec();
+    let version = u32::from_be_bytes(post[..4].try_into().unwrap());
+    let glyph_names = match version {
+        POST_VERSION_1_0 => PostGlyphNames {
+            // version 1.0 names only the first 258 glyphs, other glyphs become .notdef
+            name_indices: old_glyph_ids
+                .iter()
+                .map(|&glyph_id| if glyph_id < NUM_STANDARD_NAMES { glyph_id } else { 0 })
+                .collect(),
+            names: vec![],
+        },
+        POST_VERSION_2_0 => {
+            let glyph_names = PostGlyphNames::parse(data)?;
+            let mut subset_names = PostGlyphNames {
+                name_indices: Vec::with_capacity(old_glyph_ids.len()),
+                names: vec![],
+            };
+            let mut new_name_indices: BTreeMap<u16, u16> = BTreeMap::new();
+            for &glyph_id in old_glyph_ids {
+                let name_index = glyph_names.name_indices.get(glyph_id as usize).copied().unwrap_or_default();
+                if name_index < NUM_STANDARD_NAMES {
+                    subset_names.name_indices.push(name_index);
+                    continue;
+                }
+                let name = glyph_names
+                    .names
+                    .get((name_index - NUM_STANDARD_NAMES) as usize)
+                    .ok_or(Error::InvalidTableData)?;
+                let new_index = *new_name_indices.entry(name_index).or_insert_with(|| {
+                    subset_names.names.push(name.clone());
+                    NUM_STANDARD_NAMES + subset_names.names.len() as u16 - 1
+                });
+                subset_names.name_indices.push(new_index);
+            }
+            subset_names
+        }
+        _ => {
+            post[..4].copy_from_slice(&POST_VERSION_3_0.to_be_bytes());
+            return Ok(post);
+        }
+    };
+    post[..4].copy_from_slice(&POST_VERSION_2_0.to_be_bytes());
+    post.extend_from_slice(&glyph_names.transform_to_u8_vec());
+    Ok(post)
+}
+
+/// Sets first and last character indices of 'OS/2' table to the first and the last subset characters
+fn update_os2_char_indices(tables: &mut [(u32, Vec<u8>)], mappings: &BTreeMap<u32, u16>) {
+    let os2 = tables.iter_mut().find(|(table_tag, _)| *table_tag == tag(b"OS/2"));
+    if let Some((_, os2)) = os2.filter(|(_, os2)| os2.len() >= OS2_FIRST_CHAR_INDEX_OFFSET + 4) {
+        let first = mappings.keys().next().copied().unwrap_or_default().min(0xFFFF) as u16;
+        let last = mappings.keys().next_back().copied().unwrap_or_default().min(0xFFFF) as u16;
+        os2[OS2_FIRST_CHAR_INDEX_OFFSET..OS2_FIRST_CHAR_INDEX_OFFSET + 2].copy_from_slice(&first.to_be_bytes());
+        os2[OS2_FIRST_CHAR_INDEX_OFFSET + 2..OS2_FIRST_CHAR_INDEX_OFFSET + 4].copy_from_slice(&last.to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::HEAD_CHECK_SUM_MAGIC;
+    use crate::tables::cmap::{CmapSubtable, CmapTable};
+    use crate::tables::name::PLATFORM_WINDOWS;
+    use crate::test_utils::*;
+    use crate::WoffFont;
+
+    fn test_font_tables() -> Vec<(u32, Vec<u8>)> {
+        let mut post = post_table(0, -100);
+        post[..4].copy_from_slice(&POST_VERSION_2_0.to_be_bytes());
+        post.extend_from_slice(&5u16.to_be_bytes());
+        for index in [0u16, 36, 258, 3, 259] {
+            post.extend_from_slice(&index.to_be_bytes());
+        }
+        post.extend_from_slice(b"\x03tri\x04comp");
+        let name = name_table(
+            &[
+                (PLATFORM_WINDOWS, 1, 0x0409, name_id::FAMILY, utf16_be("Test")),
+                (PLATFORM_WINDOWS, 1, 0x0409, name_id::DESCRIPTION, utf16_be("Test")),
+            ],
+            &[],
+        );
+        let mut tables = truetype_font_tables();
+        tables.retain(|(table_tag, _)| *table_tag != tag(b"post"));
+        tables.push((tag(b"post"), post));
+        tables.push((tag(b"name"), name));
+        tables.push((tag(b"GSUB"), vec![0; 10]));
+        tables
+    }
+
+    #[test]
+    fn test_subset_by_codepoints() {
+        let woff = build_woff(0x00010000, &test_font_tables());
+        let font = WoffFont::new(&woff).unwrap();
+        let subset = subset_font(&font, &SubsetGlyphs::Codepoints(BTreeSet::from([0xC4, 0x10000]))).unwrap();
+        let sfnt = &subset.sfnt;
+        debug_assert!(subset.old_glyph_ids == vec![0, 1, 2, 4]);
+        debug_assert!(calculate_table_checksum(sfnt.as_bytes()) == HEAD_CHECK_SUM_MAGIC);
+        debug_assert!(!sfnt.has_table(tag(b"GSUB")) && sfnt.has_table(tag(b"OS/2")));
+        debug_assert!(sfnt.head().unwrap().index_to_loc_format == 0);
+
+        let cmap = sfnt.cmap().unwrap();
+        debug_assert!(cmap.glyph_for_char('\u{C4}') == Some(3) && cmap.glyph_for_char('A').is_none());
+        debug_assert!(sfnt.os2().unwrap().us_first_char_index == 0xC4);
+
+        let glyphs = sfnt.truetype_glyphs().unwrap();
+        debug_assert!(glyphs.outline(3) == font.truetype_glyphs().unwrap().outline(4));
+        debug_assert!(sfnt.hmtx().unwrap().advances == vec![500, 700, 400, 800]);
+        debug_assert!(sfnt.hhea().unwrap().number_of_h_metrics == 4);
+        debug_assert!(sfnt.hhea().unwrap().advance_width_max == 800);
+
+        let maxp = sfnt.maxp().unwrap();
+        debug_assert!(maxp.num_glyphs == 4 && maxp.max_points == 4 && maxp.max_contours == 1);
+        debug_assert!((maxp.max_composite_points, maxp.max_composite_contours) == (7, 2));
+        debug_assert!((maxp.max_component_elements, maxp.max_component_depth) == (2, 1));
+
+        let post_names = PostGlyphNames::parse(&sfnt.table_data(tag(b"post")).unwrap()).unwrap();
+        debug_assert!(post_names.name_indices == vec![0, 36, 258, 259]);
+        debug_assert!(post_names.names == vec![b"tri".to_vec(), b"comp".to_vec()]);
+
+        let name = sfnt.name().unwrap();
+        debug_assert!(name.records.len() == 1 && name.family_name(&[]) == Some("Test"));
+    }
+
+    #[test]
+    fn test_subset_by_glyph_ids() {
+        let sfnt = build_sfnt(0x00010000, &test_font_tables());
+        let font = Sfnt::from_vec(sfnt).unwrap();
+        let subset = subset_font(&font, &SubsetGlyphs::GlyphIds(BTreeSet::from([2, 3, 100]))).unwrap();
+        let sfnt = &subset.sfnt;
+        debug_assert!(subset.old_glyph_ids == vec![0, 2, 3]);
+        debug_assert!(sfnt.cmap().unwrap().glyph_for_char('B') == Some(1));
+        debug_assert!(sfnt.cmap().unwrap().glyph_for_char(' ') == Some(2));
+        debug_assert!(sfnt.truetype_glyphs().unwrap().glyph(2).unwrap() == Glyph::Empty);
+        debug_assert!(sfnt.hhea().unwrap().advance_width_max == 500);
+
+        let post_names = PostGlyphNames::parse(&sfnt.table_data(tag(b"post")).unwrap()).unwrap();
+        debug_assert!(post_names.name_indices == vec![0, 258, 3] && post_names.names == vec![b"tri".to_vec()]);
+    }
+
+    #[test]
+    fn test_subset_maxp_of_shared_components() {
+        // every composite references the next glyph twice, so glyph 1 has 2^30 simple glyph components
+        let mut glyphs: Vec<Vec<u8>> = vec![vec![]];
+        glyphs.extend((2..32).map(|next| composite_glyph([0, 0, 0, 0], &[(next, 0, 0, None), (next, 0, 0, None)])));
+        glyphs.push(simple_glyph(&[&[(0, 0, true), (0, 10, true), (10, 0, true)]]));
+        let (glyf, loca) = glyf_and_loca(&glyphs);
+        let tables = vec![
+            (tag(b"head"), head_table(1000, 1)),
+            (tag(b"hhea"), hhea_table(800, -200, 0, 1)),
+            (tag(b"maxp"), maxp_table(32, 0)),
+            (tag(b"cmap"), cmap_table(&[(0x41, 31)], &[])),
+            (tag(b"glyf"), glyf),
+            (tag(b"loca"), loca),
+            (tag(b"hmtx"), [500u16.to_be_bytes(), [0, 0]].concat().into_iter().chain(vec![0; 62]).collect()),
+        ];
+        let font = Sfnt::from_tables(0x00010000, tables).unwrap();
+        let subset = subset_font(&font, &SubsetGlyphs::GlyphIds(BTreeSet::from([21]))).unwrap();
+        let maxp = subset.sfnt.maxp().unwrap();
+        debug_assert!(subset.old_glyph_ids == [0].into_iter().chain(21..32).collect::<Vec<u16>>());
+        debug_assert!((maxp.max_component_depth, maxp.max_composite_points, maxp.max_composite_contours) == (10, 3 << 10, 1 << 10));
+        let maxp = subset_font(&font, &SubsetGlyphs::GlyphIds(BTreeSet::from([1]))).unwrap().sfnt.maxp().unwrap();
+        debug_assert!((maxp.max_component_depth, maxp.max_composite_points) == (30, u16::MAX));
+    }
+
+    #[test]
+    fn test_hmtx_shares_trailing_advances() {
+        let hmtx = HmtxTable {
+            advances: vec![500, 600, 600, 600],
+            left_side_bearings: vec![0, 10, 20, 30],
+        };
+        let (data, number_of_h_metrics) = hmtx.transform_to_u8_vec();
+        debug_assert!(number_of_h_metrics == 2 && data.len() == 12);
+        debug_assert!(HmtxTable::parse(&data, number_of_h_metrics, 4).unwrap() == hmtx);
+    }
+
+    #[test]
+    fn test_unicode_cmap_builder() {
+        let mappings = BTreeMap::from([(0x41, 1), (0x42, 2), (0x43, 3), (0x45, 3), (0x1F600, 4)]);
+        let cmap = CmapTable::parse(&build_unicode_cmap(&mappings)).unwrap();
+        debug_assert!(cmap.encoding_records.len() == 2);
+        match &cmap.subtables[0] {
+            CmapSubtable::SegmentMapping { segments, .. } => debug_assert!(segments.len() == 3),
+            _ => unreachable!(),
+        }
+        let subtable = cmap.unicode_subtable().unwrap();
+        let mut result: BTreeMap<u32, u16> = BTreeMap::new();
+        subtable.for_each_mapping(|code, glyph_id| {
+            result.insert(code, glyph_id);
+        });
+        debug_assert!(result == mappings);
+    }
+
+    #[test]
+    fn test_subset_post_version_1() {
+        let mut post = post_table(0, -100);
+        post[..4].copy_from_slice(&POST_VERSION_1_0.to_be_bytes());
+        let subset = subset_post(&post, &[0, 3, 257, 258, 300]).unwrap();
+        debug_assert!(u32::from_be_bytes(subset[..4].try_into().unwrap()) == POST_VERSION_2_0);
+        let names = PostGlyphNames::parse(&subset).unwrap();
+        debug_assert!(names.name_indices == vec![0, 3, 257, 0, 0] && names.names.is_empty());
+    }
+}
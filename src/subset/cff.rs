@@ -0,0 +1,313 @@
+//! 'CFF ' table subsetting.
+//!
+//! Subroutines are inlined into glyph charstrings, so the subset has empty global subroutines
+//! and Private DICTs without Subrs. Charset is rebuilt for the kept glyphs; Font DICTs
+//! of CID-keyed fonts which aren't used by the kept glyphs are dropped.
+
+use crate::tables::cff::*;
+use crate::tables::charstring::desubroutinize;
+use crate::Error;
+use std::collections::BTreeMap;
+
+/// Top DICT operators which are rewritten in the subset
+const TOP_DICT_OFFSET_OPERATORS: [u16; 6] = [OP_CHARSET, OP_ENCODING, OP_CHAR_STRINGS, OP_PRIVATE, OP_FD_ARRAY, OP_FD_SELECT];
+
+/// Builds 'CFF ' table data with glyphs `old_glyph_ids`; the new glyph ID is the index in the list
+pub(crate) fn subset_cff(font: &CffGlyphs, old_glyph_ids: &[u16]) -> Result<Vec<u8>, Error> {
+    if font.is_cff2 {
+        return Err(Error::InvalidTableData);
+    }
+    let char_strings = old_glyph_ids
+        .iter()
+        .map(|&glyph_id| desubroutinize(font, glyph_id))
+        .collect::<Result<Vec<Vec<u8>>, Error>>()?;
+    let charset = font.charset()?;
+    let charset = old_glyph_ids
+        .iter()
+        .map(|&glyph_id| charset.get(glyph_id as usize).copied().ok_or(Error::InvalidTableData))
+        .collect::<Result<Vec<u16>, Error>>()?;
+
+    // Font DICTs used by the kept glyphs and their new indices
+    let mut font_dict_indices: BTreeMap<usize, u8> = BTreeMap::new();
+    let mut glyph_font_dicts: Vec<u8> = Vec::with_capacity(old_glyph_ids.len());
+    if font.is_cid_keyed() {
+        for &glyph_id in old_glyph_ids {
+            let index = font.font_dict_index(glyph_id)?;
+            font_dict_indices.insert(index, 0);
+        }
+        for (new_index, index) in font_dict_indices.values_mut().enumerate() {
+            *index = u8::try_from(new_index).map_err(|_| Error::InvalidTableData)?;
+        }
+        for &glyph_id in old_glyph_ids {
+            glyph_font_dicts.push(font_dict_indices[&font.font_dict_index(glyph_id)?]);
+        }
+    } else {
+        font_dict_indices.insert(0, 0);
+    }
+    let private_dicts = font_dict_indices
+        .keys()
+        .map(|&index| {
+            let private_dict = font.private_dicts.get(index).ok_or(Error::InvalidTableData)?;
+            let entries: Vec<DictEntry> =
+                private_dict.entries.iter().filter(|entry| entry.0 != OP_SUBRS).cloned().collect();
+            Ok(encode_dict(&entries))
+        })
+        .collect::<Result<Vec<Vec<u8>>, Error>>()?;
+
+    let build_top_dict = |offsets: &[usize]| -> Vec<u8> {
+        let entries: Vec<DictEntry> = font
+            .top_dict
+            .iter()
+            .filter(|entry| !TOP_DICT_OFFSET_OPERATORS.contains(&entry.0))
+            .cloned()
+            .collect();
+        let mut top_dict = encode_dict(&entries);
+        top_dict.extend(encode_dict_offsets(OP_CHARSET, &offsets[..1]));
+        top_dict.extend(encode_dict_offsets(OP_CHAR_STRINGS, &offsets[1..2]));
+        if font.is_cid_keyed() {
+            top_dict.extend(encode_dict_offsets(OP_FD_ARRAY, &offsets[2..3]));
+            top_dict.extend(encode_dict_offsets(OP_FD_SELECT, &offsets[3..4]));
+        } else {
+            top_dict.extend(encode_dict_offsets(OP_PRIVATE, &[private_dicts[0].len(), offsets[2]]));
+        }
+        top_dict
+    };
+
+    let header: Vec<u8> = vec![1, 0, 4, 4];
+    let name_index = &font.data[font.name_index.range.clone()];
+    let string_index = &font.data[font.string_index.range.clone()];
+    let global_subrs = build_index(&[]);
+    let top_dict_index_len = build_index(&[build_top_dict(&[0; 4])]).len();
+
+    let charset_offset = header.len() + name_index.len() + top_dict_index_len + string_index.len() + global_subrs.len();
+    let mut charset_data: Vec<u8> = vec![0];
+    charset.iter().skip(1).for_each(|id| charset_data.extend_from_slice(&id.to_be_bytes()));
+    let char_strings_offset = charset_offset + charset_data.len();
+    let char_strings_index = build_index(&char_strings);
+
+    let mut tail: Vec<u8> = vec![];
+    let tail_offset = char_strings_offset + char_strings_index.len();
+    let top_dict = if font.is_cid_keyed() {
+        let fd_select = build_fd_select(&glyph_font_dicts);
+        let fd_array_offset = tail_offset + fd_select.len();
+        // Private DICT offsets are encoded in 5 bytes form, so FDArray length doesn't depend on them
+        let font_dicts: Vec<Vec<u8>> = font_dict_indices.keys().map(|&index| font_dict(font, index, 0, 0)).collect();
+        let font_dicts_len = build_index(&font_dicts).len();
+        let mut private_offset = fd_array_offset + font_dicts_len;
+        let mut font_dicts: Vec<Vec<u8>> = vec![];
+        for (&index, private_dict) in font_dict_indices.keys().zip(private_dicts.iter()) {
+            font_dicts.push(font_dict(font, index, private_dict.len(), private_offset));
+            private_offset += private_dict.len();
+        }
+        tail.extend(fd_select);
+        tail.extend(build_index(&font_dicts));
+        build_top_dict(&[charset_offset, char_strings_offset, fd_array_offset, tail_offset])
+    } else {
+        build_top_dict(&[charset_offset, char_strings_offset, tail_offset])
+    };
+    private_dicts.iter().for_each(|private_dict| tail.extend_from_slice(private_dict));
+
+    Ok([
+        header,
+        name_index.to_vec(),
+        build_index(&[top_dict]),
+        string_index.to_vec(),
+        global_subrs,
+        charset_data,
+        char_strings_index,
+        tail,
+    ]
+    .concat())
+}
+
+/// Encodes Font DICT with `index` pointing to Private DICT of `private_size` at `private_offset`
+fn font_dict(font: &CffGlyphs, index: usize, private_size: usize, private_offset: usize) -> Vec<u8> {
+    let entries: Vec<DictEntry> = font
+        .font_dicts
+        .get(index)
+        .map(|entries| entries.iter().filter(|entry| entry.0 != OP_PRIVATE).cloned().collect())
+        .unwrap_or_default();
+    let mut data = encode_dict(&entries);
+    data.extend(encode_dict_offsets(OP_PRIVATE, &[private_size, private_offset]));
+    data
+}
+
+/// Builds format 3 FDSelect from Font DICT index of every glyph
+fn build_fd_select(glyph_font_dicts: &[u8]) -> Vec<u8> {
+    let mut ranges: Vec<(u16, u8)> = vec![];
+    for (glyph_id, &font_dict) in glyph_font_dicts.iter().enumerate() {
+        if ranges.last().is_none_or(|range| range.1 != font_dict) {
+            ranges.push((glyph_id as u16, font_dict));
+        }
+    }
+    let mut data: Vec<u8> = vec![3];
+    data.extend_from_slice(&(ranges.len() as u16).to_be_bytes());
+    for (first, font_dict) in ranges {
+        data.extend_from_slice(&first.to_be_bytes());
+        data.push(font_dict);
+    }
+    data.extend_from_slice(&(glyph_font_dicts.len() as u16).to_be_bytes());
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outline::SvgPathBuilder;
+    use crate::subset::{subset_font, SubsetGlyphs};
+    use crate::test_utils::*;
+    use crate::utils::tag;
+    use crate::{FontTables, Sfnt};
+    use std::borrow::Cow;
+    use std::collections::BTreeSet;
+
+    const CALLSUBR: u16 = 10;
+    const RETURN: u16 = 11;
+    const ENDCHAR: u16 = 14;
+    const HSTEMHM: u16 = 18;
+    const HINTMASK: u16 = 19;
+    const RMOVETO: u16 = 21;
+    const HLINETO: u16 = 6;
+    const VLINETO: u16 = 7;
+    const CALLGSUBR: u16 = 29;
+
+    fn draw(glyphs: &CffGlyphs, glyph_id: u16) -> String {
+        let mut svg = SvgPathBuilder::new();
+        glyphs.draw(glyph_id, &mut svg).unwrap();
+        svg.finish()
+    }
+
+    fn test_char_strings() -> Vec<Vec<u8>> {
+        let call_local = char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(-107), Err(CALLSUBR), Err(ENDCHAR)]);
+        vec![
+            call_local.clone(),
+            call_local.clone(),
+            char_string(&[Ok(-107), Err(CALLGSUBR), Ok(-106), Err(CALLGSUBR)]),
+            call_local,
+        ]
+    }
+
+    #[test]
+    fn test_cid_keyed_cff_subset() {
+        let global_subrs = vec![
+            [char_string(&[Ok(5), Ok(10), Ok(20), Ok(30), Err(HSTEMHM), Err(HINTMASK)]), vec![0xC0]].concat(),
+            char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(40), Err(VLINETO), Err(ENDCHAR)]),
+        ];
+        let local_subrs = vec![
+            vec![char_string(&[Ok(10), Err(HLINETO), Err(RETURN)])],
+            vec![char_string(&[Ok(20), Err(HLINETO), Err(RETURN)])],
+            // subroutine which calls global subroutine with hints and hintmask
+            vec![char_string(&[Ok(-107), Err(CALLGSUBR), Ok(30), Err(HLINETO), Err(RETURN)])],
+        ];
+        let data = cff_table(&test_char_strings(), &global_subrs, &local_subrs, Some(&[0, 1, 1, 2]));
+        let font = CffGlyphs::parse_cff(Cow::Owned(data)).unwrap();
+        let subset = subset_cff(&font, &[0, 2, 3]).unwrap();
+        let subset = CffGlyphs::parse_cff(Cow::Owned(subset)).unwrap();
+
+        debug_assert!(subset.is_cid_keyed() && subset.num_glyphs() == 3);
+        debug_assert!(subset.global_subrs.len() == 0 && subset.private_dicts.len() == 3);
+        debug_assert!(subset.private_dicts.iter().all(|private_dict| private_dict.local_subrs.len() == 0));
+        debug_assert!(subset.charset().unwrap() == vec![0, 2, 3]);
+        debug_assert!(subset.string_index.len() == 2);
+        for (new_id, old_id) in [(0u16, 0u16), (1, 2), (2, 3)] {
+            debug_assert!(draw(&subset, new_id) == draw(&font, old_id));
+        }
+        debug_assert!(draw(&subset, 1) == "M0 0 L0 -40 Z" && draw(&subset, 2) == "M0 0 L30 0 Z");
+
+        // Font DICT 1 isn't used by the kept glyphs
+        let subset = CffGlyphs::parse_cff(Cow::Owned(subset_cff(&font, &[0, 3]).unwrap())).unwrap();
+        debug_assert!(subset.private_dicts.len() == 2 && subset.font_dict_index(1).unwrap() == 1);
+        debug_assert!(draw(&subset, 1) == draw(&font, 3));
+    }
+
+    #[test]
+    fn test_otto_font_subset() {
+        let cff = cff_table(&test_char_strings(), &[], &[vec![char_string(&[Ok(10), Err(HLINETO), Err(RETURN)])]], None);
+        let mut hmtx: Vec<u8> = vec![];
+        for advance in [500u16, 600, 700, 800] {
+            hmtx.extend_from_slice(&advance.to_be_bytes());
+            hmtx.extend_from_slice(&0i16.to_be_bytes());
+        }
+        let tables = vec![
+            (tag(b"CFF "), cff),
+            (tag(b"head"), head_table(1000, 0)),
+            (tag(b"hhea"), hhea_table(800, -200, 0, 4)),
+            (tag(b"maxp"), vec![0, 0, 0x50, 0, 0, 4]),
+            (tag(b"hmtx"), hmtx),
+            (tag(b"cmap"), cmap_table(&[(0x41, 1), (0x42, 3)], &[])),
+            (tag(b"post"), post_table(0, -100)),
+        ];
+        let font = Sfnt::from_vec(build_sfnt(0x4F54544F, &tables)).unwrap();
+        let subset = subset_font(&font, &SubsetGlyphs::Codepoints(BTreeSet::from([0x42]))).unwrap();
+        let sfnt = &subset.sfnt;
+        debug_assert!(sfnt.version() == 0x4F54544F && subset.old_glyph_ids == vec![0, 3]);
+        debug_assert!(sfnt.maxp().unwrap().num_glyphs == 2 && sfnt.maxp().unwrap().version == 0x00005000);
+        debug_assert!(sfnt.cmap().unwrap().glyph_for_char('B') == Some(1));
+        debug_assert!(sfnt.hmtx().unwrap().advances == vec![500, 800]);
+        debug_assert!(sfnt.cff_glyphs().unwrap().charset().unwrap() == vec![0, 3]);
+
+        let mut expected = SvgPathBuilder::new();
+        font.draw_glyph(3, &mut expected).unwrap();
+        let mut svg = SvgPathBuilder::new();
+        sfnt.draw_glyph(1, &mut svg).unwrap();
+        debug_assert!(svg.finish() == expected.finish());
+    }
+
+    #[test]
+    fn test_subroutine_fan_out_subset() {
+        // global subroutines 0..levels call the next one 10 times, the last one draws 33 lines
+        let fan_out_font = |levels: i16| -> CffGlyphs<'static> {
+            let mut global_subrs: Vec<Vec<u8>> = (1..=levels)
+                .map(|next| char_string(&[Ok(next - 107), Err(CALLGSUBR)].repeat(10)))
+                .collect();
+            global_subrs.push(char_string(&[Ok(10), Err(HLINETO)].repeat(33)));
+            let char_strings = vec![
+                char_string(&[Err(ENDCHAR)]),
+                char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(-107), Err(CALLGSUBR), Err(ENDCHAR)]),
+            ];
+            CffGlyphs::parse_cff(Cow::Owned(cff_table(&char_strings, &global_subrs, &[vec![]], None))).unwrap()
+        };
+        debug_assert!(subset_cff(&fan_out_font(1), &[0, 1]).is_ok());
+        // 1000 inlined copies are longer than 65535 bytes
+        debug_assert!(subset_cff(&fan_out_font(3), &[0, 1]) == Err(Error::InvalidTableData));
+        // 10^9 subroutine calls
+        debug_assert!(subset_cff(&fan_out_font(9), &[0, 1]) == Err(Error::InvalidTableData));
+    }
+
+    #[test]
+    fn test_seac_components_subset() {
+        // with predefined ISOAdobe charset glyph ID is the SID of glyph name: 'exclam' is 2 and 'quoteright' is 8
+        let mut char_strings = vec![char_string(&[Err(ENDCHAR)]); 10];
+        char_strings[2] = char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(100), Err(HLINETO), Err(ENDCHAR)]);
+        char_strings[8] = char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(10), Err(HLINETO), Err(ENDCHAR)]);
+        // adx, ady, bchar ('!') and achar ('’')
+        char_strings[9] = char_string(&[Ok(50), Ok(400), Ok(33), Ok(39), Err(ENDCHAR)]);
+        let tables = vec![
+            (tag(b"CFF "), cff_table(&char_strings, &[], &[vec![]], None)),
+            (tag(b"head"), head_table(1000, 0)),
+            (tag(b"hhea"), hhea_table(800, -200, 0, 1)),
+            (tag(b"maxp"), vec![0, 0, 0x50, 0, 0, 10]),
+            (tag(b"hmtx"), [500u16.to_be_bytes(), [0, 0]].concat().into_iter().chain(vec![0; 18]).collect()),
+            (tag(b"cmap"), cmap_table(&[(0xC0, 9)], &[])),
+        ];
+        let font = Sfnt::from_vec(build_sfnt(0x4F54544F, &tables)).unwrap();
+        let subset = subset_font(&font, &SubsetGlyphs::Codepoints(BTreeSet::from([0xC0]))).unwrap();
+        debug_assert!(subset.old_glyph_ids == vec![0, 2, 8, 9]);
+        let mut svg = SvgPathBuilder::new();
+        subset.sfnt.draw_glyph(3, &mut svg).unwrap();
+        debug_assert!(svg.finish() == "M0 0 L100 0 Z M50 -400 L60 -400 Z");
+
+        // 'CFF2' outlines aren't supported
+        let mut tables = tables;
+        tables[0] = (tag(b"CFF2"), cff2_table(&char_strings[..1], &[]));
+        let font = Sfnt::from_vec(build_sfnt(0x4F54544F, &tables)).unwrap();
+        debug_assert!(subset_font(&font, &SubsetGlyphs::Codepoints(BTreeSet::from([0xC0]))).err() == Some(Error::TableNotFound));
+    }
+
+    #[test]
+    fn test_dict_encoding_round_trip() {
+        let entries: Vec<DictEntry> =
+            vec![(OP_SUBRS, vec![0.0, -107.0, 108.0, -1131.0, 32767.0, 100000.0]), (1210, vec![0.039625, -2.5, 1e-5])];
+        debug_assert!(parse_dict(&encode_dict(&entries)).unwrap() == entries);
+    }
+}
@@ -0,0 +1,87 @@
+//! 'avar' table parsing: axis variation segment maps
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Piecewise linear mapping of normalized coordinates of one axis
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SegmentMap {
+    // (from, to) pairs of normalized coordinates sorted by `from`
+    pub mappings: Vec<(f32, f32)>,
+}
+
+impl SegmentMap {
+    /// Maps normalized coordinate. Coordinates are kept as is if the map is empty
+    pub fn map(&self, coordinate: f32) -> f32 {
+        let mappings = &self.mappings;
+        match mappings.iter().position(|(from, _)| coordinate <= *from) {
+            _ if mappings.is_empty() => coordinate,
+            Some(0) => coordinate - mappings[0].0 + mappings[0].1,
+            Some(index) => {
+                let (from_start, to_start) = mappings[index - 1];
+                let (from_end, to_end) = mappings[index];
+                if from_end == from_start {
+                    to_end
+                } else {
+                    to_start + (to_end - to_start) * (coordinate - from_start) / (from_end - from_start)
+                }
+            }
+            None => {
+                let (from, to) = mappings[mappings.len() - 1];
+                coordinate - from + to
+            }
+        }
+    }
+}
+
+/// Parsed 'avar' table (segment maps of version 1)
+#[derive(Clone, Debug, PartialEq)]
+pub struct AvarTable {
+    // Segment map for every axis in 'fvar' axis order
+    pub segment_maps: Vec<SegmentMap>,
+}
+
+impl AvarTable {
+    /// Parses raw 'avar' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let major_version = try_read_u16_be(&mut buffer)?;
+        let _minor_version = try_read_u16_be(&mut buffer)?;
+        let _reserved = try_read_u16_be(&mut buffer)?;
+        let axis_count = try_read_u16_be(&mut buffer)?;
+        if major_version != 1 && major_version != 2 {
+            return Err(Error::InvalidTableData);
+        }
+        let mut segment_maps: Vec<SegmentMap> = Vec::with_capacity(axis_count as usize);
+        for _ in 0..axis_count {
+            let position_map_count = try_read_u16_be(&mut buffer)?;
+            let mappings = (0..position_map_count)
+                .map(|_| Ok((f2dot14_to_f32(try_read_i16_be(&mut buffer)?), f2dot14_to_f32(try_read_i16_be(&mut buffer)?))))
+                .collect::<Result<Vec<(f32, f32)>, Error>>()?;
+            segment_maps.push(SegmentMap { mappings });
+        }
+        Ok(AvarTable { segment_maps })
+    }
+
+    /// Maps normalized coordinate of the axis with `axis_index`
+    pub fn map(&self, axis_index: usize, coordinate: f32) -> f32 {
+        match self.segment_maps.get(axis_index) {
+            Some(segment_map) => segment_map.map(coordinate),
+            None => coordinate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::avar_table;
+
+    #[test]
+    fn test_avar() {
+        let avar = AvarTable::parse(&avar_table(&[vec![(-1.0, -1.0), (0.0, 0.0), (0.5, 0.25), (1.0, 1.0)], vec![]])).unwrap();
+        debug_assert!(avar.map(0, 0.5) == 0.25 && avar.map(0, 0.75) == 0.625 && avar.map(0, -0.5) == -0.5);
+        debug_assert!(avar.map(1, 0.3) == 0.3 && avar.map(5, 0.3) == 0.3);
+    }
+}
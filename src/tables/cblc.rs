@@ -0,0 +1,281 @@
+//! 'CBLC' table parsing: locations of color bitmap glyph images stored in 'CBDT' table
+
+use crate::bitmap::{BitmapGlyph, BitmapMetrics, ImageFormat};
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Size of BitmapSize record
+const BITMAP_SIZE_RECORD_SIZE: usize = 48;
+
+/// Bitmap strike: glyph images of one size
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitmapStrike {
+    pub ppem_x: u8,
+    pub ppem_y: u8,
+    pub bit_depth: u8,
+    pub flags: i8,
+    // Range of glyphs which may have images in the strike
+    pub start_glyph_id: u16,
+    pub end_glyph_id: u16,
+    index_subtables: Vec<IndexSubtable>,
+}
+
+/// Glyph image locations of a glyph range
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct IndexSubtable {
+    first_glyph_id: u16,
+    last_glyph_id: u16,
+    image_format: u16,
+    // Offset of image data of the range in 'CBDT' table
+    image_data_offset: usize,
+    locations: GlyphLocations,
+}
+
+/// Glyph image offsets relative to image data of the range in one of index subtable formats
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum GlyphLocations {
+    // Formats 1 and 3: offsets of every glyph of the range and the end offset
+    Offsets(Vec<u32>),
+    // Format 2: images of the same size and metrics for every glyph of the range
+    Constant { image_size: u32, metrics: BitmapMetrics },
+    // Format 4: sparse (glyph ID, offset) pairs with the end offset in the last pair
+    Sparse(Vec<(u16, u32)>),
+    // Format 5: images of the same size and metrics for sparse glyph IDs
+    SparseConstant { image_size: u32, metrics: BitmapMetrics, glyph_ids: Vec<u16> },
+}
+
+/// Parsed 'CBLC' table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CblcTable {
+    pub major_version: u16,
+    pub strikes: Vec<BitmapStrike>,
+}
+
+impl CblcTable {
+    /// Parses raw 'CBLC' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let major_version = try_read_u16_be(&mut buffer)?;
+        let _minor_version = try_read_u16_be(&mut buffer)?;
+        let num_sizes = try_read_u32_be(&mut buffer)? as usize;
+        // version 2 is 'EBLC' table with the same structure
+        if major_version != 2 && major_version != 3 {
+            return Err(Error::InvalidTableData);
+        }
+
+        let mut strikes: Vec<BitmapStrike> = Vec::with_capacity(num_sizes.min(data.len() / BITMAP_SIZE_RECORD_SIZE));
+        for index in 0..num_sizes {
+            let mut record = ByteReader::from_bytes(try_slice(data, 8 + index * BITMAP_SIZE_RECORD_SIZE, BITMAP_SIZE_RECORD_SIZE)?);
+            let array_offset = try_read_u32_be(&mut record)? as usize;
+            let _index_tables_size = try_read_u32_be(&mut record)?;
+            let number_of_index_subtables = try_read_u32_be(&mut record)? as usize;
+            let _color_ref = try_read_u32_be(&mut record)?;
+            // horizontal and vertical line metrics
+            record.read_bytes(24).map_err(|_| Error::InvalidTableData)?;
+            let start_glyph_id = try_read_u16_be(&mut record)?;
+            let end_glyph_id = try_read_u16_be(&mut record)?;
+            let ppem_x = try_read_u8(&mut record)?;
+            let ppem_y = try_read_u8(&mut record)?;
+            let bit_depth = try_read_u8(&mut record)?;
+            let flags = try_read_i8(&mut record)?;
+
+            let mut array = ByteReader::from_bytes(try_slice(data, array_offset, number_of_index_subtables * 8)?);
+            let mut index_subtables: Vec<IndexSubtable> = Vec::with_capacity(number_of_index_subtables);
+            for _ in 0..number_of_index_subtables {
+                let first_glyph_id = try_read_u16_be(&mut array)?;
+                let last_glyph_id = try_read_u16_be(&mut array)?;
+                let subtable_offset = array_offset + try_read_u32_be(&mut array)? as usize;
+                if last_glyph_id < first_glyph_id {
+                    return Err(Error::InvalidTableData);
+                }
+                index_subtables.push(parse_index_subtable(data, subtable_offset, first_glyph_id, last_glyph_id)?);
+            }
+            strikes.push(BitmapStrike { ppem_x, ppem_y, bit_depth, flags, start_glyph_id, end_glyph_id, index_subtables });
+        }
+        Ok(CblcTable { major_version, strikes })
+    }
+
+    /// Returns the image of the glyph in the strike from `cbdt` data, `None` if the strike has no image of the glyph.
+    /// Returns `UnsupportedFontFormat` error for image formats other than PNG ones (17, 18 and 19)
+    pub fn glyph_image(&self, cbdt: &[u8], strike_index: usize, glyph_id: u16) -> Result<Option<BitmapGlyph>, Error> {
+        let strike = self.strikes.get(strike_index).ok_or(Error::InvalidTableData)?;
+        let subtable = match strike.index_subtables.iter()
+            .find(|subtable| (subtable.first_glyph_id..=subtable.last_glyph_id).contains(&glyph_id))
+        {
+            Some(subtable) => subtable,
+            None => return Ok(None),
+        };
+        let index = (glyph_id - subtable.first_glyph_id) as usize;
+        let (offset, length, index_metrics) = match &subtable.locations {
+            GlyphLocations::Offsets(offsets) => {
+                let (start, end) = (offsets[index], offsets[index + 1]);
+                if end < start {
+                    return Err(Error::InvalidTableData);
+                }
+                (start, end - start, None)
+            }
+            GlyphLocations::Constant { image_size, metrics } => {
+                let offset = (index as u32).checked_mul(*image_size).ok_or(Error::InvalidTableData)?;
+                (offset, *image_size, Some(*metrics))
+            }
+            GlyphLocations::Sparse(pairs) => match pairs[..pairs.len() - 1].iter().position(|pair| pair.0 == glyph_id) {
+                Some(position) => {
+                    let (start, end) = (pairs[position].1, pairs[position + 1].1);
+                    if end < start {
+                        return Err(Error::InvalidTableData);
+                    }
+                    (start, end - start, None)
+                }
+                None => return Ok(None),
+            },
+            GlyphLocations::SparseConstant { image_size, metrics, glyph_ids } => {
+                match glyph_ids.binary_search(&glyph_id) {
+                    Ok(position) => {
+                        let offset = (position as u32).checked_mul(*image_size).ok_or(Error::InvalidTableData)?;
+                        (offset, *image_size, Some(*metrics))
+                    }
+                    Err(_) => return Ok(None),
+                }
+            }
+        };
+        // missing glyphs have empty images
+        if length == 0 {
+            return Ok(None);
+        }
+
+        let image = try_slice(cbdt, subtable.image_data_offset + offset as usize, length as usize)?;
+        let mut buffer = ByteReader::from_bytes(image);
+        let metrics = match subtable.image_format {
+            17 => Some(read_small_metrics(&mut buffer)?),
+            18 => Some(read_big_metrics(&mut buffer)?),
+            19 => index_metrics,
+            _ => return Err(Error::UnsupportedFontFormat),
+        };
+        let data_len = try_read_u32_be(&mut buffer)? as usize;
+        let data = try_slice(image, buffer.get_rpos(), data_len)?;
+        Ok(Some(BitmapGlyph {
+            image_format: ImageFormat::Png,
+            data: data.to_vec(),
+            ppem_x: strike.ppem_x as u16,
+            ppem_y: strike.ppem_y as u16,
+            metrics,
+            origin_offset: (0, 0),
+        }))
+    }
+}
+
+/// Parses index subtable of the glyph range
+fn parse_index_subtable(data: &[u8], offset: usize, first_glyph_id: u16, last_glyph_id: u16) -> Result<IndexSubtable, Error> {
+    let mut buffer = ByteReader::from_bytes(data.get(offset..).ok_or(Error::InvalidTableData)?);
+    let index_format = try_read_u16_be(&mut buffer)?;
+    let image_format = try_read_u16_be(&mut buffer)?;
+    let image_data_offset = try_read_u32_be(&mut buffer)? as usize;
+    let glyph_count = (last_glyph_id - first_glyph_id) as usize + 1;
+    let locations = match index_format {
+        1 => GlyphLocations::Offsets((0..=glyph_count).map(|_| try_read_u32_be(&mut buffer)).collect::<Result<_, _>>()?),
+        3 => GlyphLocations::Offsets(
+            (0..=glyph_count).map(|_| try_read_u16_be(&mut buffer).map(|value| value as u32)).collect::<Result<_, _>>()?,
+        ),
+        2 => GlyphLocations::Constant {
+            image_size: try_read_u32_be(&mut buffer)?,
+            metrics: read_big_metrics(&mut buffer)?,
+        },
+        4 => {
+            let num_glyphs = try_read_u32_be(&mut buffer)? as usize;
+            let mut pairs: Vec<(u16, u32)> = Vec::with_capacity(num_glyphs.min(data.len() / 4) + 1);
+            for _ in 0..=num_glyphs {
+                pairs.push((try_read_u16_be(&mut buffer)?, try_read_u16_be(&mut buffer)? as u32));
+            }
+            GlyphLocations::Sparse(pairs)
+        }
+        5 => {
+            let image_size = try_read_u32_be(&mut buffer)?;
+            let metrics = read_big_metrics(&mut buffer)?;
+            let num_glyphs = try_read_u32_be(&mut buffer)? as usize;
+            let glyph_ids = try_slice(data, offset + buffer.get_rpos(), num_glyphs * 2)?
+                .chunks(2)
+                .map(|value| u16::from_be_bytes([value[0], value[1]]))
+                .collect();
+            GlyphLocations::SparseConstant { image_size, metrics, glyph_ids }
+        }
+        _ => return Err(Error::InvalidTableData),
+    };
+    Ok(IndexSubtable { first_glyph_id, last_glyph_id, image_format, image_data_offset, locations })
+}
+
+fn read_small_metrics(buffer: &mut ByteReader) -> Result<BitmapMetrics, Error> {
+    Ok(BitmapMetrics {
+        height: try_read_u8(buffer)?,
+        width: try_read_u8(buffer)?,
+        hori_bearing_x: try_read_i8(buffer)?,
+        hori_bearing_y: try_read_i8(buffer)?,
+        hori_advance: try_read_u8(buffer)?,
+        ..BitmapMetrics::default()
+    })
+}
+
+fn read_big_metrics(buffer: &mut ByteReader) -> Result<BitmapMetrics, Error> {
+    Ok(BitmapMetrics {
+        height: try_read_u8(buffer)?,
+        width: try_read_u8(buffer)?,
+        hori_bearing_x: try_read_i8(buffer)?,
+        hori_bearing_y: try_read_i8(buffer)?,
+        hori_advance: try_read_u8(buffer)?,
+        vert_bearing_x: try_read_i8(buffer)?,
+        vert_bearing_y: try_read_i8(buffer)?,
+        vert_advance: try_read_u8(buffer)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_glyph_image() {
+        let images = vec![(2u16, vec![0x89, b'P', b'N', b'G']), (4, vec![1, 2, 3]), (5, vec![4, 5])];
+        for index_format in [1, 3, 4] {
+            let (cblc_data, cbdt) = cblc_and_cbdt(&[20, 40], index_format, &images);
+            let cblc = CblcTable::parse(&cblc_data).unwrap();
+            debug_assert!(cblc.strikes.len() == 2 && cblc.strikes[1].ppem_y == 40);
+            let glyph = cblc.glyph_image(&cbdt, 1, 4).unwrap().unwrap();
+            debug_assert!(glyph.image_format == ImageFormat::Png && glyph.data == vec![1, 2, 3]);
+            debug_assert!(glyph.ppem_x == 40 && glyph.ppem_y == 40);
+            let metrics = glyph.metrics.unwrap();
+            debug_assert!(metrics.width == 40 && metrics.hori_bearing_y == 40 && metrics.hori_advance == 40);
+            debug_assert!(metrics.vert_advance == if index_format == 3 { 40 } else { 0 });
+            debug_assert!(cblc.glyph_image(&cbdt, 0, 2).unwrap().unwrap().data[1..] == *b"PNG");
+            for glyph_id in [1, 3, 6] {
+                debug_assert!(cblc.glyph_image(&cbdt, 0, glyph_id).unwrap().is_none());
+            }
+        }
+
+        let images = vec![(3u16, vec![1, 2]), (4, vec![3, 4]), (7, vec![5, 6])];
+        let (cblc_data, cbdt) = cblc_and_cbdt(&[20], 5, &images);
+        let cblc = CblcTable::parse(&cblc_data).unwrap();
+        let glyph = cblc.glyph_image(&cbdt, 0, 7).unwrap().unwrap();
+        debug_assert!(glyph.data == vec![5, 6] && glyph.metrics.unwrap().vert_advance == 20);
+        debug_assert!(cblc.glyph_image(&cbdt, 0, 5).unwrap().is_none());
+
+        let (cblc_data, cbdt) = cblc_and_cbdt(&[20], 2, &images[..2]);
+        let cblc = CblcTable::parse(&cblc_data).unwrap();
+        debug_assert!(cblc.glyph_image(&cbdt, 0, 4).unwrap().unwrap().data == vec![3, 4]);
+        debug_assert!(cblc.glyph_image(&cbdt[..cbdt.len() - 1], 0, 4).is_err());
+
+        // offset of the third image overflows 32 bits
+        let (cblc_data, cbdt) = cblc_and_cbdt(&[20], 2, &[(3u16, vec![1, 2]), (4, vec![3, 4]), (5, vec![5, 6])]);
+        let mut cblc = CblcTable::parse(&cblc_data).unwrap();
+        if let GlyphLocations::Constant { image_size, .. } = &mut cblc.strikes[0].index_subtables[0].locations {
+            *image_size = 0x8000_0000;
+        }
+        debug_assert!(cblc.glyph_image(&cbdt, 0, 5) == Err(Error::InvalidTableData));
+        let (cblc_data, cbdt) = cblc_and_cbdt(&[20], 5, &images);
+        let mut cblc = CblcTable::parse(&cblc_data).unwrap();
+        if let GlyphLocations::SparseConstant { image_size, .. } = &mut cblc.strikes[0].index_subtables[0].locations {
+            *image_size = 0x8000_0000;
+        }
+        debug_assert!(cblc.glyph_image(&cbdt, 0, 7) == Err(Error::InvalidTableData));
+    }
+}
@@ -0,0 +1,686 @@
+//! 'CFF ' and 'CFF2' tables parsing: INDEX and DICT structures, Private DICTs and FDSelect
+
+use crate::outline::PathBuilder;
+use crate::tables::charstring::CharStringInterpreter;
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+use std::borrow::Cow;
+use std::ops::Range;
+
+// Top DICT operators
+pub(crate) const OP_CHARSET: u16 = 15;
+pub(crate) const OP_ENCODING: u16 = 16;
+pub(crate) const OP_CHAR_STRINGS: u16 = 17;
+pub(crate) const OP_PRIVATE: u16 = 18;
+pub(crate) const OP_VSTORE: u16 = 24;
+pub(crate) const OP_ROS: u16 = 1230;
+pub(crate) const OP_FD_ARRAY: u16 = 1236;
+pub(crate) const OP_FD_SELECT: u16 = 1237;
+// Private DICT operators
+pub(crate) const OP_SUBRS: u16 = 19;
+
+/// SIDs of glyph names of StandardEncoding codes 161..=255; codes 32..=126 map to SIDs 1..=95
+/// and all other codes are not encoded
+const STANDARD_ENCODING_HIGH: [u16; 95] = [
+    96, 97, 98, 99, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110, 0,
+    111, 112, 113, 114, 0, 115, 116, 117, 118, 119, 120, 121, 122, 0, 123, 0,
+    124, 125, 126, 127, 128, 129, 130, 131, 0, 132, 133, 0, 134, 135, 136, 137,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    138, 0, 139, 0, 0, 0, 0, 140, 141, 142, 143, 0, 0, 0, 0, 0,
+    144, 0, 0, 0, 145, 0, 0, 146, 147, 148, 149, 0, 0, 0, 0,
+];
+
+/// Returns SID of the glyph name which StandardEncoding assigns to the code, 0 if the code isn't encoded
+pub(crate) fn standard_encoding_sid(code: u8) -> u16 {
+    match code {
+        32..=126 => code as u16 - 31,
+        161..=255 => STANDARD_ENCODING_HIGH[(code - 161) as usize],
+        _ => 0,
+    }
+}
+
+/// DICT entry: operator and its operands. Two-byte operators (12 x) are stored as 1200 + x
+pub(crate) type DictEntry = (u16, Vec<f64>);
+
+/// CFF INDEX: absolute ranges of its items in table data
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CffIndex {
+    pub(crate) items: Vec<Range<usize>>,
+    // Range of the whole INDEX structure in table data
+    pub(crate) range: Range<usize>,
+}
+
+impl CffIndex {
+    /// Parses INDEX which starts at `offset`. Count of items is 16-bit for CFF and 32-bit for CFF2
+    pub(crate) fn parse(data: &[u8], offset: usize, is_cff2: bool) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        buffer.set_rpos(offset);
+        let count = if is_cff2 {
+            try_read_u32_be(&mut buffer)? as usize
+        } else {
+            try_read_u16_be(&mut buffer)? as usize
+        };
+        if count == 0 {
+            return Ok(CffIndex {
+                items: vec![],
+                range: offset..buffer.get_rpos(),
+            });
+        }
+        let off_size = try_read_u8(&mut buffer)? as usize;
+        if !(1..=4).contains(&off_size) {
+            return Err(Error::InvalidTableData);
+        }
+        let offsets_start = buffer.get_rpos();
+        try_slice(data, offsets_start, (count + 1) * off_size)?;
+        // offsets are 1-based from the byte which precedes the data
+        let data_start = offsets_start + (count + 1) * off_size - 1;
+        let read_offset = |index: usize| -> usize {
+            data[offsets_start + index * off_size..offsets_start + (index + 1) * off_size]
+                .iter()
+                .fold(0usize, |value, &byte| (value << 8) | byte as usize)
+        };
+        let mut items: Vec<Range<usize>> = Vec::with_capacity(count);
+        let mut start = read_offset(0);
+        for index in 1..=count {
+            let end = read_offset(index);
+            if start < 1 || end < start || data_start + end > data.len() {
+                return Err(Error::InvalidTableData);
+            }
+            items.push(data_start + start..data_start + end);
+            start = end;
+        }
+        let end = data_start + start;
+        Ok(CffIndex {
+            items,
+            range: offset..end,
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<Range<usize>> {
+        self.items.get(index).cloned()
+    }
+}
+
+/// Parses DICT data to the list of entries
+pub(crate) fn parse_dict(data: &[u8]) -> Result<Vec<DictEntry>, Error> {
+    let mut entries: Vec<DictEntry> = vec![];
+    let mut operands: Vec<f64> = vec![];
+    let mut buffer = ByteReader::from_bytes(data);
+    while buffer.get_rpos() < data.len() {
+        let b0 = try_read_u8(&mut buffer)?;
+        match b0 {
+            0..=11 | 13..=27 => entries.push((b0 as u16, std::mem::take(&mut operands))),
+            12 => entries.push((1200 + try_read_u8(&mut buffer)? as u16, std::mem::take(&mut operands))),
+            28 => operands.push(try_read_i16_be(&mut buffer)? as f64),
+            29 => operands.push(try_read_i32_be(&mut buffer)? as f64),
+            30 => operands.push(parse_real(&mut buffer)?),
+            32..=246 => operands.push(b0 as f64 - 139.0),
+            247..=250 => operands.push((b0 as f64 - 247.0) * 256.0 + try_read_u8(&mut buffer)? as f64 + 108.0),
+            251..=254 => operands.push(-(b0 as f64 - 251.0) * 256.0 - try_read_u8(&mut buffer)? as f64 - 108.0),
+            _ => return Err(Error::InvalidTableData),
+        }
+    }
+    Ok(entries)
+}
+
+/// Encodes DICT entries; integer operands get the shortest encoding, other ones are stored as reals
+pub(crate) fn encode_dict(entries: &[DictEntry]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![];
+    for (op, operands) in entries {
+        for &operand in operands {
+            if operand.fract() == 0.0 && operand.abs() <= i32::MAX as f64 {
+                encode_dict_int(&mut data, operand as i32);
+            } else {
+                encode_dict_real(&mut data, operand);
+            }
+        }
+        encode_dict_operator(&mut data, *op);
+    }
+    data
+}
+
+/// Encodes DICT entry with offset operands in 5-bytes form, so its length doesn't depend on the values
+pub(crate) fn encode_dict_offsets(op: u16, offsets: &[usize]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![];
+    for &offset in offsets {
+        data.push(29);
+        data.extend_from_slice(&(offset as i32).to_be_bytes());
+    }
+    encode_dict_operator(&mut data, op);
+    data
+}
+
+fn encode_dict_operator(data: &mut Vec<u8>, op: u16) {
+    if op >= 1200 {
+        data.extend_from_slice(&[12, (op - 1200) as u8]);
+    } else {
+        data.push(op as u8);
+    }
+}
+
+fn encode_dict_int(data: &mut Vec<u8>, value: i32) {
+    match value {
+        -107..=107 => data.push((value + 139) as u8),
+        108..=1131 => data.extend_from_slice(&[((value - 108) / 256 + 247) as u8, ((value - 108) % 256) as u8]),
+        -1131..=-108 => data.extend_from_slice(&[((-value - 108) / 256 + 251) as u8, ((-value - 108) % 256) as u8]),
+        -32768..=32767 => {
+            data.push(28);
+            data.extend_from_slice(&(value as i16).to_be_bytes());
+        }
+        _ => {
+            data.push(29);
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+/// Encodes real number as BCD nibbles
+fn encode_dict_real(data: &mut Vec<u8>, value: f64) {
+    let mut nibbles: Vec<u8> = vec![];
+    let text = format!("{:E}", value);
+    let (mantissa, exponent) = text.split_once('E').unwrap_or((&text, "0"));
+    for ch in mantissa.chars() {
+        match ch {
+            '0'..='9' => nibbles.push(ch as u8 - b'0'),
+            '.' => nibbles.push(0xA),
+            _ => nibbles.push(0xE),
+        }
+    }
+    if exponent != "0" {
+        match exponent.strip_prefix('-') {
+            Some(exponent) => {
+                nibbles.push(0xC);
+                nibbles.extend(exponent.bytes().map(|byte| byte - b'0'));
+            }
+            None => {
+                nibbles.push(0xB);
+                nibbles.extend(exponent.bytes().map(|byte| byte - b'0'));
+            }
+        }
+    }
+    nibbles.push(0xF);
+    if nibbles.len() % 2 == 1 {
+        nibbles.push(0xF);
+    }
+    data.push(30);
+    data.extend(nibbles.chunks_exact(2).map(|pair| (pair[0] << 4) | pair[1]));
+}
+
+/// Builds CFF INDEX (16-bit count) with the smallest possible offset size
+pub(crate) fn build_index(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut data: Vec<u8> = (items.len() as u16).to_be_bytes().to_vec();
+    if items.is_empty() {
+        return data;
+    }
+    let last_offset = 1 + items.iter().map(|item| item.len()).sum::<usize>();
+    let off_size = match last_offset {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x10000..=0xFF_FFFF => 3,
+        _ => 4,
+    };
+    data.push(off_size as u8);
+    let mut offset = 1usize;
+    data.extend_from_slice(&(offset as u32).to_be_bytes()[4 - off_size..]);
+    for item in items {
+        offset += item.len();
+        data.extend_from_slice(&(offset as u32).to_be_bytes()[4 - off_size..]);
+    }
+    items.iter().for_each(|item| data.extend_from_slice(item));
+    data
+}
+
+/// Parses real number operand stored as BCD nibbles
+fn parse_real(buffer: &mut ByteReader) -> Result<f64, Error> {
+    let mut value = String::new();
+    'bytes: loop {
+        let byte = try_read_u8(buffer)?;
+        for nibble in [byte >> 4, byte & 0x0F] {
+            match nibble {
+                0..=9 => value.push((b'0' + nibble) as char),
+                0xA => value.push('.'),
+                0xB => value.push('E'),
+                0xC => value.push_str("E-"),
+                0xE => value.push('-'),
+                0xF => break 'bytes,
+                _ => return Err(Error::InvalidTableData),
+            }
+        }
+    }
+    value.parse::<f64>().map_err(|_| Error::InvalidTableData)
+}
+
+/// Returns operands of the DICT entry with operator `op`
+pub(crate) fn dict_operands(entries: &[DictEntry], op: u16) -> Option<&[f64]> {
+    entries.iter().find(|entry| entry.0 == op).map(|entry| entry.1.as_slice())
+}
+
+/// Private DICT entries and local subroutines
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PrivateDict {
+    pub(crate) entries: Vec<DictEntry>,
+    pub(crate) local_subrs: CffIndex,
+}
+
+/// Maps glyph IDs to Font DICTs of CID-keyed fonts
+#[derive(Clone, Debug)]
+pub(crate) enum FdSelect {
+    // Format 0: Font DICT index for every glyph
+    Glyphs(Vec<u16>),
+    // Formats 3 and 4: ranges of glyphs (first glyph, Font DICT index) with sentinel glyph
+    Ranges(Vec<(u32, u16)>, u32),
+}
+
+impl FdSelect {
+    fn parse(data: &[u8], offset: usize, num_glyphs: usize) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        buffer.set_rpos(offset);
+        match try_read_u8(&mut buffer)? {
+            0 => Ok(FdSelect::Glyphs(
+                try_slice(data, offset + 1, num_glyphs)?.iter().map(|&fd| fd as u16).collect(),
+            )),
+            3 => {
+                let num_ranges = try_read_u16_be(&mut buffer)?;
+                let mut ranges: Vec<(u32, u16)> = Vec::with_capacity(num_ranges as usize);
+                for _ in 0..num_ranges {
+                    ranges.push((try_read_u16_be(&mut buffer)? as u32, try_read_u8(&mut buffer)? as u16));
+                }
+                Ok(FdSelect::Ranges(ranges, try_read_u16_be(&mut buffer)? as u32))
+            }
+            4 => {
+                let num_ranges = try_read_u32_be(&mut buffer)?;
+                try_slice(data, offset + 5, num_ranges as usize * 6)?;
+                let mut ranges: Vec<(u32, u16)> = Vec::with_capacity(num_ranges as usize);
+                for _ in 0..num_ranges {
+                    ranges.push((try_read_u32_be(&mut buffer)?, try_read_u16_be(&mut buffer)?));
+                }
+                Ok(FdSelect::Ranges(ranges, try_read_u32_be(&mut buffer)?))
+            }
+            _ => Err(Error::InvalidTableData),
+        }
+    }
+
+    /// Returns Font DICT index for the glyph
+    pub(crate) fn font_dict_index(&self, glyph_id: u16) -> Option<u16> {
+        match self {
+            FdSelect::Glyphs(fds) => fds.get(glyph_id as usize).copied(),
+            FdSelect::Ranges(ranges, sentinel) => {
+                if glyph_id as u32 >= *sentinel {
+                    return None;
+                }
+                let index = ranges.partition_point(|range| range.0 <= glyph_id as u32);
+                ranges.get(index.checked_sub(1)?).map(|range| range.1)
+            }
+        }
+    }
+}
+
+/// Glyphs of a font with CFF ('CFF ' table) or CFF2 ('CFF2' table) outlines
+pub struct CffGlyphs<'a> {
+    pub(crate) data: Cow<'a, [u8]>,
+    pub(crate) is_cff2: bool,
+    pub(crate) top_dict: Vec<DictEntry>,
+    // Name and String INDEXes; CFF2 has none of them
+    pub(crate) name_index: CffIndex,
+    pub(crate) string_index: CffIndex,
+    pub(crate) global_subrs: CffIndex,
+    pub(crate) char_strings: CffIndex,
+    // Font DICTs of CID-keyed CFF fonts and CFF2 fonts
+    pub(crate) font_dicts: Vec<Vec<DictEntry>>,
+    // One Private DICT for every Font DICT or the only Private DICT of non CID-keyed font
+    pub(crate) private_dicts: Vec<PrivateDict>,
+    pub(crate) fd_select: Option<FdSelect>,
+    // Number of variation regions of every ItemVariationData of CFF2 variation store
+    pub(crate) region_counts: Vec<u16>,
+}
+
+impl<'a> CffGlyphs<'a> {
+    /// Parses raw 'CFF ' table data
+    pub(crate) fn parse_cff(data: Cow<'a, [u8]>) -> Result<Self, Error> {
+        let header_size = *data.get(2).ok_or(Error::InvalidTableData)? as usize;
+        if data[0] != 1 {
+            return Err(Error::InvalidTableData);
+        }
+        let name_index = CffIndex::parse(&data, header_size, false)?;
+        let top_dict_index = CffIndex::parse(&data, name_index.range.end, false)?;
+        let string_index = CffIndex::parse(&data, top_dict_index.range.end, false)?;
+        let global_subrs = CffIndex::parse(&data, string_index.range.end, false)?;
+        let top_dict_range = top_dict_index.get(0).ok_or(Error::InvalidTableData)?;
+        let top_dict = parse_dict(&data[top_dict_range])?;
+        let mut font = Self::parse_fonts(data, false, top_dict, global_subrs)?;
+        font.name_index = name_index;
+        font.string_index = string_index;
+        Ok(font)
+    }
+
+    /// Parses raw 'CFF2' table data
+    pub(crate) fn parse_cff2(data: Cow<'a, [u8]>) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(&data);
+        let major_version = try_read_u8(&mut buffer)?;
+        let _minor_version = try_read_u8(&mut buffer)?;
+        let header_size = try_read_u8(&mut buffer)? as usize;
+        let top_dict_length = try_read_u16_be(&mut buffer)? as usize;
+        if major_version != 2 {
+            return Err(Error::InvalidTableData);
+        }
+        let top_dict = parse_dict(try_slice(&data, header_size, top_dict_length)?)?;
+        let global_subrs = CffIndex::parse(&data, header_size + top_dict_length, true)?;
+        Self::parse_fonts(data, true, top_dict, global_subrs)
+    }
+
+    fn parse_fonts(
+        data: Cow<'a, [u8]>,
+        is_cff2: bool,
+        top_dict: Vec<DictEntry>,
+        global_subrs: CffIndex,
+    ) -> Result<Self, Error> {
+        let char_strings_offset = dict_offset(&top_dict, OP_CHAR_STRINGS).ok_or(Error::InvalidTableData)?;
+        let char_strings = CffIndex::parse(&data, char_strings_offset, is_cff2)?;
+
+        let mut font_dicts: Vec<Vec<DictEntry>> = vec![];
+        let mut fd_select: Option<FdSelect> = None;
+        if let Some(fd_array_offset) = dict_offset(&top_dict, OP_FD_ARRAY) {
+            let fd_array = CffIndex::parse(&data, fd_array_offset, is_cff2)?;
+            for range in fd_array.items.iter() {
+                font_dicts.push(parse_dict(&data[range.clone()])?);
+            }
+            if let Some(fd_select_offset) = dict_offset(&top_dict, OP_FD_SELECT) {
+                fd_select = Some(FdSelect::parse(&data, fd_select_offset, char_strings.len())?);
+            } else if font_dicts.len() != 1 {
+                return Err(Error::InvalidTableData);
+            }
+        }
+
+        let private_dicts = if font_dicts.is_empty() {
+            vec![parse_private_dict(&data, &top_dict, is_cff2)?]
+        } else {
+            font_dicts
+                .iter()
+                .map(|font_dict| parse_private_dict(&data, font_dict, is_cff2))
+                .collect::<Result<Vec<PrivateDict>, Error>>()?
+        };
+
+        let mut region_counts: Vec<u16> = vec![];
+        if let Some(vstore_offset) = dict_offset(&top_dict, OP_VSTORE) {
+            region_counts = parse_region_counts(&data, vstore_offset)?;
+        }
+
+        Ok(CffGlyphs {
+            data,
+            is_cff2,
+            top_dict,
+            name_index: CffIndex::default(),
+            string_index: CffIndex::default(),
+            global_subrs,
+            char_strings,
+            font_dicts,
+            private_dicts,
+            fd_select,
+            region_counts,
+        })
+    }
+
+    /// Number of glyphs in the font
+    pub fn num_glyphs(&self) -> u16 {
+        self.char_strings.len() as u16
+    }
+
+    /// Returns true for CID-keyed CFF fonts
+    pub fn is_cid_keyed(&self) -> bool {
+        dict_operands(&self.top_dict, OP_ROS).is_some()
+    }
+
+    /// Returns index of Font DICT (and Private DICT) used by the glyph; always 0 for non CID-keyed fonts
+    pub(crate) fn font_dict_index(&self, glyph_id: u16) -> Result<usize, Error> {
+        match &self.fd_select {
+            Some(fd_select) => Ok(fd_select.font_dict_index(glyph_id).ok_or(Error::InvalidTableData)? as usize),
+            None => Ok(0),
+        }
+    }
+
+    /// Returns Private DICT used by the glyph
+    pub(crate) fn private_dict(&self, glyph_id: u16) -> Result<&PrivateDict, Error> {
+        self.private_dicts.get(self.font_dict_index(glyph_id)?).ok_or(Error::InvalidTableData)
+    }
+
+    /// Parses charset of CFF font: SID of glyph name (or CID for CID-keyed fonts) for every glyph.
+    /// Predefined ISOAdobe charset is supported, predefined Expert charsets aren't
+    pub(crate) fn charset(&self) -> Result<Vec<u16>, Error> {
+        let num_glyphs = self.char_strings.len();
+        let offset = match dict_offset(&self.top_dict, OP_CHARSET).unwrap_or(0) {
+            0 => return Ok((0..num_glyphs as u16).collect()),
+            1 | 2 => return Err(Error::InvalidTableData),
+            offset => offset,
+        };
+        let mut buffer = ByteReader::from_bytes(&self.data);
+        buffer.set_rpos(offset);
+        let format = try_read_u8(&mut buffer)?;
+        // .notdef is not stored in charset
+        let mut charset: Vec<u16> = vec![0];
+        while charset.len() < num_glyphs {
+            match format {
+                0 => charset.push(try_read_u16_be(&mut buffer)?),
+                1 | 2 => {
+                    let first = try_read_u16_be(&mut buffer)?;
+                    let n_left = if format == 1 {
+                        try_read_u8(&mut buffer)? as u16
+                    } else {
+                        try_read_u16_be(&mut buffer)?
+                    };
+                    for id in first..=first.saturating_add(n_left) {
+                        charset.push(id);
+                    }
+                }
+                _ => return Err(Error::InvalidTableData),
+            }
+        }
+        charset.truncate(num_glyphs.max(1));
+        Ok(charset)
+    }
+
+    /// Returns glyph with the name which StandardEncoding assigns to the code, as it's used by
+    /// seac accented characters. CID-keyed and CFF2 fonts have no glyph names
+    pub(crate) fn standard_encoding_glyph(&self, code: u8) -> Result<u16, Error> {
+        let sid = standard_encoding_sid(code);
+        if sid == 0 || self.is_cff2 || self.is_cid_keyed() {
+            return Err(Error::InvalidTableData);
+        }
+        let position = self.charset()?.iter().position(|&glyph_sid| glyph_sid == sid);
+        position.map(|glyph_id| glyph_id as u16).ok_or(Error::InvalidTableData)
+    }
+
+    /// Emits cubic glyph outline to the path builder
+    pub fn draw<B: PathBuilder + ?Sized>(&self, glyph_id: u16, builder: &mut B) -> Result<(), Error> {
+        let char_string = self.char_strings.get(glyph_id as usize).ok_or(Error::InvalidTableData)?;
+        let private_dict = self.private_dict(glyph_id)?;
+        let mut interpreter = CharStringInterpreter::new(self, &private_dict.local_subrs, builder);
+        interpreter.run(&self.data[char_string])
+    }
+}
+
+/// Returns offset operand of the DICT entry
+pub(crate) fn dict_offset(entries: &[DictEntry], op: u16) -> Option<usize> {
+    dict_operands(entries, op).and_then(|operands| operands.last()).map(|&offset| offset as usize)
+}
+
+/// Parses Private DICT referenced from Top DICT or Font DICT and its local subroutines
+fn parse_private_dict(data: &[u8], dict: &[DictEntry], is_cff2: bool) -> Result<PrivateDict, Error> {
+    let (size, offset) = match dict_operands(dict, OP_PRIVATE) {
+        Some([size, offset]) => (*size as usize, *offset as usize),
+        _ => return Ok(PrivateDict::default()),
+    };
+    let entries = parse_dict(try_slice(data, offset, size)?)?;
+    let local_subrs = match dict_offset(&entries, OP_SUBRS) {
+        // Subrs offset is relative to the beginning of Private DICT
+        Some(subrs_offset) => CffIndex::parse(data, offset + subrs_offset, is_cff2)?,
+        None => CffIndex::default(),
+    };
+    Ok(PrivateDict { entries, local_subrs })
+}
+
+/// Reads number of regions of every ItemVariationData from CFF2 variation store
+fn parse_region_counts(data: &[u8], offset: usize) -> Result<Vec<u16>, Error> {
+    // variation store starts with 16-bit length followed by ItemVariationStore
+    let store_offset = offset + 2;
+    let mut buffer = ByteReader::from_bytes(data);
+    buffer.set_rpos(store_offset);
+    let _format = try_read_u16_be(&mut buffer)?;
+    let _region_list_offset = try_read_u32_be(&mut buffer)?;
+    let data_count = try_read_u16_be(&mut buffer)?;
+    let data_offsets = (0..data_count)
+        .map(|_| try_read_u32_be(&mut buffer))
+        .collect::<Result<Vec<u32>, Error>>()?;
+    let mut region_counts: Vec<u16> = Vec::with_capacity(data_count as usize);
+    for data_offset in data_offsets {
+        // skip itemCount and shortDeltaCount
+        buffer.set_rpos(store_offset + data_offset as usize + 4);
+        region_counts.push(try_read_u16_be(&mut buffer)?);
+    }
+    Ok(region_counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outline::SvgPathBuilder;
+    use crate::test_utils::*;
+    use crate::{FontTables, WoffFont};
+
+    const RMOVETO: u16 = 21;
+    const RLINETO: u16 = 5;
+    const HLINETO: u16 = 6;
+    const RRCURVETO: u16 = 8;
+    const CALLSUBR: u16 = 10;
+    const RETURN: u16 = 11;
+    const ENDCHAR: u16 = 14;
+    const BLEND: u16 = 16;
+    const HSTEMHM: u16 = 18;
+    const HINTMASK: u16 = 19;
+    const CALLGSUBR: u16 = 29;
+    const HFLEX: u16 = 12 * 256 + 34;
+    const MUL: u16 = 12 * 256 + 24;
+    const DUP: u16 = 12 * 256 + 27;
+
+    fn draw(glyphs: &CffGlyphs, glyph_id: u16) -> String {
+        let mut svg = SvgPathBuilder::new();
+        glyphs.draw(glyph_id, &mut svg).unwrap();
+        svg.finish()
+    }
+
+    #[test]
+    fn test_cff_outlines_with_subroutines() {
+        let char_strings = vec![
+            // width, move, lines and endchar
+            char_string(&[Ok(500), Ok(10), Ok(20), Err(RMOVETO), Ok(100), Ok(0), Ok(0), Ok(100), Err(RLINETO), Err(ENDCHAR)]),
+            // local subroutine with curve
+            char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(-107), Err(CALLSUBR), Err(ENDCHAR)]),
+            // hints, hintmask and global subroutine which ends the glyph
+            [
+                char_string(&[Ok(1), Ok(2), Ok(3), Ok(4), Err(HSTEMHM), Ok(5), Ok(6), Err(HINTMASK)]),
+                vec![0xE0],
+                char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(-107), Err(CALLGSUBR)]),
+            ]
+            .concat(),
+            // hflex
+            char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(10), Ok(10), Ok(10), Ok(10), Ok(10), Ok(10), Ok(10), Err(HFLEX), Err(ENDCHAR)]),
+        ];
+        let global_subrs = vec![char_string(&[Ok(100), Err(HLINETO), Err(ENDCHAR)])];
+        let local_subrs = vec![vec![char_string(&[Ok(10), Ok(20), Ok(30), Ok(40), Ok(50), Ok(60), Err(RRCURVETO), Err(RETURN)])]];
+        let cff = cff_table(&char_strings, &global_subrs, &local_subrs, None);
+        let woff = build_woff(0x4F54544F, &[(tag(b"CFF "), cff)]);
+        let font = WoffFont::new(&woff).unwrap();
+        let glyphs = font.cff_glyphs().unwrap();
+        debug_assert!(glyphs.num_glyphs() == 4 && !glyphs.is_cid_keyed());
+        debug_assert!(draw(&glyphs, 0) == "M10 -20 L110 -20 L110 -120 Z");
+        debug_assert!(draw(&glyphs, 1) == "M0 0 C10 -20 40 -60 90 -120 Z");
+        debug_assert!(draw(&glyphs, 2) == "M0 0 L100 0 Z");
+        debug_assert!(draw(&glyphs, 3) == "M0 0 C10 0 20 -10 30 -10 C40 -10 50 0 60 0 Z");
+
+        let mut svg = SvgPathBuilder::new();
+        font.draw_glyph(0, &mut svg).unwrap();
+        debug_assert!(svg.finish() == draw(&glyphs, 0));
+    }
+
+    #[test]
+    fn test_cid_keyed_cff_font_dicts() {
+        let char_strings = vec![
+            char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(-107), Err(CALLSUBR), Err(ENDCHAR)]),
+            char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(-107), Err(CALLSUBR), Err(ENDCHAR)]),
+        ];
+        let local_subrs = vec![
+            vec![char_string(&[Ok(10), Err(HLINETO), Err(RETURN)])],
+            vec![char_string(&[Ok(20), Err(HLINETO), Err(RETURN)])],
+        ];
+        let cff = cff_table(&char_strings, &[], &local_subrs, Some(&[0, 1]));
+        let glyphs = CffGlyphs::parse_cff(Cow::Owned(cff)).unwrap();
+        debug_assert!(glyphs.is_cid_keyed() && glyphs.private_dicts.len() == 2);
+        debug_assert!(draw(&glyphs, 0) == "M0 0 L10 0 Z");
+        debug_assert!(draw(&glyphs, 1) == "M0 0 L20 0 Z");
+    }
+
+    #[test]
+    fn test_cff2_blend_uses_default_values() {
+        let char_strings = vec![char_string(&[Ok(10), Ok(5), Ok(1), Err(BLEND), Ok(20), Err(RMOVETO), Ok(30), Err(HLINETO)])];
+        let glyphs = CffGlyphs::parse_cff2(Cow::Owned(cff2_table(&char_strings, &[]))).unwrap();
+        debug_assert!(glyphs.region_counts == vec![1]);
+        debug_assert!(draw(&glyphs, 0) == "M10 -20 L40 -20 Z");
+    }
+
+    #[test]
+    fn test_infinite_subroutine_recursion() {
+        let char_strings = vec![char_string(&[Ok(-107), Err(CALLGSUBR)])];
+        let global_subrs = vec![char_string(&[Ok(-107), Err(CALLGSUBR)])];
+        let glyphs = CffGlyphs::parse_cff(Cow::Owned(cff_table(&char_strings, &global_subrs, &[vec![]], None))).unwrap();
+        debug_assert!(glyphs.draw(0, &mut SvgPathBuilder::new()) == Err(Error::InvalidTableData));
+    }
+
+    #[test]
+    fn test_subroutine_fan_out() {
+        // every global subroutine calls the next one 10 times, so the glyph would run 10^9 subroutines
+        let mut global_subrs: Vec<Vec<u8>> = (1..10)
+            .map(|next: i16| char_string(&[Ok(next - 107), Err(CALLGSUBR)].repeat(10)))
+            .collect();
+        global_subrs.push(char_string(&[Err(RETURN)]));
+        let char_strings = vec![char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(-107), Err(CALLGSUBR), Err(ENDCHAR)])];
+        let glyphs = CffGlyphs::parse_cff(Cow::Owned(cff_table(&char_strings, &global_subrs, &[vec![]], None))).unwrap();
+        debug_assert!(glyphs.draw(0, &mut SvgPathBuilder::new()) == Err(Error::InvalidTableData));
+    }
+
+    #[test]
+    fn test_cff2_blend_with_huge_count() {
+        let square = [Ok(30000), Err(DUP), Err(MUL), Err(DUP), Err(MUL), Err(DUP), Err(MUL)];
+        let char_strings = vec![
+            char_string(&[&square[..], &[Err(BLEND)]].concat()),
+            char_string(&[Ok(10), Ok(5), Ok(-1), Err(BLEND)]),
+        ];
+        let glyphs = CffGlyphs::parse_cff2(Cow::Owned(cff2_table(&char_strings, &[]))).unwrap();
+        debug_assert!(glyphs.draw(0, &mut SvgPathBuilder::new()) == Err(Error::InvalidTableData));
+        debug_assert!(glyphs.draw(1, &mut SvgPathBuilder::new()) == Err(Error::InvalidTableData));
+        debug_assert!(crate::tables::charstring::desubroutinize(&glyphs, 1) == Err(Error::InvalidTableData));
+    }
+
+    #[test]
+    fn test_seac_accented_character() {
+        // with predefined ISOAdobe charset glyph ID is the SID of glyph name: 'exclam' is 2 and 'quoteright' is 8
+        let mut char_strings = vec![char_string(&[Err(ENDCHAR)]); 10];
+        char_strings[2] = char_string(&[Ok(0), Ok(0), Err(RMOVETO), Ok(100), Err(HLINETO), Err(ENDCHAR)]);
+        char_strings[8] = char_string(&[Ok(200), Ok(0), Ok(0), Err(RMOVETO), Ok(10), Err(HLINETO), Err(ENDCHAR)]);
+        // width, adx, ady, bchar ('!') and achar ('’')
+        char_strings[9] = char_string(&[Ok(300), Ok(50), Ok(400), Ok(33), Ok(39), Err(ENDCHAR)]);
+        // base glyph '(' (SID 9) is an accented character itself
+        char_strings[3] = char_string(&[Ok(0), Ok(0), Ok(40), Ok(39), Err(ENDCHAR)]);
+        // accent '-' (SID 14) isn't in the font and 300 isn't a character code
+        char_strings[4] = char_string(&[Ok(0), Ok(0), Ok(33), Ok(45), Err(ENDCHAR)]);
+        char_strings[5] = char_string(&[Ok(0), Ok(0), Ok(33), Ok(300), Err(ENDCHAR)]);
+        let glyphs = CffGlyphs::parse_cff(Cow::Owned(cff_table(&char_strings, &[], &[vec![]], None))).unwrap();
+        debug_assert!(draw(&glyphs, 9) == "M0 0 L100 0 Z M50 -400 L60 -400 Z");
+        for glyph_id in 3..6 {
+            debug_assert!(glyphs.draw(glyph_id, &mut SvgPathBuilder::new()) == Err(Error::InvalidTableData));
+        }
+    }
+}
@@ -0,0 +1,752 @@
+//! Type 2 (CFF) and CFF2 charstring interpreter which emits cubic outlines
+//! and subroutine inlining used by CFF subsetting
+
+use crate::outline::PathBuilder;
+use crate::tables::cff::{CffGlyphs, CffIndex};
+use crate::Error;
+
+/// Maximum nesting of subroutine calls
+const MAX_SUBR_DEPTH: usize = 10;
+/// Maximum number of operators and operands executed for one glyph, subroutine code is counted
+/// every time it's called. It stops subroutines which call the next nesting level many times
+/// from taking exponential time
+const MAX_OPERATIONS: usize = 1 << 18;
+/// Maximum length of charstring with inlined subroutines, the Type 2 charstring limit
+const MAX_CHAR_STRING_LEN: usize = 65535;
+/// Maximum argument stack depth of CFF and CFF2 charstrings
+const MAX_CFF_STACK: usize = 48;
+const MAX_CFF2_STACK: usize = 513;
+/// Size of the transient array used by put and get operators
+const TRANSIENT_ARRAY_SIZE: usize = 32;
+
+// One-byte operators
+const HSTEM: u8 = 1;
+const VSTEM: u8 = 3;
+const VMOVETO: u8 = 4;
+const RLINETO: u8 = 5;
+const HLINETO: u8 = 6;
+const VLINETO: u8 = 7;
+const RRCURVETO: u8 = 8;
+const CALLSUBR: u8 = 10;
+const RETURN: u8 = 11;
+const ESCAPE: u8 = 12;
+const ENDCHAR: u8 = 14;
+const VSINDEX: u8 = 15;
+const BLEND: u8 = 16;
+const HSTEMHM: u8 = 18;
+const HINTMASK: u8 = 19;
+const CNTRMASK: u8 = 20;
+const RMOVETO: u8 = 21;
+const HMOVETO: u8 = 22;
+const VSTEMHM: u8 = 23;
+const RCURVELINE: u8 = 24;
+const RLINECURVE: u8 = 25;
+const VVCURVETO: u8 = 26;
+const HHCURVETO: u8 = 27;
+const SHORTINT: u8 = 28;
+const CALLGSUBR: u8 = 29;
+const VHCURVETO: u8 = 30;
+const HVCURVETO: u8 = 31;
+const FIXED_16_16: u8 = 255;
+
+// Two-byte (12 x) operators
+const AND: u8 = 3;
+const OR: u8 = 4;
+const NOT: u8 = 5;
+const ABS: u8 = 9;
+const ADD: u8 = 10;
+const SUB: u8 = 11;
+const DIV: u8 = 12;
+const NEG: u8 = 14;
+const EQ: u8 = 15;
+const DROP: u8 = 18;
+const PUT: u8 = 20;
+const GET: u8 = 21;
+const IFELSE: u8 = 22;
+const RANDOM: u8 = 23;
+const MUL: u8 = 24;
+const SQRT: u8 = 26;
+const DUP: u8 = 27;
+const EXCH: u8 = 28;
+const INDEX: u8 = 29;
+const ROLL: u8 = 30;
+const HFLEX: u8 = 34;
+const FLEX: u8 = 35;
+const HFLEX1: u8 = 36;
+const FLEX1: u8 = 37;
+
+/// Calculates subroutine number bias
+pub(crate) fn subr_bias(count: usize) -> i32 {
+    if count < 1240 {
+        107
+    } else if count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
+
+/// Counts one more executed operator or operand against `MAX_OPERATIONS`
+fn count_operation(operations: &mut usize) -> Result<(), Error> {
+    *operations += 1;
+    if *operations > MAX_OPERATIONS {
+        return Err(Error::InvalidTableData);
+    }
+    Ok(())
+}
+
+/// Validates number of blended values of blend operator against the number of operands on the stack
+fn blend_count(count: f32, stack_len: usize) -> Result<usize, Error> {
+    if count < 0.0 || count > stack_len as f32 {
+        return Err(Error::InvalidTableData);
+    }
+    Ok(count as usize)
+}
+
+/// Returns glyph charstring with all subroutine calls replaced by the subroutines code,
+/// so the result doesn't depend on local and global subroutines.
+/// Fails if the result is longer than `MAX_CHAR_STRING_LEN` or takes more than `MAX_OPERATIONS`
+pub(crate) fn desubroutinize(font: &CffGlyphs, glyph_id: u16) -> Result<Vec<u8>, Error> {
+    let char_string = font.char_strings.get(glyph_id as usize).ok_or(Error::InvalidTableData)?;
+    let private_dict = font.private_dict(glyph_id)?;
+    let mut inliner = SubroutineInliner {
+        font,
+        local_subrs: &private_dict.local_subrs,
+        output: Vec::with_capacity(char_string.len()),
+        operands: vec![],
+        num_stems: 0,
+        vsindex: 0,
+        operations: 0,
+    };
+    inliner.inline(&font.data[char_string], 0)?;
+    if inliner.output.len() > MAX_CHAR_STRING_LEN {
+        return Err(Error::InvalidTableData);
+    }
+    Ok(inliner.output)
+}
+
+/// Returns base and accent glyphs of seac accented character, `None` for other glyphs.
+/// CFF2 and CID-keyed fonts have no accented characters
+pub(crate) fn seac_components(font: &CffGlyphs, glyph_id: u16) -> Result<Option<(u16, u16)>, Error> {
+    if font.is_cff2 || font.is_cid_keyed() {
+        return Ok(None);
+    }
+    let char_string = font.char_strings.get(glyph_id as usize).ok_or(Error::InvalidTableData)?;
+    let private_dict = font.private_dict(glyph_id)?;
+    let mut builder = NullPathBuilder;
+    let mut interpreter = CharStringInterpreter::new(font, &private_dict.local_subrs, &mut builder);
+    interpreter.run(&font.data[char_string])?;
+    Ok(interpreter.seac_components)
+}
+
+/// Path builder which ignores the outline
+struct NullPathBuilder;
+
+impl PathBuilder for NullPathBuilder {
+    fn move_to(&mut self, _x: f32, _y: f32) {}
+    fn line_to(&mut self, _x: f32, _y: f32) {}
+    fn quad_to(&mut self, _x1: f32, _y1: f32, _x: f32, _y: f32) {}
+    fn curve_to(&mut self, _x1: f32, _y1: f32, _x2: f32, _y2: f32, _x: f32, _y: f32) {}
+    fn close(&mut self) {}
+}
+
+/// State of subroutine inlining for one glyph. Operators and operands are copied as is,
+/// only operands count is tracked to find hint mask lengths and blend results
+struct SubroutineInliner<'f, 'a> {
+    font: &'f CffGlyphs<'a>,
+    local_subrs: &'f CffIndex,
+    output: Vec<u8>,
+    // Operand values with their positions in the output; None for results of arithmetic operators
+    operands: Vec<(Option<f32>, usize)>,
+    num_stems: usize,
+    vsindex: usize,
+    // Operators and operands copied so far, see `MAX_OPERATIONS`
+    operations: usize,
+}
+
+impl SubroutineInliner<'_, '_> {
+    /// Copies charstring or subroutine code to the output. Returns true if endchar operator was reached
+    fn inline(&mut self, code: &[u8], depth: usize) -> Result<bool, Error> {
+        if depth > MAX_SUBR_DEPTH {
+            return Err(Error::InvalidTableData);
+        }
+        let mut pos = 0usize;
+        while pos < code.len() {
+            count_operation(&mut self.operations)?;
+            if self.output.len() > MAX_CHAR_STRING_LEN {
+                return Err(Error::InvalidTableData);
+            }
+            let b0 = code[pos];
+            let operand_len = match b0 {
+                32..=246 => 1,
+                247..=254 => 2,
+                SHORTINT => 3,
+                FIXED_16_16 => 5,
+                _ => 0,
+            };
+            if operand_len > 0 {
+                let bytes = code.get(pos..pos + operand_len).ok_or(Error::InvalidTableData)?;
+                let value = match b0 {
+                    32..=246 => b0 as f32 - 139.0,
+                    247..=250 => (b0 as f32 - 247.0) * 256.0 + bytes[1] as f32 + 108.0,
+                    251..=254 => -(b0 as f32 - 251.0) * 256.0 - bytes[1] as f32 - 108.0,
+                    SHORTINT => i16::from_be_bytes([bytes[1], bytes[2]]) as f32,
+                    _ => i32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]) as f32 / 65536.0,
+                };
+                self.operands.push((Some(value), self.output.len()));
+                self.output.extend_from_slice(bytes);
+                pos += operand_len;
+                continue;
+            }
+            pos += 1;
+            match b0 {
+                CALLSUBR | CALLGSUBR => {
+                    // subroutine number has to be a literal operand, it's removed from the output
+                    let (number, start) = self.operands.pop().ok_or(Error::InvalidTableData)?;
+                    let number = number.ok_or(Error::InvalidTableData)?;
+                    self.output.truncate(start);
+                    let subrs = if b0 == CALLSUBR { self.local_subrs } else { &self.font.global_subrs };
+                    let range = usize::try_from(number as i32 + subr_bias(subrs.len()))
+                        .ok()
+                        .and_then(|index| subrs.get(index))
+                        .ok_or(Error::InvalidTableData)?;
+                    let font = self.font;
+                    if self.inline(&font.data[range], depth + 1)? {
+                        return Ok(true);
+                    }
+                }
+                RETURN => return Ok(false),
+                ENDCHAR if !self.font.is_cff2 => {
+                    self.output.push(b0);
+                    return Ok(true);
+                }
+                HINTMASK | CNTRMASK => {
+                    // arguments before hintmask are implicit vstem hints
+                    self.num_stems += self.operands.len() / 2;
+                    self.operands.clear();
+                    let mask_len = self.num_stems.div_ceil(8);
+                    self.output.push(b0);
+                    self.output.extend_from_slice(code.get(pos..pos + mask_len).ok_or(Error::InvalidTableData)?);
+                    pos += mask_len;
+                }
+                HSTEM | VSTEM | HSTEMHM | VSTEMHM => {
+                    self.num_stems += self.operands.len() / 2;
+                    self.operands.clear();
+                    self.output.push(b0);
+                }
+                VSINDEX if self.font.is_cff2 => {
+                    let (vsindex, _) = self.operands.pop().ok_or(Error::InvalidTableData)?;
+                    self.vsindex = vsindex.ok_or(Error::InvalidTableData)? as usize;
+                    self.operands.clear();
+                    self.output.push(b0);
+                }
+                BLEND if self.font.is_cff2 => {
+                    let (count, _) = self.operands.pop().ok_or(Error::InvalidTableData)?;
+                    let count = blend_count(count.ok_or(Error::InvalidTableData)?, self.operands.len())?;
+                    let region_count = *self.font.region_counts.get(self.vsindex).ok_or(Error::InvalidTableData)? as usize;
+                    let len = count
+                        .checked_mul(region_count)
+                        .and_then(|deltas| self.operands.len().checked_sub(deltas))
+                        .ok_or(Error::InvalidTableData)?;
+                    self.operands.truncate(len);
+                    self.output.push(b0);
+                }
+                ESCAPE => {
+                    let b1 = *code.get(pos).ok_or(Error::InvalidTableData)?;
+                    pos += 1;
+                    self.apply_escape(b1)?;
+                    self.output.extend_from_slice(&[b0, b1]);
+                }
+                _ => {
+                    self.operands.clear();
+                    self.output.push(b0);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Updates operands after two-byte operator: flex operators clear them,
+    /// arithmetic ones replace their arguments with a result of unknown value
+    fn apply_escape(&mut self, op: u8) -> Result<(), Error> {
+        let (pop_count, push_count) = match op {
+            ABS | NEG | NOT | SQRT | GET | INDEX => (1, 1),
+            ADD | SUB | MUL | DIV | AND | OR | EQ => (2, 1),
+            DROP => (1, 0),
+            PUT | ROLL => (2, 0),
+            IFELSE => (4, 1),
+            RANDOM | DUP => (0, 1),
+            EXCH => (0, 0),
+            _ => {
+                self.operands.clear();
+                return Ok(());
+            }
+        };
+        let len = self.operands.len().checked_sub(pop_count).ok_or(Error::InvalidTableData)?;
+        self.operands.truncate(len);
+        for _ in 0..push_count {
+            self.operands.push((None, self.output.len()));
+        }
+        Ok(())
+    }
+}
+
+/// Interpreter state for one glyph
+pub(crate) struct CharStringInterpreter<'f, 'a, B: PathBuilder + ?Sized> {
+    font: &'f CffGlyphs<'a>,
+    local_subrs: &'f CffIndex,
+    builder: &'f mut B,
+    stack: Vec<f32>,
+    transient: [f32; TRANSIENT_ARRAY_SIZE],
+    x: f32,
+    y: f32,
+    num_stems: usize,
+    // Set after the first stack-clearing operator, which may have an optional width argument
+    width_parsed: bool,
+    contour_open: bool,
+    vsindex: usize,
+    // Set for base and accent glyphs of seac, which can't be accented characters themselves
+    is_seac_component: bool,
+    // Base and accent glyphs of seac accented character
+    seac_components: Option<(u16, u16)>,
+    // Operators and operands executed so far, see `MAX_OPERATIONS`
+    operations: usize,
+}
+
+impl<'f, 'a, B: PathBuilder + ?Sized> CharStringInterpreter<'f, 'a, B> {
+    pub(crate) fn new(font: &'f CffGlyphs<'a>, local_subrs: &'f CffIndex, builder: &'f mut B) -> Self {
+        CharStringInterpreter {
+            font,
+            local_subrs,
+            builder,
+            stack: Vec::with_capacity(MAX_CFF_STACK),
+            transient: [0.0; TRANSIENT_ARRAY_SIZE],
+            x: 0.0,
+            y: 0.0,
+            num_stems: 0,
+            width_parsed: font.is_cff2,
+            contour_open: false,
+            vsindex: 0,
+            is_seac_component: false,
+            seac_components: None,
+            operations: 0,
+        }
+    }
+
+    /// Interprets glyph charstring
+    pub(crate) fn run(&mut self, char_string: &[u8]) -> Result<(), Error> {
+        self.execute(char_string, 0)?;
+        self.close_contour();
+        Ok(())
+    }
+
+    fn push(&mut self, value: f32) -> Result<(), Error> {
+        let limit = if self.font.is_cff2 { MAX_CFF2_STACK } else { MAX_CFF_STACK };
+        if self.stack.len() >= limit {
+            return Err(Error::InvalidTableData);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<f32, Error> {
+        self.stack.pop().ok_or(Error::InvalidTableData)
+    }
+
+    /// Removes width argument of the first stack-clearing operator if there is one.
+    /// `has_width` says if the number of arguments means that width is present
+    fn parse_width(&mut self, has_width: bool) {
+        if !self.width_parsed {
+            if has_width && !self.stack.is_empty() {
+                self.stack.remove(0);
+            }
+            self.width_parsed = true;
+        }
+    }
+
+    fn move_to(&mut self, dx: f32, dy: f32) {
+        self.close_contour();
+        self.x += dx;
+        self.y += dy;
+        self.builder.move_to(self.x, self.y);
+        self.contour_open = true;
+    }
+
+    fn line_to(&mut self, dx: f32, dy: f32) -> Result<(), Error> {
+        if !self.contour_open {
+            return Err(Error::InvalidTableData);
+        }
+        self.x += dx;
+        self.y += dy;
+        self.builder.line_to(self.x, self.y);
+        Ok(())
+    }
+
+    fn curve_to(&mut self, dx1: f32, dy1: f32, dx2: f32, dy2: f32, dx3: f32, dy3: f32) -> Result<(), Error> {
+        if !self.contour_open {
+            return Err(Error::InvalidTableData);
+        }
+        let x1 = self.x + dx1;
+        let y1 = self.y + dy1;
+        let x2 = x1 + dx2;
+        let y2 = y1 + dy2;
+        self.x = x2 + dx3;
+        self.y = y2 + dy3;
+        self.builder.curve_to(x1, y1, x2, y2, self.x, self.y);
+        Ok(())
+    }
+
+    fn close_contour(&mut self) {
+        if self.contour_open {
+            self.builder.close();
+            self.contour_open = false;
+        }
+    }
+
+    fn add_stems(&mut self) {
+        self.parse_width(self.stack.len() % 2 == 1);
+        self.num_stems += self.stack.len() / 2;
+        self.stack.clear();
+    }
+
+    /// Executes charstring or subroutine. Returns true if endchar operator was reached
+    fn execute(&mut self, code: &[u8], depth: usize) -> Result<bool, Error> {
+        if depth > MAX_SUBR_DEPTH {
+            return Err(Error::InvalidTableData);
+        }
+        let mut pos = 0usize;
+        let next_byte = |pos: &mut usize| -> Result<u8, Error> {
+            let byte = *code.get(*pos).ok_or(Error::InvalidTableData)?;
+            *pos += 1;
+            Ok(byte)
+        };
+        while pos < code.len() {
+            count_operation(&mut self.operations)?;
+            let b0 = next_byte(&mut pos)?;
+            match b0 {
+                32..=246 => self.push(b0 as f32 - 139.0)?,
+                247..=250 => {
+                    let b1 = next_byte(&mut pos)?;
+                    self.push((b0 as f32 - 247.0) * 256.0 + b1 as f32 + 108.0)?
+                }
+                251..=254 => {
+                    let b1 = next_byte(&mut pos)?;
+                    self.push(-(b0 as f32 - 251.0) * 256.0 - b1 as f32 - 108.0)?
+                }
+                SHORTINT => {
+                    let value = i16::from_be_bytes([next_byte(&mut pos)?, next_byte(&mut pos)?]);
+                    self.push(value as f32)?
+                }
+                FIXED_16_16 => {
+                    let mut bytes = [0u8; 4];
+                    for byte in bytes.iter_mut() {
+                        *byte = next_byte(&mut pos)?;
+                    }
+                    self.push(i32::from_be_bytes(bytes) as f32 / 65536.0)?
+                }
+                HSTEM | VSTEM | HSTEMHM | VSTEMHM => self.add_stems(),
+                HINTMASK | CNTRMASK => {
+                    // arguments before hintmask are implicit vstem hints
+                    self.add_stems();
+                    pos += self.num_stems.div_ceil(8);
+                }
+                RMOVETO => {
+                    self.parse_width(self.stack.len() > 2);
+                    if self.stack.len() < 2 {
+                        return Err(Error::InvalidTableData);
+                    }
+                    self.move_to(self.stack[0], self.stack[1]);
+                    self.stack.clear();
+                }
+                HMOVETO | VMOVETO => {
+                    self.parse_width(self.stack.len() > 1);
+                    let delta = *self.stack.first().ok_or(Error::InvalidTableData)?;
+                    if b0 == HMOVETO {
+                        self.move_to(delta, 0.0);
+                    } else {
+                        self.move_to(0.0, delta);
+                    }
+                    self.stack.clear();
+                }
+                RLINETO => {
+                    for pair in std::mem::take(&mut self.stack).chunks_exact(2) {
+                        self.line_to(pair[0], pair[1])?;
+                    }
+                }
+                HLINETO | VLINETO => {
+                    let mut horizontal = b0 == HLINETO;
+                    for delta in std::mem::take(&mut self.stack) {
+                        if horizontal {
+                            self.line_to(delta, 0.0)?;
+                        } else {
+                            self.line_to(0.0, delta)?;
+                        }
+                        horizontal = !horizontal;
+                    }
+                }
+                RRCURVETO => {
+                    for args in std::mem::take(&mut self.stack).chunks_exact(6) {
+                        self.curve_to(args[0], args[1], args[2], args[3], args[4], args[5])?;
+                    }
+                }
+                RCURVELINE => {
+                    let args = std::mem::take(&mut self.stack);
+                    if args.len() < 8 {
+                        return Err(Error::InvalidTableData);
+                    }
+                    let (curves, line) = args.split_at(args.len() - 2);
+                    for args in curves.chunks_exact(6) {
+                        self.curve_to(args[0], args[1], args[2], args[3], args[4], args[5])?;
+                    }
+                    self.line_to(line[0], line[1])?;
+                }
+                RLINECURVE => {
+                    let args = std::mem::take(&mut self.stack);
+                    if args.len() < 8 {
+                        return Err(Error::InvalidTableData);
+                    }
+                    let (lines, curve) = args.split_at(args.len() - 6);
+                    for pair in lines.chunks_exact(2) {
+                        self.line_to(pair[0], pair[1])?;
+                    }
+                    self.curve_to(curve[0], curve[1], curve[2], curve[3], curve[4], curve[5])?;
+                }
+                VVCURVETO | HHCURVETO => {
+                    let mut args = std::mem::take(&mut self.stack);
+                    let mut first_delta = 0.0;
+                    if args.len() % 4 == 1 {
+                        first_delta = args.remove(0);
+                    }
+                    for curve in args.chunks_exact(4) {
+                        if b0 == VVCURVETO {
+                            self.curve_to(first_delta, curve[0], curve[1], curve[2], 0.0, curve[3])?;
+                        } else {
+                            self.curve_to(curve[0], first_delta, curve[1], curve[2], curve[3], 0.0)?;
+                        }
+                        first_delta = 0.0;
+                    }
+                }
+                HVCURVETO | VHCURVETO => {
+                    let args = std::mem::take(&mut self.stack);
+                    let mut horizontal = b0 == HVCURVETO;
+                    let mut index = 0usize;
+                    while index + 4 <= args.len() {
+                        let last_delta = if index + 5 == args.len() { args[index + 4] } else { 0.0 };
+                        let curve = &args[index..index + 4];
+                        if horizontal {
+                            self.curve_to(curve[0], 0.0, curve[1], curve[2], last_delta, curve[3])?;
+                        } else {
+                            self.curve_to(0.0, curve[0], curve[1], curve[2], curve[3], last_delta)?;
+                        }
+                        horizontal = !horizontal;
+                        index += 4;
+                    }
+                }
+                CALLSUBR | CALLGSUBR => {
+                    let subrs = if b0 == CALLSUBR { self.local_subrs } else { &self.font.global_subrs };
+                    let index = self.pop()? as i32 + subr_bias(subrs.len());
+                    let range = usize::try_from(index)
+                        .ok()
+                        .and_then(|index| subrs.get(index))
+                        .ok_or(Error::InvalidTableData)?;
+                    let font = self.font;
+                    if self.execute(&font.data[range], depth + 1)? {
+                        return Ok(true);
+                    }
+                }
+                RETURN => return Ok(false),
+                ENDCHAR if !self.font.is_cff2 => {
+                    // 4 additional arguments mean deprecated seac accented character
+                    self.parse_width(self.stack.len() == 1 || self.stack.len() == 5);
+                    let args = std::mem::take(&mut self.stack);
+                    self.close_contour();
+                    if args.len() == 4 {
+                        self.seac(&args)?;
+                    }
+                    return Ok(true);
+                }
+                VSINDEX if self.font.is_cff2 => {
+                    self.vsindex = self.pop()? as usize;
+                    self.stack.clear();
+                }
+                BLEND if self.font.is_cff2 => {
+                    // without variation coordinates blend leaves default values only
+                    let count = blend_count(self.pop()?, self.stack.len())?;
+                    let region_count = *self.font.region_counts.get(self.vsindex).ok_or(Error::InvalidTableData)? as usize;
+                    let total = count
+                        .checked_mul(region_count + 1)
+                        .filter(|&total| total <= self.stack.len())
+                        .ok_or(Error::InvalidTableData)?;
+                    let first = self.stack.len() - total;
+                    self.stack.truncate(first + count);
+                }
+                ESCAPE => {
+                    let b1 = next_byte(&mut pos)?;
+                    self.execute_escape(b1)?;
+                }
+                _ => return Err(Error::InvalidTableData),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Draws seac accented character: StandardEncoding `bchar` glyph at the origin
+    /// and `achar` glyph shifted by (`adx`, `ady`). `args` are adx, ady, bchar and achar
+    fn seac(&mut self, args: &[f32]) -> Result<(), Error> {
+        if self.is_seac_component {
+            return Err(Error::InvalidTableData);
+        }
+        let code = |value: f32| -> Result<u8, Error> {
+            if value.fract() == 0.0 && (0.0..=255.0).contains(&value) {
+                Ok(value as u8)
+            } else {
+                Err(Error::InvalidTableData)
+            }
+        };
+        let base = self.font.standard_encoding_glyph(code(args[2])?)?;
+        let accent = self.font.standard_encoding_glyph(code(args[3])?)?;
+        self.seac_components = Some((base, accent));
+        let font = self.font;
+        for (glyph_id, x, y) in [(base, 0.0, 0.0), (accent, args[0], args[1])] {
+            let char_string = font.char_strings.get(glyph_id as usize).ok_or(Error::InvalidTableData)?;
+            let private_dict = font.private_dict(glyph_id)?;
+            let mut component = CharStringInterpreter::new(font, &private_dict.local_subrs, &mut *self.builder);
+            component.x = x;
+            component.y = y;
+            component.is_seac_component = true;
+            // components share the operations budget of the accented character
+            component.operations = self.operations;
+            component.run(&font.data[char_string])?;
+            self.operations = component.operations;
+        }
+        Ok(())
+    }
+
+    /// Executes two-byte operator
+    fn execute_escape(&mut self, op: u8) -> Result<(), Error> {
+        match op {
+            HFLEX => {
+                let args = std::mem::take(&mut self.stack);
+                if args.len() != 7 {
+                    return Err(Error::InvalidTableData);
+                }
+                self.curve_to(args[0], 0.0, args[1], args[2], args[3], 0.0)?;
+                self.curve_to(args[4], 0.0, args[5], -args[2], args[6], 0.0)?;
+            }
+            FLEX => {
+                let args = std::mem::take(&mut self.stack);
+                if args.len() != 13 {
+                    return Err(Error::InvalidTableData);
+                }
+                self.curve_to(args[0], args[1], args[2], args[3], args[4], args[5])?;
+                self.curve_to(args[6], args[7], args[8], args[9], args[10], args[11])?;
+            }
+            HFLEX1 => {
+                let args = std::mem::take(&mut self.stack);
+                if args.len() != 9 {
+                    return Err(Error::InvalidTableData);
+                }
+                let start_y = self.y;
+                self.curve_to(args[0], args[1], args[2], args[3], args[4], 0.0)?;
+                let dy6 = start_y - (self.y + args[7]);
+                self.curve_to(args[5], 0.0, args[6], args[7], args[8], dy6)?;
+            }
+            FLEX1 => {
+                let args = std::mem::take(&mut self.stack);
+                if args.len() != 11 {
+                    return Err(Error::InvalidTableData);
+                }
+                let (start_x, start_y) = (self.x, self.y);
+                let dx: f32 = args[..10].iter().step_by(2).sum();
+                let dy: f32 = args[1..10].iter().step_by(2).sum();
+                self.curve_to(args[0], args[1], args[2], args[3], args[4], args[5])?;
+                let (x5, y5) = (self.x + args[6] + args[8], self.y + args[7] + args[9]);
+                let (dx6, dy6) = if dx.abs() > dy.abs() {
+                    (args[10], start_y - y5)
+                } else {
+                    (start_x - x5, args[10])
+                };
+                self.curve_to(args[6], args[7], args[8], args[9], dx6, dy6)?;
+            }
+            ABS => {
+                let value = self.pop()?;
+                self.push(value.abs())?
+            }
+            ADD | SUB | MUL | DIV | AND | OR | EQ => {
+                let second = self.pop()?;
+                let first = self.pop()?;
+                let result = match op {
+                    ADD => first + second,
+                    SUB => first - second,
+                    MUL => first * second,
+                    DIV if second != 0.0 => first / second,
+                    DIV => 0.0,
+                    AND => (first != 0.0 && second != 0.0) as u8 as f32,
+                    OR => (first != 0.0 || second != 0.0) as u8 as f32,
+                    _ => (first == second) as u8 as f32,
+                };
+                self.push(result)?
+            }
+            NEG | NOT | SQRT => {
+                let value = self.pop()?;
+                let result = match op {
+                    NEG => -value,
+                    NOT => (value == 0.0) as u8 as f32,
+                    _ => value.max(0.0).sqrt(),
+                };
+                self.push(result)?
+            }
+            DROP => {
+                self.pop()?;
+            }
+            PUT => {
+                let index = self.pop()? as usize;
+                let value = self.pop()?;
+                *self.transient.get_mut(index).ok_or(Error::InvalidTableData)? = value;
+            }
+            GET => {
+                let index = self.pop()? as usize;
+                let value = *self.transient.get(index).ok_or(Error::InvalidTableData)?;
+                self.push(value)?
+            }
+            IFELSE => {
+                let v2 = self.pop()?;
+                let v1 = self.pop()?;
+                let s2 = self.pop()?;
+                let s1 = self.pop()?;
+                self.push(if v1 <= v2 { s1 } else { s2 })?
+            }
+            // results of random have to be unpredictable for rendering, but the outline has to be stable
+            RANDOM => self.push(0.5)?,
+            DUP => {
+                let value = *self.stack.last().ok_or(Error::InvalidTableData)?;
+                self.push(value)?
+            }
+            EXCH => {
+                let second = self.pop()?;
+                let first = self.pop()?;
+                self.push(second)?;
+                self.push(first)?
+            }
+            INDEX => {
+                let index = self.pop()?.max(0.0) as usize;
+                let position = self.stack.len().checked_sub(index + 1).ok_or(Error::InvalidTableData)?;
+                self.push(self.stack[position])?
+            }
+            ROLL => {
+                let shift = self.pop()? as i32;
+                let count = self.pop()? as usize;
+                if count > self.stack.len() {
+                    return Err(Error::InvalidTableData);
+                }
+                if count > 0 {
+                    let start = self.stack.len() - count;
+                    let shift = shift.rem_euclid(count as i32) as usize;
+                    self.stack[start..].rotate_right(shift);
+                }
+            }
+            _ => return Err(Error::InvalidTableData),
+        }
+        Ok(())
+    }
+}
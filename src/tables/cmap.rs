@@ -0,0 +1,653 @@
+//! 'cmap' table parsing (subtable formats 0, 4, 6, 10, 12, 13 and 14) and codepoint coverage queries
+
+use crate::tables::name::{PLATFORM_UNICODE, PLATFORM_WINDOWS};
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// Windows platform encoding of Unicode BMP characters
+const ENCODING_WINDOWS_BMP: u16 = 1;
+/// Windows platform encoding of full Unicode repertoire
+const ENCODING_WINDOWS_FULL_REPERTOIRE: u16 = 10;
+
+/// Parsed 'cmap' table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CmapTable {
+    pub encoding_records: Vec<CmapEncodingRecord>,
+    // Subtables in order of their offsets; several encoding records may share the same subtable
+    pub subtables: Vec<CmapSubtable>,
+}
+
+/// Encoding record of 'cmap' table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CmapEncodingRecord {
+    pub platform_id: u16,
+    pub encoding_id: u16,
+    // Index of the subtable in `CmapTable::subtables`
+    pub subtable_index: usize,
+}
+
+/// Parsed 'cmap' subtable
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CmapSubtable {
+    // Format 0: byte encoding table
+    ByteEncoding {
+        language: u16,
+        glyph_ids: Vec<u8>,
+    },
+    // Format 4: segment mapping to delta values
+    SegmentMapping {
+        language: u16,
+        segments: Vec<CmapSegment>,
+        glyph_id_array: Vec<u16>,
+    },
+    // Format 6 and 10: trimmed table mapping
+    TrimmedTable {
+        language: u32,
+        first_code: u32,
+        glyph_ids: Vec<u16>,
+    },
+    // Format 12: segmented coverage
+    SegmentedCoverage {
+        language: u32,
+        groups: Vec<CmapGroup>,
+    },
+    // Format 13: many-to-one range mappings
+    ManyToOne {
+        language: u32,
+        groups: Vec<CmapGroup>,
+    },
+    // Format 14: Unicode variation sequences
+    VariationSequences {
+        records: Vec<VariationSelectorRecord>,
+    },
+    // Formats which aren't supported by the library (e.g. 2 or 8)
+    Unsupported {
+        format: u16,
+    },
+}
+
+/// Segment of format 4 subtable
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CmapSegment {
+    pub start_code: u16,
+    pub end_code: u16,
+    pub id_delta: i16,
+    pub id_range_offset: u16,
+}
+
+/// Sequential (format 12) or constant (format 13) map group
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CmapGroup {
+    pub start_char_code: u32,
+    pub end_char_code: u32,
+    // First glyph of the range for format 12 and glyph for all characters of the range for format 13
+    pub glyph_id: u32,
+}
+
+/// Variation selector record of format 14 subtable
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VariationSelectorRecord {
+    pub var_selector: u32,
+    // Base characters for which default glyph (from the Unicode subtable) is used
+    pub default_uvs: Vec<RangeInclusive<u32>>,
+    // Base characters which have their own glyph with this selector
+    pub non_default_uvs: Vec<(u32, u16)>,
+}
+
+impl CmapTable {
+    /// Parses raw 'cmap' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let _version = try_read_u16_be(&mut buffer)?;
+        let num_tables = try_read_u16_be(&mut buffer)?;
+
+        let mut raw_records: Vec<(u16, u16, u32)> = Vec::with_capacity(num_tables as usize);
+        for _ in 0..num_tables {
+            raw_records.push((
+                try_read_u16_be(&mut buffer)?,
+                try_read_u16_be(&mut buffer)?,
+                try_read_u32_be(&mut buffer)?,
+            ));
+        }
+
+        let mut offsets: Vec<u32> = raw_records.iter().map(|record| record.2).collect();
+        offsets.sort_unstable();
+        offsets.dedup();
+        let subtables = offsets
+            .iter()
+            .map(|&offset| parse_subtable(data, offset as usize))
+            .collect::<Result<Vec<CmapSubtable>, Error>>()?;
+        let encoding_records = raw_records
+            .into_iter()
+            .map(|(platform_id, encoding_id, offset)| CmapEncodingRecord {
+                platform_id,
+                encoding_id,
+                subtable_index: offsets.binary_search(&offset).unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(CmapTable {
+            encoding_records,
+            subtables,
+        })
+    }
+
+    /// Returns the subtable which maps Unicode characters to glyphs.
+    /// Full repertoire subtables are preferred over BMP only ones and Windows Symbol one is the last option
+    pub fn unicode_subtable(&self) -> Option<&CmapSubtable> {
+        self.unicode_encoding_record().map(|record| &self.subtables[record.subtable_index])
+    }
+
+    /// Returns encoding record of the subtable which maps Unicode characters to glyphs
+    fn unicode_encoding_record(&self) -> Option<&CmapEncodingRecord> {
+        self.encoding_records
+            .iter()
+            .filter_map(|record| {
+                let subtable = &self.subtables[record.subtable_index];
+                let full_repertoire = matches!(
+                    subtable,
+                    CmapSubtable::SegmentedCoverage { .. } | CmapSubtable::ManyToOne { .. }
+                );
+                let rank = match (record.platform_id, record.encoding_id) {
+                    (PLATFORM_WINDOWS, 10) | (PLATFORM_UNICODE, 4) | (PLATFORM_UNICODE, 6) if full_repertoire => 0,
+                    (PLATFORM_WINDOWS, 1) | (PLATFORM_UNICODE, 0..=3) => 1,
+                    (PLATFORM_WINDOWS, 0) => 2,
+                    _ => return None,
+                };
+                match subtable {
+                    CmapSubtable::VariationSequences { .. } | CmapSubtable::Unsupported { .. } => None,
+                    _ => Some((rank, record)),
+                }
+            })
+            .min_by_key(|(rank, _)| *rank)
+            .map(|(_, record)| record)
+    }
+
+    /// Returns format 14 subtable with Unicode variation sequences if the table contains it
+    pub fn variation_subtable(&self) -> Option<&CmapSubtable> {
+        self.subtables
+            .iter()
+            .find(|subtable| matches!(subtable, CmapSubtable::VariationSequences { .. }))
+    }
+
+    /// Returns true if Unicode mapping is a Windows Symbol one (characters are mapped to 0xF000..0xF0FF)
+    fn is_symbol(&self) -> bool {
+        self.unicode_encoding_record()
+            .is_some_and(|record| record.platform_id == PLATFORM_WINDOWS && record.encoding_id == 0)
+    }
+
+    /// Returns glyph ID for a character or None if the font doesn't cover it
+    pub fn glyph_for_char(&self, ch: char) -> Option<u16> {
+        let subtable = self.unicode_subtable()?;
+        let code = ch as u32;
+        let glyph_id = subtable.glyph_id(code);
+        if glyph_id.is_none() && code <= 0xFF && self.is_symbol() {
+            return subtable.glyph_id(0xF000 + code);
+        }
+        glyph_id
+    }
+
+    /// Returns glyph ID for a Unicode variation sequence (base character and variation selector).
+    /// If the font has no special glyph for the sequence the default glyph of the base character is returned
+    pub fn glyph_for_variation(&self, ch: char, selector: char) -> Option<u16> {
+        if let Some(CmapSubtable::VariationSequences { records }) = self.variation_subtable() {
+            let code = ch as u32;
+            if let Ok(index) = records.binary_search_by_key(&(selector as u32), |record| record.var_selector) {
+                let record = &records[index];
+                if let Ok(index) = record.non_default_uvs.binary_search_by_key(&code, |mapping| mapping.0) {
+                    return Some(record.non_default_uvs[index].1);
+                }
+            }
+        }
+        self.glyph_for_char(ch)
+    }
+
+    /// Iterator of covered (mapped to non-zero glyph) Unicode codepoint ranges in ascending order
+    pub fn codepoint_ranges(&self) -> impl Iterator<Item = RangeInclusive<u32>> {
+        let mut ranges: Vec<RangeInclusive<u32>> = vec![];
+        if let Some(subtable) = self.unicode_subtable() {
+            subtable.for_each_range(|range| match ranges.last_mut() {
+                Some(last) if *last.end() + 1 >= *range.start() => {
+                    *last = *last.start()..=(*last.end()).max(*range.end());
+                }
+                _ => ranges.push(range),
+            });
+        }
+        ranges.into_iter()
+    }
+
+    /// Reverse mapping: all codepoints which are mapped to each glyph
+    pub fn glyph_to_codepoints(&self) -> BTreeMap<u16, Vec<u32>> {
+        let mut result: BTreeMap<u16, Vec<u32>> = BTreeMap::new();
+        if let Some(subtable) = self.unicode_subtable() {
+            subtable.for_each_mapping(|code, glyph_id| result.entry(glyph_id).or_default().push(code));
+        }
+        result
+    }
+}
+
+impl CmapSubtable {
+    /// Returns glyph ID for a character code or None if it's not mapped (or mapped to glyph 0)
+    pub fn glyph_id(&self, code: u32) -> Option<u16> {
+        let glyph_id = match self {
+            CmapSubtable::ByteEncoding { glyph_ids, .. } => glyph_ids.get(code as usize).map(|&id| id as u16),
+            CmapSubtable::SegmentMapping { segments, glyph_id_array, .. } => {
+                if code > 0xFFFF {
+                    return None;
+                }
+                let index = segments.partition_point(|segment| (segment.end_code as u32) < code);
+                let segment = segments.get(index)?;
+                if (segment.start_code as u32) > code {
+                    return None;
+                }
+                segment_glyph_id(segments, glyph_id_array, index, code as u16)
+            }
+            CmapSubtable::TrimmedTable { first_code, glyph_ids, .. } => {
+                code.checked_sub(*first_code).and_then(|index| glyph_ids.get(index as usize)).copied()
+            }
+            CmapSubtable::SegmentedCoverage { groups, .. } | CmapSubtable::ManyToOne { groups, .. } => {
+                let index = groups.partition_point(|group| group.end_char_code < code);
+                let group = groups.get(index)?;
+                if group.start_char_code > code {
+                    return None;
+                }
+                let glyph_id = match self {
+                    CmapSubtable::SegmentedCoverage { .. } => group.glyph_id.checked_add(code - group.start_char_code)?,
+                    _ => group.glyph_id,
+                };
+                u16::try_from(glyph_id).ok()
+            }
+            CmapSubtable::VariationSequences { .. } | CmapSubtable::Unsupported { .. } => None,
+        };
+        glyph_id.filter(|&glyph_id| glyph_id != 0)
+    }
+
+    /// Calls `f` for every mapped character code and its (non-zero) glyph ID in ascending codes order
+    pub fn for_each_mapping<F: FnMut(u32, u16)>(&self, mut f: F) {
+        match self {
+            CmapSubtable::SegmentedCoverage { groups, .. } | CmapSubtable::ManyToOne { groups, .. } => {
+                for group in groups {
+                    for code in group.start_char_code..=group.end_char_code {
+                        if let Some(glyph_id) = self.glyph_id(code) {
+                            f(code, glyph_id)
+                        }
+                    }
+                }
+            }
+            _ => self.for_each_range(|range| {
+                for code in range {
+                    if let Some(glyph_id) = self.glyph_id(code) {
+                        f(code, glyph_id)
+                    }
+                }
+            }),
+        }
+    }
+
+    /// Calls `f` for every range of mapped character codes in ascending order.
+    /// Ranges may be adjacent to each other but never overlap
+    fn for_each_range<F: FnMut(RangeInclusive<u32>)>(&self, mut f: F) {
+        let mut emit_codes = |codes: &mut dyn Iterator<Item = u32>| {
+            let mut current: Option<RangeInclusive<u32>> = None;
+            for code in codes {
+                if self.glyph_id(code).is_none() {
+                    continue;
+                }
+                current = match current {
+                    Some(range) if *range.end() + 1 == code => Some(*range.start()..=code),
+                    Some(range) => {
+                        f(range);
+                        Some(code..=code)
+                    }
+                    None => Some(code..=code),
+                };
+            }
+            if let Some(range) = current {
+                f(range);
+            }
+        };
+        match self {
+            CmapSubtable::ByteEncoding { glyph_ids, .. } => emit_codes(&mut (0..glyph_ids.len() as u32)),
+            CmapSubtable::SegmentMapping { segments, .. } => emit_codes(
+                &mut segments.iter().flat_map(|segment| segment.start_code as u32..=segment.end_code as u32),
+            ),
+            CmapSubtable::TrimmedTable { first_code, glyph_ids, .. } => {
+                emit_codes(&mut (*first_code..first_code.saturating_add(glyph_ids.len() as u32)))
+            }
+            CmapSubtable::SegmentedCoverage { groups, .. } | CmapSubtable::ManyToOne { groups, .. } => {
+                for group in groups {
+                    // glyph IDs above 65535 aren't mapped: format 12 group is cut where its glyph IDs overflow,
+                    // format 13 group is skipped
+                    let Some(last_offset) = (u16::MAX as u32).checked_sub(group.glyph_id) else {
+                        continue;
+                    };
+                    let end = match self {
+                        CmapSubtable::SegmentedCoverage { .. } => {
+                            group.end_char_code.min(group.start_char_code.saturating_add(last_offset))
+                        }
+                        _ => group.end_char_code,
+                    };
+                    let mut start = group.start_char_code;
+                    // only the first character of format 12 group may be mapped to .notdef
+                    if self.glyph_id(start).is_none() {
+                        start += 1;
+                    }
+                    if start <= end && self.glyph_id(start).is_some() {
+                        f(start..=end)
+                    }
+                }
+            }
+            CmapSubtable::VariationSequences { .. } | CmapSubtable::Unsupported { .. } => {}
+        }
+    }
+}
+
+/// Calculates glyph ID for character code from format 4 segment with `index`
+fn segment_glyph_id(segments: &[CmapSegment], glyph_id_array: &[u16], index: usize, code: u16) -> Option<u16> {
+    let segment = &segments[index];
+    if segment.id_range_offset == 0 {
+        return Some(code.wrapping_add(segment.id_delta as u16));
+    }
+    // idRangeOffset is an offset from the idRangeOffset value itself to the glyphIdArray item
+    let array_index = (segment.id_range_offset / 2) as usize + (code - segment.start_code) as usize;
+    let array_index = array_index.checked_sub(segments.len() - index)?;
+    let glyph_id = *glyph_id_array.get(array_index)?;
+    if glyph_id == 0 {
+        return None;
+    }
+    Some(glyph_id.wrapping_add(segment.id_delta as u16))
+}
+
+/// Builds 'cmap' table for Unicode `mappings` (character code to glyph ID): format 4 subtable
+/// for BMP characters (Windows platform, Unicode BMP encoding) and, if there are characters
+/// outside of BMP, format 12 subtable for all of them (Windows platform, full repertoire encoding)
+pub(crate) fn build_unicode_cmap(mappings: &BTreeMap<u32, u16>) -> Vec<u8> {
+    // runs of consecutive character codes mapped to consecutive glyph IDs
+    let mut groups: Vec<CmapGroup> = vec![];
+    for (&code, &glyph_id) in mappings.iter() {
+        match groups.last_mut() {
+            Some(group)
+                if group.end_char_code + 1 == code
+                    && group.glyph_id + (code - group.start_char_code) == glyph_id as u32
+                    // format 4 segments can't cross the BMP boundary
+                    && code != 0x10000 =>
+            {
+                group.end_char_code = code
+            }
+            _ => groups.push(CmapGroup {
+                start_char_code: code,
+                end_char_code: code,
+                glyph_id: glyph_id as u32,
+            }),
+        }
+    }
+
+    let mut subtables: Vec<(u16, u16, Vec<u8>)> = vec![];
+    let bmp_groups: Vec<&CmapGroup> = groups.iter().filter(|group| group.end_char_code < 0xFFFF).collect();
+    let seg_count = bmp_groups.len() + 1;
+    let search_range = 2 * (1u16 << (seg_count as u32).ilog2());
+    let mut format4: Vec<u8> = vec![];
+    for value in [
+        4u16,
+        (16 + seg_count * 8) as u16,
+        0,
+        (seg_count * 2) as u16,
+        search_range,
+        (search_range / 2).trailing_zeros() as u16,
+        (seg_count * 2) as u16 - search_range,
+    ] {
+        format4.extend_from_slice(&value.to_be_bytes());
+    }
+    bmp_groups.iter().for_each(|group| format4.extend_from_slice(&(group.end_char_code as u16).to_be_bytes()));
+    format4.extend_from_slice(&[0xFF, 0xFF, 0, 0]);
+    bmp_groups.iter().for_each(|group| format4.extend_from_slice(&(group.start_char_code as u16).to_be_bytes()));
+    format4.extend_from_slice(&[0xFF, 0xFF]);
+    for group in bmp_groups.iter() {
+        let id_delta = (group.glyph_id as u16).wrapping_sub(group.start_char_code as u16);
+        format4.extend_from_slice(&id_delta.to_be_bytes());
+    }
+    format4.extend_from_slice(&1u16.to_be_bytes());
+    format4.resize(format4.len() + seg_count * 2, 0);
+    // too many segments for format 4 table length field, rely on format 12 subtable then
+    if format4.len() <= u16::MAX as usize {
+        subtables.push((PLATFORM_WINDOWS, ENCODING_WINDOWS_BMP, format4));
+    }
+
+    if groups.last().is_some_and(|group| group.end_char_code > 0xFFFF) || subtables.is_empty() {
+        let mut format12: Vec<u8> = vec![0, 12, 0, 0];
+        format12.extend_from_slice(&(16 + groups.len() as u32 * 12).to_be_bytes());
+        format12.extend_from_slice(&0u32.to_be_bytes());
+        format12.extend_from_slice(&(groups.len() as u32).to_be_bytes());
+        for group in groups.iter() {
+            for value in [group.start_char_code, group.end_char_code, group.glyph_id] {
+                format12.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        subtables.push((PLATFORM_WINDOWS, ENCODING_WINDOWS_FULL_REPERTOIRE, format12));
+    }
+
+    let mut data: Vec<u8> = vec![0, 0];
+    data.extend_from_slice(&(subtables.len() as u16).to_be_bytes());
+    let mut offset = 4 + 8 * subtables.len();
+    for (platform_id, encoding_id, subtable) in subtables.iter() {
+        data.extend_from_slice(&platform_id.to_be_bytes());
+        data.extend_from_slice(&encoding_id.to_be_bytes());
+        data.extend_from_slice(&(offset as u32).to_be_bytes());
+        offset += subtable.len();
+    }
+    for (_, _, subtable) in subtables {
+        data.extend_from_slice(&subtable);
+    }
+    data
+}
+
+/// Parses 'cmap' subtable which starts at `offset`
+fn parse_subtable(data: &[u8], offset: usize) -> Result<CmapSubtable, Error> {
+    let mut buffer = ByteReader::from_bytes(data);
+    buffer.set_rpos(offset);
+    let format = try_read_u16_be(&mut buffer)?;
+    match format {
+        0 => {
+            let _length = try_read_u16_be(&mut buffer)?;
+            let language = try_read_u16_be(&mut buffer)?;
+            Ok(CmapSubtable::ByteEncoding {
+                language,
+                glyph_ids: try_slice(data, offset + 6, 256)?.to_vec(),
+            })
+        }
+        4 => {
+            let length = try_read_u16_be(&mut buffer)? as usize;
+            let language = try_read_u16_be(&mut buffer)?;
+            let seg_count = (try_read_u16_be(&mut buffer)? / 2) as usize;
+            let arrays_offset = offset + 14;
+            let mut segments: Vec<CmapSegment> = Vec::with_capacity(seg_count);
+            for index in 0..seg_count {
+                buffer.set_rpos(arrays_offset + index * 2);
+                let end_code = try_read_u16_be(&mut buffer)?;
+                // skip reservedPad
+                buffer.set_rpos(arrays_offset + (seg_count + index) * 2 + 2);
+                let start_code = try_read_u16_be(&mut buffer)?;
+                buffer.set_rpos(arrays_offset + (seg_count * 2 + index) * 2 + 2);
+                let id_delta = try_read_i16_be(&mut buffer)?;
+                buffer.set_rpos(arrays_offset + (seg_count * 3 + index) * 2 + 2);
+                let id_range_offset = try_read_u16_be(&mut buffer)?;
+                segments.push(CmapSegment {
+                    start_code,
+                    end_code,
+                    id_delta,
+                    id_range_offset,
+                });
+            }
+            // some fonts have wrong length of the subtable, so glyphIdArray is limited by the table data too
+            let glyph_id_array_offset = arrays_offset + seg_count * 8 + 2;
+            let end = (offset + length).min(data.len());
+            buffer.set_rpos(glyph_id_array_offset);
+            let mut glyph_id_array: Vec<u16> = vec![];
+            while buffer.get_rpos() + 2 <= end {
+                glyph_id_array.push(try_read_u16_be(&mut buffer)?);
+            }
+            Ok(CmapSubtable::SegmentMapping {
+                language,
+                segments,
+                glyph_id_array,
+            })
+        }
+        6 => {
+            let _length = try_read_u16_be(&mut buffer)?;
+            let language = try_read_u16_be(&mut buffer)? as u32;
+            let first_code = try_read_u16_be(&mut buffer)? as u32;
+            let entry_count = try_read_u16_be(&mut buffer)?;
+            let glyph_ids = (0..entry_count)
+                .map(|_| try_read_u16_be(&mut buffer))
+                .collect::<Result<Vec<u16>, Error>>()?;
+            Ok(CmapSubtable::TrimmedTable {
+                language,
+                first_code,
+                glyph_ids,
+            })
+        }
+        10 => {
+            let _reserved = try_read_u16_be(&mut buffer)?;
+            let _length = try_read_u32_be(&mut buffer)?;
+            let language = try_read_u32_be(&mut buffer)?;
+            let first_code = try_read_u32_be(&mut buffer)?;
+            let num_chars = try_read_u32_be(&mut buffer)?;
+            try_slice(data, offset + 20, num_chars as usize * 2)?;
+            let glyph_ids = (0..num_chars)
+                .map(|_| try_read_u16_be(&mut buffer))
+                .collect::<Result<Vec<u16>, Error>>()?;
+            Ok(CmapSubtable::TrimmedTable {
+                language,
+                first_code,
+                glyph_ids,
+            })
+        }
+        12 | 13 => {
+            let _reserved = try_read_u16_be(&mut buffer)?;
+            let _length = try_read_u32_be(&mut buffer)?;
+            let language = try_read_u32_be(&mut buffer)?;
+            let num_groups = try_read_u32_be(&mut buffer)?;
+            try_slice(data, offset + 16, num_groups as usize * 12)?;
+            let mut groups: Vec<CmapGroup> = Vec::with_capacity(num_groups as usize);
+            for _ in 0..num_groups {
+                let group = CmapGroup {
+                    start_char_code: try_read_u32_be(&mut buffer)?,
+                    end_char_code: try_read_u32_be(&mut buffer)?,
+                    glyph_id: try_read_u32_be(&mut buffer)?,
+                };
+                if group.start_char_code > group.end_char_code
+                    || group.end_char_code > char::MAX as u32
+                    || groups.last().is_some_and(|last: &CmapGroup| last.end_char_code >= group.start_char_code)
+                {
+                    return Err(Error::InvalidTableData);
+                }
+                groups.push(group);
+            }
+            if format == 12 {
+                Ok(CmapSubtable::SegmentedCoverage { language, groups })
+            } else {
+                Ok(CmapSubtable::ManyToOne { language, groups })
+            }
+        }
+        14 => {
+            let _length = try_read_u32_be(&mut buffer)?;
+            let num_records = try_read_u32_be(&mut buffer)?;
+            try_slice(data, offset + 10, num_records as usize * 11)?;
+            let mut records: Vec<VariationSelectorRecord> = Vec::with_capacity(num_records as usize);
+            for index in 0..num_records as usize {
+                buffer.set_rpos(offset + 10 + index * 11);
+                let var_selector = try_read_u24_be(&mut buffer)?;
+                let default_uvs_offset = try_read_u32_be(&mut buffer)? as usize;
+                let non_default_uvs_offset = try_read_u32_be(&mut buffer)? as usize;
+
+                let mut default_uvs: Vec<RangeInclusive<u32>> = vec![];
+                if default_uvs_offset != 0 {
+                    buffer.set_rpos(offset + default_uvs_offset);
+                    let num_ranges = try_read_u32_be(&mut buffer)?;
+                    try_slice(data, offset + default_uvs_offset + 4, num_ranges as usize * 4)?;
+                    for _ in 0..num_ranges {
+                        let start = try_read_u24_be(&mut buffer)?;
+                        let additional_count = try_read_u8(&mut buffer)? as u32;
+                        default_uvs.push(start..=start + additional_count);
+                    }
+                }
+                let mut non_default_uvs: Vec<(u32, u16)> = vec![];
+                if non_default_uvs_offset != 0 {
+                    buffer.set_rpos(offset + non_default_uvs_offset);
+                    let num_mappings = try_read_u32_be(&mut buffer)?;
+                    try_slice(data, offset + non_default_uvs_offset + 4, num_mappings as usize * 5)?;
+                    for _ in 0..num_mappings {
+                        non_default_uvs.push((try_read_u24_be(&mut buffer)?, try_read_u16_be(&mut buffer)?));
+                    }
+                }
+                records.push(VariationSelectorRecord {
+                    var_selector,
+                    default_uvs,
+                    non_default_uvs,
+                });
+            }
+            Ok(CmapSubtable::VariationSequences { records })
+        }
+        _ => Ok(CmapSubtable::Unsupported { format }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::cmap_table;
+
+    #[test]
+    fn test_cmap_lookup_and_coverage() {
+        let data = cmap_table(
+            &[(0x41, 1), (0x42, 2), (0x43, 3), (0x263A, 4), (0x1F600, 5), (0x1F601, 5)],
+            &[(0xFE0F, 0x263A, Some(6)), (0xFE0E, 0x263A, None)],
+        );
+        let cmap = CmapTable::parse(&data).unwrap();
+        debug_assert!(cmap.subtables.len() == 3);
+        debug_assert!(matches!(cmap.unicode_subtable(), Some(CmapSubtable::SegmentedCoverage { .. })));
+        debug_assert!(cmap.glyph_for_char('B') == Some(2));
+        debug_assert!(cmap.glyph_for_char('😁') == Some(5));
+        debug_assert!(cmap.glyph_for_char('Z').is_none());
+        debug_assert!(cmap.glyph_for_variation('☺', '\u{FE0F}') == Some(6));
+        debug_assert!(cmap.glyph_for_variation('☺', '\u{FE0E}') == Some(4));
+        let ranges: Vec<RangeInclusive<u32>> = cmap.codepoint_ranges().collect();
+        debug_assert!(ranges == vec![0x41..=0x43, 0x263A..=0x263A, 0x1F600..=0x1F601]);
+        let reverse = cmap.glyph_to_codepoints();
+        debug_assert!(reverse[&5] == vec![0x1F600, 0x1F601] && reverse[&1] == vec![0x41]);
+    }
+
+    #[test]
+    fn test_cmap_groups_with_glyph_id_overflow() {
+        let groups = vec![
+            CmapGroup { start_char_code: 0x100, end_char_code: 0x1FF, glyph_id: 0xFFF0 },
+            CmapGroup { start_char_code: 0x300, end_char_code: 0x310, glyph_id: 0x10000 },
+        ];
+        let segmented = CmapSubtable::SegmentedCoverage { language: 0, groups: groups.clone() };
+        let many_to_one = CmapSubtable::ManyToOne { language: 0, groups };
+        for (subtable, expected) in [(segmented, vec![0x100..=0x10F]), (many_to_one, vec![0x100..=0x1FF])] {
+            let mut ranges: Vec<RangeInclusive<u32>> = vec![];
+            subtable.for_each_range(|range| ranges.push(range));
+            debug_assert!(ranges == expected);
+            let mut mapped: Vec<u32> = vec![];
+            subtable.for_each_mapping(|code, _| mapped.push(code));
+            debug_assert!(mapped == expected.into_iter().flatten().collect::<Vec<u32>>());
+        }
+    }
+
+    #[test]
+    fn test_cmap_format4_segments() {
+        let data = cmap_table(&[(0x20, 1), (0x41, 2), (0x42, 3)], &[]);
+        let cmap = CmapTable::parse(&data).unwrap();
+        let format4 = &cmap.subtables[cmap.encoding_records[1].subtable_index];
+        debug_assert!(matches!(format4, CmapSubtable::SegmentMapping { segments, .. } if segments.len() == 4));
+        debug_assert!(format4.glyph_id(0x42) == Some(3) && format4.glyph_id(0x43).is_none());
+        debug_assert!(format4.glyph_id(0xFFFF).is_none());
+        let mut mappings: Vec<(u32, u16)> = vec![];
+        format4.for_each_mapping(|code, glyph_id| mappings.push((code, glyph_id)));
+        debug_assert!(mappings == vec![(0x20, 1), (0x41, 2), (0x42, 3)]);
+    }
+}
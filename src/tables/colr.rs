@@ -0,0 +1,596 @@
+//! 'COLR' table parsing: layered color glyphs of version 0 and paint graphs of version 1.
+//!
+//! Variable paints of version 1 are parsed as their static counterparts with default values,
+//! variation deltas are not applied.
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+use std::borrow::Cow;
+
+/// Palette index which means the foreground (text) color instead of a palette entry
+pub const FOREGROUND_PALETTE_INDEX: u16 = 0xFFFF;
+
+/// Maximum nesting of paints, deeper graphs (e.g. cyclic ones) are treated as invalid
+const MAX_PAINT_DEPTH: usize = 64;
+/// Maximum number of paints and color stops parsed for a color glyph. It stops graphs which reference
+/// the same paint many times at every nesting level (e.g. both children of composite paints) from taking
+/// exponential time and gradients sharing a large color line from copying it many times
+const MAX_PAINT_NODES: usize = 65536;
+
+/// Layer of version 0 color glyph: glyph outline filled with palette entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorLayer {
+    pub glyph_id: u16,
+    pub palette_index: u16,
+}
+
+/// Gradient extend mode outside of the color line
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extend {
+    Pad,
+    Repeat,
+    Reflect,
+}
+
+/// Color stop of a gradient
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorStop {
+    pub offset: f32,
+    pub palette_index: u16,
+    // Alpha multiplier of the palette entry
+    pub alpha: f32,
+}
+
+/// Color stops of a gradient with its extend mode
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorLine {
+    pub extend: Extend,
+    pub stops: Vec<ColorStop>,
+}
+
+/// Compositing and blending mode of `Paint::Composite`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompositeMode {
+    Clear,
+    Source,
+    Destination,
+    SourceOver,
+    DestinationOver,
+    SourceIn,
+    DestinationIn,
+    SourceOut,
+    DestinationOut,
+    SourceAtop,
+    DestinationAtop,
+    Xor,
+    Plus,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Multiply,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+const COMPOSITE_MODES: [CompositeMode; 28] = [
+    CompositeMode::Clear,
+    CompositeMode::Source,
+    CompositeMode::Destination,
+    CompositeMode::SourceOver,
+    CompositeMode::DestinationOver,
+    CompositeMode::SourceIn,
+    CompositeMode::DestinationIn,
+    CompositeMode::SourceOut,
+    CompositeMode::DestinationOut,
+    CompositeMode::SourceAtop,
+    CompositeMode::DestinationAtop,
+    CompositeMode::Xor,
+    CompositeMode::Plus,
+    CompositeMode::Screen,
+    CompositeMode::Overlay,
+    CompositeMode::Darken,
+    CompositeMode::Lighten,
+    CompositeMode::ColorDodge,
+    CompositeMode::ColorBurn,
+    CompositeMode::HardLight,
+    CompositeMode::SoftLight,
+    CompositeMode::Difference,
+    CompositeMode::Exclusion,
+    CompositeMode::Multiply,
+    CompositeMode::Hue,
+    CompositeMode::Saturation,
+    CompositeMode::Color,
+    CompositeMode::Luminosity,
+];
+
+/// Node of version 1 paint graph. Coordinates are in font units, angles are in degrees counter-clockwise.
+/// `center` of scale, rotation and skew is the origin if it's not set
+#[derive(Clone, Debug, PartialEq)]
+pub enum Paint {
+    // Layers painted bottom to top
+    Layers(Vec<Paint>),
+    Solid { palette_index: u16, alpha: f32 },
+    // Gradient along the line from p0 to p1, rotated so that p0-p2 is perpendicular to the color line
+    LinearGradient { color_line: ColorLine, p0: (f32, f32), p1: (f32, f32), p2: (f32, f32) },
+    RadialGradient { color_line: ColorLine, center0: (f32, f32), radius0: f32, center1: (f32, f32), radius1: f32 },
+    SweepGradient { color_line: ColorLine, center: (f32, f32), start_angle: f32, end_angle: f32 },
+    // Fills the glyph outline with the paint
+    Glyph { glyph_id: u16, paint: Box<Paint> },
+    // Paints another color glyph of the table
+    ColorGlyph { glyph_id: u16 },
+    // Affine transform [xx, yx, xy, yy, dx, dy]
+    Transform { transform: [f32; 6], paint: Box<Paint> },
+    Translate { dx: f32, dy: f32, paint: Box<Paint> },
+    Scale { scale_x: f32, scale_y: f32, center: Option<(f32, f32)>, paint: Box<Paint> },
+    Rotate { angle: f32, center: Option<(f32, f32)>, paint: Box<Paint> },
+    Skew { x_angle: f32, y_angle: f32, center: Option<(f32, f32)>, paint: Box<Paint> },
+    Composite { source: Box<Paint>, mode: CompositeMode, backdrop: Box<Paint> },
+}
+
+/// Clip box of version 1 color glyph in font units
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClipBox {
+    pub x_min: i16,
+    pub y_min: i16,
+    pub x_max: i16,
+    pub y_max: i16,
+}
+
+/// Color glyph description
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColorGlyph {
+    // Version 0 layers painted bottom to top
+    Layers(Vec<ColorLayer>),
+    // Version 1 paint graph with optional clip box
+    Paint { paint: Paint, clip_box: Option<ClipBox> },
+}
+
+/// Parsed 'COLR' table. Base glyph records are parsed eagerly, paint graphs are parsed on request
+pub struct ColrTable<'a> {
+    data: Cow<'a, [u8]>,
+    pub version: u16,
+    // Version 0 base glyphs (glyph ID, first layer index, number of layers) sorted by glyph ID
+    base_glyphs: Vec<(u16, usize, usize)>,
+    layers: Vec<ColorLayer>,
+    // Version 1 base glyphs (glyph ID, absolute paint offset) sorted by glyph ID
+    base_glyph_paints: Vec<(u16, usize)>,
+    // Absolute offsets of version 1 layer paints
+    layer_paints: Vec<usize>,
+    // Clip boxes of glyph ID ranges
+    clips: Vec<(u16, u16, ClipBox)>,
+}
+
+impl<'a> ColrTable<'a> {
+    /// Parses raw 'COLR' table data
+    pub(crate) fn parse(data: Cow<'a, [u8]>) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(&data);
+        let version = try_read_u16_be(&mut buffer)?;
+        let num_base_glyph_records = try_read_u16_be(&mut buffer)? as usize;
+        let base_glyph_records_offset = try_read_u32_be(&mut buffer)? as usize;
+        let layer_records_offset = try_read_u32_be(&mut buffer)? as usize;
+        let num_layer_records = try_read_u16_be(&mut buffer)? as usize;
+        if version > 1 {
+            return Err(Error::InvalidTableData);
+        }
+
+        let mut base_glyphs: Vec<(u16, usize, usize)> = Vec::with_capacity(num_base_glyph_records);
+        if num_base_glyph_records > 0 {
+            let mut records = ByteReader::from_bytes(try_slice(&data, base_glyph_records_offset, num_base_glyph_records * 6)?);
+            for _ in 0..num_base_glyph_records {
+                base_glyphs.push((
+                    try_read_u16_be(&mut records)?,
+                    try_read_u16_be(&mut records)? as usize,
+                    try_read_u16_be(&mut records)? as usize,
+                ));
+            }
+        }
+        let mut layers: Vec<ColorLayer> = Vec::with_capacity(num_layer_records);
+        if num_layer_records > 0 {
+            let mut records = ByteReader::from_bytes(try_slice(&data, layer_records_offset, num_layer_records * 4)?);
+            for _ in 0..num_layer_records {
+                layers.push(ColorLayer {
+                    glyph_id: try_read_u16_be(&mut records)?,
+                    palette_index: try_read_u16_be(&mut records)?,
+                });
+            }
+        }
+        base_glyphs.sort_by_key(|record| record.0);
+
+        let mut base_glyph_paints: Vec<(u16, usize)> = vec![];
+        let mut layer_paints: Vec<usize> = vec![];
+        let mut clips: Vec<(u16, u16, ClipBox)> = vec![];
+        if version == 1 {
+            let base_glyph_list_offset = try_read_u32_be(&mut buffer)? as usize;
+            let layer_list_offset = try_read_u32_be(&mut buffer)? as usize;
+            let clip_list_offset = try_read_u32_be(&mut buffer)? as usize;
+            if base_glyph_list_offset != 0 {
+                let mut list = reader_at(&data, base_glyph_list_offset)?;
+                let count = try_read_u32_be(&mut list)? as usize;
+                let mut records = ByteReader::from_bytes(try_slice(&data, base_glyph_list_offset + 4, count * 6)?);
+                for _ in 0..count {
+                    let glyph_id = try_read_u16_be(&mut records)?;
+                    base_glyph_paints.push((glyph_id, base_glyph_list_offset + try_read_u32_be(&mut records)? as usize));
+                }
+                base_glyph_paints.sort_by_key(|record| record.0);
+            }
+            if layer_list_offset != 0 {
+                let mut list = reader_at(&data, layer_list_offset)?;
+                let count = try_read_u32_be(&mut list)? as usize;
+                let mut offsets = ByteReader::from_bytes(try_slice(&data, layer_list_offset + 4, count * 4)?);
+                for _ in 0..count {
+                    layer_paints.push(layer_list_offset + try_read_u32_be(&mut offsets)? as usize);
+                }
+            }
+            if clip_list_offset != 0 {
+                clips = parse_clip_list(&data, clip_list_offset)?;
+            }
+        }
+
+        Ok(ColrTable { data, version, base_glyphs, layers, base_glyph_paints, layer_paints, clips })
+    }
+
+    /// IDs of all color glyphs of the table sorted in ascending order
+    pub fn glyph_ids(&self) -> Vec<u16> {
+        let mut glyph_ids: Vec<u16> = self.base_glyphs.iter().map(|record| record.0)
+            .chain(self.base_glyph_paints.iter().map(|record| record.0))
+            .collect();
+        glyph_ids.sort_unstable();
+        glyph_ids.dedup();
+        glyph_ids
+    }
+
+    /// Color description of the glyph, `None` if it's not a color glyph.
+    /// Version 1 paint graph takes precedence over version 0 layers
+    pub fn color_glyph(&self, glyph_id: u16) -> Result<Option<ColorGlyph>, Error> {
+        if let Ok(index) = self.base_glyph_paints.binary_search_by_key(&glyph_id, |record| record.0) {
+            let paint = self.parse_paint(self.base_glyph_paints[index].1, 0, &mut 0)?;
+            let clip_box = self.clips.iter()
+                .find(|(start, end, _)| (*start..=*end).contains(&glyph_id))
+                .map(|(_, _, clip_box)| *clip_box);
+            return Ok(Some(ColorGlyph::Paint { paint, clip_box }));
+        }
+        match self.base_glyphs.binary_search_by_key(&glyph_id, |record| record.0) {
+            Ok(index) => {
+                let (_, first_layer, layer_count) = self.base_glyphs[index];
+                let layers = self.layers.get(first_layer..first_layer + layer_count).ok_or(Error::InvalidTableData)?;
+                Ok(Some(ColorGlyph::Layers(layers.to_vec())))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Parses paint graph at `offset`. `nodes` counts paints and color stops parsed so far, see `MAX_PAINT_NODES`
+    fn parse_paint(&self, offset: usize, depth: usize, nodes: &mut usize) -> Result<Paint, Error> {
+        *nodes += 1;
+        if depth > MAX_PAINT_DEPTH || *nodes > MAX_PAINT_NODES {
+            return Err(Error::InvalidTableData);
+        }
+        let data: &[u8] = &self.data;
+        let mut buffer = reader_at(data, offset)?;
+        let format = try_read_u8(&mut buffer)?;
+        // offsets of child paints and color lines are relative to the paint
+        let child = |buffer: &mut ByteReader, nodes: &mut usize| -> Result<Box<Paint>, Error> {
+            let child_offset = offset + try_read_u24_be(buffer)? as usize;
+            self.parse_paint(child_offset, depth + 1, nodes).map(Box::new)
+        };
+        let color_line = |buffer: &mut ByteReader, nodes: &mut usize| -> Result<ColorLine, Error> {
+            parse_color_line(data, offset + try_read_u24_be(buffer)? as usize, format % 2 == 1, nodes)
+        };
+        let read_fword = |buffer: &mut ByteReader| try_read_i16_be(buffer).map(|value| value as f32);
+        let read_f2dot14 = |buffer: &mut ByteReader| try_read_i16_be(buffer).map(f2dot14_to_f32);
+        let read_point = |buffer: &mut ByteReader| -> Result<(f32, f32), Error> {
+            Ok((read_fword(buffer)?, read_fword(buffer)?))
+        };
+        let read_angle = |buffer: &mut ByteReader| read_f2dot14(buffer).map(|value| value * 180.0);
+
+        let paint = match format {
+            1 => {
+                let num_layers = try_read_u8(&mut buffer)? as usize;
+                let first_layer = try_read_u32_be(&mut buffer)? as usize;
+                let offsets = self.layer_paints.get(first_layer..first_layer + num_layers).ok_or(Error::InvalidTableData)?;
+                let layers = offsets.iter().map(|&layer_offset| self.parse_paint(layer_offset, depth + 1, nodes));
+                Paint::Layers(layers.collect::<Result<Vec<Paint>, Error>>()?)
+            }
+            2 | 3 => Paint::Solid {
+                palette_index: try_read_u16_be(&mut buffer)?,
+                alpha: read_f2dot14(&mut buffer)?,
+            },
+            4 | 5 => Paint::LinearGradient {
+                color_line: color_line(&mut buffer, nodes)?,
+                p0: read_point(&mut buffer)?,
+                p1: read_point(&mut buffer)?,
+                p2: read_point(&mut buffer)?,
+            },
+            6 | 7 => Paint::RadialGradient {
+                color_line: color_line(&mut buffer, nodes)?,
+                center0: read_point(&mut buffer)?,
+                radius0: try_read_u16_be(&mut buffer)? as f32,
+                center1: read_point(&mut buffer)?,
+                radius1: try_read_u16_be(&mut buffer)? as f32,
+            },
+            8 | 9 => Paint::SweepGradient {
+                color_line: color_line(&mut buffer, nodes)?,
+                center: read_point(&mut buffer)?,
+                start_angle: read_angle(&mut buffer)?,
+                end_angle: read_angle(&mut buffer)?,
+            },
+            10 => {
+                let paint = child(&mut buffer, nodes)?;
+                Paint::Glyph { glyph_id: try_read_u16_be(&mut buffer)?, paint }
+            }
+            11 => Paint::ColorGlyph { glyph_id: try_read_u16_be(&mut buffer)? },
+            12 | 13 => {
+                let paint = child(&mut buffer, nodes)?;
+                let mut affine = reader_at(data, offset + try_read_u24_be(&mut buffer)? as usize)?;
+                let mut transform = [0.0f32; 6];
+                for value in transform.iter_mut() {
+                    *value = fixed_to_f32(try_read_i32_be(&mut affine)?);
+                }
+                Paint::Transform { transform, paint }
+            }
+            14 | 15 => {
+                let paint = child(&mut buffer, nodes)?;
+                Paint::Translate { dx: read_fword(&mut buffer)?, dy: read_fword(&mut buffer)?, paint }
+            }
+            16..=23 => {
+                let paint = child(&mut buffer, nodes)?;
+                // formats 20-23 have uniform scale, formats 18, 19, 22 and 23 have center
+                let scale_x = read_f2dot14(&mut buffer)?;
+                let scale_y = if format < 20 { read_f2dot14(&mut buffer)? } else { scale_x };
+                let center = if matches!(format, 18 | 19 | 22 | 23) { Some(read_point(&mut buffer)?) } else { None };
+                Paint::Scale { scale_x, scale_y, center, paint }
+            }
+            24..=27 => {
+                let paint = child(&mut buffer, nodes)?;
+                let angle = read_angle(&mut buffer)?;
+                let center = if format >= 26 { Some(read_point(&mut buffer)?) } else { None };
+                Paint::Rotate { angle, center, paint }
+            }
+            28..=31 => {
+                let paint = child(&mut buffer, nodes)?;
+                let x_angle = read_angle(&mut buffer)?;
+                let y_angle = read_angle(&mut buffer)?;
+                let center = if format >= 30 { Some(read_point(&mut buffer)?) } else { None };
+                Paint::Skew { x_angle, y_angle, center, paint }
+            }
+            32 => {
+                let source = child(&mut buffer, nodes)?;
+                let mode = *COMPOSITE_MODES.get(try_read_u8(&mut buffer)? as usize).ok_or(Error::InvalidTableData)?;
+                Paint::Composite { source, mode, backdrop: child(&mut buffer, nodes)? }
+            }
+            _ => return Err(Error::InvalidTableData),
+        };
+        Ok(paint)
+    }
+}
+
+/// Parses color line, stops of variable color line have additional variation index.
+/// Stops are counted in `nodes`, see `MAX_PAINT_NODES`
+fn parse_color_line(data: &[u8], offset: usize, is_variable: bool, nodes: &mut usize) -> Result<ColorLine, Error> {
+    let mut buffer = reader_at(data, offset)?;
+    let extend = match try_read_u8(&mut buffer)? {
+        1 => Extend::Repeat,
+        2 => Extend::Reflect,
+        // unknown modes are treated as pad
+        _ => Extend::Pad,
+    };
+    let num_stops = try_read_u16_be(&mut buffer)? as usize;
+    *nodes += num_stops;
+    if *nodes > MAX_PAINT_NODES {
+        return Err(Error::InvalidTableData);
+    }
+    let mut stops: Vec<ColorStop> = Vec::with_capacity(num_stops);
+    for _ in 0..num_stops {
+        stops.push(ColorStop {
+            offset: f2dot14_to_f32(try_read_i16_be(&mut buffer)?),
+            palette_index: try_read_u16_be(&mut buffer)?,
+            alpha: f2dot14_to_f32(try_read_i16_be(&mut buffer)?),
+        });
+        if is_variable {
+            try_read_u32_be(&mut buffer)?;
+        }
+    }
+    Ok(ColorLine { extend, stops })
+}
+
+/// Parses clip list into (start glyph ID, end glyph ID, clip box) records
+fn parse_clip_list(data: &[u8], offset: usize) -> Result<Vec<(u16, u16, ClipBox)>, Error> {
+    let mut buffer = reader_at(data, offset)?;
+    if try_read_u8(&mut buffer)? != 1 {
+        return Err(Error::InvalidTableData);
+    }
+    let num_clips = try_read_u32_be(&mut buffer)? as usize;
+    let mut clips: Vec<(u16, u16, ClipBox)> = Vec::with_capacity(num_clips.min(data.len() / 7));
+    for _ in 0..num_clips {
+        let start_glyph_id = try_read_u16_be(&mut buffer)?;
+        let end_glyph_id = try_read_u16_be(&mut buffer)?;
+        let mut clip_box = reader_at(data, offset + try_read_u24_be(&mut buffer)? as usize)?;
+        // both formats start with the same fields, format 2 adds variation index
+        try_read_u8(&mut clip_box)?;
+        clips.push((start_glyph_id, end_glyph_id, ClipBox {
+            x_min: try_read_i16_be(&mut clip_box)?,
+            y_min: try_read_i16_be(&mut clip_box)?,
+            x_max: try_read_i16_be(&mut clip_box)?,
+            y_max: try_read_i16_be(&mut clip_box)?,
+        }));
+    }
+    Ok(clips)
+}
+
+fn reader_at(data: &[u8], offset: usize) -> Result<ByteReader<'_>, Error> {
+    Ok(ByteReader::from_bytes(data.get(offset..).ok_or(Error::InvalidTableData)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Version 1 table: glyph 5 has two layers, glyph 6 composites glyph 5 with rotated foreground color.
+    /// Glyph 5 also has version 0 layers, glyph 7 has only version 0 layers
+    fn colr_v1_table() -> Vec<u8> {
+        let mut data: Vec<u8> = vec![0, 1, 0, 2, 0, 0, 0, 34, 0, 0, 0, 46, 0, 3];
+        // base glyph list, layer list and clip list offsets, no variations
+        data.extend_from_slice(&[0, 0, 0, 58, 0, 0, 0, 74, 0, 0, 0, 86, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0, 5, 0, 0, 0, 1, 0, 7, 0, 1, 0, 2]);
+        data.extend_from_slice(&[0, 1, 0, 0, 0, 2, 0, 1, 0, 3, 0, 0]);
+        data.extend_from_slice(&[0, 0, 0, 2, 0, 5, 0, 0, 0, 49, 0, 6, 0, 0, 0, 111]);
+        data.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 39, 0, 0, 0, 50]);
+        data.extend_from_slice(&[1, 0, 0, 0, 1, 0, 5, 0, 6, 0, 0, 12, 1, 0, 0, 0, 0, 0, 100, 0, 100]);
+        debug_assert!(data.len() == 107);
+        // layers 0 and 1
+        data.extend_from_slice(&[1, 2, 0, 0, 0, 0]);
+        // glyph 1 filled with solid palette entry 2
+        data.extend_from_slice(&[10, 0, 0, 6, 0, 1, 2, 0, 2, 0x40, 0]);
+        // glyph 2 filled with linear gradient and translated
+        data.extend_from_slice(&[14, 0, 0, 8, 0, 10, 0xFF, 0xEC, 10, 0, 0, 6, 0, 2]);
+        data.extend_from_slice(&[4, 0, 0, 16, 0, 0, 0, 0, 0, 100, 0, 0, 0, 0, 0, 100]);
+        data.extend_from_slice(&[0, 0, 2, 0, 0, 0, 0, 0x40, 0, 0x40, 0, 0, 1, 0x20, 0]);
+        debug_assert!(data.len() == 169);
+        // glyph 5 multiplied by foreground color rotated by 90 degrees around (50, 50)
+        data.extend_from_slice(&[32, 0, 0, 8, 23, 0, 0, 11, 11, 0, 5]);
+        data.extend_from_slice(&[26, 0, 0, 10, 0x20, 0, 0, 50, 0, 50, 2, 0xFF, 0xFF, 0x40, 0]);
+        data
+    }
+
+    #[test]
+    fn test_parse_colr_v0() {
+        let data = colr_table(&[(3, vec![(1, 0), (2, 1)]), (4, vec![(2, FOREGROUND_PALETTE_INDEX)])]);
+        let colr = ColrTable::parse(Cow::Owned(data)).unwrap();
+        debug_assert!(colr.version == 0 && colr.glyph_ids() == vec![3, 4]);
+        debug_assert!(colr.color_glyph(3).unwrap() == Some(ColorGlyph::Layers(vec![
+            ColorLayer { glyph_id: 1, palette_index: 0 },
+            ColorLayer { glyph_id: 2, palette_index: 1 },
+        ])));
+        debug_assert!(colr.color_glyph(1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_colr_v1() {
+        let colr = ColrTable::parse(Cow::Owned(colr_v1_table())).unwrap();
+        debug_assert!(colr.glyph_ids() == vec![5, 6, 7]);
+        let clip_box = Some(ClipBox { x_min: 0, y_min: 0, x_max: 100, y_max: 100 });
+        let color_line = ColorLine {
+            extend: Extend::Pad,
+            stops: vec![
+                ColorStop { offset: 0.0, palette_index: 0, alpha: 1.0 },
+                ColorStop { offset: 1.0, palette_index: 1, alpha: 0.5 },
+            ],
+        };
+        let layers = Paint::Layers(vec![
+            Paint::Glyph { glyph_id: 1, paint: Box::new(Paint::Solid { palette_index: 2, alpha: 1.0 }) },
+            Paint::Translate {
+                dx: 10.0,
+                dy: -20.0,
+                paint: Box::new(Paint::Glyph {
+                    glyph_id: 2,
+                    paint: Box::new(Paint::LinearGradient { color_line, p0: (0.0, 0.0), p1: (100.0, 0.0), p2: (0.0, 100.0) }),
+                }),
+            },
+        ]);
+        debug_assert!(colr.color_glyph(5).unwrap() == Some(ColorGlyph::Paint { paint: layers, clip_box }));
+
+        let composite = Paint::Composite {
+            source: Box::new(Paint::ColorGlyph { glyph_id: 5 }),
+            mode: CompositeMode::Multiply,
+            backdrop: Box::new(Paint::Rotate {
+                angle: 90.0,
+                center: Some((50.0, 50.0)),
+                paint: Box::new(Paint::Solid { palette_index: FOREGROUND_PALETTE_INDEX, alpha: 1.0 }),
+            }),
+        };
+        debug_assert!(colr.color_glyph(6).unwrap() == Some(ColorGlyph::Paint { paint: composite, clip_box }));
+        debug_assert!(colr.color_glyph(7).unwrap() == Some(ColorGlyph::Layers(vec![
+            ColorLayer { glyph_id: 2, palette_index: 1 },
+            ColorLayer { glyph_id: 3, palette_index: 0 },
+        ])));
+    }
+
+    #[test]
+    fn test_cyclic_paint_graph() {
+        // the layer refers to the layers paint itself
+        let mut data: Vec<u8> = vec![0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        data.extend_from_slice(&[0, 0, 0, 34, 0, 0, 0, 44, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0, 0, 0, 1, 0, 1, 0, 0, 0, 18]);
+        data.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 8, 1, 1, 0, 0, 0, 0]);
+        let colr = ColrTable::parse(Cow::Owned(data)).unwrap();
+        debug_assert!(colr.color_glyph(1) == Err(Error::InvalidTableData));
+    }
+
+    /// Version 1 table with glyph 1 painted by a chain of `count` composite paints, source and backdrop
+    /// of every composite paint are the next paint of the chain which ends with solid paint
+    fn shared_paints_colr(count: usize) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        data.extend_from_slice(&[0, 0, 0, 34, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0, 0, 0, 1, 0, 1, 0, 0, 0, 10]);
+        for _ in 0..count {
+            data.extend_from_slice(&[32, 0, 0, 8, 3, 0, 0, 8]);
+        }
+        data.extend_from_slice(&[2, 0, 1, 0x40, 0]);
+        data
+    }
+
+    #[test]
+    fn test_shared_paints() {
+        let colr = ColrTable::parse(Cow::Owned(shared_paints_colr(10))).unwrap();
+        let mut paint = match colr.color_glyph(1).unwrap() {
+            Some(ColorGlyph::Paint { paint, .. }) => paint,
+            _ => panic!("paint graph expected"),
+        };
+        for _ in 0..10 {
+            paint = match paint {
+                Paint::Composite { source, backdrop, .. } if source == backdrop => *source,
+                _ => panic!("composite paint expected"),
+            };
+        }
+        debug_assert!(paint == Paint::Solid { palette_index: 1, alpha: 1.0 });
+
+        // 2^61 paints of the graph aren't parsed
+        let colr = ColrTable::parse(Cow::Owned(shared_paints_colr(60))).unwrap();
+        debug_assert!(colr.color_glyph(1) == Err(Error::InvalidTableData));
+    }
+
+    /// Version 1 table with glyph 1 painted by a composite paint, its source and backdrop are the same
+    /// linear gradient with `num_stops` stops
+    fn shared_color_line_colr(num_stops: u16) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        data.extend_from_slice(&[0, 0, 0, 34, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0, 0, 0, 1, 0, 1, 0, 0, 0, 10]);
+        data.extend_from_slice(&[32, 0, 0, 8, 3, 0, 0, 8]);
+        data.extend_from_slice(&[4, 0, 0, 16, 0, 0, 0, 0, 0, 100, 0, 0, 0, 0, 0, 100]);
+        data.push(0);
+        data.extend_from_slice(&num_stops.to_be_bytes());
+        for _ in 0..num_stops {
+            data.extend_from_slice(&[0, 0, 0, 1, 0x40, 0]);
+        }
+        data
+    }
+
+    #[test]
+    fn test_shared_color_line() {
+        let colr = ColrTable::parse(Cow::Owned(shared_color_line_colr(30000))).unwrap();
+        match colr.color_glyph(1).unwrap() {
+            Some(ColorGlyph::Paint { paint: Paint::Composite { source, backdrop, .. }, .. }) => {
+                debug_assert!(source == backdrop);
+                debug_assert!(matches!(*source, Paint::LinearGradient { color_line, .. } if color_line.stops.len() == 30000));
+            }
+            _ => panic!("composite paint expected"),
+        }
+
+        // stops of the color line are counted for both gradients
+        let colr = ColrTable::parse(Cow::Owned(shared_color_line_colr(40000))).unwrap();
+        debug_assert!(colr.color_glyph(1) == Err(Error::InvalidTableData));
+    }
+}
@@ -0,0 +1,127 @@
+//! 'CPAL' table parsing: color palettes used by 'COLR' table
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Palette type flag: the palette is appropriate for light backgrounds
+pub const PALETTE_USABLE_WITH_LIGHT_BACKGROUND: u32 = 0x0001;
+/// Palette type flag: the palette is appropriate for dark backgrounds
+pub const PALETTE_USABLE_WITH_DARK_BACKGROUND: u32 = 0x0002;
+/// 'name' table ID value which means there is no label
+pub const NO_LABEL_NAME_ID: u16 = 0xFFFF;
+
+/// sRGB color with non-premultiplied alpha
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+/// Parsed 'CPAL' table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CpalTable {
+    pub version: u16,
+    // Colors of every palette, all palettes have the same number of entries
+    pub palettes: Vec<Vec<Color>>,
+    // Type flags of every palette, empty if the table doesn't have them
+    pub palette_types: Vec<u32>,
+    // 'name' table IDs of palette labels, empty if the table doesn't have them
+    pub palette_label_ids: Vec<u16>,
+    // 'name' table IDs of palette entry labels, empty if the table doesn't have them
+    pub palette_entry_label_ids: Vec<u16>,
+}
+
+impl CpalTable {
+    /// Parses raw 'CPAL' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let version = try_read_u16_be(&mut buffer)?;
+        let num_palette_entries = try_read_u16_be(&mut buffer)? as usize;
+        let num_palettes = try_read_u16_be(&mut buffer)? as usize;
+        let num_color_records = try_read_u16_be(&mut buffer)? as usize;
+        let color_records_offset = try_read_u32_be(&mut buffer)? as usize;
+        let mut color_record_indices: Vec<usize> = Vec::with_capacity(num_palettes);
+        for _ in 0..num_palettes {
+            color_record_indices.push(try_read_u16_be(&mut buffer)? as usize);
+        }
+        if version > 1 {
+            return Err(Error::InvalidTableData);
+        }
+
+        // color records are stored in BGRA order
+        let records = try_slice(data, color_records_offset, num_color_records * 4)?;
+        let colors: Vec<Color> = records
+            .chunks(4)
+            .map(|record| Color { red: record[2], green: record[1], blue: record[0], alpha: record[3] })
+            .collect();
+        let mut palettes: Vec<Vec<Color>> = Vec::with_capacity(num_palettes);
+        for first_index in color_record_indices {
+            let palette = colors.get(first_index..first_index + num_palette_entries).ok_or(Error::InvalidTableData)?;
+            palettes.push(palette.to_vec());
+        }
+
+        let mut cpal = CpalTable {
+            version,
+            palettes,
+            palette_types: vec![],
+            palette_label_ids: vec![],
+            palette_entry_label_ids: vec![],
+        };
+        if version == 1 {
+            let palette_types_offset = try_read_u32_be(&mut buffer)? as usize;
+            let palette_labels_offset = try_read_u32_be(&mut buffer)? as usize;
+            let palette_entry_labels_offset = try_read_u32_be(&mut buffer)? as usize;
+            if palette_types_offset != 0 {
+                let types = try_slice(data, palette_types_offset, num_palettes * 4)?;
+                cpal.palette_types = types.chunks(4).map(|value| u32::from_be_bytes([value[0], value[1], value[2], value[3]])).collect();
+            }
+            if palette_labels_offset != 0 {
+                cpal.palette_label_ids = read_u16_array(data, palette_labels_offset, num_palettes)?;
+            }
+            if palette_entry_labels_offset != 0 {
+                cpal.palette_entry_label_ids = read_u16_array(data, palette_entry_labels_offset, num_palette_entries)?;
+            }
+        }
+        Ok(cpal)
+    }
+
+    /// Color of the palette entry, `None` if there is no such palette or entry
+    pub fn color(&self, palette_index: u16, entry_index: u16) -> Option<Color> {
+        self.palettes.get(palette_index as usize)?.get(entry_index as usize).copied()
+    }
+
+    /// Index of the first palette with the type flag (see `PALETTE_USABLE_WITH_LIGHT_BACKGROUND`
+    /// and `PALETTE_USABLE_WITH_DARK_BACKGROUND`), the default palette 0 if there is no such palette
+    pub fn palette_for(&self, type_flag: u32) -> u16 {
+        self.palette_types.iter().position(|types| types & type_flag != 0).unwrap_or_default() as u16
+    }
+}
+
+fn read_u16_array(data: &[u8], offset: usize, count: usize) -> Result<Vec<u16>, Error> {
+    Ok(try_slice(data, offset, count * 2)?.chunks(2).map(|value| u16::from_be_bytes([value[0], value[1]])).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_parse_cpal() {
+        let red = Color { red: 255, green: 0, blue: 0, alpha: 255 };
+        let green = Color { red: 0, green: 128, blue: 0, alpha: 128 };
+        let data = cpal_table(&[vec![red, green], vec![green, red]], &[0, PALETTE_USABLE_WITH_DARK_BACKGROUND]);
+        let cpal = CpalTable::parse(&data).unwrap();
+        debug_assert!(cpal.version == 1 && cpal.palettes == vec![vec![red, green], vec![green, red]]);
+        debug_assert!(cpal.color(1, 1) == Some(red) && cpal.color(2, 0).is_none());
+        debug_assert!(cpal.palette_for(PALETTE_USABLE_WITH_DARK_BACKGROUND) == 1);
+        debug_assert!(cpal.palette_for(PALETTE_USABLE_WITH_LIGHT_BACKGROUND) == 0);
+        debug_assert!(cpal.palette_label_ids.is_empty());
+
+        debug_assert!(CpalTable::parse(&data[..data.len() - 4]).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+//! 'fvar' table parsing: variation axes and named instances
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Axis flag which says that the axis shouldn't be shown in user interfaces
+pub const AXIS_FLAG_HIDDEN: u16 = 0x0001;
+
+// size of VariationAxisRecord
+const AXIS_RECORD_SIZE: u16 = 20;
+
+/// Variation axis of the font
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariationAxis {
+    pub tag: u32,
+    pub min_value: f32,
+    pub default_value: f32,
+    pub max_value: f32,
+    pub flags: u16,
+    // 'name' table ID of the axis name
+    pub axis_name_id: u16,
+}
+
+impl VariationAxis {
+    /// Normalizes user coordinate to -1..1 range without 'avar' mapping.
+    /// Values outside of the axis range are clamped
+    pub fn normalize(&self, value: f32) -> f32 {
+        normalize_coordinate(value, self.min_value, self.default_value, self.max_value)
+    }
+}
+
+/// Default normalization of user coordinate to -1..1 range
+pub(crate) fn normalize_coordinate(value: f32, min_value: f32, default_value: f32, max_value: f32) -> f32 {
+    let value = value.clamp(min_value.min(default_value), max_value.max(default_value));
+    if value < default_value {
+        (value - default_value) / (default_value - min_value)
+    } else if value > default_value {
+        (value - default_value) / (max_value - default_value)
+    } else {
+        0.0
+    }
+}
+
+/// Named instance (predefined coordinates) of the font
+#[derive(Clone, Debug, PartialEq)]
+pub struct NamedInstance {
+    // 'name' table ID of the instance subfamily name
+    pub subfamily_name_id: u16,
+    pub flags: u16,
+    // User coordinate for every axis in 'fvar' axis order
+    pub coordinates: Vec<f32>,
+    // 'name' table ID of the PostScript name, present only if the record contains it
+    pub post_script_name_id: Option<u16>,
+}
+
+/// Parsed 'fvar' table
+#[derive(Clone, Debug, PartialEq)]
+pub struct FvarTable {
+    pub axes: Vec<VariationAxis>,
+    pub instances: Vec<NamedInstance>,
+}
+
+impl FvarTable {
+    /// Parses raw 'fvar' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let major_version = try_read_u16_be(&mut buffer)?;
+        let _minor_version = try_read_u16_be(&mut buffer)?;
+        let axes_array_offset = try_read_u16_be(&mut buffer)? as usize;
+        let _reserved = try_read_u16_be(&mut buffer)?;
+        let axis_count = try_read_u16_be(&mut buffer)?;
+        let axis_size = try_read_u16_be(&mut buffer)?;
+        let instance_count = try_read_u16_be(&mut buffer)?;
+        let instance_size = try_read_u16_be(&mut buffer)? as usize;
+        if major_version != 1 || axis_size != AXIS_RECORD_SIZE {
+            return Err(Error::InvalidTableData);
+        }
+
+        let mut axes: Vec<VariationAxis> = Vec::with_capacity(axis_count as usize);
+        for index in 0..axis_count as usize {
+            let mut record = ByteReader::from_bytes(try_slice(data, axes_array_offset + index * AXIS_RECORD_SIZE as usize, AXIS_RECORD_SIZE as usize)?);
+            axes.push(VariationAxis {
+                tag: try_read_u32_be(&mut record)?,
+                min_value: fixed_to_f32(try_read_i32_be(&mut record)?),
+                default_value: fixed_to_f32(try_read_i32_be(&mut record)?),
+                max_value: fixed_to_f32(try_read_i32_be(&mut record)?),
+                flags: try_read_u16_be(&mut record)?,
+                axis_name_id: try_read_u16_be(&mut record)?,
+            });
+        }
+
+        // instance record may end with optional PostScript name ID
+        let coordinates_size = axis_count as usize * 4 + 4;
+        if instance_size != coordinates_size && instance_size != coordinates_size + 2 {
+            return Err(Error::InvalidTableData);
+        }
+        let instances_offset = axes_array_offset + axis_count as usize * AXIS_RECORD_SIZE as usize;
+        let mut instances: Vec<NamedInstance> = Vec::with_capacity(instance_count as usize);
+        for index in 0..instance_count as usize {
+            let mut record = ByteReader::from_bytes(try_slice(data, instances_offset + index * instance_size, instance_size)?);
+            let subfamily_name_id = try_read_u16_be(&mut record)?;
+            let flags = try_read_u16_be(&mut record)?;
+            let coordinates = (0..axis_count)
+                .map(|_| try_read_i32_be(&mut record).map(fixed_to_f32))
+                .collect::<Result<Vec<f32>, Error>>()?;
+            let post_script_name_id = if instance_size > coordinates_size {
+                Some(try_read_u16_be(&mut record)?)
+            } else {
+                None
+            };
+            instances.push(NamedInstance { subfamily_name_id, flags, coordinates, post_script_name_id });
+        }
+        Ok(FvarTable { axes, instances })
+    }
+
+    /// Index of the axis with `tag`
+    pub fn axis_index(&self, tag: u32) -> Option<usize> {
+        self.axes.iter().position(|axis| axis.tag == tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::fvar_table;
+
+    #[test]
+    fn test_fvar() {
+        let data = fvar_table(&[(tag(b"wght"), 100.0, 400.0, 900.0, 256), (tag(b"wdth"), 75.0, 100.0, 100.0, 257)],
+                              &[(258, vec![700.0, 100.0], Some(259))]);
+        let fvar = FvarTable::parse(&data).unwrap();
+        debug_assert!(fvar.axes.len() == 2 && fvar.axis_index(tag(b"wdth")) == Some(1));
+        debug_assert!(fvar.instances[0].coordinates == vec![700.0, 100.0] && fvar.instances[0].post_script_name_id == Some(259));
+        let weight = &fvar.axes[0];
+        debug_assert!(weight.normalize(100.0) == -1.0 && weight.normalize(650.0) == 0.5 && weight.normalize(1000.0) == 1.0);
+        debug_assert!(fvar.axes[1].normalize(100.0) == 0.0);
+        debug_assert!(FvarTable::parse(&data[..data.len() - 1]) == Err(Error::InvalidTableData));
+    }
+}
@@ -0,0 +1,627 @@
+//! 'glyf' table parsing: simple and composite TrueType glyphs and their outlines
+
+use crate::outline::{GlyphOutline, GlyphPoint, PathBuilder};
+use crate::tables::loca::LocaTable;
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+use std::borrow::Cow;
+
+// Simple glyph flags
+pub(crate) const ON_CURVE_POINT: u8 = 0x01;
+pub(crate) const X_SHORT_VECTOR: u8 = 0x02;
+pub(crate) const Y_SHORT_VECTOR: u8 = 0x04;
+pub(crate) const REPEAT_FLAG: u8 = 0x08;
+pub(crate) const X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR: u8 = 0x10;
+pub(crate) const Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR: u8 = 0x20;
+pub(crate) const OVERLAP_SIMPLE: u8 = 0x40;
+
+// Composite glyph flags
+pub(crate) const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+pub(crate) const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+pub(crate) const WE_HAVE_A_SCALE: u16 = 0x0008;
+pub(crate) const MORE_COMPONENTS: u16 = 0x0020;
+pub(crate) const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+pub(crate) const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+pub(crate) const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+pub(crate) const SCALED_COMPONENT_OFFSET: u16 = 0x0800;
+pub(crate) const UNSCALED_COMPONENT_OFFSET: u16 = 0x1000;
+
+/// Maximum nesting of composite glyphs; deeper (or recursive) glyphs are treated as invalid
+pub(crate) const MAX_COMPONENT_DEPTH: usize = 32;
+/// Maximum number of components visited while resolving a composite glyph. It stops composites
+/// which reference the same glyph many times at every nesting level from taking exponential time
+pub(crate) const MAX_COMPONENT_VISITS: usize = 4096;
+/// Maximum number of points of a resolved outline, the same as the limit of 'maxp' point counts
+const MAX_OUTLINE_POINTS: usize = u16::MAX as usize;
+
+/// Parsed TrueType glyph
+#[derive(Clone, Debug, PartialEq)]
+pub enum Glyph {
+    // Glyph without outline, e.g. space
+    Empty,
+    Simple(SimpleGlyph),
+    Composite(CompositeGlyph),
+}
+
+/// Glyph made of its own contours
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimpleGlyph {
+    // x_min, y_min, x_max, y_max
+    pub bounds: [i16; 4],
+    pub end_pts_of_contours: Vec<u16>,
+    pub instructions: Vec<u8>,
+    pub points: Vec<GlyphPoint>,
+    // Set if the first flag has OVERLAP_SIMPLE bit
+    pub overlap: bool,
+}
+
+/// Glyph made of other (transformed) glyphs
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompositeGlyph {
+    // x_min, y_min, x_max, y_max
+    pub bounds: [i16; 4],
+    pub components: Vec<GlyphComponent>,
+    pub instructions: Vec<u8>,
+}
+
+/// Component of composite glyph
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlyphComponent {
+    pub flags: u16,
+    pub glyph_id: u16,
+    // X and Y offsets if ARGS_ARE_XY_VALUES flag is set, otherwise
+    // the point of the parent glyph and the point of the component which have to be matched
+    pub arg1: i32,
+    pub arg2: i32,
+    // 2x2 transform matrix [xx, yx, xy, yy]: x' = xx * x + xy * y, y' = yx * x + yy * y
+    pub transform: [f32; 4],
+}
+
+impl GlyphComponent {
+    fn transform_point(&self, point: &GlyphPoint) -> GlyphPoint {
+        let [xx, yx, xy, yy] = self.transform;
+        GlyphPoint {
+            x: xx * point.x + xy * point.y,
+            y: yx * point.x + yy * point.y,
+            on_curve: point.on_curve,
+        }
+    }
+
+    fn has_transform(&self) -> bool {
+        self.transform != [1.0, 0.0, 0.0, 1.0]
+    }
+}
+
+impl Glyph {
+    /// Parses data of one glyph from 'glyf' table
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        if data.is_empty() {
+            return Ok(Glyph::Empty);
+        }
+        let mut buffer = ByteReader::from_bytes(data);
+        let number_of_contours = try_read_i16_be(&mut buffer)?;
+        let bounds = [
+            try_read_i16_be(&mut buffer)?,
+            try_read_i16_be(&mut buffer)?,
+            try_read_i16_be(&mut buffer)?,
+            try_read_i16_be(&mut buffer)?,
+        ];
+        if number_of_contours >= 0 {
+            parse_simple_glyph(&mut buffer, data, number_of_contours as usize, bounds).map(Glyph::Simple)
+        } else {
+            parse_composite_glyph(&mut buffer, data, bounds).map(Glyph::Composite)
+        }
+    }
+
+    /// Glyph bounding box (x_min, y_min, x_max, y_max) stored in glyph header
+    pub fn bounds(&self) -> Option<[i16; 4]> {
+        match self {
+            Glyph::Empty => None,
+            Glyph::Simple(simple) => Some(simple.bounds),
+            Glyph::Composite(composite) => Some(composite.bounds),
+        }
+    }
+}
+
+impl SimpleGlyph {
+    /// Splits glyph points to contours
+    pub fn contours(&self) -> Vec<Vec<GlyphPoint>> {
+        let mut contours: Vec<Vec<GlyphPoint>> = Vec::with_capacity(self.end_pts_of_contours.len());
+        let mut start = 0usize;
+        for &end in self.end_pts_of_contours.iter() {
+            let end = end as usize + 1;
+            contours.push(self.points[start..end].to_vec());
+            start = end;
+        }
+        contours
+    }
+}
+
+impl Glyph {
+    /// Serializes the glyph to 'glyf' table data. Bounds are written as they are
+    pub(crate) fn transform_to_u8_vec(&self) -> Vec<u8> {
+        match self {
+            Glyph::Empty => vec![],
+            Glyph::Simple(simple) => simple.transform_to_u8_vec(),
+            Glyph::Composite(composite) => composite.transform_to_u8_vec(),
+        }
+    }
+}
+
+impl SimpleGlyph {
+    /// Serializes the glyph with rounded coordinates and compressed flags
+    pub(crate) fn transform_to_u8_vec(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![];
+        data.extend_from_slice(&(self.end_pts_of_contours.len() as i16).to_be_bytes());
+        self.bounds.iter().for_each(|value| data.extend_from_slice(&value.to_be_bytes()));
+        self.end_pts_of_contours.iter().for_each(|end| data.extend_from_slice(&end.to_be_bytes()));
+        data.extend_from_slice(&(self.instructions.len() as u16).to_be_bytes());
+        data.extend_from_slice(&self.instructions);
+
+        let mut flags: Vec<u8> = Vec::with_capacity(self.points.len());
+        let mut xs: Vec<u8> = vec![];
+        let mut ys: Vec<u8> = vec![];
+        let (mut last_x, mut last_y) = (0i16, 0i16);
+        for (index, point) in self.points.iter().enumerate() {
+            let (x, y) = (point.x.round() as i16, point.y.round() as i16);
+            let mut flag = if point.on_curve { ON_CURVE_POINT } else { 0 };
+            if index == 0 && self.overlap {
+                flag |= OVERLAP_SIMPLE;
+            }
+            flag |= encode_coordinate(x.wrapping_sub(last_x), X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR, &mut xs);
+            flag |= encode_coordinate(y.wrapping_sub(last_y), Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR, &mut ys);
+            flags.push(flag);
+            (last_x, last_y) = (x, y);
+        }
+
+        let mut index = 0usize;
+        while index < flags.len() {
+            let flag = flags[index];
+            let repeat_count = flags[index + 1..].iter().take(255).take_while(|&&next| next == flag).count();
+            if repeat_count > 0 {
+                data.extend_from_slice(&[flag | REPEAT_FLAG, repeat_count as u8]);
+            } else {
+                data.push(flag);
+            }
+            index += repeat_count + 1;
+        }
+        data.extend_from_slice(&xs);
+        data.extend_from_slice(&ys);
+        data
+    }
+}
+
+/// Writes coordinate delta in the shortest form and returns its flags
+fn encode_coordinate(delta: i16, short_flag: u8, same_flag: u8, data: &mut Vec<u8>) -> u8 {
+    if delta == 0 {
+        same_flag
+    } else if (-255..=255).contains(&delta) {
+        data.push(delta.unsigned_abs() as u8);
+        if delta > 0 { short_flag | same_flag } else { short_flag }
+    } else {
+        data.extend_from_slice(&delta.to_be_bytes());
+        0
+    }
+}
+
+impl CompositeGlyph {
+    /// Serializes the glyph. Argument sizes are chosen by their values, transforms are written
+    /// in the form set by the component flags
+    pub(crate) fn transform_to_u8_vec(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![];
+        data.extend_from_slice(&(-1i16).to_be_bytes());
+        self.bounds.iter().for_each(|value| data.extend_from_slice(&value.to_be_bytes()));
+        for (index, component) in self.components.iter().enumerate() {
+            let mut flags = component.flags & !(ARG_1_AND_2_ARE_WORDS | MORE_COMPONENTS | WE_HAVE_INSTRUCTIONS);
+            let is_xy = flags & ARGS_ARE_XY_VALUES != 0;
+            let fits_byte = |arg: i32| if is_xy { (-128..=127).contains(&arg) } else { (0..=255).contains(&arg) };
+            let words = !fits_byte(component.arg1) || !fits_byte(component.arg2);
+            if words {
+                flags |= ARG_1_AND_2_ARE_WORDS;
+            }
+            if index + 1 < self.components.len() {
+                flags |= MORE_COMPONENTS;
+            } else if !self.instructions.is_empty() {
+                flags |= WE_HAVE_INSTRUCTIONS;
+            }
+            data.extend_from_slice(&flags.to_be_bytes());
+            data.extend_from_slice(&component.glyph_id.to_be_bytes());
+            for arg in [component.arg1, component.arg2] {
+                if words {
+                    data.extend_from_slice(&(arg as i16).to_be_bytes());
+                } else {
+                    data.push(arg as u8);
+                }
+            }
+            let [xx, yx, xy, yy] = component.transform;
+            let transform: Vec<f32> = if flags & WE_HAVE_A_SCALE != 0 {
+                vec![xx]
+            } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                vec![xx, yy]
+            } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                vec![xx, yx, xy, yy]
+            } else {
+                vec![]
+            };
+            transform.iter().for_each(|value| data.extend_from_slice(&f32_to_f2dot14(*value).to_be_bytes()));
+        }
+        if !self.instructions.is_empty() {
+            data.extend_from_slice(&(self.instructions.len() as u16).to_be_bytes());
+            data.extend_from_slice(&self.instructions);
+        }
+        data
+    }
+}
+
+fn parse_simple_glyph(
+    buffer: &mut ByteReader,
+    data: &[u8],
+    number_of_contours: usize,
+    bounds: [i16; 4],
+) -> Result<SimpleGlyph, Error> {
+    let mut end_pts_of_contours: Vec<u16> = Vec::with_capacity(number_of_contours);
+    for _ in 0..number_of_contours {
+        let end = try_read_u16_be(buffer)?;
+        // end points have to increase
+        if end_pts_of_contours.last().is_some_and(|&last| last >= end) {
+            return Err(Error::InvalidTableData);
+        }
+        end_pts_of_contours.push(end);
+    }
+    let num_points = end_pts_of_contours.last().map(|&last| last as usize + 1).unwrap_or_default();
+
+    let instruction_length = try_read_u16_be(buffer)? as usize;
+    let instructions = try_slice(data, buffer.get_rpos(), instruction_length)?.to_vec();
+    buffer.set_rpos(buffer.get_rpos() + instruction_length);
+
+    let mut flags: Vec<u8> = Vec::with_capacity(num_points);
+    while flags.len() < num_points {
+        let flag = try_read_u8(buffer)?;
+        flags.push(flag);
+        if flag & REPEAT_FLAG != 0 {
+            let repeat_count = try_read_u8(buffer)?;
+            for _ in 0..repeat_count {
+                flags.push(flag);
+            }
+        }
+    }
+    if flags.len() > num_points {
+        return Err(Error::InvalidTableData);
+    }
+
+    let read_coordinates = |buffer: &mut ByteReader, short_flag: u8, same_flag: u8| -> Result<Vec<i16>, Error> {
+        let mut value: i16 = 0;
+        let mut coordinates: Vec<i16> = Vec::with_capacity(num_points);
+        for flag in flags.iter() {
+            if flag & short_flag != 0 {
+                let delta = try_read_u8(buffer)? as i16;
+                value = value.wrapping_add(if flag & same_flag != 0 { delta } else { -delta });
+            } else if flag & same_flag == 0 {
+                value = value.wrapping_add(try_read_i16_be(buffer)?);
+            }
+            coordinates.push(value);
+        }
+        Ok(coordinates)
+    };
+    let xs = read_coordinates(buffer, X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR)?;
+    let ys = read_coordinates(buffer, Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR)?;
+
+    let points = flags
+        .iter()
+        .zip(xs.iter().zip(ys.iter()))
+        .map(|(flag, (&x, &y))| GlyphPoint {
+            x: x as f32,
+            y: y as f32,
+            on_curve: flag & ON_CURVE_POINT != 0,
+        })
+        .collect();
+
+    Ok(SimpleGlyph {
+        bounds,
+        end_pts_of_contours,
+        instructions,
+        points,
+        overlap: flags.first().is_some_and(|flag| flag & OVERLAP_SIMPLE != 0),
+    })
+}
+
+fn parse_composite_glyph(buffer: &mut ByteReader, data: &[u8], bounds: [i16; 4]) -> Result<CompositeGlyph, Error> {
+    let mut components: Vec<GlyphComponent> = vec![];
+    let mut has_instructions = false;
+    loop {
+        let flags = try_read_u16_be(buffer)?;
+        let glyph_id = try_read_u16_be(buffer)?;
+        let (arg1, arg2) = match (flags & ARG_1_AND_2_ARE_WORDS != 0, flags & ARGS_ARE_XY_VALUES != 0) {
+            (true, true) => (try_read_i16_be(buffer)? as i32, try_read_i16_be(buffer)? as i32),
+            (true, false) => (try_read_u16_be(buffer)? as i32, try_read_u16_be(buffer)? as i32),
+            (false, true) => (try_read_i8(buffer)? as i32, try_read_i8(buffer)? as i32),
+            (false, false) => (try_read_u8(buffer)? as i32, try_read_u8(buffer)? as i32),
+        };
+        let mut read_f2dot14 = || try_read_i16_be(buffer).map(f2dot14_to_f32);
+        let transform = if flags & WE_HAVE_A_SCALE != 0 {
+            let scale = read_f2dot14()?;
+            [scale, 0.0, 0.0, scale]
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            let x_scale = read_f2dot14()?;
+            let y_scale = read_f2dot14()?;
+            [x_scale, 0.0, 0.0, y_scale]
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            [read_f2dot14()?, read_f2dot14()?, read_f2dot14()?, read_f2dot14()?]
+        } else {
+            [1.0, 0.0, 0.0, 1.0]
+        };
+        has_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+        components.push(GlyphComponent {
+            flags,
+            glyph_id,
+            arg1,
+            arg2,
+            transform,
+        });
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+    let mut instructions: Vec<u8> = vec![];
+    if has_instructions {
+        let instruction_length = try_read_u16_be(buffer)? as usize;
+        instructions = try_slice(data, buffer.get_rpos(), instruction_length)?.to_vec();
+    }
+    Ok(CompositeGlyph {
+        bounds,
+        components,
+        instructions,
+    })
+}
+
+/// Replaces glyph IDs of components in raw composite glyph data using `map`.
+/// Data of simple and empty glyphs is left untouched
+pub(crate) fn remap_component_glyph_ids<F: Fn(u16) -> u16>(data: &mut [u8], map: F) -> Result<(), Error> {
+    if data.is_empty() || i16::from_be_bytes(try_slice(data, 0, 2)?.try_into().unwrap()) >= 0 {
+        return Ok(());
+    }
+    // skip number of contours and bounds
+    let mut offset = 10usize;
+    loop {
+        let flags = u16::from_be_bytes(try_slice(data, offset, 2)?.try_into().unwrap());
+        let glyph_id = u16::from_be_bytes(try_slice(data, offset + 2, 2)?.try_into().unwrap());
+        data[offset + 2..offset + 4].copy_from_slice(&map(glyph_id).to_be_bytes());
+        offset += 4 + if flags & ARG_1_AND_2_ARE_WORDS != 0 { 4 } else { 2 };
+        if flags & WE_HAVE_A_SCALE != 0 {
+            offset += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            offset += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            offset += 8;
+        }
+        if flags & MORE_COMPONENTS == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// Builds 'glyf' and 'loca' tables from glyphs data. Returns tables data and 'loca' format
+/// (short one is used when it's possible)
+pub(crate) fn build_glyf_and_loca(glyphs: &[Vec<u8>]) -> (Vec<u8>, Vec<u8>, i16) {
+    let mut glyf: Vec<u8> = vec![];
+    let mut offsets: Vec<u32> = Vec::with_capacity(glyphs.len() + 1);
+    for data in glyphs {
+        offsets.push(glyf.len() as u32);
+        glyf.extend_from_slice(data);
+        // glyphs are aligned to 4 bytes, short offsets need at least 2 bytes alignment
+        glyf.resize(calculate_padded_len(glyf.len() as u32) as usize, 0);
+    }
+    offsets.push(glyf.len() as u32);
+
+    let mut loca: Vec<u8> = vec![];
+    if glyf.len() / 2 <= u16::MAX as usize {
+        offsets.iter().for_each(|offset| loca.extend_from_slice(&((offset / 2) as u16).to_be_bytes()));
+        (glyf, loca, 0)
+    } else {
+        offsets.iter().for_each(|offset| loca.extend_from_slice(&offset.to_be_bytes()));
+        (glyf, loca, 1)
+    }
+}
+
+/// TrueType glyphs of the font: 'glyf' table data with glyph offsets from 'loca' table
+pub struct TrueTypeGlyphs<'a> {
+    glyf: Cow<'a, [u8]>,
+    loca: LocaTable,
+}
+
+impl<'a> TrueTypeGlyphs<'a> {
+    pub(crate) fn new(glyf: Cow<'a, [u8]>, loca: LocaTable) -> Self {
+        TrueTypeGlyphs { glyf, loca }
+    }
+
+    /// Number of glyphs in the font
+    pub fn num_glyphs(&self) -> u16 {
+        (self.loca.offsets.len().max(1) - 1) as u16
+    }
+
+    /// Raw data of the glyph
+    pub fn glyph_data(&self, glyph_id: u16) -> Result<&[u8], Error> {
+        let range = self.loca.glyph_range(glyph_id).ok_or(Error::InvalidTableData)?;
+        try_slice(&self.glyf, range.start, range.len())
+    }
+
+    /// Parses the glyph
+    pub fn glyph(&self, glyph_id: u16) -> Result<Glyph, Error> {
+        Glyph::parse(self.glyph_data(glyph_id)?)
+    }
+
+    /// Returns glyph outline with all composite glyph components resolved and transformed
+    pub fn outline(&self, glyph_id: u16) -> Result<GlyphOutline, Error> {
+        let mut points: Vec<GlyphPoint> = vec![];
+        let mut end_points: Vec<usize> = vec![];
+        let mut visits = 0usize;
+        self.collect_points(glyph_id, 0, &mut visits, &mut points, &mut end_points)?;
+        let mut contours: Vec<Vec<GlyphPoint>> = Vec::with_capacity(end_points.len());
+        let mut start = 0usize;
+        for end in end_points {
+            contours.push(points[start..end].to_vec());
+            start = end;
+        }
+        Ok(GlyphOutline { contours })
+    }
+
+    /// Emits glyph outline to the path builder
+    pub fn draw<B: PathBuilder + ?Sized>(&self, glyph_id: u16, builder: &mut B) -> Result<(), Error> {
+        self.outline(glyph_id)?.draw(builder);
+        Ok(())
+    }
+
+    /// Appends points of the glyph to `points` and ends of its contours (exclusive) to `end_points`.
+    /// `visits` counts glyphs resolved so far, see `MAX_COMPONENT_VISITS`
+    fn collect_points(
+        &self,
+        glyph_id: u16,
+        depth: usize,
+        visits: &mut usize,
+        points: &mut Vec<GlyphPoint>,
+        end_points: &mut Vec<usize>,
+    ) -> Result<(), Error> {
+        *visits += 1;
+        if depth > MAX_COMPONENT_DEPTH || *visits > MAX_COMPONENT_VISITS {
+            return Err(Error::InvalidTableData);
+        }
+        match self.glyph(glyph_id)? {
+            Glyph::Empty => {}
+            Glyph::Simple(simple) => {
+                let base = points.len();
+                points.extend_from_slice(&simple.points);
+                end_points.extend(simple.end_pts_of_contours.iter().map(|&end| base + end as usize + 1));
+            }
+            Glyph::Composite(composite) => {
+                for component in composite.components.iter() {
+                    let mut component_points: Vec<GlyphPoint> = vec![];
+                    let mut component_end_points: Vec<usize> = vec![];
+                    self.collect_points(component.glyph_id, depth + 1, visits, &mut component_points, &mut component_end_points)?;
+                    if points.len() + component_points.len() > MAX_OUTLINE_POINTS {
+                        return Err(Error::InvalidTableData);
+                    }
+                    for point in component_points.iter_mut() {
+                        *point = component.transform_point(point);
+                    }
+                    let (dx, dy) = component_offset(component, points, &component_points)?;
+                    let base = points.len();
+                    points.extend(component_points.iter().map(|point| GlyphPoint {
+                        x: point.x + dx,
+                        y: point.y + dy,
+                        on_curve: point.on_curve,
+                    }));
+                    end_points.extend(component_end_points.iter().map(|end| base + end));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Calculates offset of already transformed component points
+fn component_offset(
+    component: &GlyphComponent,
+    parent_points: &[GlyphPoint],
+    component_points: &[GlyphPoint],
+) -> Result<(f32, f32), Error> {
+    if component.flags & ARGS_ARE_XY_VALUES == 0 {
+        // align point of the parent glyph with point of the component
+        let parent = parent_points.get(component.arg1 as usize).ok_or(Error::InvalidTableData)?;
+        let child = component_points.get(component.arg2 as usize).ok_or(Error::InvalidTableData)?;
+        return Ok((parent.x - child.x, parent.y - child.y));
+    }
+    let offset = GlyphPoint {
+        x: component.arg1 as f32,
+        y: component.arg2 as f32,
+        on_curve: true,
+    };
+    let scaled = component.flags & SCALED_COMPONENT_OFFSET != 0 && component.flags & UNSCALED_COMPONENT_OFFSET == 0;
+    if scaled && component.has_transform() {
+        let offset = component.transform_point(&offset);
+        return Ok((offset.x, offset.y));
+    }
+    Ok((offset.x, offset.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outline::SvgPathBuilder;
+    use crate::test_utils::*;
+    use crate::{FontTables, WoffFont};
+
+    #[test]
+    fn test_simple_and_composite_outlines() {
+        let woff = build_woff(0x00010000, &truetype_font_tables());
+        let font = WoffFont::new(&woff).unwrap();
+        let glyphs = font.truetype_glyphs().unwrap();
+        debug_assert!(glyphs.num_glyphs() == 5);
+        debug_assert!(glyphs.glyph(3).unwrap() == Glyph::Empty);
+
+        let mut svg = SvgPathBuilder::new();
+        glyphs.draw(2, &mut svg).unwrap();
+        debug_assert!(svg.finish() == "M0 0 Q200 -400 400 0 Z");
+
+        let composite = glyphs.outline(4).unwrap();
+        debug_assert!(composite.contours.len() == 2);
+        debug_assert!(composite.contours[1][1] == GlyphPoint { x: 700.0, y: 200.0, on_curve: false });
+        debug_assert!(composite.bounds() == Some((100.0, 0.0, 800.0, 500.0)));
+    }
+
+    #[test]
+    fn test_outline_of_offcurve_only_contour() {
+        let outline = GlyphOutline {
+            contours: vec![vec![
+                GlyphPoint { x: 0.0, y: 0.0, on_curve: false },
+                GlyphPoint { x: 100.0, y: 0.0, on_curve: false },
+            ]],
+        };
+        let mut svg = SvgPathBuilder::new();
+        outline.draw(&mut svg);
+        debug_assert!(svg.finish() == "M50 0 Q0 0 50 0 Q100 0 50 0 Z");
+    }
+
+    #[test]
+    fn test_glyph_serialization() {
+        let tables = truetype_font_tables();
+        let woff = build_woff(0x00010000, &tables);
+        let font = WoffFont::new(&woff).unwrap();
+        let glyphs = font.truetype_glyphs().unwrap();
+        for glyph_id in 0..glyphs.num_glyphs() {
+            let glyph = glyphs.glyph(glyph_id).unwrap();
+            // arguments of composite glyph components may be stored in the shorter form
+            let data = glyph.transform_to_u8_vec();
+            let parsed = Glyph::parse(&data).unwrap();
+            debug_assert!(parsed.bounds() == glyph.bounds() && parsed.transform_to_u8_vec() == data);
+        }
+        let mut glyph = match glyphs.glyph(0).unwrap() {
+            Glyph::Simple(simple) => simple,
+            _ => unreachable!(),
+        };
+        glyph.points[1].x = -1000.0;
+        glyph.points[2].y = 1.4;
+        debug_assert!(Glyph::parse(&glyph.transform_to_u8_vec()).unwrap() == Glyph::Simple(SimpleGlyph {
+            points: vec![glyph.points[0], glyph.points[1], GlyphPoint { x: 500.0, y: 1.0, on_curve: true }, glyph.points[3]],
+            ..glyph.clone()
+        }));
+    }
+
+    #[test]
+    fn test_recursive_composite_glyph() {
+        let (glyf, loca) = glyf_and_loca(&[composite_glyph([0, 0, 0, 0], &[(0, 0, 0, None)])]);
+        let glyphs = TrueTypeGlyphs::new(Cow::Owned(glyf), LocaTable::parse(&loca, 1, 1).unwrap());
+        debug_assert!(glyphs.outline(0) == Err(Error::InvalidTableData));
+    }
+
+    #[test]
+    fn test_composite_glyph_fan_out() {
+        // every composite references the next glyph twice, so resolving glyph 0 visits 2^30 components
+        let mut data: Vec<Vec<u8>> = (1..31).map(|next| composite_glyph([0, 0, 0, 0], &[(next, 0, 0, None), (next, 0, 0, None)])).collect();
+        data.push(simple_glyph(&[&[(0, 0, true), (0, 10, true), (10, 0, true)]]));
+        let (glyf, loca) = glyf_and_loca(&data);
+        let glyphs = TrueTypeGlyphs::new(Cow::Owned(glyf), LocaTable::parse(&loca, 1, 31).unwrap());
+        debug_assert!(glyphs.outline(0) == Err(Error::InvalidTableData));
+        debug_assert!(glyphs.outline(25).unwrap().contours.len() == 32);
+    }
+}
@@ -0,0 +1,137 @@
+//! 'GPOS' lookup subtables: pair adjustment
+
+use crate::tables::layout::{coverage_index, glyph_class, reader_at};
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Lookup type of pair adjustment in 'GPOS' table
+pub const PAIR_ADJUSTMENT: u16 = 2;
+
+// Value format flags
+const X_PLACEMENT: u16 = 0x0001;
+const Y_PLACEMENT: u16 = 0x0002;
+const X_ADVANCE: u16 = 0x0004;
+const Y_ADVANCE: u16 = 0x0008;
+// Device and variation table offsets which follow the values
+const DEVICE_OFFSETS: u16 = 0x00F0;
+
+/// Positioning adjustment of a glyph in font units
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ValueRecord {
+    pub x_placement: i16,
+    pub y_placement: i16,
+    pub x_advance: i16,
+    pub y_advance: i16,
+}
+
+impl ValueRecord {
+    /// Reads value record of the format, device tables are ignored
+    fn read(buffer: &mut ByteReader, value_format: u16) -> Result<Self, Error> {
+        let mut value = ValueRecord::default();
+        let fields = [
+            (X_PLACEMENT, &mut value.x_placement),
+            (Y_PLACEMENT, &mut value.y_placement),
+            (X_ADVANCE, &mut value.x_advance),
+            (Y_ADVANCE, &mut value.y_advance),
+        ];
+        for (flag, field) in fields {
+            if value_format & flag != 0 {
+                *field = try_read_i16_be(buffer)?;
+            }
+        }
+        for _ in 0..(value_format & DEVICE_OFFSETS).count_ones() {
+            try_read_u16_be(buffer)?;
+        }
+        Ok(value)
+    }
+}
+
+/// Size of value record of the format in bytes
+fn value_record_size(value_format: u16) -> usize {
+    value_format.count_ones() as usize * 2
+}
+
+/// Adjustments of the glyph pair from pair adjustment subtable: (first glyph value, second glyph value,
+/// the second glyph has value). `None` if the subtable has no such pair
+pub(crate) fn pair_adjustment(subtable: &[u8], first: u16, second: u16) -> Result<Option<(ValueRecord, ValueRecord, bool)>, Error> {
+    let mut buffer = reader_at(subtable, 0)?;
+    let format = try_read_u16_be(&mut buffer)?;
+    let coverage_offset = try_read_u16_be(&mut buffer)? as usize;
+    let value_format1 = try_read_u16_be(&mut buffer)?;
+    let value_format2 = try_read_u16_be(&mut buffer)?;
+    let coverage = match coverage_index(subtable, coverage_offset, first)? {
+        Some(coverage) => coverage as usize,
+        None => return Ok(None),
+    };
+    let record_size = value_record_size(value_format1) + value_record_size(value_format2);
+    let has_second_value = value_format2 != 0;
+
+    let mut record = match format {
+        1 => {
+            let pair_set_count = try_read_u16_be(&mut buffer)? as usize;
+            if coverage >= pair_set_count {
+                return Err(Error::InvalidTableData);
+            }
+            let mut offset = reader_at(subtable, 10 + coverage * 2)?;
+            let pair_set_offset = try_read_u16_be(&mut offset)? as usize;
+            let mut pair_set = reader_at(subtable, pair_set_offset)?;
+            let pair_value_count = try_read_u16_be(&mut pair_set)? as usize;
+            // pair value records are sorted by the second glyph
+            let records = try_slice(subtable, pair_set_offset + 2, pair_value_count * (2 + record_size))?;
+            let position = (0..pair_value_count).find(|index| {
+                let record = &records[index * (2 + record_size)..];
+                u16::from_be_bytes([record[0], record[1]]) == second
+            });
+            match position {
+                Some(index) => reader_at(records, index * (2 + record_size) + 2)?,
+                None => return Ok(None),
+            }
+        }
+        2 => {
+            let class_def1_offset = try_read_u16_be(&mut buffer)? as usize;
+            let class_def2_offset = try_read_u16_be(&mut buffer)? as usize;
+            let class1_count = try_read_u16_be(&mut buffer)? as usize;
+            let class2_count = try_read_u16_be(&mut buffer)? as usize;
+            let class1 = glyph_class(subtable, class_def1_offset, first)? as usize;
+            let class2 = glyph_class(subtable, class_def2_offset, second)? as usize;
+            // classes out of the matrix have no adjustment, the subtable is skipped like HarfBuzz does
+            if class1 >= class1_count || class2 >= class2_count {
+                return Ok(None);
+            }
+            let offset = 16 + (class1 * class2_count + class2) * record_size;
+            ByteReader::from_bytes(try_slice(subtable, offset, record_size)?)
+        }
+        _ => return Err(Error::InvalidTableData),
+    };
+    let first_value = ValueRecord::read(&mut record, value_format1)?;
+    let second_value = ValueRecord::read(&mut record, value_format2)?;
+    Ok(Some((first_value, second_value, has_second_value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_pair_adjustment() {
+        let kerning = |x_advance: i16| ValueRecord { x_advance, ..ValueRecord::default() };
+        let pairs = pair_adjustment_subtable(&[(1, 2, -50), (1, 3, 20), (4, 2, -10)]);
+        debug_assert!(pair_adjustment(&pairs, 1, 3).unwrap() == Some((kerning(20), ValueRecord::default(), false)));
+        debug_assert!(pair_adjustment(&pairs, 4, 2).unwrap() == Some((kerning(-10), ValueRecord::default(), false)));
+        debug_assert!(pair_adjustment(&pairs, 4, 3).unwrap().is_none());
+        debug_assert!(pair_adjustment(&pairs, 2, 1).unwrap().is_none());
+
+        let classes = class_pair_adjustment_subtable(&[(1, 0), (2, 1)], &[(3, 1), (4, 1)], &[vec![0, 0], vec![0, -40]]);
+        debug_assert!(pair_adjustment(&classes, 2, 4).unwrap() == Some((kerning(-40), ValueRecord::default(), false)));
+        debug_assert!(pair_adjustment(&classes, 1, 5).unwrap() == Some((kerning(0), ValueRecord::default(), false)));
+        debug_assert!(pair_adjustment(&classes, 3, 4).unwrap().is_none());
+
+        // classes out of the matrix are skipped
+        let classes = class_pair_adjustment_subtable(&[(1, 0), (2, 3)], &[(3, 1), (4, 2)], &[vec![0, -40], vec![0, 0]]);
+        debug_assert!(pair_adjustment(&classes, 1, 3).unwrap() == Some((kerning(-40), ValueRecord::default(), false)));
+        debug_assert!(pair_adjustment(&classes, 1, 4).unwrap().is_none());
+        debug_assert!(pair_adjustment(&classes, 2, 3).unwrap().is_none());
+    }
+}
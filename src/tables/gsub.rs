@@ -0,0 +1,92 @@
+//! 'GSUB' lookup subtables: single and ligature substitution
+
+use crate::tables::layout::{coverage_index, reader_at, read_u16_array};
+use crate::utils::*;
+use crate::Error;
+
+/// Lookup types of 'GSUB' table
+pub const SINGLE_SUBSTITUTION: u16 = 1;
+pub const LIGATURE_SUBSTITUTION: u16 = 4;
+
+/// Substitute of the glyph from single substitution subtable, `None` if the glyph isn't covered
+pub(crate) fn single_substitute(subtable: &[u8], glyph_id: u16) -> Result<Option<u16>, Error> {
+    let mut buffer = reader_at(subtable, 0)?;
+    let format = try_read_u16_be(&mut buffer)?;
+    let coverage_offset = try_read_u16_be(&mut buffer)? as usize;
+    let coverage = match coverage_index(subtable, coverage_offset, glyph_id)? {
+        Some(coverage) => coverage,
+        None => return Ok(None),
+    };
+    match format {
+        1 => Ok(Some(glyph_id.wrapping_add(try_read_i16_be(&mut buffer)? as u16))),
+        2 => {
+            let substitutes = read_u16_array(&mut buffer)?;
+            substitutes.get(coverage as usize).copied().map(Some).ok_or(Error::InvalidTableData)
+        }
+        _ => Err(Error::InvalidTableData),
+    }
+}
+
+/// Ligature formed by the first glyphs of `glyphs` from ligature substitution subtable:
+/// (ligature glyph ID, number of components). The first ligature of the set which matches is used
+pub(crate) fn ligature_substitute(subtable: &[u8], glyphs: &[u16]) -> Result<Option<(u16, usize)>, Error> {
+    let first_glyph_id = match glyphs.first() {
+        Some(glyph_id) => *glyph_id,
+        None => return Ok(None),
+    };
+    let mut buffer = reader_at(subtable, 0)?;
+    if try_read_u16_be(&mut buffer)? != 1 {
+        return Err(Error::InvalidTableData);
+    }
+    let coverage_offset = try_read_u16_be(&mut buffer)? as usize;
+    let coverage = match coverage_index(subtable, coverage_offset, first_glyph_id)? {
+        Some(coverage) => coverage as usize,
+        None => return Ok(None),
+    };
+    let ligature_set_offsets = read_u16_array(&mut buffer)?;
+    let ligature_set_offset = *ligature_set_offsets.get(coverage).ok_or(Error::InvalidTableData)? as usize;
+    let mut ligature_set = reader_at(subtable, ligature_set_offset)?;
+    for ligature_offset in read_u16_array(&mut ligature_set)? {
+        let mut ligature = reader_at(subtable, ligature_set_offset + ligature_offset as usize)?;
+        let ligature_glyph_id = try_read_u16_be(&mut ligature)?;
+        let component_count = try_read_u16_be(&mut ligature)? as usize;
+        if component_count == 0 || component_count > glyphs.len() {
+            continue;
+        }
+        let mut matches = true;
+        for &glyph_id in glyphs[1..component_count].iter() {
+            if try_read_u16_be(&mut ligature)? != glyph_id {
+                matches = false;
+                break;
+            }
+        }
+        if matches {
+            return Ok(Some((ligature_glyph_id, component_count)));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_substitutions() {
+        let single = single_substitution_subtable(&[(1, 5), (3, 6)]);
+        debug_assert!(single_substitute(&single, 3).unwrap() == Some(6));
+        debug_assert!(single_substitute(&single, 2).unwrap().is_none());
+        // format 1 with glyph ID delta
+        let delta = [vec![0, 1, 0, 6, 0, 10], coverage_format2(&[(2, 4)])].concat();
+        debug_assert!(single_substitute(&delta, 4).unwrap() == Some(14));
+        debug_assert!(single_substitute(&delta, 5).unwrap().is_none());
+
+        let ligatures = ligature_substitution_subtable(&[(&[1, 2, 3], 10), (&[1, 2], 11), (&[2, 2], 12)]);
+        debug_assert!(ligature_substitute(&ligatures, &[1, 2, 3, 4]).unwrap() == Some((10, 3)));
+        debug_assert!(ligature_substitute(&ligatures, &[1, 2, 4]).unwrap() == Some((11, 2)));
+        debug_assert!(ligature_substitute(&ligatures, &[2, 2]).unwrap() == Some((12, 2)));
+        debug_assert!(ligature_substitute(&ligatures, &[1]).unwrap().is_none());
+        debug_assert!(ligature_substitute(&ligatures, &[3, 2]).unwrap().is_none());
+    }
+}
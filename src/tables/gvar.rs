@@ -0,0 +1,70 @@
+//! 'gvar' table parsing: glyph variation data
+
+use crate::tables::tuple_variations::{parse_tuple_variations, TupleVariation};
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+// flags bit which says that glyph variation data offsets are 32-bit
+const LONG_OFFSETS: u16 = 0x0001;
+
+/// Number of phantom points (left side bearing, advance, top and bottom) which follow glyph points
+pub(crate) const PHANTOM_POINT_COUNT: usize = 4;
+
+/// Parsed 'gvar' table
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GvarTable {
+    pub(crate) axis_count: u16,
+    pub(crate) shared_tuples: Vec<Vec<f32>>,
+    // GlyphVariationData of every glyph, empty for glyphs without variations
+    glyph_variation_data: Vec<Vec<u8>>,
+}
+
+impl GvarTable {
+    /// Parses raw 'gvar' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let major_version = try_read_u16_be(&mut buffer)?;
+        let _minor_version = try_read_u16_be(&mut buffer)?;
+        let axis_count = try_read_u16_be(&mut buffer)?;
+        let shared_tuple_count = try_read_u16_be(&mut buffer)?;
+        let shared_tuples_offset = try_read_u32_be(&mut buffer)? as usize;
+        let glyph_count = try_read_u16_be(&mut buffer)?;
+        let flags = try_read_u16_be(&mut buffer)?;
+        let glyph_variation_data_array_offset = try_read_u32_be(&mut buffer)? as usize;
+        if major_version != 1 {
+            return Err(Error::InvalidTableData);
+        }
+        let offsets = (0..=glyph_count)
+            .map(|_| if flags & LONG_OFFSETS != 0 {
+                try_read_u32_be(&mut buffer).map(|offset| offset as usize)
+            } else {
+                try_read_u16_be(&mut buffer).map(|offset| offset as usize * 2)
+            })
+            .collect::<Result<Vec<usize>, Error>>()?;
+
+        let mut shared = ByteReader::from_bytes(data.get(shared_tuples_offset..).ok_or(Error::InvalidTableData)?);
+        let shared_tuples = (0..shared_tuple_count)
+            .map(|_| (0..axis_count).map(|_| try_read_i16_be(&mut shared).map(f2dot14_to_f32)).collect())
+            .collect::<Result<Vec<Vec<f32>>, Error>>()?;
+
+        let glyph_variation_data = offsets
+            .windows(2)
+            .map(|range| {
+                let length = range[1].checked_sub(range[0]).ok_or(Error::InvalidTableData)?;
+                try_slice(data, glyph_variation_data_array_offset + range[0], length).map(<[u8]>::to_vec)
+            })
+            .collect::<Result<Vec<Vec<u8>>, Error>>()?;
+        Ok(GvarTable { axis_count, shared_tuples, glyph_variation_data })
+    }
+
+    /// Tuple variations of the glyph. `point_count` is the number of glyph points including phantom points
+    pub(crate) fn glyph_variations(&self, glyph_id: u16, point_count: usize) -> Result<Vec<TupleVariation>, Error> {
+        match self.glyph_variation_data.get(glyph_id as usize) {
+            Some(data) if !data.is_empty() => {
+                parse_tuple_variations(data, 0, self.axis_count, &self.shared_tuples, point_count, true)
+            }
+            _ => Ok(vec![]),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! 'hhea' table parsing
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Parsed 'hhea' table (36 bytes length)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HheaTable {
+    pub major_version: u16,
+    pub minor_version: u16,
+    // Typographic ascent, descent and line gap
+    pub ascender: i16,
+    pub descender: i16,
+    pub line_gap: i16,
+    // Maximum advance width value in 'hmtx' table
+    pub advance_width_max: u16,
+    pub min_left_side_bearing: i16,
+    pub min_right_side_bearing: i16,
+    // Max(lsb + (xMax - xMin))
+    pub x_max_extent: i16,
+    pub caret_slope_rise: i16,
+    pub caret_slope_run: i16,
+    pub caret_offset: i16,
+    // 0 for current format
+    pub metric_data_format: i16,
+    // Number of hMetric entries in 'hmtx' table
+    pub number_of_h_metrics: u16,
+}
+
+impl HheaTable {
+    /// Parses raw 'hhea' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let mut hhea = HheaTable {
+            major_version: try_read_u16_be(&mut buffer)?,
+            minor_version: try_read_u16_be(&mut buffer)?,
+            ascender: try_read_i16_be(&mut buffer)?,
+            descender: try_read_i16_be(&mut buffer)?,
+            line_gap: try_read_i16_be(&mut buffer)?,
+            advance_width_max: try_read_u16_be(&mut buffer)?,
+            min_left_side_bearing: try_read_i16_be(&mut buffer)?,
+            min_right_side_bearing: try_read_i16_be(&mut buffer)?,
+            x_max_extent: try_read_i16_be(&mut buffer)?,
+            caret_slope_rise: try_read_i16_be(&mut buffer)?,
+            caret_slope_run: try_read_i16_be(&mut buffer)?,
+            caret_offset: try_read_i16_be(&mut buffer)?,
+            metric_data_format: 0,
+            number_of_h_metrics: 0,
+        };
+        // skip 4 reserved values
+        buffer.set_rpos(32);
+        hhea.metric_data_format = try_read_i16_be(&mut buffer)?;
+        hhea.number_of_h_metrics = try_read_u16_be(&mut buffer)?;
+        Ok(hhea)
+    }
+}
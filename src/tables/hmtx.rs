@@ -0,0 +1,66 @@
+//! 'hmtx' table parsing
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Parsed 'hmtx' table with advance width and left side bearing for every glyph.
+/// Glyphs after the last long metric record get the advance width of that record
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HmtxTable {
+    pub advances: Vec<u16>,
+    pub left_side_bearings: Vec<i16>,
+}
+
+impl HmtxTable {
+    /// Parses raw 'hmtx' table data. `number_of_h_metrics` is taken from 'hhea' table
+    /// and `num_glyphs` from 'maxp' table
+    pub(crate) fn parse(data: &[u8], number_of_h_metrics: u16, num_glyphs: u16) -> Result<Self, Error> {
+        if number_of_h_metrics == 0 && num_glyphs > 0 {
+            return Err(Error::InvalidTableData);
+        }
+        let mut buffer = ByteReader::from_bytes(data);
+        let mut advances: Vec<u16> = Vec::with_capacity(num_glyphs as usize);
+        let mut left_side_bearings: Vec<i16> = Vec::with_capacity(num_glyphs as usize);
+        for _ in 0..number_of_h_metrics.min(num_glyphs) {
+            advances.push(try_read_u16_be(&mut buffer)?);
+            left_side_bearings.push(try_read_i16_be(&mut buffer)?);
+        }
+        let last_advance = advances.last().copied().unwrap_or_default();
+        while advances.len() < num_glyphs as usize {
+            advances.push(last_advance);
+            left_side_bearings.push(try_read_i16_be(&mut buffer)?);
+        }
+        Ok(HmtxTable {
+            advances,
+            left_side_bearings,
+        })
+    }
+
+    /// Advance width of the glyph in font units
+    pub fn advance(&self, glyph_id: u16) -> Option<u16> {
+        self.advances.get(glyph_id as usize).copied()
+    }
+
+    /// Left side bearing of the glyph in font units
+    pub fn left_side_bearing(&self, glyph_id: u16) -> Option<i16> {
+        self.left_side_bearings.get(glyph_id as usize).copied()
+    }
+
+    /// Serializes the table and returns its data with number of long metric records
+    /// ('hhea' numberOfHMetrics value). Trailing glyphs with the same advance width share one record
+    pub(crate) fn transform_to_u8_vec(&self) -> (Vec<u8>, u16) {
+        let mut number_of_h_metrics = self.advances.len();
+        while number_of_h_metrics > 1 && self.advances[number_of_h_metrics - 2] == self.advances[number_of_h_metrics - 1] {
+            number_of_h_metrics -= 1;
+        }
+        let mut data: Vec<u8> = Vec::with_capacity(number_of_h_metrics * 2 + self.advances.len() * 2);
+        for (index, lsb) in self.left_side_bearings.iter().enumerate() {
+            if index < number_of_h_metrics {
+                data.extend_from_slice(&self.advances[index].to_be_bytes());
+            }
+            data.extend_from_slice(&lsb.to_be_bytes());
+        }
+        (data, number_of_h_metrics as u16)
+    }
+}
@@ -0,0 +1,53 @@
+//! 'HVAR' table parsing: horizontal metrics variations
+
+use crate::tables::item_variations::{DeltaSetIndexMap, ItemVariationStore};
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Parsed 'HVAR' table
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct HvarTable {
+    store: ItemVariationStore,
+    // Without the mapping glyph ID is the inner index of outer index 0
+    advance_width_mapping: Option<DeltaSetIndexMap>,
+    left_side_bearing_mapping: Option<DeltaSetIndexMap>,
+}
+
+impl HvarTable {
+    /// Parses raw 'HVAR' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let major_version = try_read_u16_be(&mut buffer)?;
+        let _minor_version = try_read_u16_be(&mut buffer)?;
+        let store_offset = try_read_u32_be(&mut buffer)? as usize;
+        let advance_width_mapping_offset = try_read_u32_be(&mut buffer)? as usize;
+        let left_side_bearing_mapping_offset = try_read_u32_be(&mut buffer)? as usize;
+        if major_version != 1 {
+            return Err(Error::InvalidTableData);
+        }
+        let mapping = |offset: usize| -> Result<Option<DeltaSetIndexMap>, Error> {
+            if offset == 0 { Ok(None) } else { DeltaSetIndexMap::parse(data, offset).map(Some) }
+        };
+        Ok(HvarTable {
+            store: ItemVariationStore::parse(data, store_offset)?,
+            advance_width_mapping: mapping(advance_width_mapping_offset)?,
+            left_side_bearing_mapping: mapping(left_side_bearing_mapping_offset)?,
+        })
+    }
+
+    /// Advance width delta of the glyph at normalized `coordinates`
+    pub(crate) fn advance_delta(&self, glyph_id: u16, coordinates: &[f32]) -> f32 {
+        let (outer, inner) = match &self.advance_width_mapping {
+            Some(mapping) => mapping.get(glyph_id as u32).unwrap_or((0, glyph_id)),
+            None => (0, glyph_id),
+        };
+        self.store.delta(outer, inner, coordinates)
+    }
+
+    /// Left side bearing delta of the glyph, `None` if the table has no left side bearing mapping
+    pub(crate) fn left_side_bearing_delta(&self, glyph_id: u16, coordinates: &[f32]) -> Option<f32> {
+        let (outer, inner) = self.left_side_bearing_mapping.as_ref()?.get(glyph_id as u32)?;
+        Some(self.store.delta(outer, inner, coordinates))
+    }
+}
@@ -0,0 +1,188 @@
+//! Item variation store and delta-set index maps used by 'HVAR', 'MVAR' and other variation tables
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+// ItemVariationData wordDeltaCount flag: word deltas are 32-bit and short ones are 16-bit
+const LONG_WORDS: u16 = 0x8000;
+const WORD_DELTA_COUNT_MASK: u16 = 0x7FFF;
+
+/// Delta sets of one ItemVariationData subtable
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ItemVariationData {
+    // Indices of regions in the region list, one for every delta of a delta set
+    pub(crate) region_indices: Vec<u16>,
+    pub(crate) delta_sets: Vec<Vec<i32>>,
+}
+
+/// Parsed ItemVariationStore
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ItemVariationStore {
+    // (start, peak, end) normalized coordinates of every axis for every region
+    pub(crate) regions: Vec<Vec<(f32, f32, f32)>>,
+    pub(crate) data: Vec<ItemVariationData>,
+}
+
+impl ItemVariationStore {
+    /// Parses ItemVariationStore which starts at `offset` of `data`
+    pub(crate) fn parse(data: &[u8], offset: usize) -> Result<Self, Error> {
+        let store = data.get(offset..).ok_or(Error::InvalidTableData)?;
+        let mut buffer = ByteReader::from_bytes(store);
+        if try_read_u16_be(&mut buffer)? != 1 {
+            return Err(Error::InvalidTableData);
+        }
+        let region_list_offset = try_read_u32_be(&mut buffer)? as usize;
+        let data_count = try_read_u16_be(&mut buffer)?;
+        let data_offsets = (0..data_count)
+            .map(|_| try_read_u32_be(&mut buffer).map(|offset| offset as usize))
+            .collect::<Result<Vec<usize>, Error>>()?;
+
+        let mut region_list = ByteReader::from_bytes(store.get(region_list_offset..).ok_or(Error::InvalidTableData)?);
+        let axis_count = try_read_u16_be(&mut region_list)?;
+        let region_count = try_read_u16_be(&mut region_list)?;
+        let mut regions: Vec<Vec<(f32, f32, f32)>> = Vec::with_capacity(region_count as usize);
+        for _ in 0..region_count {
+            let mut read_f2dot14 = || try_read_i16_be(&mut region_list).map(f2dot14_to_f32);
+            regions.push((0..axis_count)
+                .map(|_| Ok((read_f2dot14()?, read_f2dot14()?, read_f2dot14()?)))
+                .collect::<Result<Vec<(f32, f32, f32)>, Error>>()?);
+        }
+
+        let mut variation_data: Vec<ItemVariationData> = Vec::with_capacity(data_count as usize);
+        for data_offset in data_offsets {
+            let mut buffer = ByteReader::from_bytes(store.get(data_offset..).ok_or(Error::InvalidTableData)?);
+            let item_count = try_read_u16_be(&mut buffer)?;
+            let word_delta_count = try_read_u16_be(&mut buffer)?;
+            let region_index_count = try_read_u16_be(&mut buffer)?;
+            let region_indices = (0..region_index_count)
+                .map(|_| try_read_u16_be(&mut buffer))
+                .collect::<Result<Vec<u16>, Error>>()?;
+            if region_indices.iter().any(|&index| index >= region_count) {
+                return Err(Error::InvalidTableData);
+            }
+            let long_words = word_delta_count & LONG_WORDS != 0;
+            let word_count = word_delta_count & WORD_DELTA_COUNT_MASK;
+            let mut delta_sets: Vec<Vec<i32>> = Vec::with_capacity(item_count as usize);
+            for _ in 0..item_count {
+                delta_sets.push((0..region_index_count)
+                    .map(|index| match (index < word_count, long_words) {
+                        (true, true) => try_read_i32_be(&mut buffer),
+                        (true, false) | (false, true) => try_read_i16_be(&mut buffer).map(i32::from),
+                        (false, false) => try_read_i8(&mut buffer).map(i32::from),
+                    })
+                    .collect::<Result<Vec<i32>, Error>>()?);
+            }
+            variation_data.push(ItemVariationData { region_indices, delta_sets });
+        }
+        Ok(ItemVariationStore { regions, data: variation_data })
+    }
+
+    /// Interpolated delta of the item at normalized `coordinates`.
+    /// Missing delta sets have no effect
+    pub(crate) fn delta(&self, outer_index: u16, inner_index: u16, coordinates: &[f32]) -> f32 {
+        let variation_data = match self.data.get(outer_index as usize) {
+            Some(variation_data) => variation_data,
+            None => return 0.0,
+        };
+        let deltas = match variation_data.delta_sets.get(inner_index as usize) {
+            Some(deltas) => deltas,
+            None => return 0.0,
+        };
+        deltas
+            .iter()
+            .zip(variation_data.region_indices.iter())
+            .map(|(&delta, &region_index)| {
+                let scalar = self.regions[region_index as usize]
+                    .iter()
+                    .enumerate()
+                    .map(|(axis, &(start, peak, end))| {
+                        region_scalar(start, peak, end, coordinates.get(axis).copied().unwrap_or(0.0))
+                    })
+                    .product::<f32>();
+                delta as f32 * scalar
+            })
+            .sum()
+    }
+}
+
+/// Scalar of one axis of variation region (or tuple with intermediate region) at normalized `coordinate`
+pub(crate) fn region_scalar(start: f32, peak: f32, end: f32, coordinate: f32) -> f32 {
+    // invalid regions and axes which don't participate in the region don't change the scalar
+    if peak == 0.0 || start > peak || peak > end || (start < 0.0 && end > 0.0) {
+        return 1.0;
+    }
+    if coordinate == peak {
+        1.0
+    } else if coordinate <= start || coordinate >= end {
+        0.0
+    } else if coordinate < peak {
+        (coordinate - start) / (peak - start)
+    } else {
+        (end - coordinate) / (end - peak)
+    }
+}
+
+// DeltaSetIndexMap entryFormat masks
+const INNER_INDEX_BIT_COUNT_MASK: u8 = 0x0F;
+const MAP_ENTRY_SIZE_MASK: u8 = 0x30;
+
+/// Parsed DeltaSetIndexMap: (outer, inner) delta set index for every item
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DeltaSetIndexMap {
+    pub(crate) entries: Vec<(u16, u16)>,
+}
+
+impl DeltaSetIndexMap {
+    /// Parses DeltaSetIndexMap which starts at `offset` of `data`
+    pub(crate) fn parse(data: &[u8], offset: usize) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data.get(offset..).ok_or(Error::InvalidTableData)?);
+        let format = try_read_u8(&mut buffer)?;
+        let entry_format = try_read_u8(&mut buffer)?;
+        let map_count = match format {
+            0 => try_read_u16_be(&mut buffer)? as u32,
+            1 => try_read_u32_be(&mut buffer)?,
+            _ => return Err(Error::InvalidTableData),
+        };
+        let entry_size = ((entry_format & MAP_ENTRY_SIZE_MASK) >> 4) + 1;
+        let inner_bit_count = (entry_format & INNER_INDEX_BIT_COUNT_MASK) as u32 + 1;
+        let mut entries: Vec<(u16, u16)> = Vec::with_capacity(map_count.min(u16::MAX as u32 + 1) as usize);
+        for _ in 0..map_count {
+            let mut value = 0u32;
+            for _ in 0..entry_size {
+                value = (value << 8) | try_read_u8(&mut buffer)? as u32;
+            }
+            entries.push(((value >> inner_bit_count) as u16, (value & ((1 << inner_bit_count) - 1)) as u16));
+        }
+        Ok(DeltaSetIndexMap { entries })
+    }
+
+    /// Delta set index of the item. Items after the end of the map use its last entry
+    pub(crate) fn get(&self, index: u32) -> Option<(u16, u16)> {
+        self.entries.get(index as usize).or(self.entries.last()).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::item_variation_store;
+
+    #[test]
+    fn test_item_variation_store() {
+        // one axis, regions 0..1 and -1..0, deltas [10, -20] and [300, 0]
+        let data = item_variation_store(&[(0.0, 1.0, 1.0), (-1.0, -1.0, 0.0)], &[vec![10, -20], vec![300, 0]]);
+        let store = ItemVariationStore::parse(&data, 0).unwrap();
+        debug_assert!(store.delta(0, 0, &[0.5]) == 5.0 && store.delta(0, 0, &[-0.5]) == -10.0);
+        debug_assert!(store.delta(0, 1, &[1.0]) == 300.0 && store.delta(0, 1, &[0.0]) == 0.0);
+        debug_assert!(store.delta(1, 0, &[1.0]) == 0.0 && store.delta(0, 5, &[1.0]) == 0.0);
+    }
+
+    #[test]
+    fn test_delta_set_index_map() {
+        // format 0, 2 bytes entries with 4 bits of inner index
+        let map = DeltaSetIndexMap::parse(&[0, 0x13, 0, 2, 0x00, 0x15, 0x01, 0x02], 0).unwrap();
+        debug_assert!(map.entries == vec![(1, 5), (16, 2)]);
+        debug_assert!(map.get(7) == Some((16, 2)));
+    }
+}
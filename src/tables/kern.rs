@@ -0,0 +1,107 @@
+//! 'kern' table parsing: pair kerning of format 0 subtables in Microsoft and Apple versions
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+// Coverage flags of Microsoft version subtables
+const MS_HORIZONTAL: u16 = 0x0001;
+const MS_MINIMUM: u16 = 0x0002;
+const MS_CROSS_STREAM: u16 = 0x0004;
+const MS_OVERRIDE: u16 = 0x0008;
+// Coverage flags of Apple version subtables
+const APPLE_VERTICAL: u16 = 0x8000;
+const APPLE_CROSS_STREAM: u16 = 0x4000;
+const APPLE_VARIATION: u16 = 0x2000;
+
+/// Kerning pairs of one subtable sorted by (left, right) glyph IDs
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct KernSubtable {
+    pairs: Vec<(u16, u16, i16)>,
+    // values of the subtable replace the accumulated value instead of being added to it
+    is_override: bool,
+}
+
+/// Parsed 'kern' table. Only horizontal kerning subtables of format 0 are used
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KernTable {
+    subtables: Vec<KernSubtable>,
+}
+
+impl KernTable {
+    /// Parses raw 'kern' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let version = try_read_u16_be(&mut buffer)?;
+        // Apple version has 32-bit version and number of subtables
+        let is_apple = version == 1;
+        let subtable_count = if is_apple {
+            try_read_u16_be(&mut buffer)?;
+            try_read_u32_be(&mut buffer)? as usize
+        } else if version == 0 {
+            try_read_u16_be(&mut buffer)? as usize
+        } else {
+            return Err(Error::InvalidTableData);
+        };
+
+        let mut subtables: Vec<KernSubtable> = vec![];
+        let mut offset = buffer.get_rpos();
+        for _ in 0..subtable_count {
+            let mut header = ByteReader::from_bytes(data.get(offset..).ok_or(Error::InvalidTableData)?);
+            let (length, format, is_used, is_override, header_size) = if is_apple {
+                let length = try_read_u32_be(&mut header)? as usize;
+                let coverage = try_read_u16_be(&mut header)?;
+                let _tuple_index = try_read_u16_be(&mut header)?;
+                let is_used = coverage & (APPLE_VERTICAL | APPLE_CROSS_STREAM | APPLE_VARIATION) == 0;
+                (length, coverage & 0xFF, is_used, false, 8)
+            } else {
+                let _version = try_read_u16_be(&mut header)?;
+                let length = try_read_u16_be(&mut header)? as usize;
+                let coverage = try_read_u16_be(&mut header)?;
+                let is_used = coverage & MS_HORIZONTAL != 0 && coverage & (MS_MINIMUM | MS_CROSS_STREAM) == 0;
+                (length, coverage >> 8, is_used, coverage & MS_OVERRIDE != 0, 6)
+            };
+            if length < header_size {
+                return Err(Error::InvalidTableData);
+            }
+            if is_used && format == 0 {
+                let mut pairs_header = ByteReader::from_bytes(data.get(offset + header_size..).ok_or(Error::InvalidTableData)?);
+                let pair_count = try_read_u16_be(&mut pairs_header)? as usize;
+                let mut records = ByteReader::from_bytes(try_slice(data, offset + header_size + 8, pair_count * 6)?);
+                let mut pairs: Vec<(u16, u16, i16)> = Vec::with_capacity(pair_count);
+                for _ in 0..pair_count {
+                    pairs.push((try_read_u16_be(&mut records)?, try_read_u16_be(&mut records)?, try_read_i16_be(&mut records)?));
+                }
+                pairs.sort_by_key(|pair| (pair.0, pair.1));
+                subtables.push(KernSubtable { pairs, is_override });
+            }
+            // length of the only subtable of Microsoft version may overflow 16 bits
+            offset += length;
+        }
+        Ok(KernTable { subtables })
+    }
+
+    /// Kerning value of the glyph pair in font units, 0 if there is no kerning for the pair
+    pub fn kerning(&self, left: u16, right: u16) -> i16 {
+        self.subtables.iter().fold(0i16, |value, subtable| {
+            match subtable.pairs.binary_search_by_key(&(left, right), |pair| (pair.0, pair.1)) {
+                Ok(index) if subtable.is_override => subtable.pairs[index].2,
+                Ok(index) => value.saturating_add(subtable.pairs[index].2),
+                Err(_) => value,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_kerning() {
+        let kern = KernTable::parse(&kern_table(&[(1, 2, -30), (2, 1, 15)])).unwrap();
+        debug_assert!(kern.kerning(1, 2) == -30 && kern.kerning(2, 1) == 15 && kern.kerning(1, 1) == 0);
+        debug_assert!(KernTable::parse(&[0, 2, 0, 0]).is_err());
+    }
+}
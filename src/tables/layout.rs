@@ -0,0 +1,469 @@
+//! Common part of 'GSUB' and 'GPOS' tables parsing: script, feature and lookup lists
+//! and feature variations. Lookup subtables are parsed by the users of the lookups
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// Tag of the default script
+pub const DEFAULT_SCRIPT_TAG: u32 = tag(b"DFLT");
+/// Scripts tried in order if the table has no requested script, as HarfBuzz does
+const FALLBACK_SCRIPT_TAGS: [u32; 3] = [DEFAULT_SCRIPT_TAG, tag(b"dflt"), tag(b"latn")];
+/// Value of required feature index which means there is no required feature
+const NO_REQUIRED_FEATURE: u16 = 0xFFFF;
+/// Lookup flag: lookup has mark filtering set
+pub const USE_MARK_FILTERING_SET: u16 = 0x0010;
+/// Lookup types of extension lookups in 'GSUB' and 'GPOS' tables
+const GSUB_EXTENSION_LOOKUP_TYPE: u16 = 7;
+const GPOS_EXTENSION_LOOKUP_TYPE: u16 = 9;
+
+/// Language system: features used for a language of a script
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LanguageSystem {
+    // `None` for the default language system of the script
+    pub tag: Option<u32>,
+    // Index of the feature which must be always applied
+    pub required_feature_index: Option<u16>,
+    pub feature_indices: Vec<u16>,
+}
+
+/// Script with its language systems
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Script {
+    pub tag: u32,
+    pub default_language: Option<LanguageSystem>,
+    pub languages: Vec<LanguageSystem>,
+}
+
+/// Feature and indices of the lookups it references
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Feature {
+    pub tag: u32,
+    pub lookup_indices: Vec<u16>,
+}
+
+/// Lookup header. Lookup type of extension lookups is the type of the extended lookups
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lookup {
+    pub lookup_type: u16,
+    pub lookup_flag: u16,
+    pub mark_filtering_set: Option<u16>,
+    // Absolute offsets of subtables, extension subtables are resolved
+    pub(crate) subtable_offsets: Vec<usize>,
+}
+
+impl Lookup {
+    /// Number of lookup subtables
+    pub fn subtable_count(&self) -> usize {
+        self.subtable_offsets.len()
+    }
+}
+
+/// Condition of feature variation: range of normalized coordinate of the axis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AxisCondition {
+    pub axis_index: u16,
+    pub filter_range_min: f32,
+    pub filter_range_max: f32,
+}
+
+/// Alternate features used when all conditions are met
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureVariation {
+    pub conditions: Vec<AxisCondition>,
+    // (feature index, alternate feature) pairs
+    pub substitutions: Vec<(u16, Feature)>,
+}
+
+impl FeatureVariation {
+    /// Checks if all conditions are met for normalized coordinates, missing coordinates are zero
+    pub fn matches(&self, coordinates: &[f32]) -> bool {
+        self.conditions.iter().all(|condition| {
+            let value = coordinates.get(condition.axis_index as usize).copied().unwrap_or_default();
+            (condition.filter_range_min..=condition.filter_range_max).contains(&value)
+        })
+    }
+}
+
+/// Parsed headers of 'GSUB' or 'GPOS' table
+pub struct LayoutTable<'a> {
+    data: Cow<'a, [u8]>,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub scripts: Vec<Script>,
+    pub features: Vec<Feature>,
+    pub lookups: Vec<Lookup>,
+    // Feature variations of version 1.1, checked in order
+    pub feature_variations: Vec<FeatureVariation>,
+}
+
+impl<'a> LayoutTable<'a> {
+    /// Parses raw 'GSUB' table data
+    pub(crate) fn parse_gsub(data: Cow<'a, [u8]>) -> Result<Self, Error> {
+        LayoutTable::parse(data, GSUB_EXTENSION_LOOKUP_TYPE)
+    }
+
+    /// Parses raw 'GPOS' table data
+    pub(crate) fn parse_gpos(data: Cow<'a, [u8]>) -> Result<Self, Error> {
+        LayoutTable::parse(data, GPOS_EXTENSION_LOOKUP_TYPE)
+    }
+
+    fn parse(data: Cow<'a, [u8]>, extension_lookup_type: u16) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(&data);
+        let major_version = try_read_u16_be(&mut buffer)?;
+        let minor_version = try_read_u16_be(&mut buffer)?;
+        let script_list_offset = try_read_u16_be(&mut buffer)? as usize;
+        let feature_list_offset = try_read_u16_be(&mut buffer)? as usize;
+        let lookup_list_offset = try_read_u16_be(&mut buffer)? as usize;
+        if major_version != 1 {
+            return Err(Error::InvalidTableData);
+        }
+        let feature_variations_offset = if minor_version >= 1 { try_read_u32_be(&mut buffer)? as usize } else { 0 };
+
+        let scripts = parse_script_list(&data, script_list_offset)?;
+        let mut features: Vec<Feature> = vec![];
+        for (feature_tag, offset) in parse_records(&data, feature_list_offset, feature_list_offset)? {
+            features.push(parse_feature(&data, feature_tag, offset)?);
+        }
+        let lookups = parse_lookup_list(&data, lookup_list_offset, extension_lookup_type)?;
+        let mut feature_variations = if feature_variations_offset != 0 {
+            parse_feature_variations(&data, feature_variations_offset)?
+        } else {
+            vec![]
+        };
+        // alternate features have the tags of the substituted features
+        for (feature_index, feature) in feature_variations.iter_mut().flat_map(|variation| variation.substitutions.iter_mut()) {
+            feature.tag = features.get(*feature_index as usize).ok_or(Error::InvalidTableData)?.tag;
+        }
+        Ok(LayoutTable { data, major_version, minor_version, scripts, features, lookups, feature_variations })
+    }
+
+    /// Raw data of the lookup subtable from its start to the end of the table
+    pub fn subtable_data(&self, lookup_index: u16, subtable_index: usize) -> Option<&[u8]> {
+        let offset = *self.lookups.get(lookup_index as usize)?.subtable_offsets.get(subtable_index)?;
+        self.data.get(offset..)
+    }
+
+    /// Script with the tag, `None` if the table has no such script
+    pub fn script(&self, script_tag: u32) -> Option<&Script> {
+        self.scripts.iter().find(|script| script.tag == script_tag)
+    }
+
+    /// Language system of the language of the script. Falls back to the default language system
+    /// of the script if there is no such language and to 'DFLT', 'dflt' and 'latn' scripts if there is no such script
+    pub fn language_system(&self, script_tag: u32, language_tag: Option<u32>) -> Option<&LanguageSystem> {
+        let script = self.script(script_tag)
+            .or_else(|| FALLBACK_SCRIPT_TAGS.iter().find_map(|&fallback_tag| self.script(fallback_tag)))?;
+        language_tag
+            .and_then(|language_tag| script.languages.iter().find(|language| language.tag == Some(language_tag)))
+            .or(script.default_language.as_ref())
+    }
+
+    /// Index of the first feature variation which conditions are met for normalized coordinates
+    pub fn feature_variation_index(&self, coordinates: &[f32]) -> Option<usize> {
+        self.feature_variations.iter().position(|variation| variation.matches(coordinates))
+    }
+
+    /// Indices of the lookups referenced by the feature at normalized coordinates:
+    /// alternate feature of the matching feature variation is used instead of the feature.
+    /// `None` if there is no such feature
+    pub fn feature_lookups(&self, feature_index: u16, coordinates: &[f32]) -> Option<&[u16]> {
+        let alternate = self.feature_variation_index(coordinates).and_then(|index| {
+            self.feature_variations[index].substitutions.iter().find(|(index, _)| *index == feature_index)
+        });
+        match alternate {
+            Some((_, feature)) => Some(&feature.lookup_indices),
+            None => self.features.get(feature_index as usize).map(|feature| &feature.lookup_indices[..]),
+        }
+    }
+
+    /// (feature index, feature) pairs of the language system including the required feature.
+    /// Feature indices out of range are skipped
+    pub fn language_features<'b>(&'b self, language: &'b LanguageSystem) -> impl Iterator<Item = (u16, &'b Feature)> + 'b {
+        language.required_feature_index.iter().chain(language.feature_indices.iter())
+            .filter_map(move |&index| self.features.get(index as usize).map(|feature| (index, feature)))
+    }
+
+    /// Indices of the lookups of features with `feature_tags` for the language of the script
+    /// at normalized coordinates, see `language_system`. Lookups are sorted and unique
+    pub fn lookups_for_features(&self, script_tag: u32, language_tag: Option<u32>, feature_tags: &[u32], coordinates: &[f32]) -> Vec<u16> {
+        let language = match self.language_system(script_tag, language_tag) {
+            Some(language) => language,
+            None => return vec![],
+        };
+        let mut lookups: Vec<u16> = self
+            .language_features(language)
+            .filter(|(index, feature)| feature_tags.contains(&feature.tag) || language.required_feature_index == Some(*index))
+            .filter_map(|(index, _)| self.feature_lookups(index, coordinates))
+            .flatten()
+            .copied()
+            .filter(|&lookup_index| (lookup_index as usize) < self.lookups.len())
+            .collect();
+        lookups.sort_unstable();
+        lookups.dedup();
+        lookups
+    }
+}
+
+/// Reads (tag, absolute offset) records of script, language system and feature lists,
+/// offsets are relative to `base_offset`
+fn parse_records(data: &[u8], list_offset: usize, base_offset: usize) -> Result<Vec<(u32, usize)>, Error> {
+    let mut buffer = reader_at(data, list_offset)?;
+    let count = try_read_u16_be(&mut buffer)? as usize;
+    let mut records: Vec<(u32, usize)> = Vec::with_capacity(count);
+    for _ in 0..count {
+        records.push((try_read_u32_be(&mut buffer)?, base_offset + try_read_u16_be(&mut buffer)? as usize));
+    }
+    Ok(records)
+}
+
+fn parse_script_list(data: &[u8], offset: usize) -> Result<Vec<Script>, Error> {
+    let mut scripts: Vec<Script> = vec![];
+    for (script_tag, script_offset) in parse_records(data, offset, offset)? {
+        let mut buffer = reader_at(data, script_offset)?;
+        let default_language_offset = try_read_u16_be(&mut buffer)? as usize;
+        let default_language = if default_language_offset != 0 {
+            Some(parse_language_system(data, None, script_offset + default_language_offset)?)
+        } else {
+            None
+        };
+        let mut languages: Vec<LanguageSystem> = vec![];
+        // language system records follow the default language system offset
+        for (language_tag, language_offset) in parse_records(data, script_offset + 2, script_offset)? {
+            languages.push(parse_language_system(data, Some(language_tag), language_offset)?);
+        }
+        scripts.push(Script { tag: script_tag, default_language, languages });
+    }
+    Ok(scripts)
+}
+
+fn parse_language_system(data: &[u8], language_tag: Option<u32>, offset: usize) -> Result<LanguageSystem, Error> {
+    let mut buffer = reader_at(data, offset)?;
+    let _lookup_order_offset = try_read_u16_be(&mut buffer)?;
+    let required_feature_index = try_read_u16_be(&mut buffer)?;
+    Ok(LanguageSystem {
+        tag: language_tag,
+        required_feature_index: (required_feature_index != NO_REQUIRED_FEATURE).then_some(required_feature_index),
+        feature_indices: read_u16_array(&mut buffer)?,
+    })
+}
+
+fn parse_feature(data: &[u8], feature_tag: u32, offset: usize) -> Result<Feature, Error> {
+    let mut buffer = reader_at(data, offset)?;
+    let _feature_params_offset = try_read_u16_be(&mut buffer)?;
+    Ok(Feature { tag: feature_tag, lookup_indices: read_u16_array(&mut buffer)? })
+}
+
+fn parse_lookup_list(data: &[u8], offset: usize, extension_lookup_type: u16) -> Result<Vec<Lookup>, Error> {
+    let mut list = reader_at(data, offset)?;
+    let lookup_offsets = read_u16_array(&mut list)?;
+    let mut lookups: Vec<Lookup> = Vec::with_capacity(lookup_offsets.len());
+    for lookup_offset in lookup_offsets {
+        let lookup_offset = offset + lookup_offset as usize;
+        let mut buffer = reader_at(data, lookup_offset)?;
+        let mut lookup_type = try_read_u16_be(&mut buffer)?;
+        let lookup_flag = try_read_u16_be(&mut buffer)?;
+        let mut subtable_offsets: Vec<usize> = read_u16_array(&mut buffer)?
+            .into_iter()
+            .map(|subtable_offset| lookup_offset + subtable_offset as usize)
+            .collect();
+        let mark_filtering_set = if lookup_flag & USE_MARK_FILTERING_SET != 0 { Some(try_read_u16_be(&mut buffer)?) } else { None };
+
+        if lookup_type == extension_lookup_type {
+            let mut extension_type: Option<u16> = None;
+            for subtable_offset in subtable_offsets.iter_mut() {
+                let mut extension = reader_at(data, *subtable_offset)?;
+                let _format = try_read_u16_be(&mut extension)?;
+                let extended_type = try_read_u16_be(&mut extension)?;
+                // all subtables of extension lookup must have the same type
+                if extension_type.is_some_and(|extension_type| extension_type != extended_type) {
+                    return Err(Error::InvalidTableData);
+                }
+                extension_type = Some(extended_type);
+                *subtable_offset += try_read_u32_be(&mut extension)? as usize;
+            }
+            lookup_type = extension_type.unwrap_or(lookup_type);
+        }
+        lookups.push(Lookup { lookup_type, lookup_flag, mark_filtering_set, subtable_offsets });
+    }
+    Ok(lookups)
+}
+
+fn parse_feature_variations(data: &[u8], offset: usize) -> Result<Vec<FeatureVariation>, Error> {
+    let mut buffer = reader_at(data, offset)?;
+    let major_version = try_read_u16_be(&mut buffer)?;
+    let _minor_version = try_read_u16_be(&mut buffer)?;
+    let count = try_read_u32_be(&mut buffer)? as usize;
+    if major_version != 1 {
+        return Err(Error::InvalidTableData);
+    }
+    let mut variations: Vec<FeatureVariation> = Vec::with_capacity(count.min(data.len() / 8));
+    for _ in 0..count {
+        let condition_set_offset = try_read_u32_be(&mut buffer)? as usize;
+        let substitution_offset = try_read_u32_be(&mut buffer)? as usize;
+
+        let mut conditions: Vec<AxisCondition> = vec![];
+        if condition_set_offset != 0 {
+            let condition_set_offset = offset + condition_set_offset;
+            let mut condition_set = reader_at(data, condition_set_offset)?;
+            let condition_count = try_read_u16_be(&mut condition_set)?;
+            for _ in 0..condition_count {
+                let mut condition = reader_at(data, condition_set_offset + try_read_u32_be(&mut condition_set)? as usize)?;
+                if try_read_u16_be(&mut condition)? != 1 {
+                    return Err(Error::InvalidTableData);
+                }
+                conditions.push(AxisCondition {
+                    axis_index: try_read_u16_be(&mut condition)?,
+                    filter_range_min: f2dot14_to_f32(try_read_i16_be(&mut condition)?),
+                    filter_range_max: f2dot14_to_f32(try_read_i16_be(&mut condition)?),
+                });
+            }
+        }
+
+        let mut substitutions: Vec<(u16, Feature)> = vec![];
+        if substitution_offset != 0 {
+            let substitution_offset = offset + substitution_offset;
+            let mut substitution = reader_at(data, substitution_offset)?;
+            let _version = try_read_u32_be(&mut substitution)?;
+            let substitution_count = try_read_u16_be(&mut substitution)?;
+            for _ in 0..substitution_count {
+                let feature_index = try_read_u16_be(&mut substitution)?;
+                let alternate_offset = substitution_offset + try_read_u32_be(&mut substitution)? as usize;
+                substitutions.push((feature_index, parse_feature(data, 0, alternate_offset)?));
+            }
+        }
+        variations.push(FeatureVariation { conditions, substitutions });
+    }
+    Ok(variations)
+}
+
+/// Index of the glyph in coverage table at `offset` of subtable data, `None` if the glyph isn't covered
+pub(crate) fn coverage_index(subtable: &[u8], offset: usize, glyph_id: u16) -> Result<Option<u16>, Error> {
+    let mut buffer = reader_at(subtable, offset)?;
+    let format = try_read_u16_be(&mut buffer)?;
+    let count = try_read_u16_be(&mut buffer)? as usize;
+    match format {
+        1 => {
+            let glyphs = try_slice(subtable, offset + 4, count * 2)?;
+            let glyph_at = |index: usize| u16::from_be_bytes([glyphs[index * 2], glyphs[index * 2 + 1]]);
+            // glyphs are sorted
+            let (mut low, mut high) = (0, count);
+            while low < high {
+                let middle = (low + high) / 2;
+                match glyph_at(middle).cmp(&glyph_id) {
+                    Ordering::Less => low = middle + 1,
+                    Ordering::Greater => high = middle,
+                    Ordering::Equal => return Ok(Some(middle as u16)),
+                }
+            }
+            Ok(None)
+        }
+        2 => {
+            for _ in 0..count {
+                let start_glyph_id = try_read_u16_be(&mut buffer)?;
+                let end_glyph_id = try_read_u16_be(&mut buffer)?;
+                let start_coverage_index = try_read_u16_be(&mut buffer)?;
+                if (start_glyph_id..=end_glyph_id).contains(&glyph_id) {
+                    return Ok(Some(start_coverage_index.wrapping_add(glyph_id - start_glyph_id)));
+                }
+            }
+            Ok(None)
+        }
+        _ => Err(Error::InvalidTableData),
+    }
+}
+
+/// Class of the glyph in class definition table at `offset` of subtable data, 0 for glyphs without class
+pub(crate) fn glyph_class(subtable: &[u8], offset: usize, glyph_id: u16) -> Result<u16, Error> {
+    let mut buffer = reader_at(subtable, offset)?;
+    match try_read_u16_be(&mut buffer)? {
+        1 => {
+            let start_glyph_id = try_read_u16_be(&mut buffer)?;
+            let glyph_count = try_read_u16_be(&mut buffer)?;
+            if glyph_id < start_glyph_id || glyph_id - start_glyph_id >= glyph_count {
+                return Ok(0);
+            }
+            let mut class = reader_at(subtable, offset + 6 + (glyph_id - start_glyph_id) as usize * 2)?;
+            try_read_u16_be(&mut class)
+        }
+        2 => {
+            let range_count = try_read_u16_be(&mut buffer)?;
+            for _ in 0..range_count {
+                let start_glyph_id = try_read_u16_be(&mut buffer)?;
+                let end_glyph_id = try_read_u16_be(&mut buffer)?;
+                let class = try_read_u16_be(&mut buffer)?;
+                if (start_glyph_id..=end_glyph_id).contains(&glyph_id) {
+                    return Ok(class);
+                }
+            }
+            Ok(0)
+        }
+        _ => Err(Error::InvalidTableData),
+    }
+}
+
+/// Reads u16 count followed by u16 values
+pub(crate) fn read_u16_array(buffer: &mut ByteReader) -> Result<Vec<u16>, Error> {
+    let count = try_read_u16_be(buffer)?;
+    (0..count).map(|_| try_read_u16_be(buffer)).collect()
+}
+
+pub(crate) fn reader_at(data: &[u8], offset: usize) -> Result<ByteReader<'_>, Error> {
+    Ok(ByteReader::from_bytes(data.get(offset..).ok_or(Error::InvalidTableData)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_parse_layout_table() {
+        // extension subtable of single substitution lookup
+        let extension = [vec![0, 1, 0, 1, 0, 0, 0, 8], vec![0, 1, 0, 6, 0, 1]].concat();
+        let data = layout_table(
+            &[
+                (tag(b"DFLT"), vec![(None, None, vec![0])]),
+                (tag(b"latn"), vec![(None, Some(2), vec![0, 1]), (Some(tag(b"TRK ")), None, vec![1, 3])]),
+            ],
+            &[(tag(b"liga"), vec![0]), (tag(b"smcp"), vec![1]), (tag(b"ccmp"), vec![2]), (tag(b"ss01"), vec![1, 2])],
+            &[(4, vec![vec![0, 1]]), (1, vec![vec![0, 1]]), (7, vec![extension])],
+            &[(vec![(0, 0.5, 1.0)], vec![(0, vec![2, 0])])],
+        );
+        let gsub = LayoutTable::parse_gsub(Cow::Owned(data)).unwrap();
+        debug_assert!(gsub.minor_version == 1 && gsub.scripts.len() == 2 && gsub.features.len() == 4);
+        debug_assert!(gsub.lookups[2].lookup_type == 1 && gsub.lookups[2].subtable_count() == 1);
+        debug_assert!(gsub.subtable_data(2, 0).unwrap().starts_with(&[0, 1, 0, 6]));
+        debug_assert!(gsub.subtable_data(2, 1).is_none());
+
+        let turkish = gsub.language_system(tag(b"latn"), Some(tag(b"TRK "))).unwrap();
+        debug_assert!(turkish.tag == Some(tag(b"TRK ")) && turkish.feature_indices == vec![1, 3]);
+        debug_assert!(gsub.language_system(tag(b"latn"), Some(tag(b"DEU "))).unwrap().required_feature_index == Some(2));
+        debug_assert!(gsub.language_system(tag(b"cyrl"), None).unwrap().feature_indices == vec![0]);
+
+        let latin_only = layout_table(&[(tag(b"latn"), vec![(None, None, vec![0])])], &[(tag(b"liga"), vec![0])], &[(4, vec![vec![0, 1]])], &[]);
+        let latin_only = LayoutTable::parse_gsub(Cow::Owned(latin_only)).unwrap();
+        debug_assert!(latin_only.language_system(DEFAULT_SCRIPT_TAG, None).unwrap().feature_indices == vec![0]);
+        let lowercase_default = layout_table(
+            &[(tag(b"dflt"), vec![(None, None, vec![1])]), (tag(b"latn"), vec![(None, None, vec![0])])],
+            &[(tag(b"liga"), vec![0]), (tag(b"clig"), vec![0])],
+            &[(4, vec![vec![0, 1]])],
+            &[],
+        );
+        let lowercase_default = LayoutTable::parse_gsub(Cow::Owned(lowercase_default)).unwrap();
+        debug_assert!(lowercase_default.language_system(tag(b"cyrl"), None).unwrap().feature_indices == vec![1]);
+
+        debug_assert!(gsub.feature_variations[0].substitutions[0].1.tag == tag(b"liga"));
+        debug_assert!(gsub.feature_lookups(0, &[]) == Some(&[0][..]));
+        debug_assert!(gsub.feature_lookups(0, &[0.75]) == Some(&[2, 0][..]));
+        debug_assert!(gsub.feature_lookups(4, &[]).is_none());
+
+        let liga = [tag(b"liga")];
+        debug_assert!(gsub.lookups_for_features(tag(b"latn"), None, &liga, &[]) == vec![0, 2]);
+        debug_assert!(gsub.lookups_for_features(tag(b"latn"), None, &liga, &[1.0]) == vec![0, 2]);
+        debug_assert!(gsub.lookups_for_features(tag(b"latn"), Some(tag(b"TRK ")), &[tag(b"ss01")], &[]) == vec![1, 2]);
+        debug_assert!(gsub.lookups_for_features(tag(b"DFLT"), None, &liga, &[0.75]) == vec![0, 2]);
+    }
+}
@@ -0,0 +1,41 @@
+//! 'loca' table parsing (short and long formats)
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Parsed 'loca' table: offsets of glyphs in 'glyf' table.
+/// It contains `num_glyphs + 1` offsets, the last one is the end of the last glyph
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocaTable {
+    pub offsets: Vec<u32>,
+}
+
+impl LocaTable {
+    /// Parses raw 'loca' table data. `index_to_loc_format` is taken from 'head' table:
+    /// 0 for short offsets (offset / 2 stored as u16) and 1 for long ones (u32)
+    pub(crate) fn parse(data: &[u8], index_to_loc_format: i16, num_glyphs: u16) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let count = num_glyphs as usize + 1;
+        let offsets = match index_to_loc_format {
+            0 => (0..count)
+                .map(|_| try_read_u16_be(&mut buffer).map(|offset| offset as u32 * 2))
+                .collect::<Result<Vec<u32>, Error>>()?,
+            1 => (0..count)
+                .map(|_| try_read_u32_be(&mut buffer))
+                .collect::<Result<Vec<u32>, Error>>()?,
+            _ => return Err(Error::InvalidTableData),
+        };
+        Ok(LocaTable { offsets })
+    }
+
+    /// Returns range of the glyph data in 'glyf' table
+    pub fn glyph_range(&self, glyph_id: u16) -> Option<std::ops::Range<usize>> {
+        let start = *self.offsets.get(glyph_id as usize)? as usize;
+        let end = *self.offsets.get(glyph_id as usize + 1)? as usize;
+        if start > end {
+            return None;
+        }
+        Some(start..end)
+    }
+}
@@ -0,0 +1,99 @@
+//! 'maxp' table parsing (versions 0.5 and 1.0)
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Version 0.5 of 'maxp' table used by fonts with CFF outlines
+pub(crate) const MAXP_VERSION_0_5: u32 = 0x00005000;
+/// Version 1.0 of 'maxp' table used by fonts with TrueType outlines
+pub(crate) const MAXP_VERSION_1_0: u32 = 0x00010000;
+
+/// Parsed 'maxp' table. Version 0.5 table contains only number of glyphs,
+/// so all other fields are set to zero for it
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MaxpTable {
+    pub version: u32,
+    pub num_glyphs: u16,
+    // Maximum points in a non-composite glyph
+    pub max_points: u16,
+    // Maximum contours in a non-composite glyph
+    pub max_contours: u16,
+    // Maximum points in a composite glyph
+    pub max_composite_points: u16,
+    // Maximum contours in a composite glyph
+    pub max_composite_contours: u16,
+    pub max_zones: u16,
+    pub max_twilight_points: u16,
+    pub max_storage: u16,
+    pub max_function_defs: u16,
+    pub max_instruction_defs: u16,
+    pub max_stack_elements: u16,
+    pub max_size_of_instructions: u16,
+    // Maximum number of components referenced at "top level" for any composite glyph
+    pub max_component_elements: u16,
+    // Maximum levels of recursion; 1 for simple components
+    pub max_component_depth: u16,
+}
+
+impl MaxpTable {
+    /// Parses raw 'maxp' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let version = try_read_u32_be(&mut buffer)?;
+        let num_glyphs = try_read_u16_be(&mut buffer)?;
+        match version {
+            MAXP_VERSION_0_5 => Ok(MaxpTable {
+                version,
+                num_glyphs,
+                ..Default::default()
+            }),
+            MAXP_VERSION_1_0 => Ok(MaxpTable {
+                version,
+                num_glyphs,
+                max_points: try_read_u16_be(&mut buffer)?,
+                max_contours: try_read_u16_be(&mut buffer)?,
+                max_composite_points: try_read_u16_be(&mut buffer)?,
+                max_composite_contours: try_read_u16_be(&mut buffer)?,
+                max_zones: try_read_u16_be(&mut buffer)?,
+                max_twilight_points: try_read_u16_be(&mut buffer)?,
+                max_storage: try_read_u16_be(&mut buffer)?,
+                max_function_defs: try_read_u16_be(&mut buffer)?,
+                max_instruction_defs: try_read_u16_be(&mut buffer)?,
+                max_stack_elements: try_read_u16_be(&mut buffer)?,
+                max_size_of_instructions: try_read_u16_be(&mut buffer)?,
+                max_component_elements: try_read_u16_be(&mut buffer)?,
+                max_component_depth: try_read_u16_be(&mut buffer)?,
+            }),
+            _ => Err(Error::InvalidTableData),
+        }
+    }
+
+    /// Serializes the table; only version and number of glyphs are written for version 0.5
+    pub(crate) fn transform_to_u8_vec(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::with_capacity(32);
+        data.extend_from_slice(&self.version.to_be_bytes());
+        data.extend_from_slice(&self.num_glyphs.to_be_bytes());
+        if self.version == MAXP_VERSION_0_5 {
+            return data;
+        }
+        for value in [
+            self.max_points,
+            self.max_contours,
+            self.max_composite_points,
+            self.max_composite_contours,
+            self.max_zones,
+            self.max_twilight_points,
+            self.max_storage,
+            self.max_function_defs,
+            self.max_instruction_defs,
+            self.max_stack_elements,
+            self.max_size_of_instructions,
+            self.max_component_elements,
+            self.max_component_depth,
+        ] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        data
+    }
+}
@@ -0,0 +1,30 @@
+//! Parsers of separate SFNT tables
+
+pub(crate) mod avar;
+pub(crate) mod cblc;
+pub(crate) mod cff;
+pub(crate) mod charstring;
+pub(crate) mod cmap;
+pub(crate) mod colr;
+pub(crate) mod cpal;
+pub(crate) mod fvar;
+pub(crate) mod glyf;
+pub(crate) mod gpos;
+pub(crate) mod gsub;
+pub(crate) mod gvar;
+pub(crate) mod hhea;
+pub(crate) mod hmtx;
+pub(crate) mod hvar;
+pub(crate) mod item_variations;
+pub(crate) mod kern;
+pub(crate) mod layout;
+pub(crate) mod loca;
+pub(crate) mod maxp;
+pub(crate) mod mvar;
+pub(crate) mod name;
+pub(crate) mod os2;
+pub(crate) mod post;
+pub(crate) mod sbix;
+pub(crate) mod stat;
+pub(crate) mod svg;
+pub(crate) mod tuple_variations;
@@ -0,0 +1,44 @@
+//! 'MVAR' table parsing: font-wide metrics variations
+
+use crate::tables::item_variations::ItemVariationStore;
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Parsed 'MVAR' table
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MvarTable {
+    // (value tag, outer index, inner index) for every varied metric
+    pub(crate) value_records: Vec<(u32, u16, u16)>,
+    store: Option<ItemVariationStore>,
+}
+
+impl MvarTable {
+    /// Parses raw 'MVAR' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let major_version = try_read_u16_be(&mut buffer)?;
+        let _minor_version = try_read_u16_be(&mut buffer)?;
+        let _reserved = try_read_u16_be(&mut buffer)?;
+        let value_record_size = try_read_u16_be(&mut buffer)? as usize;
+        let value_record_count = try_read_u16_be(&mut buffer)? as usize;
+        let store_offset = try_read_u16_be(&mut buffer)? as usize;
+        if major_version != 1 || (value_record_count > 0 && value_record_size < 8) {
+            return Err(Error::InvalidTableData);
+        }
+        let value_records = (0..value_record_count)
+            .map(|index| {
+                let mut record = ByteReader::from_bytes(try_slice(data, 12 + index * value_record_size, value_record_size)?);
+                Ok((try_read_u32_be(&mut record)?, try_read_u16_be(&mut record)?, try_read_u16_be(&mut record)?))
+            })
+            .collect::<Result<Vec<(u32, u16, u16)>, Error>>()?;
+        let store = if store_offset == 0 { None } else { Some(ItemVariationStore::parse(data, store_offset)?) };
+        Ok(MvarTable { value_records, store })
+    }
+
+    /// Delta of the metric with `value_tag` at normalized `coordinates`, `None` if the metric isn't varied
+    pub(crate) fn delta(&self, value_tag: u32, coordinates: &[f32]) -> Option<f32> {
+        let &(_, outer, inner) = self.value_records.iter().find(|(record_tag, _, _)| *record_tag == value_tag)?;
+        Some(self.store.as_ref()?.delta(outer, inner, coordinates))
+    }
+}
@@ -0,0 +1,475 @@
+//! 'name' table parsing (formats 0 and 1) with decoding of platform specific strings
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Predefined name IDs
+pub mod name_id {
+    pub const COPYRIGHT: u16 = 0;
+    pub const FAMILY: u16 = 1;
+    pub const SUBFAMILY: u16 = 2;
+    pub const UNIQUE_ID: u16 = 3;
+    pub const FULL_NAME: u16 = 4;
+    pub const VERSION: u16 = 5;
+    pub const POSTSCRIPT_NAME: u16 = 6;
+    pub const TRADEMARK: u16 = 7;
+    pub const MANUFACTURER: u16 = 8;
+    pub const DESIGNER: u16 = 9;
+    pub const DESCRIPTION: u16 = 10;
+    pub const VENDOR_URL: u16 = 11;
+    pub const DESIGNER_URL: u16 = 12;
+    pub const LICENSE: u16 = 13;
+    pub const LICENSE_URL: u16 = 14;
+    pub const TYPOGRAPHIC_FAMILY: u16 = 16;
+    pub const TYPOGRAPHIC_SUBFAMILY: u16 = 17;
+    pub const SAMPLE_TEXT: u16 = 19;
+}
+
+pub(crate) const PLATFORM_UNICODE: u16 = 0;
+pub(crate) const PLATFORM_MACINTOSH: u16 = 1;
+pub(crate) const PLATFORM_WINDOWS: u16 = 3;
+
+/// Language ID values starting from this one refer to language-tag records (format 1 only)
+const FIRST_LANG_TAG_ID: u16 = 0x8000;
+
+/// Parsed 'name' table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameTable {
+    pub format: u16,
+    pub records: Vec<NameRecord>,
+    // Language tags from format 1 table, language ID 0x8000 refers to the first one
+    pub lang_tags: Vec<String>,
+}
+
+/// Single name record with raw and decoded string
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameRecord {
+    pub platform_id: u16,
+    pub encoding_id: u16,
+    pub language_id: u16,
+    pub name_id: u16,
+    // Raw string data as it's stored in the table
+    pub raw: Vec<u8>,
+    // Decoded string. None if the encoding is not supported or the string is invalid. Strings in Macintosh
+    // encodings other than Roman, Greek, Cyrillic and Central European are decoded only if they are ASCII
+    // (or half-width katakana in Japanese encoding), e.g. CJK characters give None, see `decode_mac_ascii`
+    pub value: Option<String>,
+    // BCP 47 language tag of the record if it's known
+    pub language: Option<String>,
+}
+
+impl NameTable {
+    /// Parses raw 'name' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let format = try_read_u16_be(&mut buffer)?;
+        if format > 1 {
+            return Err(Error::InvalidTableData);
+        }
+        let count = try_read_u16_be(&mut buffer)?;
+        let storage_offset = try_read_u16_be(&mut buffer)? as usize;
+
+        let mut raw_records: Vec<[u16; 6]> = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let mut record = [0u16; 6];
+            for value in record.iter_mut() {
+                *value = try_read_u16_be(&mut buffer)?;
+            }
+            raw_records.push(record);
+        }
+
+        let mut lang_tags: Vec<String> = vec![];
+        if format == 1 {
+            let lang_tag_count = try_read_u16_be(&mut buffer)?;
+            for _ in 0..lang_tag_count {
+                let length = try_read_u16_be(&mut buffer)? as usize;
+                let offset = try_read_u16_be(&mut buffer)? as usize;
+                let raw = try_slice(data, storage_offset + offset, length)?;
+                lang_tags.push(decode_utf16_be(raw).ok_or(Error::InvalidTableData)?);
+            }
+        }
+
+        let mut records: Vec<NameRecord> = Vec::with_capacity(raw_records.len());
+        for [platform_id, encoding_id, language_id, name_id, length, offset] in raw_records {
+            let raw = try_slice(data, storage_offset + offset as usize, length as usize)?.to_vec();
+            let language = if language_id >= FIRST_LANG_TAG_ID {
+                lang_tags.get((language_id - FIRST_LANG_TAG_ID) as usize).cloned()
+            } else {
+                language_tag(platform_id, language_id).map(String::from)
+            };
+            records.push(NameRecord {
+                platform_id,
+                encoding_id,
+                language_id,
+                name_id,
+                value: decode_name_string(platform_id, encoding_id, &raw),
+                raw,
+                language,
+            });
+        }
+
+        Ok(NameTable {
+            format,
+            records,
+            lang_tags,
+        })
+    }
+
+    /// Serializes records to format 0 table. Records are written in the required order
+    /// (by platform, encoding, language and name IDs) and equal strings share storage.
+    /// Records which refer to language tags are skipped because format 0 has no language tags
+    pub(crate) fn transform_to_u8_vec(&self) -> Vec<u8> {
+        let mut records: Vec<&NameRecord> =
+            self.records.iter().filter(|record| record.language_id < FIRST_LANG_TAG_ID).collect();
+        records.sort_by_key(|record| (record.platform_id, record.encoding_id, record.language_id, record.name_id));
+
+        let storage_offset = 6 + 12 * records.len();
+        let mut data: Vec<u8> = Vec::with_capacity(storage_offset);
+        let mut storage: Vec<u8> = vec![];
+        for value in [0u16, records.len() as u16, storage_offset as u16] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        for record in records {
+            let offset = match storage.windows(record.raw.len().max(1)).position(|window| window == record.raw) {
+                Some(offset) if !record.raw.is_empty() => offset,
+                _ => {
+                    storage.extend_from_slice(&record.raw);
+                    storage.len() - record.raw.len()
+                }
+            };
+            for value in [
+                record.platform_id,
+                record.encoding_id,
+                record.language_id,
+                record.name_id,
+                record.raw.len() as u16,
+                offset as u16,
+            ] {
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        data.extend_from_slice(&storage);
+        data
+    }
+
+    /// Finds the best string for `name_id`.
+    ///
+    /// `preferred_languages` is a list of BCP 47 tags in order of preference, e.g. `["de-AT", "de"]`.
+    /// Exact tag match wins over primary language match (e.g. "de-DE" for "de-AT"),
+    /// if nothing matches - English and then any other language is used.
+    /// With the same language Windows strings are preferred over Unicode and Macintosh ones.
+    /// Records which can't be decoded are skipped. It includes Macintosh Japanese, Chinese and Korean
+    /// strings with double byte characters, so Windows or Unicode strings are used instead of them.
+    pub fn get(&self, name_id: u16, preferred_languages: &[&str]) -> Option<&str> {
+        self.records
+            .iter()
+            .filter(|record| record.name_id == name_id && record.value.is_some())
+            .min_by_key(|record| (language_rank(record, preferred_languages), platform_rank(record)))
+            .and_then(|record| record.value.as_deref())
+    }
+
+    /// Font family name
+    pub fn family_name(&self, preferred_languages: &[&str]) -> Option<&str> {
+        self.get(name_id::TYPOGRAPHIC_FAMILY, preferred_languages)
+            .or_else(|| self.get(name_id::FAMILY, preferred_languages))
+    }
+
+    /// Font subfamily (style) name
+    pub fn subfamily_name(&self, preferred_languages: &[&str]) -> Option<&str> {
+        self.get(name_id::TYPOGRAPHIC_SUBFAMILY, preferred_languages)
+            .or_else(|| self.get(name_id::SUBFAMILY, preferred_languages))
+    }
+
+    /// Full font name
+    pub fn full_name(&self, preferred_languages: &[&str]) -> Option<&str> {
+        self.get(name_id::FULL_NAME, preferred_languages)
+    }
+
+    /// PostScript name. It's the same for all languages
+    pub fn postscript_name(&self) -> Option<&str> {
+        self.get(name_id::POSTSCRIPT_NAME, &[])
+    }
+}
+
+/// Rank of the record language: less is better
+fn language_rank(record: &NameRecord, preferred_languages: &[&str]) -> usize {
+    let language = match &record.language {
+        Some(language) => language.to_ascii_lowercase(),
+        None => return preferred_languages.len() * 2 + 1,
+    };
+    let primary = language.split('-').next().unwrap_or_default();
+    for (index, preferred) in preferred_languages.iter().enumerate() {
+        let preferred = preferred.to_ascii_lowercase();
+        if language == preferred {
+            return index * 2;
+        }
+        if primary == preferred.split('-').next().unwrap_or_default() {
+            return index * 2 + 1;
+        }
+    }
+    if primary == "en" {
+        preferred_languages.len() * 2
+    } else {
+        preferred_languages.len() * 2 + 2
+    }
+}
+
+/// Rank of the record platform: less is better
+fn platform_rank(record: &NameRecord) -> u16 {
+    match record.platform_id {
+        PLATFORM_WINDOWS => 0,
+        PLATFORM_UNICODE => 1,
+        _ => 2,
+    }
+}
+
+// Macintosh encodings (script codes) decoded exactly
+const MAC_ROMAN: u16 = 0;
+const MAC_GREEK: u16 = 6;
+const MAC_CYRILLIC: u16 = 7;
+const MAC_CENTRAL_EUROPEAN: u16 = 29;
+// Macintosh Japanese encoding, its half-width katakana is decoded along with ASCII
+const MAC_JAPANESE: u16 = 1;
+
+/// Decodes string of name record according to its platform and encoding.
+/// Macintosh Roman, Greek, Cyrillic and Central European strings are decoded exactly,
+/// strings in other Macintosh encodings are decoded only if they are ASCII, see `decode_mac_ascii`
+pub(crate) fn decode_name_string(platform_id: u16, encoding_id: u16, raw: &[u8]) -> Option<String> {
+    match (platform_id, encoding_id) {
+        // all Unicode platform encodings and Windows Symbol, Unicode BMP and full Unicode encodings
+        (PLATFORM_UNICODE, _) | (PLATFORM_WINDOWS, 0) | (PLATFORM_WINDOWS, 1) | (PLATFORM_WINDOWS, 10) => {
+            decode_utf16_be(raw)
+        }
+        (PLATFORM_MACINTOSH, MAC_ROMAN) => Some(decode_mac_roman(raw)),
+        (PLATFORM_MACINTOSH, MAC_GREEK) => Some(decode_single_byte(raw, &MAC_GREEK_HIGH)),
+        (PLATFORM_MACINTOSH, MAC_CYRILLIC) => Some(decode_single_byte(raw, &MAC_CYRILLIC_HIGH)),
+        (PLATFORM_MACINTOSH, MAC_CENTRAL_EUROPEAN) => Some(decode_single_byte(raw, &MAC_CENTRAL_EUROPEAN_HIGH)),
+        (PLATFORM_MACINTOSH, _) => decode_mac_ascii(encoding_id, raw),
+        _ => None,
+    }
+}
+
+/// Decodes UTF-16 string in big endian order
+pub(crate) fn decode_utf16_be(raw: &[u8]) -> Option<String> {
+    if !raw.len().is_multiple_of(2) {
+        return None;
+    }
+    let units = raw.chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+    char::decode_utf16(units).collect::<Result<String, _>>().ok()
+}
+
+/// Upper half (0x80..0xFF) of Mac OS Roman encoding
+const MAC_ROMAN_HIGH: [char; 128] = [
+    'Ä', 'Å', 'Ç', 'É', 'Ñ', 'Ö', 'Ü', 'á', 'à', 'â', 'ä', 'ã', 'å', 'ç', 'é', 'è',
+    'ê', 'ë', 'í', 'ì', 'î', 'ï', 'ñ', 'ó', 'ò', 'ô', 'ö', 'õ', 'ú', 'ù', 'û', 'ü',
+    '†', '°', '¢', '£', '§', '•', '¶', 'ß', '®', '©', '™', '´', '¨', '≠', 'Æ', 'Ø',
+    '∞', '±', '≤', '≥', '¥', 'µ', '∂', '∑', '∏', 'π', '∫', 'ª', 'º', 'Ω', 'æ', 'ø',
+    '¿', '¡', '¬', '√', 'ƒ', '≈', '∆', '«', '»', '…', '\u{A0}', 'À', 'Ã', 'Õ', 'Œ', 'œ',
+    '–', '—', '“', '”', '‘', '’', '÷', '◊', 'ÿ', 'Ÿ', '⁄', '€', '‹', '›', 'ﬁ', 'ﬂ',
+    '‡', '·', '‚', '„', '‰', 'Â', 'Ê', 'Á', 'Ë', 'È', 'Í', 'Î', 'Ï', 'Ì', 'Ó', 'Ô',
+    '\u{F8FF}', 'Ò', 'Ú', 'Û', 'Ù', 'ı', 'ˆ', '˜', '¯', '˘', '˙', '˚', '¸', '˝', '˛', 'ˇ',
+];
+
+/// Upper half (0x80..0xFF) of Mac OS Greek encoding
+const MAC_GREEK_HIGH: [char; 128] = [
+    'Ä', '¹', '²', 'É', '³', 'Ö', 'Ü', '΅', 'à', 'â', 'ä', '΄', '¨', 'ç', 'é', 'è',
+    'ê', 'ë', '£', '™', 'î', 'ï', '•', '½', '‰', 'ô', 'ö', '¦', '€', 'ù', 'û', 'ü',
+    '†', 'Γ', 'Δ', 'Θ', 'Λ', 'Ξ', 'Π', 'ß', '®', '©', 'Σ', 'Ϊ', '§', '≠', '°', '·',
+    'Α', '±', '≤', '≥', '¥', 'Β', 'Ε', 'Ζ', 'Η', 'Ι', 'Κ', 'Μ', 'Φ', 'Ϋ', 'Ψ', 'Ω',
+    'ά', 'Ν', '¬', 'Ο', 'Ρ', '≈', 'Τ', '«', '»', '…', '\u{A0}', 'Υ', 'Χ', 'Ά', 'Έ', 'œ',
+    '–', '―', '“', '”', '‘', '’', '÷', 'Ή', 'Ί', 'Ό', 'Ύ', 'έ', 'ή', 'ί', 'ό', 'Ώ',
+    'ύ', 'α', 'β', 'ψ', 'δ', 'ε', 'φ', 'γ', 'η', 'ι', 'ξ', 'κ', 'λ', 'μ', 'ν', 'ο',
+    'π', 'ώ', 'ρ', 'σ', 'τ', 'θ', 'ω', 'ς', 'χ', 'υ', 'ζ', 'ϊ', 'ϋ', 'ΐ', 'ΰ', '\u{AD}',
+];
+
+/// Upper half (0x80..0xFF) of Mac OS Cyrillic encoding
+const MAC_CYRILLIC_HIGH: [char; 128] = [
+    'А', 'Б', 'В', 'Г', 'Д', 'Е', 'Ж', 'З', 'И', 'Й', 'К', 'Л', 'М', 'Н', 'О', 'П',
+    'Р', 'С', 'Т', 'У', 'Ф', 'Х', 'Ц', 'Ч', 'Ш', 'Щ', 'Ъ', 'Ы', 'Ь', 'Э', 'Ю', 'Я',
+    '†', '°', 'Ґ', '£', '§', '•', '¶', 'І', '®', '©', '™', 'Ђ', 'ђ', '≠', 'Ѓ', 'ѓ',
+    '∞', '±', '≤', '≥', 'і', 'µ', 'ґ', 'Ј', 'Є', 'є', 'Ї', 'ї', 'Љ', 'љ', 'Њ', 'њ',
+    'ј', 'Ѕ', '¬', '√', 'ƒ', '≈', '∆', '«', '»', '…', '\u{A0}', 'Ћ', 'ћ', 'Ќ', 'ќ', 'ѕ',
+    '–', '—', '“', '”', '‘', '’', '÷', '„', 'Ў', 'ў', 'Џ', 'џ', '№', 'Ё', 'ё', 'я',
+    'а', 'б', 'в', 'г', 'д', 'е', 'ж', 'з', 'и', 'й', 'к', 'л', 'м', 'н', 'о', 'п',
+    'р', 'с', 'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'ъ', 'ы', 'ь', 'э', 'ю', '€',
+];
+
+/// Upper half (0x80..0xFF) of Mac OS Central European encoding
+const MAC_CENTRAL_EUROPEAN_HIGH: [char; 128] = [
+    'Ä', 'Ā', 'ā', 'É', 'Ą', 'Ö', 'Ü', 'á', 'ą', 'Č', 'ä', 'č', 'Ć', 'ć', 'é', 'Ź',
+    'ź', 'Ď', 'í', 'ď', 'Ē', 'ē', 'Ė', 'ó', 'ė', 'ô', 'ö', 'õ', 'ú', 'Ě', 'ě', 'ü',
+    '†', '°', 'Ę', '£', '§', '•', '¶', 'ß', '®', '©', '™', 'ę', '¨', '≠', 'ģ', 'Į',
+    'į', 'Ī', '≤', '≥', 'ī', 'Ķ', '∂', '∑', 'ł', 'Ļ', 'ļ', 'Ľ', 'ľ', 'Ĺ', 'ĺ', 'Ņ',
+    'ņ', 'Ń', '¬', '√', 'ń', 'Ň', '∆', '«', '»', '…', '\u{A0}', 'ň', 'Ő', 'Õ', 'ő', 'Ō',
+    '–', '—', '“', '”', '‘', '’', '÷', '◊', 'ō', 'Ŕ', 'ŕ', 'Ř', '‹', '›', 'ř', 'Ŗ',
+    'ŗ', 'Š', '‚', '„', 'š', 'Ś', 'ś', 'Á', 'Ť', 'ť', 'Í', 'Ž', 'ž', 'Ū', 'Ó', 'Ô',
+    'ū', 'Ů', 'Ú', 'ů', 'Ű', 'ű', 'Ų', 'ų', 'Ý', 'ý', 'ķ', 'Ż', 'Ł', 'ż', 'Ģ', 'ˇ',
+];
+
+/// Decodes string in Mac OS Roman encoding
+pub(crate) fn decode_mac_roman(raw: &[u8]) -> String {
+    decode_single_byte(raw, &MAC_ROMAN_HIGH)
+}
+
+/// Decodes string in ASCII compatible single byte encoding with the upper half `high`
+fn decode_single_byte(raw: &[u8], high: &[char; 128]) -> String {
+    raw.iter().map(|&byte| if byte < 0x80 { byte as char } else { high[(byte - 0x80) as usize] }).collect()
+}
+
+/// Decodes string in other Macintosh encodings if all its characters are ASCII or half-width katakana
+/// of Japanese encoding. Returns `None` for other characters (e.g. double byte characters of Japanese,
+/// Chinese and Korean encodings) which aren't mapped, so callers fall back to Windows or Unicode strings
+fn decode_mac_ascii(encoding_id: u16, raw: &[u8]) -> Option<String> {
+    raw.iter()
+        .map(|&byte| match byte {
+            0..0x80 => Some(byte as char),
+            0xA1..=0xDF if encoding_id == MAC_JAPANESE => char::from_u32(0xFF61 + (byte - 0xA1) as u32),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Converts platform specific language ID to BCP 47 tag
+fn language_tag(platform_id: u16, language_id: u16) -> Option<&'static str> {
+    match platform_id {
+        PLATFORM_WINDOWS => windows_language_tag(language_id),
+        PLATFORM_MACINTOSH => mac_language_tag(language_id),
+        _ => None,
+    }
+}
+
+/// Windows LCID to BCP 47 tag for the most common languages
+fn windows_language_tag(language_id: u16) -> Option<&'static str> {
+    let tag = match language_id {
+        0x0401 => "ar-SA",
+        0x0404 => "zh-TW",
+        0x0405 => "cs-CZ",
+        0x0406 => "da-DK",
+        0x0407 => "de-DE",
+        0x0408 => "el-GR",
+        0x0409 => "en-US",
+        0x040B => "fi-FI",
+        0x040C => "fr-FR",
+        0x040D => "he-IL",
+        0x040E => "hu-HU",
+        0x0410 => "it-IT",
+        0x0411 => "ja-JP",
+        0x0412 => "ko-KR",
+        0x0413 => "nl-NL",
+        0x0414 => "nb-NO",
+        0x0415 => "pl-PL",
+        0x0416 => "pt-BR",
+        0x0419 => "ru-RU",
+        0x041D => "sv-SE",
+        0x041E => "th-TH",
+        0x041F => "tr-TR",
+        0x0421 => "id-ID",
+        0x0422 => "uk-UA",
+        0x042A => "vi-VN",
+        0x0439 => "hi-IN",
+        0x0804 => "zh-CN",
+        0x0807 => "de-CH",
+        0x0809 => "en-GB",
+        0x080C => "fr-BE",
+        0x0816 => "pt-PT",
+        0x0C04 => "zh-HK",
+        0x0C07 => "de-AT",
+        0x0C09 => "en-AU",
+        0x0C0A => "es-ES",
+        0x0C0C => "fr-CA",
+        0x1004 => "zh-SG",
+        0x1009 => "en-CA",
+        _ => return None,
+    };
+    Some(tag)
+}
+
+/// Macintosh language ID to BCP 47 tag
+fn mac_language_tag(language_id: u16) -> Option<&'static str> {
+    let tag = match language_id {
+        0 => "en",
+        1 => "fr",
+        2 => "de",
+        3 => "it",
+        4 => "nl",
+        5 => "sv",
+        6 => "es",
+        7 => "da",
+        8 => "pt",
+        9 => "no",
+        10 => "he",
+        11 => "ja",
+        12 => "ar",
+        13 => "fi",
+        14 => "el",
+        15 => "is",
+        16 => "mt",
+        17 => "tr",
+        18 => "hr",
+        19 => "zh-Hant",
+        20 => "ur",
+        21 => "hi",
+        22 => "th",
+        23 => "ko",
+        32 => "ru",
+        33 => "zh-Hans",
+        _ => return None,
+    };
+    Some(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::{FontTables, WoffFont};
+
+    #[test]
+    fn test_name_lookup_by_language() {
+        let name = name_table(
+            &[
+                (1, 0, 0, name_id::FAMILY, b"Caf\x8E Sans".to_vec()),
+                (3, 1, 0x0409, name_id::FAMILY, utf16_be("Cafe Sans")),
+                (3, 1, 0x0407, name_id::FAMILY, utf16_be("Kaffee Sans")),
+                (3, 1, 0x8000, name_id::FAMILY, utf16_be("Kafi Sans")),
+                (3, 1, 0x0409, name_id::POSTSCRIPT_NAME, utf16_be("CafeSans-Regular")),
+                (3, 1, 0x0409, name_id::LICENSE, vec![0xD8]),
+            ],
+            &["de-CH"],
+        );
+        let woff = build_woff(0x00010000, &[(tag(b"name"), name)]);
+        let table = WoffFont::new(&woff).unwrap().name().unwrap();
+        debug_assert!(table.format == 1 && table.records.len() == 6);
+        debug_assert!(table.records[0].value.as_deref() == Some("Café Sans"));
+        debug_assert!(table.records[3].language.as_deref() == Some("de-CH"));
+        debug_assert!(table.family_name(&[]) == Some("Cafe Sans"));
+        debug_assert!(table.family_name(&["de-CH"]) == Some("Kafi Sans"));
+        debug_assert!(table.family_name(&["de-AT", "fr"]) == Some("Kaffee Sans"));
+        debug_assert!(table.family_name(&["ja"]) == Some("Cafe Sans"));
+        debug_assert!(table.postscript_name() == Some("CafeSans-Regular"));
+        // odd length UTF-16 string can't be decoded
+        debug_assert!(table.get(name_id::LICENSE, &[]).is_none());
+    }
+
+    #[test]
+    fn test_decode_mac_encodings() {
+        debug_assert!(decode_name_string(1, 7, b"\x98\xF0\xE8\xF4\xF2").as_deref() == Some("Шрифт"));
+        debug_assert!(decode_name_string(1, 6, b"\xBC\xE1\xEC\xE1").as_deref() == Some("Φαλα"));
+        debug_assert!(decode_name_string(1, 29, b"\x8B\x90\xEB").as_deref() == Some("čźŽ"));
+        // half-width katakana is decoded, strings with Shift JIS kanji, Hangul or non-ASCII Arabic aren't
+        debug_assert!(decode_name_string(1, 1, b"A\xB1").as_deref() == Some("Aｱ"));
+        debug_assert!(decode_name_string(1, 3, b"Gothic").as_deref() == Some("Gothic"));
+        debug_assert!(decode_name_string(1, 1, b"A\x96\xBE\xB1").is_none());
+        debug_assert!(decode_name_string(1, 3, b"\xB0\xA1 Gothic").is_none());
+        debug_assert!(decode_name_string(1, 4, b"\xC7").is_none());
+        debug_assert!(decode_name_string(3, 2, b"\x82\xA0").is_none());
+
+        // Macintosh Japanese family name with kanji falls back to Windows English one
+        let utf16 = |text: &str| -> Vec<u8> { text.encode_utf16().flat_map(|unit| unit.to_be_bytes()).collect() };
+        let data = name_table(&[(1, 1, 11, 1, b"\x96\xBE\x92\xA9".to_vec()), (3, 1, 0x0409, 1, utf16("Mincho"))], &[]);
+        let table = NameTable::parse(&data).unwrap();
+        debug_assert!(table.records[0].value.is_none() && table.records[0].language.as_deref() == Some("ja"));
+        debug_assert!(table.family_name(&["ja"]) == Some("Mincho"));
+    }
+}
@@ -0,0 +1,223 @@
+//! 'OS/2' table parsing (versions 0-5)
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Parsed 'OS/2' table. Fields which were added in later versions of the table are optional
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Os2Table {
+    pub version: u16,
+    pub x_avg_char_width: i16,
+    // 100 (Thin) - 900 (Black)
+    pub us_weight_class: u16,
+    // 1 (Ultra-condensed) - 9 (Ultra-expanded)
+    pub us_width_class: u16,
+    // Font embedding licensing rights
+    pub fs_type: u16,
+    pub y_subscript_x_size: i16,
+    pub y_subscript_y_size: i16,
+    pub y_subscript_x_offset: i16,
+    pub y_subscript_y_offset: i16,
+    pub y_superscript_x_size: i16,
+    pub y_superscript_y_size: i16,
+    pub y_superscript_x_offset: i16,
+    pub y_superscript_y_offset: i16,
+    pub y_strikeout_size: i16,
+    pub y_strikeout_position: i16,
+    pub s_family_class: i16,
+    pub panose: [u8; 10],
+    pub ul_unicode_range: [u32; 4],
+    pub ach_vend_id: [u8; 4],
+    pub fs_selection: u16,
+    pub us_first_char_index: u16,
+    pub us_last_char_index: u16,
+    // Typographic and Windows metrics. Old Apple version 0 tables may end before them
+    pub s_typo_ascender: Option<i16>,
+    pub s_typo_descender: Option<i16>,
+    pub s_typo_line_gap: Option<i16>,
+    pub us_win_ascent: Option<u16>,
+    pub us_win_descent: Option<u16>,
+    // Version 1 and later
+    pub ul_code_page_range: Option<[u32; 2]>,
+    // Version 2 and later
+    pub sx_height: Option<i16>,
+    pub s_cap_height: Option<i16>,
+    pub us_default_char: Option<u16>,
+    pub us_break_char: Option<u16>,
+    pub us_max_context: Option<u16>,
+    // Version 5 only
+    pub us_lower_optical_point_size: Option<u16>,
+    pub us_upper_optical_point_size: Option<u16>,
+}
+
+/// Font embedding permission level from fsType bits 0-3
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmbeddingLevel {
+    // may be embedded and permanently installed
+    Installable,
+    // must not be embedded, modified or exchanged without permission of the legal owner
+    Restricted,
+    // may be embedded for viewing and printing only
+    PreviewAndPrint,
+    // may be embedded for viewing, printing and editing
+    Editable,
+}
+
+/// Font embedding permissions parsed from fsType
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmbeddingPermissions {
+    pub level: EmbeddingLevel,
+    // the font must not be subsetted before embedding
+    pub no_subsetting: bool,
+    // only bitmaps of the font may be embedded
+    pub bitmap_only: bool,
+}
+
+/// Offset of fsType field in 'OS/2' table
+pub(crate) const OS2_FS_TYPE_OFFSET: usize = 8;
+
+// fsType bits
+const FS_TYPE_RESTRICTED: u16 = 0x0002;
+const FS_TYPE_PREVIEW_AND_PRINT: u16 = 0x0004;
+const FS_TYPE_EDITABLE: u16 = 0x0008;
+const FS_TYPE_NO_SUBSETTING: u16 = 0x0100;
+const FS_TYPE_BITMAP_ONLY: u16 = 0x0200;
+
+impl EmbeddingPermissions {
+    /// Parses fsType value. If several level bits are set, the least restrictive level is used
+    pub fn from_fs_type(fs_type: u16) -> Self {
+        let level = if fs_type & FS_TYPE_EDITABLE != 0 {
+            EmbeddingLevel::Editable
+        } else if fs_type & FS_TYPE_PREVIEW_AND_PRINT != 0 {
+            EmbeddingLevel::PreviewAndPrint
+        } else if fs_type & FS_TYPE_RESTRICTED != 0 {
+            EmbeddingLevel::Restricted
+        } else {
+            EmbeddingLevel::Installable
+        };
+        EmbeddingPermissions {
+            level,
+            no_subsetting: fs_type & FS_TYPE_NO_SUBSETTING != 0,
+            bitmap_only: fs_type & FS_TYPE_BITMAP_ONLY != 0,
+        }
+    }
+}
+
+/// fsSelection bit which says that typographic metrics have to be used for line spacing
+pub(crate) const FS_SELECTION_USE_TYPO_METRICS: u16 = 1 << 7;
+
+impl Os2Table {
+    /// Parses raw 'OS/2' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let version = try_read_u16_be(&mut buffer)?;
+        if version > 5 {
+            return Err(Error::InvalidTableData);
+        }
+        let mut os2 = Os2Table {
+            version,
+            x_avg_char_width: try_read_i16_be(&mut buffer)?,
+            us_weight_class: try_read_u16_be(&mut buffer)?,
+            us_width_class: try_read_u16_be(&mut buffer)?,
+            fs_type: try_read_u16_be(&mut buffer)?,
+            y_subscript_x_size: try_read_i16_be(&mut buffer)?,
+            y_subscript_y_size: try_read_i16_be(&mut buffer)?,
+            y_subscript_x_offset: try_read_i16_be(&mut buffer)?,
+            y_subscript_y_offset: try_read_i16_be(&mut buffer)?,
+            y_superscript_x_size: try_read_i16_be(&mut buffer)?,
+            y_superscript_y_size: try_read_i16_be(&mut buffer)?,
+            y_superscript_x_offset: try_read_i16_be(&mut buffer)?,
+            y_superscript_y_offset: try_read_i16_be(&mut buffer)?,
+            y_strikeout_size: try_read_i16_be(&mut buffer)?,
+            y_strikeout_position: try_read_i16_be(&mut buffer)?,
+            s_family_class: try_read_i16_be(&mut buffer)?,
+            panose: try_slice(data, 32, 10)?.try_into().map_err(|_| Error::InvalidTableData)?,
+            ul_unicode_range: [0; 4],
+            ach_vend_id: [0; 4],
+            fs_selection: 0,
+            us_first_char_index: 0,
+            us_last_char_index: 0,
+            s_typo_ascender: None,
+            s_typo_descender: None,
+            s_typo_line_gap: None,
+            us_win_ascent: None,
+            us_win_descent: None,
+            ul_code_page_range: None,
+            sx_height: None,
+            s_cap_height: None,
+            us_default_char: None,
+            us_break_char: None,
+            us_max_context: None,
+            us_lower_optical_point_size: None,
+            us_upper_optical_point_size: None,
+        };
+        buffer.set_rpos(42);
+        for range in os2.ul_unicode_range.iter_mut() {
+            *range = try_read_u32_be(&mut buffer)?;
+        }
+        os2.ach_vend_id = try_slice(data, 58, 4)?.try_into().map_err(|_| Error::InvalidTableData)?;
+        buffer.set_rpos(62);
+        os2.fs_selection = try_read_u16_be(&mut buffer)?;
+        os2.us_first_char_index = try_read_u16_be(&mut buffer)?;
+        os2.us_last_char_index = try_read_u16_be(&mut buffer)?;
+
+        // 68 bytes length version 0 tables are valid too
+        if version == 0 && data.len() < 78 {
+            return Ok(os2);
+        }
+        os2.s_typo_ascender = Some(try_read_i16_be(&mut buffer)?);
+        os2.s_typo_descender = Some(try_read_i16_be(&mut buffer)?);
+        os2.s_typo_line_gap = Some(try_read_i16_be(&mut buffer)?);
+        os2.us_win_ascent = Some(try_read_u16_be(&mut buffer)?);
+        os2.us_win_descent = Some(try_read_u16_be(&mut buffer)?);
+        if version >= 1 {
+            os2.ul_code_page_range = Some([try_read_u32_be(&mut buffer)?, try_read_u32_be(&mut buffer)?]);
+        }
+        if version >= 2 {
+            os2.sx_height = Some(try_read_i16_be(&mut buffer)?);
+            os2.s_cap_height = Some(try_read_i16_be(&mut buffer)?);
+            os2.us_default_char = Some(try_read_u16_be(&mut buffer)?);
+            os2.us_break_char = Some(try_read_u16_be(&mut buffer)?);
+            os2.us_max_context = Some(try_read_u16_be(&mut buffer)?);
+        }
+        if version >= 5 {
+            os2.us_lower_optical_point_size = Some(try_read_u16_be(&mut buffer)?);
+            os2.us_upper_optical_point_size = Some(try_read_u16_be(&mut buffer)?);
+        }
+        Ok(os2)
+    }
+
+    /// Font embedding permissions from fsType
+    pub fn embedding_permissions(&self) -> EmbeddingPermissions {
+        EmbeddingPermissions::from_fs_type(self.fs_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::os2_table;
+
+    #[test]
+    fn test_os2_versions() {
+        let v1 = Os2Table::parse(&os2_table(1, 400, 8)).unwrap();
+        debug_assert!(v1.fs_type == 8 && v1.ach_vend_id == *b"TEST" && v1.panose[0] == 2);
+        debug_assert!(v1.ul_code_page_range == Some([1, 0]) && v1.sx_height.is_none());
+        let v5 = Os2Table::parse(&os2_table(5, 400, 0)).unwrap();
+        debug_assert!(v5.us_max_context == Some(2) && v5.us_upper_optical_point_size == Some(0xFFFF));
+        // version 2 table without its last fields
+        let data = os2_table(2, 400, 0);
+        debug_assert!(Os2Table::parse(&data[..data.len() - 2]) == Err(Error::InvalidTableData));
+    }
+
+    #[test]
+    fn test_embedding_permissions() {
+        let permissions = EmbeddingPermissions::from_fs_type(0x0302);
+        debug_assert!(permissions.level == EmbeddingLevel::Restricted && permissions.no_subsetting && permissions.bitmap_only);
+        debug_assert!(EmbeddingPermissions::from_fs_type(0x0006).level == EmbeddingLevel::PreviewAndPrint);
+        debug_assert!(EmbeddingPermissions::from_fs_type(0).level == EmbeddingLevel::Installable);
+    }
+}
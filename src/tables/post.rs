@@ -0,0 +1,96 @@
+//! 'post' table parsing
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Length of 'post' table header
+pub(crate) const POST_HEADER_SIZE: usize = 32;
+/// Version 1.0 of 'post' table: glyphs use the standard Macintosh glyph names
+pub(crate) const POST_VERSION_1_0: u32 = 0x00010000;
+/// Version 2.0 of 'post' table: glyph names are stored in the table
+pub(crate) const POST_VERSION_2_0: u32 = 0x00020000;
+/// Version 3.0 of 'post' table: no glyph names
+pub(crate) const POST_VERSION_3_0: u32 = 0x00030000;
+/// Number of standard Macintosh glyph names; name indices starting from it refer to custom names
+pub(crate) const NUM_STANDARD_NAMES: u16 = 258;
+
+/// Parsed 'post' table header (32 bytes length)
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostTable {
+    // 0x00010000, 0x00020000, 0x00025000 or 0x00030000
+    pub version: u32,
+    // Italic angle in counter-clockwise degrees from the vertical
+    pub italic_angle: f32,
+    // Suggested distance of the top of the underline from the baseline
+    pub underline_position: i16,
+    pub underline_thickness: i16,
+    // Set to 0 if the font is proportionally spaced, non-zero if monospaced
+    pub is_fixed_pitch: u32,
+    pub min_mem_type42: u32,
+    pub max_mem_type42: u32,
+    pub min_mem_type1: u32,
+    pub max_mem_type1: u32,
+}
+
+impl PostTable {
+    /// Parses raw 'post' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        Ok(PostTable {
+            version: try_read_u32_be(&mut buffer)?,
+            italic_angle: fixed_to_f32(try_read_i32_be(&mut buffer)?),
+            underline_position: try_read_i16_be(&mut buffer)?,
+            underline_thickness: try_read_i16_be(&mut buffer)?,
+            is_fixed_pitch: try_read_u32_be(&mut buffer)?,
+            min_mem_type42: try_read_u32_be(&mut buffer)?,
+            max_mem_type42: try_read_u32_be(&mut buffer)?,
+            min_mem_type1: try_read_u32_be(&mut buffer)?,
+            max_mem_type1: try_read_u32_be(&mut buffer)?,
+        })
+    }
+}
+
+/// Glyph names of version 2.0 'post' table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PostGlyphNames {
+    // Name index for every glyph: standard Macintosh name index
+    // or custom name index plus NUM_STANDARD_NAMES
+    pub(crate) name_indices: Vec<u16>,
+    // Raw (Pascal string contents) custom names
+    pub(crate) names: Vec<Vec<u8>>,
+}
+
+impl PostGlyphNames {
+    /// Parses glyph names of version 2.0 'post' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        if try_read_u32_be(&mut buffer)? != POST_VERSION_2_0 {
+            return Err(Error::InvalidTableData);
+        }
+        buffer.set_rpos(POST_HEADER_SIZE);
+        let num_glyphs = try_read_u16_be(&mut buffer)?;
+        let name_indices = (0..num_glyphs)
+            .map(|_| try_read_u16_be(&mut buffer))
+            .collect::<Result<Vec<u16>, Error>>()?;
+        let mut names: Vec<Vec<u8>> = vec![];
+        while buffer.get_rpos() < data.len() {
+            let length = try_read_u8(&mut buffer)? as usize;
+            names.push(try_slice(data, buffer.get_rpos(), length)?.to_vec());
+            buffer.set_rpos(buffer.get_rpos() + length);
+        }
+        Ok(PostGlyphNames { name_indices, names })
+    }
+
+    /// Serializes glyph names to the part of the table which follows the header
+    pub(crate) fn transform_to_u8_vec(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![];
+        data.extend_from_slice(&(self.name_indices.len() as u16).to_be_bytes());
+        self.name_indices.iter().for_each(|index| data.extend_from_slice(&index.to_be_bytes()));
+        for name in self.names.iter() {
+            data.push(name.len() as u8);
+            data.extend_from_slice(name);
+        }
+        data
+    }
+}
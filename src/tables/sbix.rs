@@ -0,0 +1,118 @@
+//! 'sbix' table parsing: strikes of glyph images in PNG, JPEG or TIFF format
+
+use crate::bitmap::{BitmapGlyph, ImageFormat};
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Strike of glyph images of one size
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbixStrike {
+    pub ppem: u16,
+    // Resolution the strike is designed for in pixels per inch
+    pub ppi: u16,
+    // Absolute offsets of glyph data in 'sbix' table for every glyph and the end offset
+    glyph_data_offsets: Vec<usize>,
+}
+
+/// Parsed 'sbix' table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbixTable {
+    pub flags: u16,
+    pub strikes: Vec<SbixStrike>,
+}
+
+impl SbixTable {
+    /// Parses raw 'sbix' table data using number of glyphs from 'maxp' table
+    pub(crate) fn parse(data: &[u8], num_glyphs: u16) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let version = try_read_u16_be(&mut buffer)?;
+        let flags = try_read_u16_be(&mut buffer)?;
+        let num_strikes = try_read_u32_be(&mut buffer)? as usize;
+        if version != 1 {
+            return Err(Error::InvalidTableData);
+        }
+
+        let mut strikes: Vec<SbixStrike> = Vec::with_capacity(num_strikes.min(data.len() / 4));
+        for _ in 0..num_strikes {
+            let strike_offset = try_read_u32_be(&mut buffer)? as usize;
+            let mut strike = ByteReader::from_bytes(data.get(strike_offset..).ok_or(Error::InvalidTableData)?);
+            let ppem = try_read_u16_be(&mut strike)?;
+            let ppi = try_read_u16_be(&mut strike)?;
+            let mut glyph_data_offsets: Vec<usize> = Vec::with_capacity(num_glyphs as usize + 1);
+            for _ in 0..=num_glyphs {
+                glyph_data_offsets.push(strike_offset + try_read_u32_be(&mut strike)? as usize);
+            }
+            strikes.push(SbixStrike { ppem, ppi, glyph_data_offsets });
+        }
+        Ok(SbixTable { flags, strikes })
+    }
+
+    /// Returns the image of the glyph in the strike from `sbix` data. 'dupe' glyphs are resolved.
+    /// `None` if the strike has no image of the glyph or the image has unsupported graphic type (e.g. 'pdf ')
+    pub fn glyph_image(&self, sbix: &[u8], strike_index: usize, glyph_id: u16) -> Result<Option<BitmapGlyph>, Error> {
+        let strike = self.strikes.get(strike_index).ok_or(Error::InvalidTableData)?;
+        let mut glyph_id = glyph_id;
+        // only one level of 'dupe' glyphs is followed
+        for _ in 0..2 {
+            let offsets = match strike.glyph_data_offsets.get(glyph_id as usize..glyph_id as usize + 2) {
+                Some(offsets) => offsets,
+                None => return Ok(None),
+            };
+            if offsets[1] < offsets[0] {
+                return Err(Error::InvalidTableData);
+            }
+            if offsets[1] == offsets[0] {
+                return Ok(None);
+            }
+            let glyph_data = try_slice(sbix, offsets[0], offsets[1] - offsets[0])?;
+            let mut buffer = ByteReader::from_bytes(glyph_data);
+            let origin_offset = (try_read_i16_be(&mut buffer)?, try_read_i16_be(&mut buffer)?);
+            let graphic_type = try_read_u32_be(&mut buffer)?;
+            let data = &glyph_data[8..];
+            let image_format = match graphic_type {
+                value if value == tag(b"png ") => ImageFormat::Png,
+                value if value == tag(b"jpg ") => ImageFormat::Jpeg,
+                value if value == tag(b"tiff") => ImageFormat::Tiff,
+                value if value == tag(b"dupe") => {
+                    glyph_id = try_read_u16_be(&mut buffer)?;
+                    continue;
+                }
+                _ => return Ok(None),
+            };
+            return Ok(Some(BitmapGlyph {
+                image_format,
+                data: data.to_vec(),
+                ppem_x: strike.ppem,
+                ppem_y: strike.ppem,
+                metrics: None,
+                origin_offset,
+            }));
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_glyph_image() {
+        let data = sbix_table(4, &[
+            (32, vec![(1, tag(b"png "), vec![1, 2, 3]), (2, tag(b"dupe"), vec![0, 1]), (3, tag(b"pdf "), vec![4])]),
+            (64, vec![(1, tag(b"jpg "), vec![5, 6])]),
+        ]);
+        let sbix = SbixTable::parse(&data, 4).unwrap();
+        debug_assert!(sbix.strikes.len() == 2 && sbix.strikes[1].ppem == 64 && sbix.strikes[1].ppi == 72);
+        let glyph = sbix.glyph_image(&data, 0, 1).unwrap().unwrap();
+        debug_assert!(glyph.image_format == ImageFormat::Png && glyph.data == vec![1, 2, 3]);
+        debug_assert!(glyph.origin_offset == (10, -10) && glyph.ppem_x == 32 && glyph.metrics.is_none());
+        debug_assert!(sbix.glyph_image(&data, 0, 2).unwrap() == Some(glyph));
+        debug_assert!(sbix.glyph_image(&data, 0, 3).unwrap().is_none());
+        debug_assert!(sbix.glyph_image(&data, 0, 0).unwrap().is_none());
+        debug_assert!(sbix.glyph_image(&data, 1, 1).unwrap().unwrap().image_format == ImageFormat::Jpeg);
+        debug_assert!(sbix.glyph_image(&data, 2, 1).is_err());
+    }
+}
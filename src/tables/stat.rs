@@ -0,0 +1,160 @@
+//! 'STAT' table parsing: design axes and axis value records
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+/// Axis value flag: the value is compatible with older families which use this value as default
+pub const OLDER_SIBLING_FONT_ATTRIBUTE: u16 = 0x0001;
+/// Axis value flag: the name of the value may be omitted in composed names (e.g. "Regular")
+pub const ELIDABLE_AXIS_VALUE_NAME: u16 = 0x0002;
+
+/// Design axis of the font family
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatAxisRecord {
+    pub tag: u32,
+    // 'name' table ID of the axis name
+    pub axis_name_id: u16,
+    // Order of the axis in composed names
+    pub axis_ordering: u16,
+}
+
+/// Axis value record. `axis_index` is the index in design axes of 'STAT' table
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatAxisValue {
+    // Single value of one axis
+    Format1 { axis_index: u16, flags: u16, value_name_id: u16, value: f32 },
+    // Range of values of one axis
+    Format2 { axis_index: u16, flags: u16, value_name_id: u16, nominal_value: f32, range_min_value: f32, range_max_value: f32 },
+    // Single value of one axis with its style-linked value (e.g. Bold for Regular)
+    Format3 { axis_index: u16, flags: u16, value_name_id: u16, value: f32, linked_value: f32 },
+    // Combination of values of several axes: (axis index, value) pairs
+    Format4 { flags: u16, value_name_id: u16, values: Vec<(u16, f32)> },
+}
+
+impl StatAxisValue {
+    /// Axis value flags
+    pub fn flags(&self) -> u16 {
+        match *self {
+            StatAxisValue::Format1 { flags, .. }
+            | StatAxisValue::Format2 { flags, .. }
+            | StatAxisValue::Format3 { flags, .. }
+            | StatAxisValue::Format4 { flags, .. } => flags,
+        }
+    }
+
+    /// 'name' table ID of the value name
+    pub fn value_name_id(&self) -> u16 {
+        match *self {
+            StatAxisValue::Format1 { value_name_id, .. }
+            | StatAxisValue::Format2 { value_name_id, .. }
+            | StatAxisValue::Format3 { value_name_id, .. }
+            | StatAxisValue::Format4 { value_name_id, .. } => value_name_id,
+        }
+    }
+}
+
+/// Parsed 'STAT' table
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatTable {
+    pub design_axes: Vec<StatAxisRecord>,
+    // Axis values of known formats, records of unknown formats are skipped
+    pub axis_values: Vec<StatAxisValue>,
+    // 'name' table ID used when all axis value names are elided, version 1.1 and later
+    pub elided_fallback_name_id: Option<u16>,
+}
+
+impl StatTable {
+    /// Parses raw 'STAT' table data
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(data);
+        let major_version = try_read_u16_be(&mut buffer)?;
+        let minor_version = try_read_u16_be(&mut buffer)?;
+        let design_axis_size = try_read_u16_be(&mut buffer)? as usize;
+        let design_axis_count = try_read_u16_be(&mut buffer)? as usize;
+        let design_axes_offset = try_read_u32_be(&mut buffer)? as usize;
+        let axis_value_count = try_read_u16_be(&mut buffer)? as usize;
+        let axis_value_offsets_offset = try_read_u32_be(&mut buffer)? as usize;
+        if major_version != 1 || design_axis_size < 8 {
+            return Err(Error::InvalidTableData);
+        }
+        let elided_fallback_name_id = if minor_version >= 1 { Some(try_read_u16_be(&mut buffer)?) } else { None };
+
+        let mut design_axes: Vec<StatAxisRecord> = Vec::with_capacity(design_axis_count);
+        for index in 0..design_axis_count {
+            let mut record = ByteReader::from_bytes(try_slice(data, design_axes_offset + index * design_axis_size, design_axis_size)?);
+            design_axes.push(StatAxisRecord {
+                tag: try_read_u32_be(&mut record)?,
+                axis_name_id: try_read_u16_be(&mut record)?,
+                axis_ordering: try_read_u16_be(&mut record)?,
+            });
+        }
+
+        let mut axis_values: Vec<StatAxisValue> = Vec::with_capacity(axis_value_count);
+        let offsets = try_slice(data, axis_value_offsets_offset, axis_value_count * 2)?;
+        for offset in offsets.chunks(2) {
+            let value_offset = axis_value_offsets_offset + u16::from_be_bytes([offset[0], offset[1]]) as usize;
+            let mut record = ByteReader::from_bytes(data.get(value_offset..).ok_or(Error::InvalidTableData)?);
+            let format = try_read_u16_be(&mut record)?;
+            let read_fixed = |record: &mut ByteReader| try_read_i32_be(record).map(fixed_to_f32);
+            let axis_value = match format {
+                1 => StatAxisValue::Format1 {
+                    axis_index: try_read_u16_be(&mut record)?,
+                    flags: try_read_u16_be(&mut record)?,
+                    value_name_id: try_read_u16_be(&mut record)?,
+                    value: read_fixed(&mut record)?,
+                },
+                2 => StatAxisValue::Format2 {
+                    axis_index: try_read_u16_be(&mut record)?,
+                    flags: try_read_u16_be(&mut record)?,
+                    value_name_id: try_read_u16_be(&mut record)?,
+                    nominal_value: read_fixed(&mut record)?,
+                    range_min_value: read_fixed(&mut record)?,
+                    range_max_value: read_fixed(&mut record)?,
+                },
+                3 => StatAxisValue::Format3 {
+                    axis_index: try_read_u16_be(&mut record)?,
+                    flags: try_read_u16_be(&mut record)?,
+                    value_name_id: try_read_u16_be(&mut record)?,
+                    value: read_fixed(&mut record)?,
+                    linked_value: read_fixed(&mut record)?,
+                },
+                4 => {
+                    let axis_count = try_read_u16_be(&mut record)?;
+                    let flags = try_read_u16_be(&mut record)?;
+                    let value_name_id = try_read_u16_be(&mut record)?;
+                    let values = (0..axis_count)
+                        .map(|_| Ok((try_read_u16_be(&mut record)?, read_fixed(&mut record)?)))
+                        .collect::<Result<Vec<(u16, f32)>, Error>>()?;
+                    StatAxisValue::Format4 { flags, value_name_id, values }
+                }
+                _ => continue,
+            };
+            axis_values.push(axis_value);
+        }
+        Ok(StatTable { design_axes, axis_values, elided_fallback_name_id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::stat_table;
+
+    #[test]
+    fn test_stat() {
+        let data = stat_table(&[(tag(b"wght"), 256)], 2);
+        let stat = StatTable::parse(&data).unwrap();
+        debug_assert!(stat.design_axes == vec![StatAxisRecord { tag: tag(b"wght"), axis_name_id: 256, axis_ordering: 0 }]);
+        debug_assert!(stat.elided_fallback_name_id == Some(2) && stat.axis_values.len() == 4);
+        debug_assert!(stat.axis_values[1] == StatAxisValue::Format2 {
+            axis_index: 0,
+            flags: 0,
+            value_name_id: 261,
+            nominal_value: 700.0,
+            range_min_value: 600.0,
+            range_max_value: 900.0,
+        });
+        debug_assert!(stat.axis_values[0].flags() == ELIDABLE_AXIS_VALUE_NAME && stat.axis_values[3].value_name_id() == 263);
+    }
+}
@@ -0,0 +1,152 @@
+//! 'SVG ' table parsing: SVG documents of glyph ID ranges
+
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+use flate2::read::GzDecoder;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Signature of gzip-compressed documents
+const GZIP_SIGNATURE: [u8; 2] = [0x1F, 0x8B];
+/// Maximum size of decompressed document. It stops small gzip bombs in untrusted fonts
+/// from expanding to gigabytes
+pub(crate) const MAX_SVG_DOCUMENT_SIZE: usize = 1 << 24;
+
+/// Range of glyphs sharing one SVG document
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SvgDocumentRange {
+    pub start_glyph_id: u16,
+    pub end_glyph_id: u16,
+    // Index of the unique document, see `SvgTable::document`
+    pub document_index: usize,
+}
+
+/// Parsed 'SVG ' table. Documents referenced by several glyph ranges are stored once
+pub struct SvgTable<'a> {
+    data: Cow<'a, [u8]>,
+    // Glyph ranges sorted by start glyph ID
+    pub ranges: Vec<SvgDocumentRange>,
+    // Absolute offsets and lengths of unique documents
+    documents: Vec<(usize, usize)>,
+}
+
+impl<'a> SvgTable<'a> {
+    /// Parses raw 'SVG ' table data
+    pub(crate) fn parse(data: Cow<'a, [u8]>) -> Result<Self, Error> {
+        let mut buffer = ByteReader::from_bytes(&data);
+        let version = try_read_u16_be(&mut buffer)?;
+        let document_list_offset = try_read_u32_be(&mut buffer)? as usize;
+        if version != 0 {
+            return Err(Error::InvalidTableData);
+        }
+
+        let mut list = ByteReader::from_bytes(data.get(document_list_offset..).ok_or(Error::InvalidTableData)?);
+        let num_entries = try_read_u16_be(&mut list)? as usize;
+        let mut ranges: Vec<SvgDocumentRange> = Vec::with_capacity(num_entries);
+        let mut documents: Vec<(usize, usize)> = vec![];
+        // indices of unique documents by their offsets and lengths
+        let mut document_indices: HashMap<(usize, usize), usize> = HashMap::new();
+        for _ in 0..num_entries {
+            let start_glyph_id = try_read_u16_be(&mut list)?;
+            let end_glyph_id = try_read_u16_be(&mut list)?;
+            let document_offset = document_list_offset + try_read_u32_be(&mut list)? as usize;
+            let document_length = try_read_u32_be(&mut list)? as usize;
+            if end_glyph_id < start_glyph_id {
+                return Err(Error::InvalidTableData);
+            }
+            try_slice(&data, document_offset, document_length)?;
+            let document = (document_offset, document_length);
+            let document_index = *document_indices.entry(document).or_insert_with(|| {
+                documents.push(document);
+                documents.len() - 1
+            });
+            ranges.push(SvgDocumentRange { start_glyph_id, end_glyph_id, document_index });
+        }
+        ranges.sort_by_key(|range| range.start_glyph_id);
+        Ok(SvgTable { data, ranges, documents })
+    }
+
+    /// Number of unique documents
+    pub fn documents_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Index of the document which contains the glyph, `None` if the glyph has no SVG document
+    pub fn document_index(&self, glyph_id: u16) -> Option<usize> {
+        let position = self.ranges.partition_point(|range| range.start_glyph_id <= glyph_id);
+        let range = self.ranges[..position].last()?;
+        (glyph_id <= range.end_glyph_id).then_some(range.document_index)
+    }
+
+    /// Text of the document, gzip-compressed documents are decompressed.
+    /// Returns `DecompressError` error if compressed document is broken or decompresses
+    /// to more than `MAX_SVG_DOCUMENT_SIZE` bytes
+    pub fn document(&self, document_index: usize) -> Result<Cow<'_, [u8]>, Error> {
+        let (offset, length) = *self.documents.get(document_index).ok_or(Error::InvalidTableData)?;
+        let document = &self.data[offset..offset + length];
+        if !document.starts_with(&GZIP_SIGNATURE) {
+            return Ok(Cow::Borrowed(document));
+        }
+        let mut decompressed: Vec<u8> = vec![];
+        GzDecoder::new(document)
+            .take(MAX_SVG_DOCUMENT_SIZE as u64 + 1)
+            .read_to_end(&mut decompressed)
+            .map_err(|_| Error::DecompressError)?;
+        if decompressed.len() > MAX_SVG_DOCUMENT_SIZE {
+            return Err(Error::DecompressError);
+        }
+        Ok(Cow::Owned(decompressed))
+    }
+
+    /// Text of the document which contains the glyph, `None` if the glyph has no SVG document.
+    /// Glyph elements in the document have "glyph<ID>" identifiers
+    pub fn glyph_document(&self, glyph_id: u16) -> Result<Option<Cow<'_, [u8]>>, Error> {
+        self.document_index(glyph_id).map(|index| self.document(index)).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn test_glyph_document() {
+        let shared = b"<svg><g id=\"glyph2\"/><g id=\"glyph7\"/></svg>".to_vec();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"<svg><g id=\"glyph5\"/></svg>").unwrap();
+        let compressed = encoder.finish().unwrap();
+        let data = svg_table(&[(7, 7, 0), (2, 3, 0), (5, 5, 1)], &[shared.clone(), compressed]);
+
+        let svg = SvgTable::parse(Cow::Owned(data)).unwrap();
+        debug_assert!(svg.documents_count() == 2 && svg.ranges[0].start_glyph_id == 2);
+        debug_assert!(svg.document_index(3) == Some(0) && svg.document_index(7) == Some(0));
+        for glyph_id in [0, 4, 6, 8] {
+            debug_assert!(svg.glyph_document(glyph_id).unwrap().is_none());
+        }
+        debug_assert!(*svg.glyph_document(2).unwrap().unwrap() == shared[..]);
+        debug_assert!(*svg.glyph_document(5).unwrap().unwrap() == b"<svg><g id=\"glyph5\"/></svg>"[..]);
+
+        let broken = svg_table(&[(1, 1, 0)], &[vec![0x1F, 0x8B, 8, 0, 1, 2]]);
+        let svg = SvgTable::parse(Cow::Owned(broken)).unwrap();
+        debug_assert!(svg.glyph_document(1) == Err(Error::DecompressError));
+    }
+
+    #[test]
+    fn test_gzip_bomb_document() {
+        let gzip = |size: usize| -> Vec<u8> {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+            encoder.write_all(&vec![b' '; size]).unwrap();
+            encoder.finish().unwrap()
+        };
+        let data = svg_table(&[(1, 1, 0), (2, 2, 1)], &[gzip(MAX_SVG_DOCUMENT_SIZE), gzip(MAX_SVG_DOCUMENT_SIZE + 1)]);
+        let svg = SvgTable::parse(Cow::Owned(data)).unwrap();
+        debug_assert!(svg.glyph_document(1).unwrap().unwrap().len() == MAX_SVG_DOCUMENT_SIZE);
+        debug_assert!(svg.glyph_document(2) == Err(Error::DecompressError));
+    }
+}
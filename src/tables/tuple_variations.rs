@@ -0,0 +1,184 @@
+//! Tuple variation store used by 'gvar' and 'cvar' tables
+
+use crate::tables::item_variations::region_scalar;
+use crate::utils::*;
+use crate::Error;
+use bytebuffer::ByteReader;
+
+// tupleVariationCount flags
+const SHARED_POINT_NUMBERS: u16 = 0x8000;
+const COUNT_MASK: u16 = 0x0FFF;
+
+// tupleIndex flags
+const EMBEDDED_PEAK_TUPLE: u16 = 0x8000;
+const INTERMEDIATE_REGION: u16 = 0x4000;
+const PRIVATE_POINT_NUMBERS: u16 = 0x2000;
+const TUPLE_INDEX_MASK: u16 = 0x0FFF;
+
+// packed point numbers control byte
+const POINTS_ARE_WORDS: u8 = 0x80;
+const POINT_RUN_COUNT_MASK: u8 = 0x7F;
+
+// packed deltas control byte
+const DELTAS_ARE_ZERO: u8 = 0x80;
+const DELTAS_ARE_WORDS: u8 = 0x40;
+const DELTAS_ARE_LONGS: u8 = 0xC0;
+const DELTA_RUN_COUNT_MASK: u8 = 0x3F;
+
+/// Deltas of one region of the variation space
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TupleVariation {
+    // Peak normalized coordinate of every axis
+    pub(crate) peak: Vec<f32>,
+    // Start and end coordinates of every axis if the tuple has intermediate region
+    pub(crate) intermediate: Option<(Vec<f32>, Vec<f32>)>,
+    // Indices of points with explicit deltas, `None` means all points
+    pub(crate) points: Option<Vec<u16>>,
+    // Delta for every point of `points`; Y deltas are empty for 'cvar' table
+    pub(crate) x_deltas: Vec<i32>,
+    pub(crate) y_deltas: Vec<i32>,
+}
+
+impl TupleVariation {
+    /// Scalar of the tuple deltas at normalized `coordinates`
+    pub(crate) fn scalar(&self, coordinates: &[f32]) -> f32 {
+        self.peak.iter().enumerate().map(|(axis, &peak)| {
+            let coordinate = coordinates.get(axis).copied().unwrap_or(0.0);
+            match &self.intermediate {
+                Some((start, end)) => region_scalar(start[axis], peak, end[axis], coordinate),
+                None => region_scalar(peak.min(0.0), peak, peak.max(0.0), coordinate),
+            }
+        }).product()
+    }
+}
+
+/// Parses tuple variations. `data` is the data `dataOffset` field is relative to, `header_offset` is the offset
+/// of tupleVariationCount field in it. `point_count` is the number of points (or CVT values) which tuples
+/// without point numbers have deltas for, Y deltas are read only if `has_y_deltas` is set
+pub(crate) fn parse_tuple_variations(
+    data: &[u8],
+    header_offset: usize,
+    axis_count: u16,
+    shared_tuples: &[Vec<f32>],
+    point_count: usize,
+    has_y_deltas: bool,
+) -> Result<Vec<TupleVariation>, Error> {
+    let mut headers = ByteReader::from_bytes(data.get(header_offset..).ok_or(Error::InvalidTableData)?);
+    let tuple_variation_count = try_read_u16_be(&mut headers)?;
+    let data_offset = try_read_u16_be(&mut headers)? as usize;
+    let mut serialized = ByteReader::from_bytes(data.get(data_offset..).ok_or(Error::InvalidTableData)?);
+    let shared_points = if tuple_variation_count & SHARED_POINT_NUMBERS != 0 {
+        parse_points(&mut serialized)?
+    } else {
+        None
+    };
+    let mut tuple_data_offset = data_offset + serialized.get_rpos();
+
+    let read_tuple = |headers: &mut ByteReader| -> Result<Vec<f32>, Error> {
+        (0..axis_count).map(|_| try_read_i16_be(headers).map(f2dot14_to_f32)).collect()
+    };
+    let mut variations: Vec<TupleVariation> = Vec::with_capacity((tuple_variation_count & COUNT_MASK) as usize);
+    for _ in 0..tuple_variation_count & COUNT_MASK {
+        let variation_data_size = try_read_u16_be(&mut headers)? as usize;
+        let tuple_index = try_read_u16_be(&mut headers)?;
+        let peak = if tuple_index & EMBEDDED_PEAK_TUPLE != 0 {
+            read_tuple(&mut headers)?
+        } else {
+            shared_tuples.get((tuple_index & TUPLE_INDEX_MASK) as usize).ok_or(Error::InvalidTableData)?.clone()
+        };
+        let intermediate = if tuple_index & INTERMEDIATE_REGION != 0 {
+            Some((read_tuple(&mut headers)?, read_tuple(&mut headers)?))
+        } else {
+            None
+        };
+
+        let mut buffer = ByteReader::from_bytes(try_slice(data, tuple_data_offset, variation_data_size)?);
+        tuple_data_offset += variation_data_size;
+        let points = if tuple_index & PRIVATE_POINT_NUMBERS != 0 {
+            parse_points(&mut buffer)?
+        } else {
+            shared_points.clone()
+        };
+        let count = points.as_ref().map(Vec::len).unwrap_or(point_count);
+        let x_deltas = parse_deltas(&mut buffer, count)?;
+        let y_deltas = if has_y_deltas { parse_deltas(&mut buffer, count)? } else { vec![] };
+        variations.push(TupleVariation { peak, intermediate, points, x_deltas, y_deltas });
+    }
+    Ok(variations)
+}
+
+/// Parses packed point numbers. Returns `None` if deltas are for all points
+fn parse_points(buffer: &mut ByteReader) -> Result<Option<Vec<u16>>, Error> {
+    let first = try_read_u8(buffer)?;
+    let count = if first & POINTS_ARE_WORDS != 0 {
+        ((first & POINT_RUN_COUNT_MASK) as usize) << 8 | try_read_u8(buffer)? as usize
+    } else {
+        first as usize
+    };
+    if count == 0 {
+        return Ok(None);
+    }
+    let mut points: Vec<u16> = Vec::with_capacity(count);
+    let mut point: u16 = 0;
+    while points.len() < count {
+        let control = try_read_u8(buffer)?;
+        let run_count = (control & POINT_RUN_COUNT_MASK) as usize + 1;
+        if points.len() + run_count > count {
+            return Err(Error::InvalidTableData);
+        }
+        for _ in 0..run_count {
+            let delta = if control & POINTS_ARE_WORDS != 0 { try_read_u16_be(buffer)? } else { try_read_u8(buffer)? as u16 };
+            point = point.wrapping_add(delta);
+            points.push(point);
+        }
+    }
+    Ok(Some(points))
+}
+
+/// Parses `count` packed deltas
+fn parse_deltas(buffer: &mut ByteReader, count: usize) -> Result<Vec<i32>, Error> {
+    let mut deltas: Vec<i32> = Vec::with_capacity(count);
+    while deltas.len() < count {
+        let control = try_read_u8(buffer)?;
+        let run_count = (control & DELTA_RUN_COUNT_MASK) as usize + 1;
+        if deltas.len() + run_count > count {
+            return Err(Error::InvalidTableData);
+        }
+        for _ in 0..run_count {
+            deltas.push(match control & DELTAS_ARE_LONGS {
+                DELTAS_ARE_ZERO => 0,
+                DELTAS_ARE_WORDS => try_read_i16_be(buffer)? as i32,
+                DELTAS_ARE_LONGS => try_read_i32_be(buffer)?,
+                _ => try_read_i8(buffer)? as i32,
+            });
+        }
+    }
+    Ok(deltas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packed_points_and_deltas() {
+        // 3 points: run of 2 byte points (1, +2) and run of 1 word point (+300)
+        let mut buffer = ByteReader::from_bytes(&[3, 1, 1, 2, 0x80, 1, 44]);
+        debug_assert!(parse_points(&mut buffer).unwrap() == Some(vec![1, 3, 303]));
+        let mut buffer = ByteReader::from_bytes(&[0]);
+        debug_assert!(parse_points(&mut buffer).unwrap().is_none());
+        // 2 byte deltas, 2 zero deltas and 1 word delta
+        let mut buffer = ByteReader::from_bytes(&[1, 5, 0xFB, 0x81, 0x40, 0x01, 0x00]);
+        debug_assert!(parse_deltas(&mut buffer, 5).unwrap() == vec![5, -5, 0, 0, 256]);
+        let mut buffer = ByteReader::from_bytes(&[3, 1, 2, 3, 4]);
+        debug_assert!(parse_deltas(&mut buffer, 2) == Err(Error::InvalidTableData));
+    }
+
+    #[test]
+    fn test_tuple_scalar() {
+        let tuple = TupleVariation { peak: vec![1.0, 0.0], intermediate: None, points: None, x_deltas: vec![], y_deltas: vec![] };
+        debug_assert!(tuple.scalar(&[0.5, 0.7]) == 0.5 && tuple.scalar(&[-0.5, 0.0]) == 0.0);
+        let tuple = TupleVariation { intermediate: Some((vec![0.25, 0.0], vec![1.0, 0.0])), peak: vec![0.5, 0.0], ..tuple };
+        debug_assert!(tuple.scalar(&[0.75, 0.0]) == 0.5 && tuple.scalar(&[0.2, 0.0]) == 0.0);
+    }
+}
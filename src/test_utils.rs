@@ -0,0 +1,1640 @@
+//! Helpers for building small synthetic fonts used by unit tests
+
+use crate::eot::EotHeader;
+use crate::font::{FontTables, Sfnt};
+use crate::mtx::ctf::*;
+use crate::mtx::lzcomp::*;
+use crate::structures::SfntHeaderTable;
+use crate::tables::glyf::Glyph;
+use crate::tables::cpal::Color;
+use crate::utils::*;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+/// Calculates SFNT table checksum
+pub fn table_checksum(data: &[u8]) -> u32 {
+    let mut sum: u32 = 0;
+    for chunk in data.chunks(4) {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum = sum.wrapping_add(u32::from_be_bytes(word));
+    }
+    sum
+}
+
+/// Builds SFNT binary with tables sorted by tag and laid out in tag order
+pub fn build_sfnt(version: u32, tables: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut tables = tables.to_vec();
+    tables.sort_by_key(|(tag, _)| *tag);
+    let num_tables = tables.len() as u16;
+    let search_range = calculate_search_range(num_tables);
+    let mut result: Vec<u8> = vec![];
+    result.extend_from_slice(&version.to_be_bytes());
+    result.extend_from_slice(&num_tables.to_be_bytes());
+    result.extend_from_slice(&search_range.to_be_bytes());
+    result.extend_from_slice(&calculate_entry_selector(search_range).to_be_bytes());
+    result.extend_from_slice(&calculate_range_shift(num_tables, search_range).to_be_bytes());
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, data) in tables.iter() {
+        result.extend_from_slice(&tag.to_be_bytes());
+        result.extend_from_slice(&table_checksum(data).to_be_bytes());
+        result.extend_from_slice(&(offset as u32).to_be_bytes());
+        result.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        offset += calculate_padded_len(data.len() as u32) as usize;
+    }
+    for (_, data) in tables.iter() {
+        result.extend_from_slice(data);
+        result.resize(calculate_padded_len(result.len() as u32) as usize, 0);
+    }
+    result
+}
+
+/// Builds SFNT binary with directory sorted by tag but tables data laid out in the given order
+pub fn build_sfnt_in_order(version: u32, tables: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut records: Vec<(u32, u32, usize)> = vec![];
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, data) in tables.iter() {
+        records.push((*tag, table_checksum(data), offset));
+        offset += calculate_padded_len(data.len() as u32) as usize;
+    }
+    let mut sorted = tables.to_vec();
+    sorted.sort_by_key(|(tag, _)| *tag);
+    records.sort_by_key(|(tag, _, _)| *tag);
+    let mut result = build_sfnt(version, &sorted);
+    for (index, (_, _, offset)) in records.iter().enumerate() {
+        let position = 12 + 16 * index + 8;
+        result[position..position + 4].copy_from_slice(&(*offset as u32).to_be_bytes());
+    }
+    result.truncate(12 + 16 * tables.len());
+    for (_, data) in tables.iter() {
+        result.extend_from_slice(data);
+        result.resize(calculate_padded_len(result.len() as u32) as usize, 0);
+    }
+    result
+}
+
+/// Sets checkSumAdjustment of 'head' table of SFNT binary for the whole font
+pub fn set_check_sum_adjustment(sfnt: &mut [u8]) {
+    let num_tables = u16::from_be_bytes([sfnt[4], sfnt[5]]) as usize;
+    let head = (0..num_tables).map(|index| &sfnt[12 + 16 * index..28 + 16 * index]).find(|record| &record[..4] == b"head");
+    let Some(record) = head else {
+        return;
+    };
+    let offset = u32::from_be_bytes([record[8], record[9], record[10], record[11]]) as usize + 8;
+    sfnt[offset..offset + 4].fill(0);
+    let adjustment = 0xB1B0AFBAu32.wrapping_sub(table_checksum(sfnt));
+    sfnt[offset..offset + 4].copy_from_slice(&adjustment.to_be_bytes());
+}
+
+/// Encodes SFNT binary to WOFF keeping the physical order of tables data
+pub fn sfnt_to_woff(sfnt: &[u8]) -> Vec<u8> {
+    let num_tables = u16::from_be_bytes([sfnt[4], sfnt[5]]) as usize;
+    let mut tables: Vec<(u32, usize, Vec<u8>)> = (0..num_tables)
+        .map(|index| {
+            let record = &sfnt[12 + 16 * index..28 + 16 * index];
+            let offset = u32::from_be_bytes([record[8], record[9], record[10], record[11]]) as usize;
+            let length = u32::from_be_bytes([record[12], record[13], record[14], record[15]]) as usize;
+            (u32::from_be_bytes([record[0], record[1], record[2], record[3]]), offset, sfnt[offset..offset + length].to_vec())
+        })
+        .collect();
+    tables.sort_by_key(|(_, offset, _)| *offset);
+    let tables: Vec<(u32, Vec<u8>)> = tables.into_iter().map(|(tag, _, data)| (tag, data)).collect();
+    let mut woff = build_woff(u32::from_be_bytes([sfnt[0], sfnt[1], sfnt[2], sfnt[3]]), &tables);
+
+    // WOFF table directory has to be sorted by tag too
+    let mut directory: Vec<[u8; 20]> = woff[44..44 + 20 * num_tables]
+        .chunks(20)
+        .map(|entry| entry.try_into().unwrap())
+        .collect();
+    directory.sort();
+    woff[44..44 + 20 * num_tables].copy_from_slice(&directory.concat());
+    woff
+}
+
+/// Builds TrueType collection binary where every font has its own copy of tables data
+pub fn build_collection(fonts: &[&[(u32, Vec<u8>)]]) -> Vec<u8> {
+    let header_size = 12 + 4 * fonts.len();
+    let mut result: Vec<u8> = vec![];
+    result.extend_from_slice(b"ttcf");
+    result.extend_from_slice(&[0, 1, 0, 0]);
+    result.extend_from_slice(&(fonts.len() as u32).to_be_bytes());
+    let mut font_data: Vec<u8> = vec![];
+    for tables in fonts {
+        let font_offset = header_size + font_data.len();
+        result.extend_from_slice(&(font_offset as u32).to_be_bytes());
+        let mut sfnt = build_sfnt(0x00010000, tables);
+        // shift table offsets to be relative to the beginning of the collection
+        for index in 0..tables.len() {
+            let position = 12 + 16 * index + 8;
+            let offset = u32::from_be_bytes(sfnt[position..position + 4].try_into().unwrap()) + font_offset as u32;
+            sfnt[position..position + 4].copy_from_slice(&offset.to_be_bytes());
+        }
+        font_data.extend_from_slice(&sfnt);
+    }
+    result.extend_from_slice(&font_data);
+    result
+}
+
+/// Builds EOT binary from header fields and font data; sizes are taken from the data
+pub fn build_eot(header: &EotHeader, font_data: &[u8]) -> Vec<u8> {
+    fn name(value: &str) -> Vec<u8> {
+        let data: Vec<u8> = value.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        [vec![0, 0], (data.len() as u16).to_le_bytes().to_vec(), data].concat()
+    }
+    let mut result: Vec<u8> = vec![0; 8];
+    result.extend_from_slice(&header.version.to_le_bytes());
+    result.extend_from_slice(&header.flags.to_le_bytes());
+    result.extend_from_slice(&header.font_panose);
+    result.push(header.charset);
+    result.push(header.italic);
+    result.extend_from_slice(&header.weight.to_le_bytes());
+    result.extend_from_slice(&header.fs_type.to_le_bytes());
+    result.extend_from_slice(&0x504Cu16.to_le_bytes());
+    header.unicode_range.iter().for_each(|range| result.extend_from_slice(&range.to_le_bytes()));
+    header.code_page_range.iter().for_each(|range| result.extend_from_slice(&range.to_le_bytes()));
+    result.extend_from_slice(&header.check_sum_adjustment.to_le_bytes());
+    result.extend_from_slice(&[0; 16]);
+    result.extend(name(&header.family_name));
+    result.extend(name(&header.style_name));
+    result.extend(name(&header.version_name));
+    result.extend(name(&header.full_name));
+    if header.version >= 0x00020001 {
+        result.extend(name(&header.root_string));
+    }
+    if header.version == 0x00020002 {
+        result.extend_from_slice(&header.root_string_checksum.to_le_bytes());
+        result.extend_from_slice(&header.eudc_code_page.to_le_bytes());
+        result.extend_from_slice(&[0, 0]);
+        result.extend_from_slice(&(header.signature.len() as u16).to_le_bytes());
+        result.extend_from_slice(&header.signature);
+        result.extend_from_slice(&header.eudc_flags.to_le_bytes());
+        result.extend_from_slice(&(header.eudc_font_data.len() as u32).to_le_bytes());
+        result.extend_from_slice(&header.eudc_font_data);
+    }
+    result.extend_from_slice(font_data);
+    let eot_size = result.len() as u32;
+    result[0..4].copy_from_slice(&eot_size.to_le_bytes());
+    result[4..8].copy_from_slice(&(font_data.len() as u32).to_le_bytes());
+    result
+}
+
+/// Builds WOFF binary from tables; tables data is placed in the given order
+/// and every table which becomes smaller after compression is stored compressed
+pub fn build_woff(flavor: u32, tables: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let num_tables = tables.len();
+    let mut directory: Vec<u8> = vec![];
+    let mut table_data: Vec<u8> = vec![];
+    let data_start = 44 + 20 * num_tables;
+    let mut total_sfnt_size = 12 + 16 * num_tables as u32;
+    for (tag, data) in tables.iter() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let stored = if compressed.len() < data.len() { compressed } else { data.clone() };
+        directory.extend_from_slice(&tag.to_be_bytes());
+        directory.extend_from_slice(&((data_start + table_data.len()) as u32).to_be_bytes());
+        directory.extend_from_slice(&(stored.len() as u32).to_be_bytes());
+        directory.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        directory.extend_from_slice(&table_checksum(data).to_be_bytes());
+        table_data.extend_from_slice(&stored);
+        table_data.resize(calculate_padded_len(table_data.len() as u32) as usize, 0);
+        total_sfnt_size += calculate_padded_len(data.len() as u32);
+    }
+    let length = (data_start + table_data.len()) as u32;
+    let mut result: Vec<u8> = vec![];
+    result.extend_from_slice(b"wOFF");
+    result.extend_from_slice(&flavor.to_be_bytes());
+    result.extend_from_slice(&length.to_be_bytes());
+    result.extend_from_slice(&(num_tables as u16).to_be_bytes());
+    result.extend_from_slice(&0u16.to_be_bytes());
+    result.extend_from_slice(&total_sfnt_size.to_be_bytes());
+    result.extend_from_slice(&[0, 1, 0, 0]);
+    result.extend_from_slice(&[0; 20]);
+    result.extend_from_slice(&directory);
+    result.extend_from_slice(&table_data);
+    result
+}
+
+/// Builds 'head' table data
+pub fn head_table(units_per_em: u16, index_to_loc_format: i16) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![];
+    data.extend_from_slice(&[0, 1, 0, 0]);
+    data.extend_from_slice(&0x0001_8000u32.to_be_bytes());
+    data.extend_from_slice(&0u32.to_be_bytes());
+    data.extend_from_slice(&0x5F0F_3CF5u32.to_be_bytes());
+    data.extend_from_slice(&0x000Bu16.to_be_bytes());
+    data.extend_from_slice(&units_per_em.to_be_bytes());
+    // created: 2020-01-01T00:00:00Z, modified: 1970-01-01T00:00:00Z
+    data.extend_from_slice(&(1_577_836_800u64 + 2_082_844_800).to_be_bytes());
+    data.extend_from_slice(&2_082_844_800u64.to_be_bytes());
+    for value in [-100i16, -200, 900, 800] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    data.extend_from_slice(&0u16.to_be_bytes());
+    data.extend_from_slice(&8u16.to_be_bytes());
+    data.extend_from_slice(&2i16.to_be_bytes());
+    data.extend_from_slice(&index_to_loc_format.to_be_bytes());
+    data.extend_from_slice(&0i16.to_be_bytes());
+    data
+}
+
+/// Builds 'name' table data; format 1 is used if `lang_tags` isn't empty.
+/// Each record is (platform ID, encoding ID, language ID, name ID, raw string)
+pub fn name_table(records: &[(u16, u16, u16, u16, Vec<u8>)], lang_tags: &[&str]) -> Vec<u8> {
+    let format: u16 = if lang_tags.is_empty() { 0 } else { 1 };
+    let mut header: Vec<u8> = vec![];
+    let mut storage: Vec<u8> = vec![];
+    let header_len = 6 + 12 * records.len() + if format == 1 { 2 + 4 * lang_tags.len() } else { 0 };
+    header.extend_from_slice(&format.to_be_bytes());
+    header.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    header.extend_from_slice(&(header_len as u16).to_be_bytes());
+    for (platform_id, encoding_id, language_id, name_id, raw) in records {
+        for value in [*platform_id, *encoding_id, *language_id, *name_id, raw.len() as u16, storage.len() as u16] {
+            header.extend_from_slice(&value.to_be_bytes());
+        }
+        storage.extend_from_slice(raw);
+    }
+    if format == 1 {
+        header.extend_from_slice(&(lang_tags.len() as u16).to_be_bytes());
+        for lang_tag in lang_tags {
+            let raw = utf16_be(lang_tag);
+            header.extend_from_slice(&(raw.len() as u16).to_be_bytes());
+            header.extend_from_slice(&(storage.len() as u16).to_be_bytes());
+            storage.extend_from_slice(&raw);
+        }
+    }
+    header.extend_from_slice(&storage);
+    header
+}
+
+/// Encodes string to UTF-16 in big endian order
+pub fn utf16_be(value: &str) -> Vec<u8> {
+    value.encode_utf16().flat_map(|unit| unit.to_be_bytes()).collect()
+}
+
+/// Builds 'hhea' table data
+pub fn hhea_table(ascender: i16, descender: i16, line_gap: i16, number_of_h_metrics: u16) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![0, 1, 0, 0];
+    for value in [ascender, descender, line_gap] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    data.extend_from_slice(&[0u8; 24]);
+    data.extend_from_slice(&number_of_h_metrics.to_be_bytes());
+    data
+}
+
+/// Builds version 1.0 'maxp' table data with the given number of glyphs and all maxima set to `max_value`
+pub fn maxp_table(num_glyphs: u16, max_value: u16) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![0, 1, 0, 0];
+    data.extend_from_slice(&num_glyphs.to_be_bytes());
+    for _ in 0..13 {
+        data.extend_from_slice(&max_value.to_be_bytes());
+    }
+    data
+}
+
+/// Builds 'OS/2' table data of the given version with the given weight class and fsType.
+/// Version 0 table is built in its short (68 bytes) Apple form
+pub fn os2_table(version: u16, weight_class: u16, fs_type: u16) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![];
+    data.extend_from_slice(&version.to_be_bytes());
+    data.extend_from_slice(&500i16.to_be_bytes());
+    data.extend_from_slice(&weight_class.to_be_bytes());
+    data.extend_from_slice(&5u16.to_be_bytes());
+    data.extend_from_slice(&fs_type.to_be_bytes());
+    // subscript, superscript, strikeout and family class values
+    for value in 0..11i16 {
+        data.extend_from_slice(&(value * 10).to_be_bytes());
+    }
+    data.extend_from_slice(&[2, 11, 5, 2, 4, 5, 4, 2, 2, 4]);
+    data.extend_from_slice(&[0xFF; 16]);
+    data.extend_from_slice(b"TEST");
+    // fsSelection with USE_TYPO_METRICS set, first and last char indices
+    for value in [0x00C0u16, 0x20, 0xFFFF] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    if version == 0 {
+        return data;
+    }
+    for value in [800i16, -200, 100] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    for value in [1000u16, 300] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    data.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0]);
+    if version >= 2 {
+        for value in [500u16, 700, 0, 32, 2] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+    if version >= 5 {
+        data.extend_from_slice(&[0, 20, 0xFF, 0xFF]);
+    }
+    data
+}
+
+/// Builds version 3.0 'post' table data
+pub fn post_table(italic_angle: i32, underline_position: i16) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![0, 3, 0, 0];
+    data.extend_from_slice(&italic_angle.to_be_bytes());
+    data.extend_from_slice(&underline_position.to_be_bytes());
+    data.extend_from_slice(&50i16.to_be_bytes());
+    data.extend_from_slice(&[0u8; 20]);
+    data
+}
+
+/// Builds 'cmap' table data with format 4 (Windows BMP) subtable which maps BMP characters
+/// with one segment per character, format 12 (Windows full repertoire) subtable for all characters
+/// and format 14 subtable for `variations` (selector, base character, Some(glyph) or None for default glyph)
+pub fn cmap_table(mappings: &[(u32, u16)], variations: &[(u32, u32, Option<u16>)]) -> Vec<u8> {
+    let mut mappings = mappings.to_vec();
+    mappings.sort_unstable();
+    let bmp: Vec<(u32, u16)> = mappings.iter().copied().filter(|(code, _)| *code < 0xFFFF).collect();
+
+    let seg_count = bmp.len() + 1;
+    let mut format4: Vec<u8> = vec![];
+    for value in [4u16, (16 + seg_count * 8) as u16, 0, (seg_count * 2) as u16, 0, 0, 0] {
+        format4.extend_from_slice(&value.to_be_bytes());
+    }
+    bmp.iter().for_each(|(code, _)| format4.extend_from_slice(&(*code as u16).to_be_bytes()));
+    format4.extend_from_slice(&[0xFF, 0xFF, 0, 0]);
+    bmp.iter().for_each(|(code, _)| format4.extend_from_slice(&(*code as u16).to_be_bytes()));
+    format4.extend_from_slice(&[0xFF, 0xFF]);
+    bmp.iter().for_each(|(code, glyph)| format4.extend_from_slice(&glyph.wrapping_sub(*code as u16).to_be_bytes()));
+    format4.extend_from_slice(&1u16.to_be_bytes());
+    format4.extend_from_slice(&vec![0u8; seg_count * 2]);
+
+    let mut format12: Vec<u8> = vec![0, 12, 0, 0];
+    format12.extend_from_slice(&(16 + mappings.len() as u32 * 12).to_be_bytes());
+    format12.extend_from_slice(&0u32.to_be_bytes());
+    format12.extend_from_slice(&(mappings.len() as u32).to_be_bytes());
+    for (code, glyph) in mappings.iter() {
+        for value in [*code, *code, *glyph as u32] {
+            format12.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    let mut selectors: Vec<u32> = variations.iter().map(|variation| variation.0).collect();
+    selectors.sort_unstable();
+    selectors.dedup();
+    let mut format14: Vec<u8> = vec![0, 14, 0, 0, 0, 0];
+    format14.extend_from_slice(&(selectors.len() as u32).to_be_bytes());
+    let mut uvs_data: Vec<u8> = vec![];
+    let records_end = 10 + selectors.len() * 11;
+    for selector in selectors.iter() {
+        let defaults: Vec<u32> = variations.iter().filter(|v| v.0 == *selector && v.2.is_none()).map(|v| v.1).collect();
+        let non_defaults: Vec<(u32, u16)> =
+            variations.iter().filter(|v| v.0 == *selector).filter_map(|v| v.2.map(|glyph| (v.1, glyph))).collect();
+        format14.extend_from_slice(&selector.to_be_bytes()[1..]);
+        format14.extend_from_slice(&((records_end + uvs_data.len()) as u32).to_be_bytes());
+        uvs_data.extend_from_slice(&(defaults.len() as u32).to_be_bytes());
+        defaults.iter().for_each(|code| uvs_data.extend_from_slice(&(code << 8).to_be_bytes()));
+        format14.extend_from_slice(&((records_end + uvs_data.len()) as u32).to_be_bytes());
+        uvs_data.extend_from_slice(&(non_defaults.len() as u32).to_be_bytes());
+        for (code, glyph) in non_defaults {
+            uvs_data.extend_from_slice(&code.to_be_bytes()[1..]);
+            uvs_data.extend_from_slice(&glyph.to_be_bytes());
+        }
+    }
+    format14.extend_from_slice(&uvs_data);
+    let format14_len = format14.len() as u32;
+    format14[2..6].copy_from_slice(&format14_len.to_be_bytes());
+
+    let mut data: Vec<u8> = vec![0, 0, 0, 3];
+    let mut offset = 4 + 3 * 8;
+    for (platform_id, encoding_id, subtable) in [(0u16, 5u16, &format14), (3, 1, &format4), (3, 10, &format12)] {
+        data.extend_from_slice(&platform_id.to_be_bytes());
+        data.extend_from_slice(&encoding_id.to_be_bytes());
+        data.extend_from_slice(&(offset as u32).to_be_bytes());
+        offset += subtable.len();
+    }
+    for subtable in [format14, format4, format12] {
+        data.extend_from_slice(&subtable);
+    }
+    data
+}
+
+/// Builds simple glyph data; every coordinate is stored as 16-bit delta
+pub fn simple_glyph(contours: &[&[(i16, i16, bool)]]) -> Vec<u8> {
+    let points: Vec<(i16, i16, bool)> = contours.iter().flat_map(|contour| contour.iter().copied()).collect();
+    let mut data: Vec<u8> = vec![];
+    data.extend_from_slice(&(contours.len() as i16).to_be_bytes());
+    let x_min = points.iter().map(|point| point.0).min().unwrap_or_default();
+    let y_min = points.iter().map(|point| point.1).min().unwrap_or_default();
+    let x_max = points.iter().map(|point| point.0).max().unwrap_or_default();
+    let y_max = points.iter().map(|point| point.1).max().unwrap_or_default();
+    for value in [x_min, y_min, x_max, y_max] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    let mut end = 0usize;
+    for contour in contours {
+        end += contour.len();
+        data.extend_from_slice(&((end - 1) as u16).to_be_bytes());
+    }
+    data.extend_from_slice(&0u16.to_be_bytes());
+    points.iter().for_each(|point| data.push(point.2 as u8));
+    let mut previous = (0i16, 0i16);
+    for point in points.iter() {
+        data.extend_from_slice(&(point.0 - previous.0).to_be_bytes());
+        previous.0 = point.0;
+    }
+    for point in points.iter() {
+        data.extend_from_slice(&(point.1 - previous.1).to_be_bytes());
+        previous.1 = point.1;
+    }
+    data
+}
+
+/// Builds composite glyph data from components (glyph ID, x offset, y offset, scale)
+pub fn composite_glyph(bounds: [i16; 4], components: &[(u16, i16, i16, Option<f32>)]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![];
+    data.extend_from_slice(&(-1i16).to_be_bytes());
+    for value in bounds {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    for (index, (glyph_id, dx, dy, scale)) in components.iter().enumerate() {
+        let mut flags: u16 = 0x0001 | 0x0002;
+        if index + 1 < components.len() {
+            flags |= 0x0020;
+        }
+        if scale.is_some() {
+            flags |= 0x0008;
+        }
+        data.extend_from_slice(&flags.to_be_bytes());
+        data.extend_from_slice(&glyph_id.to_be_bytes());
+        data.extend_from_slice(&dx.to_be_bytes());
+        data.extend_from_slice(&dy.to_be_bytes());
+        if let Some(scale) = scale {
+            data.extend_from_slice(&((scale * 16384.0) as i16).to_be_bytes());
+        }
+    }
+    data
+}
+
+/// Builds 'glyf' and long format 'loca' tables data from glyphs data
+pub fn glyf_and_loca(glyphs: &[Vec<u8>]) -> (Vec<u8>, Vec<u8>) {
+    let mut glyf: Vec<u8> = vec![];
+    let mut loca: Vec<u8> = vec![];
+    for glyph in glyphs {
+        loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+        glyf.extend_from_slice(glyph);
+        glyf.resize(calculate_padded_len(glyf.len() as u32) as usize, 0);
+    }
+    loca.extend_from_slice(&(glyf.len() as u32).to_be_bytes());
+    (glyf, loca)
+}
+
+/// Builds tables of a small TrueType font with glyphs: .notdef, square, triangle,
+/// empty glyph and composite glyph made of the square and the scaled and shifted triangle
+pub fn truetype_font_tables() -> Vec<(u32, Vec<u8>)> {
+    let glyphs = vec![
+        simple_glyph(&[&[(0, 0, true), (0, 700, true), (500, 700, true), (500, 0, true)]]),
+        simple_glyph(&[&[(100, 0, true), (100, 500, true), (600, 500, true), (600, 0, true)]]),
+        simple_glyph(&[&[(0, 0, true), (200, 400, false), (400, 0, true)]]),
+        vec![],
+        composite_glyph([0, 0, 800, 500], &[(1, 0, 0, None), (2, 600, 0, Some(0.5))]),
+    ];
+    let (glyf, loca) = glyf_and_loca(&glyphs);
+    let mut hmtx: Vec<u8> = vec![];
+    for (advance, lsb) in [(500u16, 0i16), (700, 100), (400, 0), (250, 0), (800, 0)] {
+        hmtx.extend_from_slice(&advance.to_be_bytes());
+        hmtx.extend_from_slice(&lsb.to_be_bytes());
+    }
+    vec![
+        (tag(b"head"), head_table(1000, 1)),
+        (tag(b"hhea"), hhea_table(800, -200, 0, 5)),
+        (tag(b"maxp"), maxp_table(5, 16)),
+        (tag(b"OS/2"), os2_table(4, 400, 0)),
+        (tag(b"post"), post_table(0, -100)),
+        (tag(b"cmap"), cmap_table(&[(0x41, 1), (0x42, 2), (0x20, 3), (0xC4, 4)], &[])),
+        (tag(b"glyf"), glyf),
+        (tag(b"loca"), loca),
+        (tag(b"hmtx"), hmtx),
+    ]
+}
+
+/// Builds CFF INDEX with 4-bytes offsets; count is 32-bit for CFF2
+pub fn cff_index(items: &[Vec<u8>], is_cff2: bool) -> Vec<u8> {
+    let mut data: Vec<u8> = if is_cff2 {
+        (items.len() as u32).to_be_bytes().to_vec()
+    } else {
+        (items.len() as u16).to_be_bytes().to_vec()
+    };
+    if items.is_empty() {
+        return data;
+    }
+    data.push(4);
+    let mut offset = 1u32;
+    data.extend_from_slice(&offset.to_be_bytes());
+    for item in items {
+        offset += item.len() as u32;
+        data.extend_from_slice(&offset.to_be_bytes());
+    }
+    items.iter().for_each(|item| data.extend_from_slice(item));
+    data
+}
+
+/// Encodes DICT integer operand in its 5 bytes form
+pub fn dict_int(value: i32) -> Vec<u8> {
+    let mut data = vec![29];
+    data.extend_from_slice(&value.to_be_bytes());
+    data
+}
+
+/// Encodes charstring program: numbers are encoded as shortint (28) and
+/// operators are given as `Err(byte)` (or `Err(12 * 256 + byte)` for two-byte ones)
+pub fn char_string(program: &[Result<i16, u16>]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![];
+    for item in program {
+        match item {
+            Ok(value) => {
+                data.push(28);
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+            Err(op) if *op > 255 => data.extend_from_slice(&op.to_be_bytes()),
+            Err(op) => data.push(*op as u8),
+        }
+    }
+    data
+}
+
+/// Builds 'CFF ' table. If `fd_select` is set the font is CID-keyed: each element of `local_subrs`
+/// becomes Private DICT subroutines of its own Font DICT and `fd_select` gives Font DICT for each glyph
+pub fn cff_table(
+    char_strings: &[Vec<u8>],
+    global_subrs: &[Vec<u8>],
+    local_subrs: &[Vec<Vec<u8>>],
+    fd_select: Option<&[u8]>,
+) -> Vec<u8> {
+    let build_top_dict = |offsets: &[i32]| -> Vec<u8> {
+        let mut dict: Vec<u8> = vec![];
+        if fd_select.is_some() {
+            // ROS: Adobe-Identity-0 (custom strings 391 and 392)
+            for value in [391, 392, 0] {
+                dict.extend_from_slice(&dict_int(value));
+            }
+            dict.extend_from_slice(&[12, 30]);
+            dict.extend_from_slice(&dict_int(offsets[0]));
+            dict.push(17);
+            dict.extend_from_slice(&dict_int(offsets[1]));
+            dict.extend_from_slice(&[12, 36]);
+            dict.extend_from_slice(&dict_int(offsets[2]));
+            dict.extend_from_slice(&[12, 37]);
+        } else {
+            dict.extend_from_slice(&dict_int(offsets[0]));
+            dict.push(17);
+            dict.extend_from_slice(&dict_int(offsets[1]));
+            dict.extend_from_slice(&dict_int(offsets[2]));
+            dict.push(18);
+        }
+        dict
+    };
+    let header: Vec<u8> = vec![1, 0, 4, 4];
+    let name_index = cff_index(&[b"Test".to_vec()], false);
+    let string_index = cff_index(&[b"Adobe".to_vec(), b"Identity".to_vec()], false);
+    let global_subrs_index = cff_index(global_subrs, false);
+    let top_dict_len = cff_index(&[build_top_dict(&[0, 0, 0])], false).len();
+    let char_strings_offset = header.len() + name_index.len() + top_dict_len + string_index.len() + global_subrs_index.len();
+    let char_strings_index = cff_index(char_strings, false);
+
+    // Private DICTs: Subrs offset is relative to Private DICT and subroutines follow their DICT
+    let mut privates: Vec<u8> = vec![];
+    let privates_offset = char_strings_offset + char_strings_index.len();
+    let mut private_ranges: Vec<(i32, i32)> = vec![];
+    for subrs in local_subrs {
+        let mut private_dict = dict_int(6);
+        private_dict.push(19);
+        private_ranges.push((private_dict.len() as i32, (privates_offset + privates.len()) as i32));
+        privates.extend_from_slice(&private_dict);
+        privates.extend_from_slice(&cff_index(subrs, false));
+    }
+
+    let mut tail: Vec<u8> = vec![];
+    let top_dict = match fd_select {
+        Some(fd_select) => {
+            let font_dicts: Vec<Vec<u8>> = private_ranges
+                .iter()
+                .map(|(size, offset)| [dict_int(*size), dict_int(*offset), vec![18]].concat())
+                .collect();
+            let fd_array_offset = privates_offset + privates.len();
+            tail.extend_from_slice(&cff_index(&font_dicts, false));
+            let fd_select_offset = fd_array_offset + tail.len();
+            tail.push(0);
+            tail.extend_from_slice(fd_select);
+            build_top_dict(&[char_strings_offset as i32, fd_array_offset as i32, fd_select_offset as i32])
+        }
+        None => build_top_dict(&[char_strings_offset as i32, private_ranges[0].0, private_ranges[0].1]),
+    };
+    [header, name_index, cff_index(&[top_dict], false), string_index, global_subrs_index, char_strings_index, privates, tail]
+        .concat()
+}
+
+/// Builds 'CFF2' table with one Font DICT and variation store with one region
+pub fn cff2_table(char_strings: &[Vec<u8>], global_subrs: &[Vec<u8>]) -> Vec<u8> {
+    let top_dict = |offsets: [i32; 3]| -> Vec<u8> {
+        [dict_int(offsets[0]), vec![17], dict_int(offsets[1]), vec![12, 36], dict_int(offsets[2]), vec![24]].concat()
+    };
+    let top_dict_len = top_dict([0; 3]).len();
+    let global_subrs_index = cff_index(global_subrs, true);
+    let char_strings_offset = 5 + top_dict_len + global_subrs_index.len();
+    let char_strings_index = cff_index(char_strings, true);
+    let private_offset = char_strings_offset + char_strings_index.len();
+    let private_dict: Vec<u8> = vec![];
+    let fd_array_offset = private_offset + private_dict.len();
+    let fd_array = cff_index(&[[dict_int(0), dict_int(private_offset as i32), vec![18]].concat()], true);
+    let vstore_offset = fd_array_offset + fd_array.len();
+    // ItemVariationStore: format, region list offset, one ItemVariationData with one region
+    let mut store: Vec<u8> = vec![0, 1, 0, 0, 0, 12, 0, 1, 0, 0, 0, 22];
+    // region list: one axis, one region with start 0, peak 1.0 and end 1.0
+    store.extend_from_slice(&[0, 1, 0, 1, 0, 0, 0x40, 0, 0x40, 0]);
+    // ItemVariationData: no items, no word deltas, region index count 1, region index 0
+    store.extend_from_slice(&[0, 0, 0, 0, 0, 1, 0, 0]);
+    let mut vstore = (store.len() as u16).to_be_bytes().to_vec();
+    vstore.extend_from_slice(&store);
+    let mut header: Vec<u8> = vec![2, 0, 5];
+    header.extend_from_slice(&(top_dict_len as u16).to_be_bytes());
+    [
+        header,
+        top_dict([char_strings_offset as i32, fd_array_offset as i32, vstore_offset as i32]),
+        global_subrs_index,
+        char_strings_index,
+        private_dict,
+        fd_array,
+        vstore,
+    ]
+    .concat()
+}
+
+fn fixed(value: f32) -> [u8; 4] {
+    ((value * 65536.0) as i32).to_be_bytes()
+}
+
+fn f2dot14(value: f32) -> [u8; 2] {
+    ((value * 16384.0) as i16).to_be_bytes()
+}
+
+/// Builds 'fvar' table with `axes` (tag, min, default, max, name ID) and `instances`
+/// (subfamily name ID, coordinates, PostScript name ID). All instances must have or miss PostScript name ID
+pub fn fvar_table(axes: &[(u32, f32, f32, f32, u16)], instances: &[(u16, Vec<f32>, Option<u16>)]) -> Vec<u8> {
+    let has_post_script_name = instances.iter().any(|(_, _, name_id)| name_id.is_some());
+    let instance_size = axes.len() * 4 + 4 + if has_post_script_name { 2 } else { 0 };
+    let mut data: Vec<u8> = vec![];
+    for value in [1u16, 0, 16, 2, axes.len() as u16, 20, instances.len() as u16, instance_size as u16] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    for (axis_tag, min, default, max, name_id) in axes {
+        data.extend_from_slice(&axis_tag.to_be_bytes());
+        [*min, *default, *max].iter().for_each(|value| data.extend_from_slice(&fixed(*value)));
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(&name_id.to_be_bytes());
+    }
+    for (name_id, coordinates, post_script_name_id) in instances {
+        data.extend_from_slice(&name_id.to_be_bytes());
+        data.extend_from_slice(&[0, 0]);
+        coordinates.iter().for_each(|value| data.extend_from_slice(&fixed(*value)));
+        if let Some(post_script_name_id) = post_script_name_id {
+            data.extend_from_slice(&post_script_name_id.to_be_bytes());
+        }
+    }
+    data
+}
+
+/// Builds version 1 'avar' table with (from, to) segment maps for every axis
+pub fn avar_table(segment_maps: &[Vec<(f32, f32)>]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![0, 1, 0, 0, 0, 0];
+    data.extend_from_slice(&(segment_maps.len() as u16).to_be_bytes());
+    for mappings in segment_maps {
+        data.extend_from_slice(&(mappings.len() as u16).to_be_bytes());
+        for (from, to) in mappings {
+            data.extend_from_slice(&f2dot14(*from));
+            data.extend_from_slice(&f2dot14(*to));
+        }
+    }
+    data
+}
+
+/// Builds version 1.1 'STAT' table with design `axes` (tag, name ID) and four axis values of the first axis:
+/// format 1 (elidable, name ID 260, 400), format 2 (name ID 261, 700 in 600..900),
+/// format 3 (name ID 262, 400 linked to 700) and format 4 (name ID 263, 300)
+pub fn stat_table(axes: &[(u32, u16)], elided_fallback_name_id: u16) -> Vec<u8> {
+    let design_axes_offset = 20u32;
+    let axis_value_offsets_offset = design_axes_offset + axes.len() as u32 * 8;
+    let mut data: Vec<u8> = vec![0, 1, 0, 1, 0, 8];
+    data.extend_from_slice(&(axes.len() as u16).to_be_bytes());
+    data.extend_from_slice(&design_axes_offset.to_be_bytes());
+    data.extend_from_slice(&4u16.to_be_bytes());
+    data.extend_from_slice(&axis_value_offsets_offset.to_be_bytes());
+    data.extend_from_slice(&elided_fallback_name_id.to_be_bytes());
+    for (ordering, (axis_tag, name_id)) in axes.iter().enumerate() {
+        data.extend_from_slice(&axis_tag.to_be_bytes());
+        data.extend_from_slice(&name_id.to_be_bytes());
+        data.extend_from_slice(&(ordering as u16).to_be_bytes());
+    }
+    let values: Vec<Vec<u8>> = vec![
+        [vec![0, 1, 0, 0, 0, 2, 1, 4], fixed(400.0).to_vec()].concat(),
+        [vec![0, 2, 0, 0, 0, 0, 1, 5], fixed(700.0).to_vec(), fixed(600.0).to_vec(), fixed(900.0).to_vec()].concat(),
+        [vec![0, 3, 0, 0, 0, 0, 1, 6], fixed(400.0).to_vec(), fixed(700.0).to_vec()].concat(),
+        [vec![0, 4, 0, 1, 0, 0, 1, 7, 0, 0], fixed(300.0).to_vec()].concat(),
+    ];
+    let mut offset = values.len() * 2;
+    for value in values.iter() {
+        data.extend_from_slice(&(offset as u16).to_be_bytes());
+        offset += value.len();
+    }
+    values.iter().for_each(|value| data.extend_from_slice(value));
+    data
+}
+
+/// Builds ItemVariationStore for one axis with `regions` (start, peak, end) and one ItemVariationData
+/// which uses all regions and has 16-bit deltas
+pub fn item_variation_store(regions: &[(f32, f32, f32)], delta_sets: &[Vec<i16>]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![0, 1, 0, 0, 0, 12, 0, 1];
+    let region_list_len = 4 + regions.len() * 6;
+    data.extend_from_slice(&(12 + region_list_len as u32).to_be_bytes());
+    data.extend_from_slice(&[0, 1]);
+    data.extend_from_slice(&(regions.len() as u16).to_be_bytes());
+    for (start, peak, end) in regions {
+        [*start, *peak, *end].iter().for_each(|value| data.extend_from_slice(&f2dot14(*value)));
+    }
+    for value in [delta_sets.len() as u16, regions.len() as u16, regions.len() as u16] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    (0..regions.len() as u16).for_each(|index| data.extend_from_slice(&index.to_be_bytes()));
+    delta_sets.iter().flatten().for_each(|delta| data.extend_from_slice(&delta.to_be_bytes()));
+    data
+}
+
+/// Tuple variation for one axis: (peak, point numbers or None for all points, deltas)
+pub type TupleVariationSpec = (f32, Option<Vec<u16>>, Vec<(i16, i16)>);
+
+/// Serializes tuple variations for one axis with embedded peaks and private point numbers.
+/// Y deltas are written if `has_y_deltas` is set
+fn tuple_variations(tuples: &[TupleVariationSpec], has_y_deltas: bool, data_offset_base: usize) -> Vec<u8> {
+    let mut headers: Vec<u8> = vec![];
+    let mut serialized: Vec<u8> = vec![];
+    for (peak, points, deltas) in tuples {
+        let mut tuple_data: Vec<u8> = vec![];
+        match points {
+            None => tuple_data.push(0),
+            Some(points) => {
+                tuple_data.push(points.len() as u8);
+                let mut last = 0u16;
+                for point in points {
+                    tuple_data.push(0x80);
+                    tuple_data.extend_from_slice(&(point - last).to_be_bytes());
+                    last = *point;
+                }
+            }
+        }
+        for value in deltas.iter().map(|delta| delta.0).chain(deltas.iter().filter(|_| has_y_deltas).map(|delta| delta.1)) {
+            tuple_data.push(0x40);
+            tuple_data.extend_from_slice(&value.to_be_bytes());
+        }
+        headers.extend_from_slice(&(tuple_data.len() as u16).to_be_bytes());
+        headers.extend_from_slice(&0xA000u16.to_be_bytes());
+        headers.extend_from_slice(&f2dot14(*peak));
+        serialized.extend(tuple_data);
+    }
+    let mut data = (tuples.len() as u16).to_be_bytes().to_vec();
+    data.extend_from_slice(&((data_offset_base + 4 + headers.len()) as u16).to_be_bytes());
+    [data, headers, serialized].concat()
+}
+
+/// Builds 'gvar' table for one axis with tuple variations of every glyph, see `tuple_variations`
+pub fn gvar_table(glyphs: &[Vec<TupleVariationSpec>]) -> Vec<u8> {
+    let glyph_data: Vec<Vec<u8>> = glyphs
+        .iter()
+        .map(|tuples| if tuples.is_empty() { vec![] } else { tuple_variations(tuples, true, 0) })
+        .collect();
+    let data_offset = 20 + (glyphs.len() + 1) * 4;
+    let mut data: Vec<u8> = vec![0, 1, 0, 0, 0, 1, 0, 0];
+    data.extend_from_slice(&(data_offset as u32).to_be_bytes());
+    data.extend_from_slice(&(glyphs.len() as u16).to_be_bytes());
+    data.extend_from_slice(&[0, 1]);
+    data.extend_from_slice(&(data_offset as u32).to_be_bytes());
+    let mut offset = 0u32;
+    for glyph in glyph_data.iter() {
+        data.extend_from_slice(&offset.to_be_bytes());
+        offset += glyph.len() as u32;
+    }
+    data.extend_from_slice(&offset.to_be_bytes());
+    glyph_data.iter().for_each(|glyph| data.extend_from_slice(glyph));
+    data
+}
+
+/// Builds 'cvar' table for one axis with (peak, CVT deltas for all values) tuples
+pub fn cvar_table(tuples: &[(f32, Vec<i16>)]) -> Vec<u8> {
+    let tuples: Vec<TupleVariationSpec> =
+        tuples.iter().map(|(peak, deltas)| (*peak, None, deltas.iter().map(|delta| (*delta, 0)).collect())).collect();
+    [vec![0, 1, 0, 0], tuple_variations(&tuples, false, 4)].concat()
+}
+
+/// Builds 'HVAR' table without mappings with advance width delta of every glyph at the maximum of one axis
+pub fn hvar_table(advance_deltas: &[i16]) -> Vec<u8> {
+    let delta_sets: Vec<Vec<i16>> = advance_deltas.iter().map(|delta| vec![*delta]).collect();
+    [vec![0, 1, 0, 0, 0, 0, 0, 20], vec![0; 12], item_variation_store(&[(0.0, 1.0, 1.0)], &delta_sets)].concat()
+}
+
+/// Builds 'MVAR' table with (value tag, delta at the maximum of one axis) records sorted by tag
+pub fn mvar_table(records: &[(u32, i16)]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![0, 1, 0, 0, 0, 0, 0, 8];
+    data.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    data.extend_from_slice(&(12 + records.len() as u16 * 8).to_be_bytes());
+    for (index, (value_tag, _)) in records.iter().enumerate() {
+        data.extend_from_slice(&value_tag.to_be_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(&(index as u16).to_be_bytes());
+    }
+    let delta_sets: Vec<Vec<i16>> = records.iter().map(|(_, delta)| vec![*delta]).collect();
+    data.extend(item_variation_store(&[(0.0, 1.0, 1.0)], &delta_sets));
+    data
+}
+
+/// Builds 'CPAL' table with `palettes` of the same size; version 1 with palette types if `palette_types` isn't empty
+pub fn cpal_table(palettes: &[Vec<Color>], palette_types: &[u32]) -> Vec<u8> {
+    let entries = palettes.first().map(|palette| palette.len()).unwrap_or_default();
+    let version: u16 = if palette_types.is_empty() { 0 } else { 1 };
+    let header_len = 12 + palettes.len() * 2 + if version == 1 { 12 } else { 0 };
+    let color_records_offset = header_len + palette_types.len() * 4;
+    let mut data: Vec<u8> = vec![];
+    for value in [version, entries as u16, palettes.len() as u16, (entries * palettes.len()) as u16] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    data.extend_from_slice(&(color_records_offset as u32).to_be_bytes());
+    (0..palettes.len()).for_each(|index| data.extend_from_slice(&((index * entries) as u16).to_be_bytes()));
+    if version == 1 {
+        data.extend_from_slice(&(header_len as u32).to_be_bytes());
+        data.extend_from_slice(&[0; 8]);
+        palette_types.iter().for_each(|types| data.extend_from_slice(&types.to_be_bytes()));
+    }
+    for color in palettes.iter().flatten() {
+        data.extend_from_slice(&[color.blue, color.green, color.red, color.alpha]);
+    }
+    data
+}
+
+/// Builds version 0 'COLR' table with base glyphs and their (glyph ID, palette index) layers
+pub fn colr_table(base_glyphs: &[(u16, Vec<(u16, u16)>)]) -> Vec<u8> {
+    let layer_count: usize = base_glyphs.iter().map(|(_, layers)| layers.len()).sum();
+    let mut data: Vec<u8> = vec![0, 0];
+    data.extend_from_slice(&(base_glyphs.len() as u16).to_be_bytes());
+    data.extend_from_slice(&14u32.to_be_bytes());
+    data.extend_from_slice(&(14 + base_glyphs.len() as u32 * 6).to_be_bytes());
+    data.extend_from_slice(&(layer_count as u16).to_be_bytes());
+    let mut first_layer = 0u16;
+    for (glyph_id, layers) in base_glyphs {
+        for value in [*glyph_id, first_layer, layers.len() as u16] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        first_layer += layers.len() as u16;
+    }
+    for (glyph_id, palette_index) in base_glyphs.iter().flat_map(|(_, layers)| layers.iter()) {
+        data.extend_from_slice(&glyph_id.to_be_bytes());
+        data.extend_from_slice(&palette_index.to_be_bytes());
+    }
+    data
+}
+
+/// Builds 'CBLC' and 'CBDT' tables with one index subtable of `index_format` per strike and the same
+/// (glyph ID, PNG data) images in every strike. Glyph IDs must be sorted. Image format is 17 for index
+/// formats 1 and 4, 18 for format 3 and 19 for formats 2 and 5 (all images must have the same size)
+pub fn cblc_and_cbdt(strikes: &[u8], index_format: u16, images: &[(u16, Vec<u8>)]) -> (Vec<u8>, Vec<u8>) {
+    let image_format: u16 = match index_format {
+        1 | 4 => 17,
+        3 => 18,
+        _ => 19,
+    };
+    let (first_glyph_id, last_glyph_id) = (images[0].0, images[images.len() - 1].0);
+    let mut cblc: Vec<u8> = vec![0, 3, 0, 0];
+    cblc.extend_from_slice(&(strikes.len() as u32).to_be_bytes());
+    let mut cbdt: Vec<u8> = vec![0, 3, 0, 0];
+    let mut subtables: Vec<u8> = vec![];
+    let subtables_offset = 8 + strikes.len() * 48;
+    for &ppem in strikes {
+        let big_metrics = [ppem, ppem, 0, ppem, ppem, 0, 0, ppem];
+        let image_data_offset = cbdt.len() as u32;
+        let mut offsets: Vec<u32> = vec![];
+        for (_, png) in images {
+            offsets.push(cbdt.len() as u32 - image_data_offset);
+            match image_format {
+                17 => cbdt.extend_from_slice(&big_metrics[..5]),
+                18 => cbdt.extend_from_slice(&big_metrics),
+                _ => {}
+            }
+            cbdt.extend_from_slice(&(png.len() as u32).to_be_bytes());
+            cbdt.extend_from_slice(png);
+        }
+        let end_offset = cbdt.len() as u32 - image_data_offset;
+        let image_size = end_offset / images.len() as u32;
+        // offsets of every glyph of the range, missing glyphs have empty images
+        let range_offsets: Vec<u32> = (first_glyph_id..=last_glyph_id)
+            .map(|glyph_id| images.iter().position(|image| image.0 >= glyph_id).map(|index| offsets[index]).unwrap())
+            .chain([end_offset])
+            .collect();
+
+        let array_offset = (subtables_offset + subtables.len()) as u32;
+        for value in [array_offset, 0, 1, 0] {
+            cblc.extend_from_slice(&value.to_be_bytes());
+        }
+        cblc.extend_from_slice(&[0; 24]);
+        cblc.extend_from_slice(&first_glyph_id.to_be_bytes());
+        cblc.extend_from_slice(&last_glyph_id.to_be_bytes());
+        cblc.extend_from_slice(&[ppem, ppem, 32, 1]);
+
+        subtables.extend_from_slice(&first_glyph_id.to_be_bytes());
+        subtables.extend_from_slice(&last_glyph_id.to_be_bytes());
+        subtables.extend_from_slice(&8u32.to_be_bytes());
+        subtables.extend_from_slice(&index_format.to_be_bytes());
+        subtables.extend_from_slice(&image_format.to_be_bytes());
+        subtables.extend_from_slice(&image_data_offset.to_be_bytes());
+        match index_format {
+            1 => range_offsets.iter().for_each(|offset| subtables.extend_from_slice(&offset.to_be_bytes())),
+            3 => range_offsets.iter().for_each(|offset| subtables.extend_from_slice(&(*offset as u16).to_be_bytes())),
+            4 => {
+                subtables.extend_from_slice(&(images.len() as u32).to_be_bytes());
+                let pairs = images.iter().map(|image| image.0).zip(offsets.iter().copied()).chain([(0, end_offset)]);
+                for (glyph_id, offset) in pairs {
+                    subtables.extend_from_slice(&glyph_id.to_be_bytes());
+                    subtables.extend_from_slice(&(offset as u16).to_be_bytes());
+                }
+            }
+            _ => {
+                subtables.extend_from_slice(&image_size.to_be_bytes());
+                subtables.extend_from_slice(&big_metrics);
+                if index_format == 5 {
+                    subtables.extend_from_slice(&(images.len() as u32).to_be_bytes());
+                    images.iter().for_each(|(glyph_id, _)| subtables.extend_from_slice(&glyph_id.to_be_bytes()));
+                }
+            }
+        }
+    }
+    cblc.extend(subtables);
+    (cblc, cbdt)
+}
+
+/// Glyph image of 'sbix' strike: (glyph ID, graphic type, data)
+pub type SbixImageSpec = (u16, u32, Vec<u8>);
+
+/// Builds 'sbix' table with strikes (ppem, glyph images)
+pub fn sbix_table(num_glyphs: u16, strikes: &[(u16, Vec<SbixImageSpec>)]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![0, 1, 0, 1];
+    data.extend_from_slice(&(strikes.len() as u32).to_be_bytes());
+    let mut strike_data: Vec<u8> = vec![];
+    let strikes_offset = 8 + strikes.len() * 4;
+    for (ppem, images) in strikes {
+        data.extend_from_slice(&((strikes_offset + strike_data.len()) as u32).to_be_bytes());
+        let mut strike: Vec<u8> = vec![];
+        strike.extend_from_slice(&ppem.to_be_bytes());
+        strike.extend_from_slice(&72u16.to_be_bytes());
+        let mut glyph_data: Vec<u8> = vec![];
+        let glyph_data_offset = 4 + (num_glyphs as usize + 1) * 4;
+        for glyph_id in 0..=num_glyphs {
+            strike.extend_from_slice(&((glyph_data_offset + glyph_data.len()) as u32).to_be_bytes());
+            if let Some((_, graphic_type, image)) = images.iter().find(|image| image.0 == glyph_id) {
+                glyph_data.extend_from_slice(&[0, 10, 0xFF, 0xF6]);
+                glyph_data.extend_from_slice(&graphic_type.to_be_bytes());
+                glyph_data.extend_from_slice(image);
+            }
+        }
+        strike.extend(glyph_data);
+        strike_data.extend(strike);
+    }
+    data.extend(strike_data);
+    data
+}
+
+/// Builds 'SVG ' table with glyph ranges (start glyph ID, end glyph ID, index in `documents`)
+pub fn svg_table(ranges: &[(u16, u16, usize)], documents: &[Vec<u8>]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![0, 0, 0, 0, 0, 10, 0, 0, 0, 0];
+    data.extend_from_slice(&(ranges.len() as u16).to_be_bytes());
+    let mut document_offsets: Vec<u32> = vec![];
+    let mut offset = 2 + ranges.len() as u32 * 12;
+    for document in documents {
+        document_offsets.push(offset);
+        offset += document.len() as u32;
+    }
+    for (start_glyph_id, end_glyph_id, index) in ranges {
+        data.extend_from_slice(&start_glyph_id.to_be_bytes());
+        data.extend_from_slice(&end_glyph_id.to_be_bytes());
+        data.extend_from_slice(&document_offsets[*index].to_be_bytes());
+        data.extend_from_slice(&(documents[*index].len() as u32).to_be_bytes());
+    }
+    documents.iter().for_each(|document| data.extend_from_slice(document));
+    data
+}
+
+/// Language system of 'GSUB' or 'GPOS' script: (language tag or None for the default one,
+/// required feature index, feature indices)
+pub type LanguageSpec = (Option<u32>, Option<u16>, Vec<u16>);
+/// Feature variation: (conditions (axis index, min, max), substitutions (feature index, lookup indices))
+pub type FeatureVariationSpec = (Vec<(u16, f32, f32)>, Vec<(u16, Vec<u16>)>);
+
+/// Serializes (tag, data) records followed by the data, offsets are relative to the list start
+fn tagged_list(records: &[(u32, Vec<u8>)], header: &[u8]) -> Vec<u8> {
+    let mut data: Vec<u8> = header.to_vec();
+    data.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    let mut offset = data.len() + records.len() * 6;
+    for (record_tag, record) in records {
+        data.extend_from_slice(&record_tag.to_be_bytes());
+        data.extend_from_slice(&(offset as u16).to_be_bytes());
+        offset += record.len();
+    }
+    records.iter().for_each(|(_, record)| data.extend_from_slice(record));
+    data
+}
+
+/// Serializes u16 count followed by u16 values
+fn u16_array(values: &[u16]) -> Vec<u8> {
+    let mut data = (values.len() as u16).to_be_bytes().to_vec();
+    values.iter().for_each(|value| data.extend_from_slice(&value.to_be_bytes()));
+    data
+}
+
+/// Builds 'GSUB' or 'GPOS' table with scripts (tag, language systems), features (tag, lookup indices),
+/// lookups (type, subtables) and feature variations. Version 1.1 is used if there are feature variations
+pub fn layout_table(
+    scripts: &[(u32, Vec<LanguageSpec>)],
+    features: &[(u32, Vec<u16>)],
+    lookups: &[(u16, Vec<Vec<u8>>)],
+    feature_variations: &[FeatureVariationSpec],
+) -> Vec<u8> {
+    let language_system = |(_, required, indices): &LanguageSpec| -> Vec<u8> {
+        [vec![0, 0], required.unwrap_or(0xFFFF).to_be_bytes().to_vec(), u16_array(indices)].concat()
+    };
+    let script_records: Vec<(u32, Vec<u8>)> = scripts.iter().map(|(script_tag, languages)| {
+        let records: Vec<(u32, Vec<u8>)> = languages.iter()
+            .filter_map(|language| language.0.map(|language_tag| (language_tag, language_system(language))))
+            .collect();
+        let default_language = languages.iter().find(|language| language.0.is_none()).map(language_system);
+        // language system offsets are relative to the script, the default one follows the records
+        let mut script = tagged_list(&records, &[0, 0]);
+        if let Some(default_language) = default_language {
+            let default_language_offset = script.len() as u16;
+            script[..2].copy_from_slice(&default_language_offset.to_be_bytes());
+            script.extend(default_language);
+        }
+        (*script_tag, script)
+    }).collect();
+    let script_list = tagged_list(&script_records, &[]);
+    let feature_records: Vec<(u32, Vec<u8>)> =
+        features.iter().map(|(feature_tag, indices)| (*feature_tag, [vec![0, 0], u16_array(indices)].concat())).collect();
+    let feature_list = tagged_list(&feature_records, &[]);
+
+    let mut lookup_list = (lookups.len() as u16).to_be_bytes().to_vec();
+    let mut lookup_data: Vec<u8> = vec![];
+    for (lookup_type, subtables) in lookups {
+        lookup_list.extend_from_slice(&((2 + lookups.len() * 2 + lookup_data.len()) as u16).to_be_bytes());
+        let mut lookup = [lookup_type.to_be_bytes(), [0, 0], (subtables.len() as u16).to_be_bytes()].concat();
+        let mut offset = 6 + subtables.len() * 2;
+        for subtable in subtables {
+            lookup.extend_from_slice(&(offset as u16).to_be_bytes());
+            offset += subtable.len();
+        }
+        subtables.iter().for_each(|subtable| lookup.extend_from_slice(subtable));
+        lookup_data.extend(lookup);
+    }
+    lookup_list.extend(lookup_data);
+
+    let mut variations: Vec<u8> = vec![0, 1, 0, 0];
+    variations.extend_from_slice(&(feature_variations.len() as u32).to_be_bytes());
+    let mut variation_data: Vec<u8> = vec![];
+    for (conditions, substitutions) in feature_variations {
+        let records_len = 8 + feature_variations.len() * 8;
+        let mut condition_set = (conditions.len() as u16).to_be_bytes().to_vec();
+        for index in 0..conditions.len() {
+            condition_set.extend_from_slice(&((2 + conditions.len() * 4 + index * 8) as u32).to_be_bytes());
+        }
+        for (axis_index, min, max) in conditions {
+            condition_set.extend_from_slice(&[0, 1]);
+            condition_set.extend_from_slice(&axis_index.to_be_bytes());
+            condition_set.extend_from_slice(&f2dot14(*min));
+            condition_set.extend_from_slice(&f2dot14(*max));
+        }
+        let mut substitution = vec![0, 1, 0, 0];
+        substitution.extend_from_slice(&(substitutions.len() as u16).to_be_bytes());
+        let mut alternates: Vec<u8> = vec![];
+        for (feature_index, indices) in substitutions {
+            substitution.extend_from_slice(&feature_index.to_be_bytes());
+            substitution.extend_from_slice(&((6 + substitutions.len() * 6 + alternates.len()) as u32).to_be_bytes());
+            alternates.extend([vec![0, 0], u16_array(indices)].concat());
+        }
+        substitution.extend(alternates);
+        variations.extend_from_slice(&((records_len + variation_data.len()) as u32).to_be_bytes());
+        variations.extend_from_slice(&((records_len + variation_data.len() + condition_set.len()) as u32).to_be_bytes());
+        variation_data.extend(condition_set);
+        variation_data.extend(substitution);
+    }
+    variations.extend(variation_data);
+
+    let header_len = if feature_variations.is_empty() { 10 } else { 14 };
+    let mut data: Vec<u8> = vec![0, 1, 0, if feature_variations.is_empty() { 0 } else { 1 }];
+    let mut offset = header_len;
+    for list in [&script_list, &feature_list, &lookup_list] {
+        data.extend_from_slice(&(offset as u16).to_be_bytes());
+        offset += list.len();
+    }
+    if !feature_variations.is_empty() {
+        data.extend_from_slice(&(offset as u32).to_be_bytes());
+    }
+    [data, script_list, feature_list, lookup_list, if feature_variations.is_empty() { vec![] } else { variations }].concat()
+}
+
+/// Builds coverage table of format 1 with sorted glyph IDs
+pub fn coverage_format1(glyphs: &[u16]) -> Vec<u8> {
+    [vec![0, 1], u16_array(glyphs)].concat()
+}
+
+/// Builds coverage table of format 2 with glyph ranges (start, end)
+pub fn coverage_format2(ranges: &[(u16, u16)]) -> Vec<u8> {
+    let mut data = [vec![0, 2], (ranges.len() as u16).to_be_bytes().to_vec()].concat();
+    let mut coverage_index = 0u16;
+    for (start, end) in ranges {
+        data.extend_from_slice(&start.to_be_bytes());
+        data.extend_from_slice(&end.to_be_bytes());
+        data.extend_from_slice(&coverage_index.to_be_bytes());
+        coverage_index += end - start + 1;
+    }
+    data
+}
+
+/// Builds class definition table of format 2 with a range per (glyph ID, class)
+fn class_definition(classes: &[(u16, u16)]) -> Vec<u8> {
+    let mut classes = classes.to_vec();
+    classes.sort_unstable();
+    let mut data = [vec![0, 2], (classes.len() as u16).to_be_bytes().to_vec()].concat();
+    for (glyph_id, class) in classes {
+        [glyph_id, glyph_id, class].iter().for_each(|value| data.extend_from_slice(&value.to_be_bytes()));
+    }
+    data
+}
+
+/// Builds single substitution subtable of format 2 from (glyph ID, substitute) pairs
+pub fn single_substitution_subtable(substitutions: &[(u16, u16)]) -> Vec<u8> {
+    let mut substitutions = substitutions.to_vec();
+    substitutions.sort_unstable();
+    let glyphs: Vec<u16> = substitutions.iter().map(|(glyph_id, _)| *glyph_id).collect();
+    let substitutes: Vec<u16> = substitutions.iter().map(|(_, substitute)| *substitute).collect();
+    let coverage_offset = 6 + substitutes.len() * 2;
+    [vec![0, 2], (coverage_offset as u16).to_be_bytes().to_vec(), u16_array(&substitutes), coverage_format1(&glyphs)].concat()
+}
+
+/// Builds ligature substitution subtable from (component glyph IDs, ligature glyph ID).
+/// Ligatures with the same first component keep their order in the ligature set
+pub fn ligature_substitution_subtable(ligatures: &[(&[u16], u16)]) -> Vec<u8> {
+    let mut first_glyphs: Vec<u16> = ligatures.iter().map(|(components, _)| components[0]).collect();
+    first_glyphs.sort_unstable();
+    first_glyphs.dedup();
+    let mut ligature_sets: Vec<u8> = vec![];
+    let mut ligature_set_offsets: Vec<u16> = vec![];
+    let header_len = 6 + first_glyphs.len() * 2;
+    for first_glyph in first_glyphs.iter() {
+        ligature_set_offsets.push((header_len + ligature_sets.len()) as u16);
+        let set: Vec<&(&[u16], u16)> = ligatures.iter().filter(|(components, _)| components[0] == *first_glyph).collect();
+        let mut offsets: Vec<u16> = vec![];
+        let mut data: Vec<u8> = vec![];
+        for (components, ligature) in set.iter() {
+            offsets.push((2 + set.len() * 2 + data.len()) as u16);
+            data.extend_from_slice(&ligature.to_be_bytes());
+            data.extend_from_slice(&(components.len() as u16).to_be_bytes());
+            components[1..].iter().for_each(|component| data.extend_from_slice(&component.to_be_bytes()));
+        }
+        ligature_sets.extend(u16_array(&offsets));
+        ligature_sets.extend(data);
+    }
+    let coverage_offset = header_len + ligature_sets.len();
+    [vec![0, 1], (coverage_offset as u16).to_be_bytes().to_vec(), u16_array(&ligature_set_offsets), ligature_sets, coverage_format1(&first_glyphs)]
+        .concat()
+}
+
+/// Builds pair adjustment subtable of format 1 from (first glyph ID, second glyph ID, x advance of the first glyph)
+pub fn pair_adjustment_subtable(pairs: &[(u16, u16, i16)]) -> Vec<u8> {
+    let mut pairs = pairs.to_vec();
+    pairs.sort_unstable();
+    let mut first_glyphs: Vec<u16> = pairs.iter().map(|pair| pair.0).collect();
+    first_glyphs.dedup();
+    let header_len = 10 + first_glyphs.len() * 2;
+    let mut pair_set_offsets: Vec<u16> = vec![];
+    let mut pair_sets: Vec<u8> = vec![];
+    for first_glyph in first_glyphs.iter() {
+        pair_set_offsets.push((header_len + pair_sets.len()) as u16);
+        let set: Vec<&(u16, u16, i16)> = pairs.iter().filter(|pair| pair.0 == *first_glyph).collect();
+        pair_sets.extend_from_slice(&(set.len() as u16).to_be_bytes());
+        for (_, second, x_advance) in set {
+            pair_sets.extend_from_slice(&second.to_be_bytes());
+            pair_sets.extend_from_slice(&x_advance.to_be_bytes());
+        }
+    }
+    let coverage_offset = header_len + pair_sets.len();
+    [vec![0, 1], (coverage_offset as u16).to_be_bytes().to_vec(), vec![0, 4, 0, 0], u16_array(&pair_set_offsets), pair_sets, coverage_format1(&first_glyphs)]
+        .concat()
+}
+
+/// Builds pair adjustment subtable of format 2 from classes (glyph ID, class) of the first and the second glyphs
+/// and x advances of the first glyph for class pairs. All glyphs with the first class are covered
+pub fn class_pair_adjustment_subtable(first_classes: &[(u16, u16)], second_classes: &[(u16, u16)], values: &[Vec<i16>]) -> Vec<u8> {
+    let class2_count = values.first().map_or(0, |row| row.len());
+    let mut matrix: Vec<u8> = vec![];
+    values.iter().flatten().for_each(|value| matrix.extend_from_slice(&value.to_be_bytes()));
+    let mut first_glyphs: Vec<u16> = first_classes.iter().map(|(glyph_id, _)| *glyph_id).collect();
+    first_glyphs.sort_unstable();
+    let coverage = coverage_format1(&first_glyphs);
+    let class_def1 = class_definition(first_classes);
+    let coverage_offset = 16 + matrix.len();
+    let class_def1_offset = coverage_offset + coverage.len();
+    let class_def2_offset = class_def1_offset + class_def1.len();
+    let mut data = vec![0, 2];
+    for value in [coverage_offset, 4, 0, class_def1_offset, class_def2_offset, values.len(), class2_count] {
+        data.extend_from_slice(&(value as u16).to_be_bytes());
+    }
+    [data, matrix, coverage, class_def1, class_definition(second_classes)].concat()
+}
+
+/// Builds 'kern' table of Microsoft version with a horizontal format 0 subtable of (left, right, value) pairs
+pub fn kern_table(pairs: &[(u16, u16, i16)]) -> Vec<u8> {
+    let mut subtable: Vec<u8> = vec![0, 0];
+    subtable.extend_from_slice(&((14 + pairs.len() * 6) as u16).to_be_bytes());
+    subtable.extend_from_slice(&[0, 1]);
+    // binary search fields aren't used by the parser
+    subtable.extend_from_slice(&(pairs.len() as u16).to_be_bytes());
+    subtable.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    for (left, right, value) in pairs {
+        subtable.extend_from_slice(&left.to_be_bytes());
+        subtable.extend_from_slice(&right.to_be_bytes());
+        subtable.extend_from_slice(&value.to_be_bytes());
+    }
+    [vec![0, 0, 0, 1], subtable].concat()
+}
+
+/// Builds 'hdmx' table from (pixel size, widths) device records
+pub fn hdmx_table(records: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    let record_size = calculate_padded_len(records.first().map_or(0, |(_, widths)| widths.len() as u32) + 2) as usize;
+    let mut data: Vec<u8> = vec![0, 0];
+    data.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    data.extend_from_slice(&(record_size as u32).to_be_bytes());
+    for (pixel_size, widths) in records {
+        let start = data.len();
+        data.extend_from_slice(&[*pixel_size, widths.iter().copied().max().unwrap_or_default()]);
+        data.extend_from_slice(widths);
+        data.resize(start + record_size, 0);
+    }
+    data
+}
+
+/// Builds 'VDMX' table with one ratio for all aspect ratios and one group of (yPelHeight, yMax, yMin) records
+pub fn vdmx_table(start_size: u8, end_size: u8, records: &[(u16, i16, i16)]) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![0, 1, 0, 1, 0, 1];
+    data.extend_from_slice(&[1, 0, 0, 0]);
+    data.extend_from_slice(&12u16.to_be_bytes());
+    data.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    data.extend_from_slice(&[start_size, end_size]);
+    for (y_pel_height, y_max, y_min) in records {
+        data.extend_from_slice(&y_pel_height.to_be_bytes());
+        data.extend_from_slice(&y_max.to_be_bytes());
+        data.extend_from_slice(&y_min.to_be_bytes());
+    }
+    data
+}
+
+/// Packs bits to bytes, the most significant bit first
+pub fn pack_bits(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|chunk| chunk.iter().enumerate().fold(0u8, |byte, (index, &bit)| byte | ((bit as u8) << (7 - index))))
+        .collect()
+}
+
+/// Run length encodes data with the least frequent byte as the escape byte
+fn run_length_encode(data: &[u8]) -> Vec<u8> {
+    let mut counts = [0usize; 256];
+    data.iter().for_each(|&byte| counts[byte as usize] += 1);
+    let escape = (0..=255u8).min_by_key(|&byte| counts[byte as usize]).unwrap();
+    let mut encoded: Vec<u8> = vec![escape];
+    let mut index = 0usize;
+    while index < data.len() {
+        let byte = data[index];
+        let run = data[index..].iter().take(255).take_while(|&&next| next == byte).count();
+        if run >= 4 || (byte == escape && run > 1) {
+            encoded.extend_from_slice(&[escape, run as u8, byte]);
+        } else if byte == escape {
+            encoded.extend_from_slice(&[escape, 0]);
+        } else {
+            encoded.extend(std::iter::repeat_n(byte, run));
+        }
+        index += run;
+    }
+    encoded
+}
+
+/// Compresses data with LZCOMP. The longest copy within `max_copy_distance` is taken greedily,
+/// bytes which can't be copied are coded as one byte copies if possible or as literals
+pub fn lzcomp_compress(data: &[u8], max_copy_distance: usize, is_run_length_encoded: bool) -> Vec<u8> {
+    let data = if is_run_length_encoded { run_length_encode(data) } else { data.to_vec() };
+    let mut model = LzcompModel::new(max_copy_distance);
+    let max_distance = max_copy_distance.min(DIST_MIN + (1 << (DIST_WIDTH as usize * model.dist_ranges)) - 1);
+    let mut bits: Vec<bool> = vec![is_run_length_encoded];
+    bits.extend((0..LENGTH_BITS).rev().map(|bit| (data.len() >> bit) & 1 != 0));
+
+    let history = [preload(), data].concat();
+    let mut position = PRELOAD_SIZE;
+    while position < history.len() {
+        let min_length = |distance: usize| if distance > MAX_2BYTE_DIST { LEN_MIN + 1 } else { LEN_MIN };
+        let mut best = (0usize, 0usize);
+        for distance in 1..=max_distance.min(position) {
+            let length = history[position..].iter().zip(&history[position - distance..]).take_while(|(a, b)| a == b).count();
+            if length >= min_length(distance) && length > best.0 {
+                best = (length, distance);
+            }
+        }
+
+        let (length, distance) = best;
+        if length > 0 {
+            let mut length_value = length - min_length(distance);
+            let mut length_chunks: Vec<u16> = vec![];
+            loop {
+                length_chunks.insert(0, (length_value & (LEN_MORE_CHUNKS as usize - 1)) as u16);
+                length_value >>= LEN_VALUE_BITS;
+                if length_value == 0 {
+                    break;
+                }
+            }
+            let last = length_chunks.len() - 1;
+            length_chunks[..last].iter_mut().for_each(|chunk| *chunk |= LEN_MORE_CHUNKS);
+            let mut distance_value = distance - DIST_MIN;
+            let mut distance_chunks: Vec<u16> = vec![];
+            loop {
+                distance_chunks.insert(0, (distance_value & ((1 << DIST_WIDTH) - 1)) as u16);
+                distance_value >>= DIST_WIDTH;
+                if distance_value == 0 {
+                    break;
+                }
+            }
+            let copy_symbol = 256 + (1 << LEN_WIDTH) * (distance_chunks.len() as u16 - 1) + length_chunks[0];
+            model.symbols.write_symbol(copy_symbol, &mut bits);
+            length_chunks[1..].iter().for_each(|&chunk| model.lengths.write_symbol(chunk, &mut bits));
+            distance_chunks.iter().for_each(|&chunk| model.distances.write_symbol(chunk, &mut bits));
+            position += length;
+        } else if let Some(index) = [2, 4, 6].iter().position(|&distance| history[position - distance] == history[position]) {
+            let dup_symbol = model.dup_symbol() + index as u16;
+            model.symbols.write_symbol(dup_symbol, &mut bits);
+            position += 1;
+        } else {
+            model.symbols.write_symbol(history[position] as u16, &mut bits);
+            position += 1;
+        }
+    }
+    pack_bits(&bits)
+}
+
+/// Writes 255UShort value in the shortest form
+pub fn write_255_ushort(value: u16, data: &mut Vec<u8>) {
+    match value {
+        0..253 => data.push(value as u8),
+        253..506 => data.extend_from_slice(&[255, (value - 253) as u8]),
+        506..762 => data.extend_from_slice(&[254, (value - 506) as u8]),
+        _ => data.extend_from_slice(&[253, (value >> 8) as u8, value as u8]),
+    }
+}
+
+/// Writes 255Short value in the shortest form
+pub fn write_255_short(value: i16, data: &mut Vec<u8>) {
+    let magnitude = value.unsigned_abs();
+    if magnitude >= 756 {
+        data.push(WORD_CODE);
+        data.extend_from_slice(&value.to_be_bytes());
+        return;
+    }
+    if value < 0 {
+        data.push(FLIP_SIGN_CODE);
+    }
+    match magnitude {
+        0..250 => data.push(magnitude as u8),
+        250..500 => data.extend_from_slice(&[255, (magnitude - 250) as u8]),
+        _ => data.extend_from_slice(&[254, (magnitude - 500) as u8]),
+    }
+}
+
+/// Triplet flag and data bytes of (dx, dy) point delta
+pub fn triplet(dx: i16, dy: i16, on_curve: bool) -> (u8, Vec<u8>) {
+    let (abs_x, abs_y) = (dx.unsigned_abs() as usize, dy.unsigned_abs() as usize);
+    let on_curve_bit = if on_curve { 0 } else { OFF_CURVE_FLAG };
+    let x_sign = (dx >= 0) as u8;
+    let y_sign = (dy >= 0) as u8;
+    let xy_signs = x_sign + 2 * y_sign;
+    let (flag, data) = if dx == 0 && abs_y < 1280 {
+        (((abs_y & 0xF00) >> 7) as u8 + y_sign, vec![abs_y as u8])
+    } else if dy == 0 && abs_x < 1280 {
+        (10 + ((abs_x & 0xF00) >> 7) as u8 + x_sign, vec![abs_x as u8])
+    } else if abs_x < 65 && abs_y < 65 {
+        let flag = 20 + ((abs_x - 1) & 0x30) as u8 + (((abs_y - 1) & 0x30) >> 2) as u8 + xy_signs;
+        (flag, vec![((((abs_x - 1) & 0x0F) << 4) | ((abs_y - 1) & 0x0F)) as u8])
+    } else if abs_x < 769 && abs_y < 769 {
+        let flag = 84 + 12 * (((abs_x - 1) & 0x300) >> 8) as u8 + (((abs_y - 1) & 0x300) >> 6) as u8 + xy_signs;
+        (flag, vec![(abs_x - 1) as u8, (abs_y - 1) as u8])
+    } else if abs_x < 4096 && abs_y < 4096 {
+        (120 + xy_signs, vec![(abs_x >> 4) as u8, (((abs_x & 0x0F) << 4) | (abs_y >> 8)) as u8, abs_y as u8])
+    } else {
+        (124 + xy_signs, vec![(abs_x >> 8) as u8, abs_x as u8, (abs_y >> 8) as u8, abs_y as u8])
+    };
+    (flag + on_curve_bit, data)
+}
+
+/// Splits leading push instructions of a glyph to push values stored with hop codes and the rest of instructions
+fn ctf_instructions(instructions: &[u8], glyph: &mut Vec<u8>, pushes: &mut Vec<u8>, code: &mut Vec<u8>) {
+    let mut values: Vec<i16> = vec![];
+    let mut index = 0usize;
+    loop {
+        let (count, words, header_size) = match instructions.get(index) {
+            Some(0x40) => (instructions[index + 1] as usize, false, 2),
+            Some(0x41) => (instructions[index + 1] as usize, true, 2),
+            Some(&opcode @ 0xB0..=0xB7) => ((opcode - 0xB0 + 1) as usize, false, 1),
+            Some(&opcode @ 0xB8..=0xBF) => ((opcode - 0xB8 + 1) as usize, true, 1),
+            _ => break,
+        };
+        index += header_size;
+        for _ in 0..count {
+            if words {
+                values.push(i16::from_be_bytes([instructions[index], instructions[index + 1]]));
+                index += 2;
+            } else {
+                values.push(instructions[index] as i16);
+                index += 1;
+            }
+        }
+    }
+    write_255_ushort(values.len() as u16, glyph);
+    write_255_ushort((instructions.len() - index) as u16, glyph);
+    code.extend_from_slice(&instructions[index..]);
+
+    let mut index = 0usize;
+    while index < values.len() {
+        let repeats = |offset: usize| index > 0 && values.get(index + offset) == Some(&values[index - 1]);
+        if repeats(1) && repeats(3) {
+            pushes.push(HOP4_CODE);
+            write_255_short(values[index], pushes);
+            write_255_short(values[index + 2], pushes);
+            index += 4;
+        } else if repeats(1) {
+            pushes.push(HOP3_CODE);
+            write_255_short(values[index], pushes);
+            index += 2;
+        } else {
+            write_255_short(values[index], pushes);
+            index += 1;
+        }
+    }
+}
+
+/// Converts 'glyf' table of the font to CTF, push values and the rest of instructions are appended to the streams
+fn ctf_glyf(font: &Sfnt, pushes: &mut Vec<u8>, code: &mut Vec<u8>) -> Vec<u8> {
+    let glyphs = font.truetype_glyphs().unwrap();
+    let mut data: Vec<u8> = vec![];
+    for glyph_id in 0..glyphs.num_glyphs() {
+        match glyphs.glyph(glyph_id).unwrap() {
+            Glyph::Empty => data.extend_from_slice(&0i16.to_be_bytes()),
+            Glyph::Simple(simple) => {
+                data.extend_from_slice(&(simple.end_pts_of_contours.len() as i16).to_be_bytes());
+                let mut start = 0u16;
+                for &end in simple.end_pts_of_contours.iter() {
+                    write_255_ushort(end + 1 - start, &mut data);
+                    start = end + 1;
+                }
+                let mut coordinates: Vec<u8> = vec![];
+                let mut previous = (0i16, 0i16);
+                for point in simple.points.iter() {
+                    let (x, y) = (point.x as i16, point.y as i16);
+                    let (flag, bytes) = triplet(x.wrapping_sub(previous.0), y.wrapping_sub(previous.1), point.on_curve);
+                    data.push(flag);
+                    coordinates.extend_from_slice(&bytes);
+                    previous = (x, y);
+                }
+                data.extend_from_slice(&coordinates);
+                ctf_instructions(&simple.instructions, &mut data, pushes, code);
+            }
+            Glyph::Composite(composite) => {
+                let mut glyph = composite.transform_to_u8_vec();
+                if !composite.instructions.is_empty() {
+                    glyph.truncate(glyph.len() - 2 - composite.instructions.len());
+                }
+                data.extend_from_slice(&glyph);
+                if !composite.instructions.is_empty() {
+                    ctf_instructions(&composite.instructions, &mut data, pushes, code);
+                }
+            }
+        }
+    }
+    data
+}
+
+/// Converts 'cvt ' table to the number of values and their deltas
+pub fn ctf_cvt(cvt: &[u8]) -> Vec<u8> {
+    let mut data: Vec<u8> = ((cvt.len() / 2) as u16).to_be_bytes().to_vec();
+    let mut previous = 0i16;
+    for chunk in cvt.chunks(2) {
+        let value = i16::from_be_bytes([chunk[0], chunk[1]]);
+        let delta = value.wrapping_sub(previous) as i32;
+        previous = value;
+        if (0..CVT_POS1 as i32).contains(&delta) {
+            data.push(delta as u8);
+        } else if (CVT_STEP..CVT_STEP * 9).contains(&delta) {
+            data.extend_from_slice(&[CVT_POS1 + (delta / CVT_STEP - 1) as u8, (delta % CVT_STEP) as u8]);
+        } else if (-CVT_STEP * 9 + 1..0).contains(&delta) {
+            data.extend_from_slice(&[CVT_NEG0 + (-delta / CVT_STEP) as u8, (-delta % CVT_STEP) as u8]);
+        } else {
+            data.push(CVT_WORD_CODE);
+            data.extend_from_slice(&(delta as i16).to_be_bytes());
+        }
+    }
+    data
+}
+
+/// Appends magnitude dependent code of the value: magnitude in unary and the sign bit
+fn write_magnitude_dependent(value: i32, bits: &mut Vec<bool>) {
+    bits.extend(std::iter::repeat_n(true, value.unsigned_abs() as usize));
+    bits.push(false);
+    if value != 0 {
+        bits.push(value < 0);
+    }
+}
+
+/// Converts 'hdmx' table to device record sizes followed by the bits of width differences from the predicted widths
+fn ctf_hdmx(hdmx: &[u8], units_per_em: u16, advances: &[u16]) -> Vec<u8> {
+    let num_records = u16::from_be_bytes([hdmx[2], hdmx[3]]) as usize;
+    let record_size = u32::from_be_bytes([hdmx[4], hdmx[5], hdmx[6], hdmx[7]]) as usize;
+    let records: Vec<&[u8]> = (0..num_records).map(|index| &hdmx[8 + index * record_size..8 + (index + 1) * record_size]).collect();
+    let mut data = hdmx[..8].to_vec();
+    records.iter().for_each(|record| data.extend_from_slice(&record[..2]));
+    let mut bits: Vec<bool> = vec![];
+    for record in records {
+        for (glyph_id, &advance) in advances.iter().enumerate() {
+            write_magnitude_dependent(record[2 + glyph_id] as i32 - predicted_width(advance, record[0], units_per_em), &mut bits);
+        }
+    }
+    data.extend_from_slice(&pack_bits(&bits));
+    data
+}
+
+/// Converts 'VDMX' table with groups laid out after the header to yPelHeight deltas
+/// and differences of heights from the predicted ones
+fn ctf_vdmx(vdmx: &[u8], head: &SfntHeaderTable) -> Vec<u8> {
+    let read_u16 = |offset: usize| u16::from_be_bytes([vdmx[offset], vdmx[offset + 1]]);
+    let header_size = 6 + read_u16(4) as usize * 6;
+    let mut data = vdmx[..header_size].to_vec();
+    let mut offset = header_size;
+    for _ in 0..read_u16(2) {
+        let recs = read_u16(offset);
+        data.extend_from_slice(&vdmx[offset..offset + 4]);
+        let mut previous = vdmx[offset + 2] as u16;
+        offset += 4;
+        for _ in 0..recs {
+            let y_pel_height = read_u16(offset);
+            let (predicted_max, predicted_min) = predicted_heights(head, y_pel_height);
+            write_255_ushort(y_pel_height - previous, &mut data);
+            write_255_short((read_u16(offset + 2) as i16 as i32 - predicted_max) as i16, &mut data);
+            write_255_short((read_u16(offset + 4) as i16 as i32 - predicted_min) as i16, &mut data);
+            previous = y_pel_height;
+            offset += 6;
+        }
+    }
+    data
+}
+
+/// Converts SFNT data to MTX blocks: the font in CTF, push values and the rest of instructions
+pub fn ctf_font(sfnt: &[u8]) -> [Vec<u8>; 3] {
+    let font = Sfnt::from_vec(sfnt.to_vec()).unwrap();
+    let mut pushes: Vec<u8> = vec![];
+    let mut code: Vec<u8> = vec![];
+    let mut tables: Vec<(u32, Vec<u8>)> = vec![];
+    for table_tag in font.table_tags() {
+        let data = font.table_data(table_tag).unwrap().into_owned();
+        let table = if table_tag == tag(b"loca") {
+            continue;
+        } else if table_tag == tag(b"glyf") {
+            ctf_glyf(&font, &mut pushes, &mut code)
+        } else if table_tag == tag(b"cvt ") {
+            ctf_cvt(&data)
+        } else if table_tag == tag(b"hdmx") {
+            ctf_hdmx(&data, font.head().unwrap().units_per_em, &font.hmtx().unwrap().advances)
+        } else if table_tag == tag(b"VDMX") {
+            ctf_vdmx(&data, &font.head().unwrap())
+        } else {
+            data
+        };
+        tables.push((table_tag, table));
+    }
+    [build_sfnt(font.version(), &tables), pushes, code]
+}
+
+/// Compresses SFNT data to MTX data with copies reaching any byte of the blocks
+pub fn mtx_data(sfnt: &[u8], is_run_length_encoded: bool) -> Vec<u8> {
+    let blocks = ctf_font(sfnt);
+    let copy_limit = PRELOAD_SIZE + blocks.iter().map(|block| block.len()).max().unwrap_or_default();
+    let compressed: Vec<Vec<u8>> = blocks.iter().map(|block| lzcomp_compress(block, copy_limit, is_run_length_encoded)).collect();
+    let offset2 = 10 + compressed[0].len();
+    let offset3 = offset2 + compressed[1].len();
+    let mut data: Vec<u8> = vec![3];
+    for value in [copy_limit, offset2, offset3] {
+        data.extend_from_slice(&(value as u32).to_be_bytes()[1..]);
+    }
+    [data, compressed.concat()].concat()
+}
@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::io::{BufReader, Read, Write};
-use bytebuffer::{ByteBuffer, Endian};
+use bytebuffer::{ByteBuffer, ByteReader, Endian};
 
 
 /// Reads data from file to buffer
@@ -37,7 +37,6 @@ pub fn create_ttf_file(data_slice: &[u8], path_to_out_file: &str) -> crate::File
     let mut error = crate::Error::None;
     match File::create(path_to_out_file) {
         Ok(mut file) => {
-            let data_slice = data_slice;
             match file.write_all(data_slice) {
                 Ok(_) => {},
                 Err(err) => {
@@ -173,4 +172,106 @@ pub fn transform_u16_to_u8_vec(x: u16) -> Vec<u8> {
     let first_byte = ((x >> 8) & 0xff) as u8;
     let second_byte = (x & 0xff) as u8;
     vec![first_byte, second_byte]
-}
\ No newline at end of file
+}
+/// Builds 4-bytes table tag from its string representation, e.g. `tag(b"head")`.
+#[allow(dead_code)]
+#[inline(always)]
+pub const fn tag(bytes: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*bytes)
+}
+
+/// Reads unsigned 8-bits value from table data.
+/// Returns `InvalidTableData` error if there is not enough data
+#[allow(dead_code)]
+pub fn try_read_u8(buf: &mut ByteReader) -> Result<u8, crate::Error> {
+    buf.read_u8().map_err(|_| crate::Error::InvalidTableData)
+}
+
+/// Reads signed 8-bits value from table data.
+/// Returns `InvalidTableData` error if there is not enough data
+#[allow(dead_code)]
+pub fn try_read_i8(buf: &mut ByteReader) -> Result<i8, crate::Error> {
+    buf.read_i8().map_err(|_| crate::Error::InvalidTableData)
+}
+
+/// Reads unsigned 16-bits value in big endian order from table data.
+/// Returns `InvalidTableData` error if there is not enough data
+#[allow(dead_code)]
+pub fn try_read_u16_be(buf: &mut ByteReader) -> Result<u16, crate::Error> {
+    buf.set_endian(Endian::BigEndian);
+    buf.read_u16().map_err(|_| crate::Error::InvalidTableData)
+}
+
+/// Reads signed 16-bits value in big endian order from table data.
+/// Returns `InvalidTableData` error if there is not enough data
+#[allow(dead_code)]
+pub fn try_read_i16_be(buf: &mut ByteReader) -> Result<i16, crate::Error> {
+    buf.set_endian(Endian::BigEndian);
+    buf.read_i16().map_err(|_| crate::Error::InvalidTableData)
+}
+
+/// Reads unsigned 24-bits value in big endian order from table data.
+/// Returns `InvalidTableData` error if there is not enough data
+#[allow(dead_code)]
+pub fn try_read_u24_be(buf: &mut ByteReader) -> Result<u32, crate::Error> {
+    let high = try_read_u8(buf)? as u32;
+    let low = try_read_u16_be(buf)? as u32;
+    Ok((high << 16) | low)
+}
+
+/// Reads unsigned 32-bits value in big endian order from table data.
+/// Returns `InvalidTableData` error if there is not enough data
+#[allow(dead_code)]
+pub fn try_read_u32_be(buf: &mut ByteReader) -> Result<u32, crate::Error> {
+    buf.set_endian(Endian::BigEndian);
+    buf.read_u32().map_err(|_| crate::Error::InvalidTableData)
+}
+
+/// Reads signed 32-bits value in big endian order from table data.
+/// Returns `InvalidTableData` error if there is not enough data
+#[allow(dead_code)]
+pub fn try_read_i32_be(buf: &mut ByteReader) -> Result<i32, crate::Error> {
+    buf.set_endian(Endian::BigEndian);
+    buf.read_i32().map_err(|_| crate::Error::InvalidTableData)
+}
+
+/// Returns `len` bytes of table data starting from `offset`.
+/// Returns `InvalidTableData` error if requested range is out of data bounds
+#[allow(dead_code)]
+pub fn try_slice(data: &[u8], offset: usize, len: usize) -> Result<&[u8], crate::Error> {
+    match offset.checked_add(len) {
+        Some(end) if end <= data.len() => Ok(&data[offset..end]),
+        _ => Err(crate::Error::InvalidTableData),
+    }
+}
+
+/// Converts 16.16 fixed point number to float
+#[allow(dead_code)]
+#[inline(always)]
+pub fn fixed_to_f32(value: i32) -> f32 {
+    value as f32 / 65536.0
+}
+
+/// Converts 2.14 fixed point number to float
+#[allow(dead_code)]
+#[inline(always)]
+pub fn f2dot14_to_f32(value: i16) -> f32 {
+    value as f32 / 16384.0
+}
+
+/// Converts float to 2.14 fixed point number
+#[allow(dead_code)]
+#[inline(always)]
+pub fn f32_to_f2dot14(value: f32) -> i16 {
+    (value * 16384.0).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+/// Calculates SFNT table checksum: sum of big endian 32-bits words, the last word is padded with zeros
+#[allow(dead_code)]
+pub fn calculate_table_checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
@@ -0,0 +1,144 @@
+//! Summary of variable font axes, named instances and style attributes
+
+use crate::font::FontTables;
+use crate::tables::avar::SegmentMap;
+use crate::tables::fvar::{normalize_coordinate, AXIS_FLAG_HIDDEN};
+use crate::tables::name::NameTable;
+use crate::tables::stat::{StatAxisRecord, StatAxisValue};
+use crate::Error;
+
+/// Variation axis with its name and 'avar' mapping
+#[derive(Clone, Debug, PartialEq)]
+pub struct AxisInfo {
+    pub tag: u32,
+    pub name: Option<String>,
+    pub min_value: f32,
+    pub default_value: f32,
+    pub max_value: f32,
+    // The axis shouldn't be shown in user interfaces
+    pub hidden: bool,
+    // Mapping of normalized coordinates, empty if there is no 'avar' table
+    pub segment_map: SegmentMap,
+}
+
+/// Named instance with its names
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstanceInfo {
+    pub name: Option<String>,
+    pub post_script_name: Option<String>,
+    // User coordinate for every axis in `VariationInfo::axes` order
+    pub coordinates: Vec<f32>,
+}
+
+/// 'STAT' axis value with its name
+#[derive(Clone, Debug, PartialEq)]
+pub struct AxisValueInfo {
+    pub name: Option<String>,
+    pub value: StatAxisValue,
+}
+
+/// Variation data combined from 'fvar', 'avar', 'STAT' and 'name' tables
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariationInfo {
+    pub axes: Vec<AxisInfo>,
+    pub instances: Vec<InstanceInfo>,
+    // 'STAT' design axes, `axis_index` of axis values refers to them
+    pub stat_axes: Vec<StatAxisRecord>,
+    pub axis_values: Vec<AxisValueInfo>,
+    pub elided_fallback_name: Option<String>,
+}
+
+impl VariationInfo {
+    /// Collects variation data from font tables. 'fvar' table is required
+    pub(crate) fn from_font<F: FontTables + ?Sized>(font: &F, preferred_languages: &[&str]) -> Result<Self, Error> {
+        let fvar = font.fvar()?;
+        let avar = match font.avar() {
+            Ok(avar) => Some(avar),
+            Err(Error::TableNotFound) => None,
+            Err(err) => return Err(err),
+        };
+        let stat = match font.stat() {
+            Ok(stat) => Some(stat),
+            Err(Error::TableNotFound) => None,
+            Err(err) => return Err(err),
+        };
+        let name = match font.name() {
+            Ok(name) => Some(name),
+            Err(Error::TableNotFound) => None,
+            Err(err) => return Err(err),
+        };
+        let get_name = |name_id: u16| -> Option<String> {
+            name.as_ref().and_then(|name: &NameTable| name.get(name_id, preferred_languages)).map(str::to_string)
+        };
+
+        let axes = fvar.axes.iter().enumerate().map(|(index, axis)| AxisInfo {
+            tag: axis.tag,
+            name: get_name(axis.axis_name_id),
+            min_value: axis.min_value,
+            default_value: axis.default_value,
+            max_value: axis.max_value,
+            hidden: axis.flags & AXIS_FLAG_HIDDEN != 0,
+            segment_map: avar.as_ref().and_then(|avar| avar.segment_maps.get(index).cloned()).unwrap_or_default(),
+        }).collect();
+        let instances = fvar.instances.iter().map(|instance| InstanceInfo {
+            name: get_name(instance.subfamily_name_id),
+            post_script_name: instance.post_script_name_id.and_then(get_name),
+            coordinates: instance.coordinates.clone(),
+        }).collect();
+        let (stat_axes, axis_values, elided_fallback_name) = match stat {
+            Some(stat) => (
+                stat.design_axes,
+                stat.axis_values.into_iter().map(|value| AxisValueInfo { name: get_name(value.value_name_id()), value }).collect(),
+                stat.elided_fallback_name_id.and_then(get_name),
+            ),
+            None => (vec![], vec![], None),
+        };
+        Ok(VariationInfo { axes, instances, stat_axes, axis_values, elided_fallback_name })
+    }
+
+    /// Converts user coordinates (axis tag, value) to normalized coordinates for every axis
+    /// with 'avar' mapping applied. Axes without coordinates use their default values
+    pub fn normalize(&self, coordinates: &[(u32, f32)]) -> Vec<f32> {
+        self.axes.iter().map(|axis| {
+            let value = coordinates
+                .iter()
+                .find(|(axis_tag, _)| *axis_tag == axis.tag)
+                .map(|(_, value)| *value)
+                .unwrap_or(axis.default_value);
+            let normalized = normalize_coordinate(value, axis.min_value, axis.default_value, axis.max_value);
+            axis.segment_map.map(normalized).clamp(-1.0, 1.0)
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+    use crate::utils::tag;
+    use crate::{decode_to_sfnt, FontTables, StatAxisValue, WoffFont};
+
+    #[test]
+    fn test_variation_info() {
+        let names: Vec<(u16, u16, u16, u16, Vec<u8>)> = [(256, "Weight"), (258, "Bold"), (259, "Test-Bold"), (261, "Bold Range")]
+            .iter()
+            .map(|(name_id, value)| (3, 1, 0x409, *name_id, utf16_be(value)))
+            .collect();
+        let woff = build_woff(0x00010000, &[
+            (tag(b"fvar"), fvar_table(&[(tag(b"wght"), 100.0, 400.0, 900.0, 256)], &[(258, vec![700.0], Some(259))])),
+            (tag(b"avar"), avar_table(&[vec![(-1.0, -1.0), (0.0, 0.0), (0.6, 0.8), (1.0, 1.0)]])),
+            (tag(b"STAT"), stat_table(&[(tag(b"wght"), 256)], 2)),
+            (tag(b"name"), name_table(&names, &[])),
+        ]);
+        let info = WoffFont::new(&woff).unwrap().variation_info(&["en-US"]).unwrap();
+        debug_assert!(info == decode_to_sfnt(&woff).unwrap().variation_info(&["en-US"]).unwrap());
+        debug_assert!(info.axes[0].name.as_deref() == Some("Weight") && !info.axes[0].hidden);
+        debug_assert!(info.instances[0].name.as_deref() == Some("Bold"));
+        debug_assert!(info.instances[0].post_script_name.as_deref() == Some("Test-Bold"));
+        debug_assert!(info.axis_values.len() == 4 && info.axis_values[1].name.as_deref() == Some("Bold Range"));
+        debug_assert!(matches!(info.axis_values[0].value, StatAxisValue::Format1 { value, .. } if value == 400.0));
+        debug_assert!(info.elided_fallback_name.is_none());
+        debug_assert!(info.normalize(&[(tag(b"wght"), 700.0)]) == vec![0.8]);
+        debug_assert!(info.normalize(&[(tag(b"wdth"), 50.0)]) == vec![0.0]);
+        debug_assert!(info.normalize(&[(tag(b"wght"), 100.0)]) == vec![-1.0]);
+    }
+}
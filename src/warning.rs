@@ -0,0 +1,124 @@
+//! Non-fatal issues found while decoding WOFF data
+
+use crate::structures::{SfntOffsetTable, SfntTableRecord, WoffHeader, WoffTableDirectoryEntry};
+use crate::utils::*;
+use std::mem::size_of;
+
+/// Issue which doesn't prevent decoding but deserves attention.
+/// `tag` is the tag of the table, `offset` is the offset from the beginning of WOFF data
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    // `total_sfnt_size` of WOFF header doesn't match the size of SFNT built from the table directory
+    TotalSfntSizeMismatch { declared: u32, actual: u32 },
+    // `reserved` field of WOFF header isn't zero
+    NonZeroReserved { value: u16 },
+    // table directory isn't sorted by tag; `offset` is the offset of the directory entry
+    UnsortedTableDirectory { tag: u32, offset: u32 },
+    // zlib stream ends before the end of compressed table data; `offset` is the offset of the table data
+    ExtraDataAfterCompressedStream { tag: u32, offset: u32, extra_bytes: u32 },
+    // table required for the font flavor is missing
+    MissingRequiredTable { tag: u32 },
+}
+
+/// Kind of `Warning` for using with C wrapper
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WarningKind {
+    TotalSfntSizeMismatch,
+    NonZeroReserved,
+    UnsortedTableDirectory,
+    ExtraDataAfterCompressedStream,
+    MissingRequiredTable,
+}
+
+/// `Warning` for using with C wrapper. Fields which are not used by the kind of warning are zero
+///
+/// #Fields
+///
+/// `kind` - kind of warning
+/// `tag` - tag of the table
+/// `offset` - offset from the beginning of WOFF data
+/// `declared` - declared value (`total_sfnt_size`, `reserved`)
+/// `actual` - actual value (size of SFNT, number of extra bytes)
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CWarning {
+    pub kind: WarningKind,
+    pub tag: u32,
+    pub offset: u32,
+    pub declared: u32,
+    pub actual: u32,
+}
+
+impl From<&Warning> for CWarning {
+    fn from(warning: &Warning) -> Self {
+        let c_warning = |kind: WarningKind| CWarning { kind, tag: 0, offset: 0, declared: 0, actual: 0 };
+        match *warning {
+            Warning::TotalSfntSizeMismatch { declared, actual } => CWarning {
+                declared,
+                actual,
+                ..c_warning(WarningKind::TotalSfntSizeMismatch)
+            },
+            Warning::NonZeroReserved { value } => CWarning {
+                declared: value as u32,
+                ..c_warning(WarningKind::NonZeroReserved)
+            },
+            Warning::UnsortedTableDirectory { tag, offset } => CWarning {
+                tag,
+                offset,
+                ..c_warning(WarningKind::UnsortedTableDirectory)
+            },
+            Warning::ExtraDataAfterCompressedStream { tag, offset, extra_bytes } => CWarning {
+                tag,
+                offset,
+                actual: extra_bytes,
+                ..c_warning(WarningKind::ExtraDataAfterCompressedStream)
+            },
+            Warning::MissingRequiredTable { tag } => CWarning {
+                tag,
+                ..c_warning(WarningKind::MissingRequiredTable)
+            },
+        }
+    }
+}
+
+// tables required by every font
+const REQUIRED_TABLES: [&[u8; 4]; 8] = [b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post"];
+
+/// Checks WOFF header and the whole table directory (before filtering)
+pub(crate) fn check_directory(woff_header: &WoffHeader, entries: &[WoffTableDirectoryEntry]) -> Vec<Warning> {
+    let mut warnings: Vec<Warning> = vec![];
+    if woff_header.reserved != 0 {
+        warnings.push(Warning::NonZeroReserved { value: woff_header.reserved });
+    }
+
+    let directory_offset = size_of::<WoffHeader>();
+    for (index, pair) in entries.windows(2).enumerate() {
+        if pair[1].tag <= pair[0].tag {
+            warnings.push(Warning::UnsortedTableDirectory {
+                tag: pair[1].tag,
+                offset: (directory_offset + (index + 1) * size_of::<WoffTableDirectoryEntry>()) as u32,
+            });
+        }
+    }
+
+    let sfnt_size = entries.iter().fold(
+        (size_of::<SfntOffsetTable>() + entries.len() * size_of::<SfntTableRecord>()) as u32,
+        |size, entry| size.wrapping_add(calculate_padded_len(entry.orig_length)),
+    );
+    if sfnt_size != woff_header.total_sfnt_size {
+        warnings.push(Warning::TotalSfntSizeMismatch { declared: woff_header.total_sfnt_size, actual: sfnt_size });
+    }
+
+    let has_table = |table_tag: &[u8; 4]| entries.iter().any(|entry| entry.tag == tag(table_tag));
+    let mut missing: Vec<&[u8; 4]> = REQUIRED_TABLES.iter().copied().filter(|table_tag| !has_table(table_tag)).collect();
+    if woff_header.flavor == tag(b"OTTO") {
+        if !has_table(b"CFF ") && !has_table(b"CFF2") {
+            missing.push(b"CFF ");
+        }
+    } else {
+        missing.extend([b"glyf", b"loca"].iter().filter(|table_tag| !has_table(table_tag)));
+    }
+    warnings.extend(missing.into_iter().map(|table_tag| Warning::MissingRequiredTable { tag: tag(table_tag) }));
+    warnings
+}